@@ -7,7 +7,10 @@ use rand_xoshiro::{
     Xoshiro128StarStar,
 };
 use test::Bencher;
-use tree_ord::{Tracker, TreeOrd, TreeOrdVec};
+use tree_ord::{
+    corpus::{ClusteredBytes, ClusteredInts},
+    Tracker, TreeOrd, TreeOrdBytes, TreeOrdBytesAdaptive, TreeOrdString, TreeOrdU64s, TreeOrdVec,
+};
 
 const M: u64 = 1 << 16;
 const M1: u64 = 1 << 7;
@@ -16,25 +19,12 @@ const B1: u64 = 1 << 10;
 type T = Vec<u64>;
 
 fn gen_t() -> Vec<T> {
-    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
-    let mut res = vec![];
-    for _ in 0..M {
-        let len = (rng.next_u64() % M1) as usize;
-        let mut t0: T = vec![0; len];
-        if len != 0 {
-            for i in 0..((rng.next_u64() as usize) % len) {
-                t0[i] = u64::MAX / 2;
-            }
-            t0.rotate_left((rng.next_u64() as usize) % len);
-            for i in 0..((rng.next_u64() as usize) % len) {
-                t0[i] = u64::MAX;
-            }
-            t0.rotate_left((rng.next_u64() as usize) % len);
-        }
-        res.push(t0);
+    ClusteredInts {
+        count: M as usize,
+        max_len: M1 as usize,
+        plateau_levels: vec![u64::MAX / 2, u64::MAX],
     }
-    res.sort();
-    res
+    .generate(0)
 }
 
 #[bench]
@@ -53,6 +43,22 @@ fn t_tree(bencher: &mut Bencher) {
     })
 }
 
+#[bench]
+fn t_chunked_tree(bencher: &mut Bencher) {
+    let t = gen_t();
+    let space: Vec<TreeOrdU64s> = t.iter().map(|v| TreeOrdU64s(v)).collect();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <TreeOrdU64s as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
 #[bench]
 fn t_ord(bencher: &mut Bencher) {
     let space = gen_t();
@@ -66,20 +72,121 @@ fn t_ord(bencher: &mut Bencher) {
 }
 
 fn gen_bytes() -> Vec<TreeOrdVec> {
+    gen_bytes_with_max_len(B1)
+}
+
+fn gen_bytes_with_max_len(max_len: u64) -> Vec<TreeOrdVec> {
+    ClusteredBytes {
+        count: B as usize,
+        max_len: max_len as usize,
+        plateau_levels: vec![u8::MAX / 2, u8::MAX],
+    }
+    .generate(0)
+    .into_iter()
+    .map(TreeOrdVec)
+    .collect()
+}
+
+#[bench]
+fn bytes_tree(bencher: &mut Bencher) {
+    let space = gen_bytes();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <TreeOrdVec as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn bytes_ord(bencher: &mut Bencher) {
+    let space = gen_bytes();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
+        }
+    })
+}
+
+#[bench]
+fn short_bytes_tree(bencher: &mut Bencher) {
+    let space = gen_bytes_with_max_len(M1);
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <TreeOrdVec as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn short_bytes_ord(bencher: &mut Bencher) {
+    let space = gen_bytes_with_max_len(M1);
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
+        }
+    })
+}
+
+// `Vec<u8>` uses the same comparison as `TreeOrdVec`, through a `TypeId`
+// check or through specialization with `--features nightly-specialization`
+
+#[bench]
+fn vec_bytes_tree(bencher: &mut Bencher) {
+    let space: Vec<Vec<u8>> = gen_bytes().into_iter().map(|v| v.0).collect();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn short_vec_bytes_tree(bencher: &mut Bencher) {
+    let space: Vec<Vec<u8>> = gen_bytes_with_max_len(M1)
+        .into_iter()
+        .map(|v| v.0)
+        .collect();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+/// Long keys that share most of their bytes, run with `--features simd` to
+/// compare against the scalar path
+fn gen_long_bytes() -> Vec<TreeOrdVec> {
     let mut rng = Xoshiro128StarStar::seed_from_u64(0);
     let mut res = vec![];
-    for _ in 0..B {
-        let len = (rng.next_u64() % B1) as usize;
-        let mut t0 = vec![0; len];
-        if len != 0 {
-            for i in 0..((rng.next_u64() as usize) % len) {
-                t0[i] = u8::MAX / 2;
-            }
-            t0.rotate_left((rng.next_u64() as usize) % len);
-            for i in 0..((rng.next_u64() as usize) % len) {
-                t0[i] = u8::MAX;
-            }
-            t0.rotate_left((rng.next_u64() as usize) % len);
+    for _ in 0..(B / 16) {
+        let mut t0 = vec![0u8; 4096];
+        for _ in 0..4 {
+            let i = (rng.next_u64() as usize) % t0.len();
+            t0[i] = rng.next_u32() as u8;
         }
         res.push(TreeOrdVec(t0));
     }
@@ -88,8 +195,8 @@ fn gen_bytes() -> Vec<TreeOrdVec> {
 }
 
 #[bench]
-fn bytes_tree(bencher: &mut Bencher) {
-    let space = gen_bytes();
+fn long_bytes_tree(bencher: &mut Bencher) {
+    let space = gen_long_bytes();
     let inxs = space.clone();
 
     bencher.iter(|| {
@@ -103,8 +210,153 @@ fn bytes_tree(bencher: &mut Bencher) {
 }
 
 #[bench]
-fn bytes_ord(bencher: &mut Bencher) {
-    let space = gen_bytes();
+fn long_bytes_ord(bencher: &mut Bencher) {
+    let space = gen_long_bytes();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
+        }
+    })
+}
+
+fn gen_tuples() -> Vec<Vec<(u32, u32)>> {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut res = vec![];
+    for _ in 0..M {
+        let len = (rng.next_u64() % M1) as usize;
+        let mut t0 = vec![(0, 0); len];
+        for i in 0..len {
+            t0[i] = ((rng.next_u32() % 2), (rng.next_u32() % 2));
+        }
+        res.push(t0);
+    }
+    res.sort();
+    res.dedup();
+    res
+}
+
+#[bench]
+fn tuple_tree(bencher: &mut Bencher) {
+    type T = Vec<(u32, u32)>;
+    let space = gen_tuples();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <T as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn tuple_ord(bencher: &mut Bencher) {
+    let space = gen_tuples();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
+        }
+    })
+}
+
+/// Flat tuples that often share their leading fields
+fn gen_triples() -> Vec<(u64, u64, u64)> {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut res = vec![];
+    for _ in 0..M {
+        res.push((rng.next_u64() % 4, rng.next_u64() % 16, rng.next_u64()));
+    }
+    res.sort();
+    res.dedup();
+    res
+}
+
+#[bench]
+fn triple_tree(bencher: &mut Bencher) {
+    type T = (u64, u64, u64);
+    let space = gen_triples();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <T as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn triple_ord(bencher: &mut Bencher) {
+    let space = gen_triples();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
+        }
+    })
+}
+
+/// URL-like keys with long shared prefixes
+fn gen_long_strs() -> Vec<String> {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let hosts = ["https://example.com", "https://docs.example.org/ünïcödé"];
+    let mut res = vec![];
+    for _ in 0..(B / 4) {
+        let mut s = String::from(hosts[(rng.next_u32() % 2) as usize]);
+        for _ in 0..(2 + rng.next_u32() % 6) {
+            s.push_str("/segment-");
+            s.push_str(&(rng.next_u32() % 4).to_string());
+        }
+        s.push_str(&rng.next_u32().to_string());
+        res.push(s);
+    }
+    res.sort();
+    res.dedup();
+    res
+}
+
+#[bench]
+fn str_tree(bencher: &mut Bencher) {
+    let space = gen_long_strs();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <String as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn tree_ord_str_tree(bencher: &mut Bencher) {
+    let space: Vec<TreeOrdString> = gen_long_strs().into_iter().map(TreeOrdString).collect();
+    let inxs = space.clone();
+
+    bencher.iter(|| {
+        for rhs in &inxs {
+            let mut tracker = <TreeOrdString as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn str_ord(bencher: &mut Bencher) {
+    let space = gen_long_strs();
     let inxs = space.clone();
 
     bencher.iter(|| {
@@ -113,3 +365,102 @@ fn bytes_ord(bencher: &mut Bencher) {
         }
     })
 }
+
+/// Keys from 16 bytes to 4 KiB in one space, sharing long prefixes
+fn gen_mixed_len_bytes() -> Vec<Vec<u8>> {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut res = vec![];
+    for _ in 0..(B / 16) {
+        let len = 16 << (rng.next_u32() % 9);
+        let mut t0 = vec![0u8; len];
+        for _ in 0..2 {
+            let i = (rng.next_u32() as usize) % len;
+            t0[i] = rng.next_u32() as u8;
+        }
+        res.push(t0);
+    }
+    res.sort();
+    res.dedup();
+    res
+}
+
+#[bench]
+fn mixed_bytes_adaptive_tree(bencher: &mut Bencher) {
+    let keys = gen_mixed_len_bytes();
+    let space: Vec<TreeOrdBytesAdaptive> = keys.iter().map(|k| TreeOrdBytesAdaptive(k)).collect();
+
+    bencher.iter(|| {
+        for rhs in &space {
+            let mut tracker = <TreeOrdBytesAdaptive as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn mixed_bytes_tree(bencher: &mut Bencher) {
+    let keys = gen_mixed_len_bytes();
+    let space: Vec<TreeOrdBytes> = keys.iter().map(|k| TreeOrdBytes(k)).collect();
+
+    bencher.iter(|| {
+        for rhs in &space {
+            let mut tracker = <TreeOrdBytes as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn mixed_bytes_ord(bencher: &mut Bencher) {
+    let space = gen_mixed_len_bytes();
+
+    bencher.iter(|| {
+        for rhs in &space {
+            space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
+        }
+    })
+}
+
+/// Keys of 16 to 32 bytes
+fn gen_small_bytes() -> Vec<Vec<u8>> {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut res = vec![];
+    for _ in 0..M {
+        let len = 16 + (rng.next_u32() % 17) as usize;
+        let t0: Vec<u8> = (0..len).map(|_| (rng.next_u32() % 4) as u8).collect();
+        res.push(t0);
+    }
+    res.sort();
+    res.dedup();
+    res
+}
+
+#[bench]
+fn small_bytes_tree(bencher: &mut Bencher) {
+    let keys = gen_small_bytes();
+    let space: Vec<TreeOrdBytes> = keys.iter().map(|k| TreeOrdBytes(k)).collect();
+
+    bencher.iter(|| {
+        for rhs in &space {
+            let mut tracker = <TreeOrdBytes as TreeOrd>::Tracker::new();
+            space
+                .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
+                .unwrap();
+        }
+    })
+}
+
+#[bench]
+fn small_bytes_ord(bencher: &mut Bencher) {
+    let space = gen_small_bytes();
+
+    bencher.iter(|| {
+        for rhs in &space {
+            space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
+        }
+    })
+}
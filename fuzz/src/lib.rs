@@ -0,0 +1,229 @@
+//! Differential fuzzing of `tree_ord` against `Ord`
+//!
+//! Run the targets from the root of the repository with `cargo +nightly fuzz
+//! run search` and `cargo +nightly fuzz run tracker`. The seed corpora are in
+//! `fuzz/corpus`. The input decoding is shared with the tests of this crate,
+//! which run the seeds on stable.
+
+use std::cmp::Ordering;
+
+use tree_ord::{
+    search::{tree_binary_search, tree_lower_bound, tree_lower_bound_hereditary, tree_upper_bound},
+    utils::LexicographicTracker,
+    Tracker, TreeOrd, TreeOrdBytes,
+};
+use Ordering::*;
+
+/// The maximum number of snapshots kept by [check_tracker]
+const MAX_SNAPSHOTS: usize = 16;
+
+/// Reads the fuzzer input from the front
+#[derive(Debug, Clone)]
+pub struct Input<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Input<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    /// Returns the next byte, if any
+    pub fn byte(&mut self) -> Option<u8> {
+        let (first, rest) = self.data.split_first()?;
+        self.data = rest;
+        Some(*first)
+    }
+
+    /// Returns the next `len` bytes, or fewer at the end of the input
+    pub fn bytes(&mut self, len: usize) -> &'a [u8] {
+        let (res, rest) = self.data.split_at(len.min(self.data.len()));
+        self.data = rest;
+        res
+    }
+
+    /// Decodes a front coded key that shares a prefix with `prev`. The first
+    /// byte has the length of the shared prefix in its low 7 bits (clamped to
+    /// the length of `prev`) and a flag in its high bit, the second byte is
+    /// the length of the suffix, and the suffix follows. Front coding makes
+    /// long shared prefixes, which is where the trackers do anything, easy for
+    /// the fuzzer to produce. Returns `None` at the end of the input.
+    pub fn key(&mut self, prev: &[u8]) -> Option<(Vec<u8>, bool)> {
+        let header = self.byte()?;
+        let shared = usize::from(header & 0x7f).min(prev.len());
+        let suffix_len = usize::from(self.byte().unwrap_or(0));
+        let mut key = prev[..shared].to_vec();
+        key.extend_from_slice(self.bytes(suffix_len));
+        Some((key, (header & 0x80) != 0))
+    }
+}
+
+/// Decodes the whole input into a sorted space of keys, which may have
+/// duplicates, and the queries, which are the keys that were flagged
+pub fn decode_corpus(data: &[u8]) -> (Vec<Vec<u8>>, Vec<Vec<u8>>) {
+    let mut input = Input::new(data);
+    let mut space = vec![];
+    let mut queries = vec![];
+    let mut prev = vec![];
+    while let Some((key, is_query)) = input.key(&prev) {
+        if is_query {
+            queries.push(key.clone());
+        } else {
+            space.push(key.clone());
+        }
+        prev = key;
+    }
+    space.sort();
+    (space, queries)
+}
+
+/// Asserts that a search result agrees with the result of
+/// `binary_search`, which may have found a different one of equal keys
+fn assert_found(
+    space: &[Vec<u8>],
+    query: &[u8],
+    res: Result<usize, usize>,
+    expected: Result<usize, usize>,
+) {
+    match (res, expected) {
+        (Ok(i), Ok(_)) => assert_eq!(space[i], query),
+        (Err(i), Err(j)) => assert_eq!(i, j),
+        _ => panic!("tree search returned {res:?} but `binary_search` returned {expected:?}"),
+    }
+}
+
+/// Searches the decoded corpus for every query and every key of the space,
+/// with the functions of `tree_ord::search` over `Vec<u8>` and
+/// `TreeOrdBytes`, and asserts that the results agree with the `std`
+/// searches.
+pub fn check_search(data: &[u8]) {
+    let (space, queries) = decode_corpus(data);
+    let bytes: Vec<TreeOrdBytes> = space.iter().map(|k| TreeOrdBytes(k)).collect();
+    for query in queries.iter().chain(&space) {
+        let expected = space.binary_search_by(|k| k.cmp(query));
+        assert_found(&space, query, tree_binary_search(&space, query), expected);
+        let res = tree_binary_search(&bytes, &TreeOrdBytes(query));
+        assert_found(&space, query, res, expected);
+        let lower = space.partition_point(|k| k < query);
+        assert_eq!(tree_lower_bound(&space, query), lower);
+        assert_eq!(tree_lower_bound_hereditary(&space, query), lower);
+        assert_eq!(tree_lower_bound(&bytes, &TreeOrdBytes(query)), lower);
+        let upper = space.partition_point(|k| k <= query);
+        assert_eq!(tree_upper_bound(&space, query), upper);
+        assert_eq!(tree_upper_bound(&bytes, &TreeOrdBytes(query)), upper);
+    }
+}
+
+/// The keys that are valid to compare with, given the comparisons so far
+#[derive(Debug, Clone, Copy)]
+struct Bounds {
+    // the range of keys strictly between the strongest `Less` and `Greater`
+    // keys
+    start: usize,
+    end: usize,
+    // the last comparison, which can always be repeated
+    last: Option<usize>,
+}
+
+/// Decodes a query, a space of keys, and a sequence of operations on a single
+/// `LexicographicTracker`, and asserts that every comparison agrees with
+/// `Ord`. The operations are comparisons through either `[u8]` or
+/// `TreeOrdBytes` with keys that are within the bounds established so far,
+/// repeats of the last comparison, forks, and snapshots and restores. The
+/// bounds are tracked alongside, so that every decoded sequence is valid.
+///
+/// The first byte selects the side of the query with its low bit and the
+/// number of keys with the rest, then the query and the keys follow as front
+/// coded keys (see [Input::key]), and the operations are the rest of the
+/// input.
+pub fn check_tracker(data: &[u8]) {
+    let mut input = Input::new(data);
+    let Some(header) = input.byte() else { return };
+    let query_is_lhs = (header & 1) != 0;
+    let Some((query, _)) = input.key(&[]) else {
+        return
+    };
+    let mut space = vec![];
+    let mut prev = query.clone();
+    for _ in 0..=(header >> 1) {
+        let Some((key, _)) = input.key(&prev) else {
+            break
+        };
+        space.push(key.clone());
+        prev = key;
+    }
+    space.sort();
+    space.dedup();
+
+    let mut tracker = LexicographicTracker::<u8>::new();
+    let mut bounds = Bounds {
+        start: 0,
+        end: space.len(),
+        last: None,
+    };
+    let mut snapshots = vec![];
+    let mut seq = vec![];
+    while let Some(op) = input.byte() {
+        let through_bytes = (op & 0b100) != 0;
+        let i = match op & 0b11 {
+            0 => {
+                let len = bounds.end.saturating_sub(bounds.start);
+                let choice = input.byte().unwrap_or(0);
+                if len == 0 {
+                    continue
+                }
+                bounds.start + (usize::from(choice) % len)
+            }
+            1 => match bounds.last {
+                Some(i) => i,
+                None => continue,
+            },
+            2 => {
+                if (op & 0b1000) != 0 {
+                    tracker = tracker.fork();
+                } else if snapshots.len() < MAX_SNAPSHOTS {
+                    snapshots.push((tracker.snapshot(), bounds, seq.len()));
+                }
+                continue
+            }
+            _ => {
+                if let Some((snapshot, prev_bounds, len)) = snapshots.pop() {
+                    tracker.restore(snapshot);
+                    bounds = prev_bounds;
+                    seq.truncate(len);
+                }
+                continue
+            }
+        };
+        let key = &space[i];
+        seq.push(i);
+        let (tree_res, res) = match (query_is_lhs, through_bytes) {
+            (false, false) => (key[..].tree_cmp(&query[..], &mut tracker), key.cmp(&query)),
+            (false, true) => (
+                TreeOrdBytes(key).tree_cmp(&TreeOrdBytes(&query), &mut tracker),
+                key.cmp(&query),
+            ),
+            (true, false) => (query[..].tree_cmp(&key[..], &mut tracker), query.cmp(key)),
+            (true, true) => (
+                TreeOrdBytes(&query).tree_cmp(&TreeOrdBytes(key), &mut tracker),
+                query.cmp(key),
+            ),
+        };
+        assert_eq!(
+            tree_res, res,
+            "`tree_cmp` disagreed with `cmp` after comparing with the keys {seq:?} of {space:?} \
+             for the query {query:?}"
+        );
+        let key_ord = if query_is_lhs { res.reverse() } else { res };
+        match key_ord {
+            Less => bounds.start = i + 1,
+            Equal => (),
+            Greater => bounds.end = i,
+        }
+        bounds.last = Some(i);
+    }
+}
@@ -0,0 +1,27 @@
+//! Runs the seed corpora of the fuzz targets
+
+use std::fs;
+
+fn seeds(target: &str) -> Vec<Vec<u8>> {
+    let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/corpus/");
+    let mut res = vec![];
+    for entry in fs::read_dir(format!("{dir}{target}")).unwrap() {
+        res.push(fs::read(entry.unwrap().path()).unwrap());
+    }
+    assert!(!res.is_empty());
+    res
+}
+
+#[test]
+fn search_seeds() {
+    for seed in seeds("search") {
+        tree_ord_fuzz::check_search(&seed);
+    }
+}
+
+#[test]
+fn tracker_seeds() {
+    for seed in seeds("tracker") {
+        tree_ord_fuzz::check_tracker(&seed);
+    }
+}
@@ -0,0 +1,158 @@
+#!/usr/bin/env python3
+"""Generates the embedded Unicode tables: `src/case_fold/tables.rs` for
+`CaseFolded` from `CaseFolding.txt`, and `src/normalize/tables.rs`, the tables
+shared by the `AccentInsensitive` and `Nfc` keys, from the Unicode Character
+Database that Python's `unicodedata` module is built with.
+
+The tables are pinned to UCD 14.0.0, which is what Python 3.11 ships. Python
+has no simple case folding data, so `CaseFolding.txt` of the same version
+(https://www.unicode.org/Public/14.0.0/ucd/CaseFolding.txt) is passed as the
+argument. Run with `python3.11 scripts/gen_unicode_tables.py CaseFolding.txt`
+from the root of the repository.
+"""
+
+import sys
+import unicodedata
+
+UCD_VERSION = "14.0.0"
+CASE_FOLD_OUT = "src/case_fold/tables.rs"
+NORMALIZE_OUT = "src/normalize/tables.rs"
+
+
+def esc(c):
+    return "\\u{%X}" % ord(c)
+
+
+def chars():
+    return (chr(i) for i in range(0x80, sys.maxunicode + 1) if not 0xD800 <= i < 0xE000)
+
+
+def is_hangul_syllable(c):
+    return 0xAC00 <= ord(c) < 0xAC00 + 11172
+
+
+def ranges(pred):
+    """Returns the inclusive ranges of consecutive `char`s with the same
+    non-`None` value of `pred`"""
+    res = []
+    for c in chars():
+        v = pred(c)
+        if v is None:
+            continue
+        if res and (ord(res[-1][1]) + 1 == ord(c)) and (res[-1][2] == v):
+            res[-1][1] = c
+        else:
+            res.append([c, c, v])
+    return res
+
+
+def header():
+    return [
+        "//! Unicode %s tables generated by `scripts/gen_unicode_tables.py`, do not" % UCD_VERSION[:-2],
+        "//! edit by hand",
+        "",
+    ]
+
+
+def case_folding(path):
+    """Returns the simple case folding (the `C` and `S` mappings) of the
+    non-ASCII `char`s that fold to another `char`, sorted by `char`"""
+    with open(path, encoding="utf-8") as f:
+        lines = f.read().split("\n")
+    if lines[0] != "# CaseFolding-%s.txt" % UCD_VERSION:
+        sys.exit("expected `CaseFolding-%s.txt` but got `%s`" % (UCD_VERSION, lines[0]))
+    simple = {}
+    full = {}
+    for line in lines:
+        line = line.split("#")[0].strip()
+        if not line:
+            continue
+        code, status, mapping = (x.strip() for x in line.split(";")[:3])
+        c = chr(int(code, 16))
+        mapping = "".join(chr(int(x, 16)) for x in mapping.split())
+        if status in ("C", "S"):
+            simple[c] = mapping
+        if status in ("C", "F"):
+            full[c] = mapping
+    # the full folding is what `str.casefold` does, which cross checks the file
+    # against `unicodedata`
+    for c in chars():
+        if full.get(c, c) != c.casefold():
+            sys.exit("`CaseFolding.txt` disagrees with `str.casefold` for U+%04X" % ord(c))
+    return sorted((c, f) for (c, f) in simple.items() if not c.isascii())
+
+
+def main():
+    if unicodedata.unidata_version != UCD_VERSION:
+        sys.exit(
+            "expected UCD %s but `unicodedata` has %s, use the Python version that ships it"
+            % (UCD_VERSION, unicodedata.unidata_version)
+        )
+    if len(sys.argv) != 2:
+        sys.exit("usage: gen_unicode_tables.py path/to/CaseFolding.txt")
+
+    folds = case_folding(sys.argv[1])
+    out = header()
+    out.append("/// The simple case folding of the non-ASCII `char`s that fold to another")
+    out.append("/// `char`, sorted by `char`")
+    out.append("#[rustfmt::skip]")
+    out.append("pub(crate) const FOLDS: &[(char, char)] = &[")
+    for c, f in folds:
+        out.append("    ('%s', '%s')," % (esc(c), f if f.isascii() else esc(f)))
+    out.append("];")
+    with open(CASE_FOLD_OUT, "w") as f:
+        f.write("\n".join(out) + "\n")
+
+    marks = ranges(lambda c: True if unicodedata.category(c) == "Mn" else None)
+    ccc = ranges(lambda c: unicodedata.combining(c) or None)
+    decompositions = []
+    compositions = []
+    for c in chars():
+        if is_hangul_syllable(c):
+            continue
+        d = unicodedata.normalize("NFD", c)
+        if d != c:
+            decompositions.append((c, d))
+        raw = unicodedata.decomposition(c)
+        if raw and not raw.startswith("<"):
+            pair = [chr(int(x, 16)) for x in raw.split()]
+            if (len(pair) == 2) and (unicodedata.normalize("NFC", "".join(pair)) == c):
+                compositions.append((pair[0], pair[1], c))
+    compositions.sort()
+
+    out = header()
+    out.append("/// The inclusive ranges of nonspacing marks (general category `Mn`)")
+    out.append("#[rustfmt::skip]")
+    out.append("pub(crate) const MARKS: &[(char, char)] = &[")
+    for lo, hi, _ in marks:
+        out.append("    ('%s', '%s')," % (esc(lo), esc(hi)))
+    out.append("];")
+    out.append("")
+    out.append("/// The inclusive ranges of `char`s with a nonzero canonical combining class")
+    out.append("#[rustfmt::skip]")
+    out.append("pub(crate) const CCC: &[(char, char, u8)] = &[")
+    for lo, hi, v in ccc:
+        out.append("    ('%s', '%s', %d)," % (esc(lo), esc(hi), v))
+    out.append("];")
+    out.append("")
+    out.append("/// The full canonical decompositions of the non-ASCII `char`s other than Hangul")
+    out.append("/// syllables, sorted by `char`")
+    out.append("#[rustfmt::skip]")
+    out.append("pub(crate) const DECOMPOSITIONS: &[(char, &str)] = &[")
+    for c, d in decompositions:
+        out.append("    ('%s', \"%s\")," % (esc(c), "".join(esc(x) if ord(x) >= 0x80 else x for x in d)))
+    out.append("];")
+    out.append("")
+    out.append("/// The primary composites other than Hangul syllables, sorted by the pair of")
+    out.append("/// `char`s they are composed from")
+    out.append("#[rustfmt::skip]")
+    out.append("pub(crate) const COMPOSITIONS: &[(char, char, char)] = &[")
+    for a, b, c in compositions:
+        out.append("    ('%s', '%s', '%s')," % (esc(a), esc(b), esc(c)))
+    out.append("];")
+    with open(NORMALIZE_OUT, "w") as f:
+        f.write("\n".join(out) + "\n")
+
+
+if __name__ == "__main__":
+    main()
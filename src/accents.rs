@@ -0,0 +1,122 @@
+//! Accent stripping, that is NFD normalization with the nonspacing marks
+//! (general category `Mn`) removed, using the tables of `normalize`
+
+use core::str::Chars;
+
+use crate::normalize::{decompose_hangul, decomposition, tables::MARKS};
+
+/// Returns if `c` is a nonspacing mark
+#[inline]
+fn is_mark(c: char) -> bool {
+    MARKS
+        .binary_search_by(|(lo, hi)| {
+            if *hi < c {
+                core::cmp::Ordering::Less
+            } else if *lo > c {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+/// An iterator over the `char`s of a `str` with accents stripped, which
+/// transforms on the fly without allocating
+#[derive(Debug, Clone)]
+pub(crate) struct StrippedChars<'a> {
+    chars: Chars<'a>,
+    /// The remaining `char`s of a decomposition to multiple `char`s
+    buf: [char; 3],
+    buf_start: u8,
+    buf_end: u8,
+}
+
+impl<'a> StrippedChars<'a> {
+    #[inline]
+    pub(crate) fn new(s: &'a str) -> Self {
+        Self {
+            chars: s.chars(),
+            buf: ['\0'; 3],
+            buf_start: 0,
+            buf_end: 0,
+        }
+    }
+
+    /// Skips `n` transformed `char`s. Runs of ASCII are skipped without
+    /// decomposing, since every ASCII `char` is one transformed `char`.
+    #[inline]
+    pub(crate) fn skip_stripped(&mut self, mut n: usize) {
+        while n > 0 {
+            if self.buf_start == self.buf_end {
+                let rest = self.chars.as_str();
+                let ascii = rest.bytes().take(n).take_while(|b| b.is_ascii()).count();
+                if ascii > 0 {
+                    self.chars = rest[ascii..].chars();
+                    n = n.wrapping_sub(ascii);
+                    continue
+                }
+            }
+            if self.next().is_none() {
+                break
+            }
+            n = n.wrapping_sub(1);
+        }
+    }
+
+    /// Buffers the `char`s of `s` and returns the first one, or `None` if `s`
+    /// is empty
+    #[inline]
+    fn buffer(&mut self, s: impl Iterator<Item = char>) -> Option<char> {
+        self.buf_start = 0;
+        self.buf_end = 0;
+        for c in s {
+            self.buf[usize::from(self.buf_end)] = c;
+            self.buf_end = self.buf_end.wrapping_add(1);
+        }
+        self.buffered()
+    }
+
+    /// Returns the next buffered `char`
+    #[inline]
+    fn buffered(&mut self) -> Option<char> {
+        if self.buf_start < self.buf_end {
+            let c = self.buf[usize::from(self.buf_start)];
+            self.buf_start = self.buf_start.wrapping_add(1);
+            Some(c)
+        } else {
+            None
+        }
+    }
+}
+
+impl Iterator for StrippedChars<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        if let Some(c) = self.buffered() {
+            return Some(c)
+        }
+        loop {
+            let c = self.chars.next()?;
+            if c.is_ascii() {
+                return Some(c)
+            }
+            if let Some(jamo) = decompose_hangul(c) {
+                return self.buffer(jamo)
+            }
+            if let Some(decomposition) = decomposition(c) {
+                // the decompositions of marks consist only of marks, which
+                // leaves nothing buffered
+                if let Some(c) = self.buffer(decomposition.chars().filter(|c| !is_mark(*c))) {
+                    return Some(c)
+                }
+                continue
+            }
+            if !is_mark(c) {
+                return Some(c)
+            }
+        }
+    }
+}
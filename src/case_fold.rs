@@ -0,0 +1,52 @@
+//! Simple Unicode case folding, the `C` and `S` mappings of `CaseFolding.txt`
+//! from Unicode 14.0 in `tables`, which fold every `char` to exactly one `char`
+
+mod tables;
+
+use core::str::Chars;
+
+use tables::FOLDS;
+
+/// Returns the simple case folding of `c`
+#[inline]
+fn fold(c: char) -> char {
+    if c.is_ascii() {
+        return c.to_ascii_lowercase()
+    }
+    match FOLDS.binary_search_by_key(&c, |(c, _)| *c) {
+        Ok(i) => FOLDS[i].1,
+        Err(_) => c,
+    }
+}
+
+/// An iterator over the case folded `char`s of a `str`, which folds on the fly
+/// without allocating
+#[derive(Debug, Clone)]
+pub(crate) struct FoldedChars<'a> {
+    chars: Chars<'a>,
+}
+
+impl<'a> FoldedChars<'a> {
+    #[inline]
+    pub(crate) fn new(s: &'a str) -> Self {
+        Self { chars: s.chars() }
+    }
+
+    /// Skips `n` folded `char`s, which are the first `n` `char`s since every
+    /// `char` folds to exactly one `char`
+    #[inline]
+    pub(crate) fn skip_folded(&mut self, n: usize) {
+        if let Some(last) = n.checked_sub(1) {
+            self.chars.nth(last);
+        }
+    }
+}
+
+impl Iterator for FoldedChars<'_> {
+    type Item = char;
+
+    #[inline]
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(fold)
+    }
+}
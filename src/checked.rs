@@ -0,0 +1,242 @@
+//! Checked wrappers for validating `TreeOrd` impls and the comparison sequences
+//! of downstream tree code in tests and debug builds
+
+use core::cmp::Ordering;
+
+use Ordering::*;
+
+use crate::{Tracker, TreeOrd, TreeOrdBounded};
+
+/// A key wrapper whose [CheckedTracker] records clones of the tightest bounding
+/// keys seen so far, and panics with a clear message if a key outside of the
+/// bounds is compared, if the tracker is reused for a different query without
+/// being reset, or if the tracked comparison disagrees with `Ord::cmp`.
+///
+/// Either side of `tree_cmp` may be the query, as long as it stays the same
+/// side for the whole search. The side is determined by which side stays the
+/// same between the first two different comparisons. `tree_cmp_hereditary`
+/// also panics if the query is on the left hand side, and after it returns
+/// `Equal` the search may only continue with keys less than or equal to the
+/// query.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct Checked<K>(pub K);
+
+/// The checking state of a [CheckedTracker], which is part of its snapshots
+#[derive(Debug, Clone)]
+pub struct CheckState<K> {
+    query_is_lhs: Option<bool>,
+    // the first comparison, which is stored until the query side is known
+    first: Option<(K, K, Ordering, bool)>,
+    // if `tree_cmp_hereditary` has been used
+    hereditary: bool,
+    query: Option<K>,
+    lower: Option<K>,
+    upper: Option<K>,
+}
+
+impl<K: Ord + Clone> CheckState<K> {
+    const NEW: Self = Self {
+        query_is_lhs: None,
+        first: None,
+        hereditary: false,
+        query: None,
+        lower: None,
+        upper: None,
+    };
+
+    fn new() -> Self {
+        Self::NEW
+    }
+
+    fn check(&mut self, lhs: &K, rhs: &K, ord: Ordering, hereditary: bool) {
+        self.hereditary |= hereditary;
+        let query_is_lhs = match self.query_is_lhs {
+            Some(query_is_lhs) => query_is_lhs,
+            None => {
+                let (first_lhs, first_rhs, first_ord, first_hereditary) = match self.first.take() {
+                    None => {
+                        self.first = Some((lhs.clone(), rhs.clone(), ord, hereditary));
+                        return
+                    }
+                    Some(first) => first,
+                };
+                let query_is_lhs = match (first_lhs == *lhs, first_rhs == *rhs) {
+                    (true, true) => {
+                        // repeated comparison
+                        self.first = Some((first_lhs, first_rhs, first_ord, first_hereditary));
+                        return
+                    }
+                    (true, false) => true,
+                    (false, true) => false,
+                    (false, false) => panic!(
+                        "a `CheckedTracker` was used for comparisons that do not share a query, \
+                         trackers need to be reset or recreated for each search"
+                    ),
+                };
+                self.query_is_lhs = Some(query_is_lhs);
+                self.apply(
+                    query_is_lhs,
+                    &first_lhs,
+                    &first_rhs,
+                    first_ord,
+                    first_hereditary,
+                );
+                query_is_lhs
+            }
+        };
+        assert!(
+            !(self.hereditary && query_is_lhs),
+            "`tree_cmp_hereditary` was used with the query on the left hand side, it records \
+             `Equal` results for the key on the left hand side and must be called as \
+             `key.tree_cmp_hereditary(query)`"
+        );
+        self.apply(query_is_lhs, lhs, rhs, ord, hereditary);
+    }
+
+    fn apply(&mut self, query_is_lhs: bool, lhs: &K, rhs: &K, ord: Ordering, hereditary: bool) {
+        // `key_ord` is the ordering of `key` relative to `query`
+        let (query, key, key_ord) = if query_is_lhs {
+            (lhs, rhs, ord.reverse())
+        } else {
+            (rhs, lhs, ord)
+        };
+        match self.query {
+            Some(ref q) => {
+                if q != query {
+                    assert!(
+                        q != key,
+                        "a `CheckedTracker` was used with the query on the other side, it has to \
+                         stay on the side it was on in the first comparisons (which is the right \
+                         hand side for `search::tracker_with_extremes`)"
+                    );
+                    panic!(
+                        "a `CheckedTracker` was used with a different query, trackers need to be \
+                         reset or recreated for each search"
+                    );
+                }
+            }
+            None => self.query = Some(query.clone()),
+        }
+        if let Some(ref lower) = self.lower {
+            assert!(
+                lower <= key,
+                "a `CheckedTracker` was used with a key that is less than a key that was \
+                 previously found to be less than the query, which is outside of the bounds of \
+                 the search"
+            );
+        }
+        if let Some(ref upper) = self.upper {
+            assert!(
+                key <= upper,
+                "a `CheckedTracker` was used with a key that is greater than a key that was \
+                 previously found to be greater than the query, which is outside of the bounds of \
+                 the search"
+            );
+        }
+        match key_ord {
+            Less => self.lower = Some(key.clone()),
+            // a hereditary `Equal` is recorded like a `Greater` result
+            Equal if hereditary => self.upper = Some(key.clone()),
+            Equal => (),
+            Greater => self.upper = Some(key.clone()),
+        }
+    }
+}
+
+/// The tracker of [Checked]
+#[derive(Debug, Clone)]
+pub struct CheckedTracker<K: TreeOrd> {
+    inner: K::Tracker,
+    state: CheckState<K>,
+}
+
+impl<K: TreeOrd + Clone> Default for CheckedTracker<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: TreeOrd + Clone> Tracker for CheckedTracker<K> {
+    type Snapshot = (<K::Tracker as Tracker>::Snapshot, CheckState<K>);
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = Self {
+        inner: K::Tracker::NEW,
+        state: CheckState::NEW,
+    };
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.state = CheckState::new();
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.inner.snapshot(), self.state.clone())
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        self.inner.restore(snapshot.0);
+        self.state = snapshot.1;
+    }
+}
+
+impl<K: TreeOrd + Clone> TreeOrd<Self> for Checked<K> {
+    type Tracker = CheckedTracker<K>;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let expected = self.0.cmp(&rhs.0);
+        tracker.state.check(&self.0, &rhs.0, expected, false);
+        let res = self.0.tree_cmp(&rhs.0, &mut tracker.inner);
+        assert_eq!(
+            res, expected,
+            "`tree_cmp` disagreed with `cmp`, the `TreeOrd` impl is incorrect"
+        );
+        res
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let expected = self.0.cmp(&rhs.0);
+        tracker.state.check(&self.0, &rhs.0, expected, true);
+        let res = self.0.tree_cmp_hereditary(&rhs.0, &mut tracker.inner);
+        assert_eq!(
+            res, expected,
+            "`tree_cmp_hereditary` disagreed with `cmp`, the `TreeOrd` impl is incorrect or the \
+             search is not hereditary"
+        );
+        res
+    }
+}
+
+impl<K: TreeOrdBounded + Clone> TreeOrdBounded for Checked<K> {
+    const MAX: Self = Checked(K::MAX);
+    const MIN: Self = Checked(K::MIN);
+}
+
+/// A wrapper whose `tree_cmp` calls the inner `tree_cmp` and also `Ord::cmp`,
+/// asserting that they agree. The tracker is passed through transparently, so
+/// the tracking behavior of the inner impl is the same as without the wrapper.
+/// This is the recommended harness for developing `TreeOrd` impls.
+///
+/// The assertion is a `debug_assert`, unless the "checked" feature flag is
+/// enabled in which case it is always on.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct CheckedTreeOrd<T>(pub T);
+
+impl<T: TreeOrd> TreeOrd<Self> for CheckedTreeOrd<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let res = self.0.tree_cmp(&rhs.0, tracker);
+        if cfg!(any(debug_assertions, feature = "checked")) {
+            let expected = self.0.cmp(&rhs.0);
+            assert_eq!(
+                res, expected,
+                "`tree_cmp` disagreed with `cmp`, the `TreeOrd` impl is incorrect"
+            );
+        }
+        res
+    }
+}
@@ -0,0 +1,25 @@
+//! Containers that thread a tracker through the comparisons of each operation
+//!
+//! The containers of `std` compare keys with `Ord` and cannot carry a
+//! `Tracker` from one comparison to the next, so long shared key prefixes are
+//! compared again at every level. The containers here create a tracker per
+//! operation and pass it down the whole search, so each comparison can skip
+//! the prefix that is already known to be shared with every remaining key.
+//!
+//! [SortedVecSet] and [SortedVecMultiMap] only require the "alloc" feature
+//! flag, while the tree based containers such as `TreeMap` and `SkipListMap`
+//! require the "collections" feature flag.
+
+#[cfg(feature = "collections")]
+mod skip_list;
+mod sorted_vec_multimap;
+mod sorted_vec_set;
+#[cfg(feature = "collections")]
+mod tree_map;
+
+#[cfg(feature = "collections")]
+pub use skip_list::{Iter as SkipListIter, Range as SkipListRange, SkipListMap};
+pub use sorted_vec_multimap::{Groups, SortedVecMultiMap};
+pub use sorted_vec_set::SortedVecSet;
+#[cfg(feature = "collections")]
+pub use tree_map::{Iter, Range, TreeMap};
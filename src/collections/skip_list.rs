@@ -0,0 +1,515 @@
+use alloc::vec::Vec;
+use core::{
+    cmp::Ordering,
+    fmt, mem,
+    ops::{Bound, RangeBounds},
+};
+
+use Ordering::*;
+
+use crate::{search::TreeOrdSearchable, Tracker, TreeOrd};
+
+/// The maximum number of levels of a [SkipListMap]
+const MAX_LEVEL: usize = 32;
+
+/// The end of a level
+const NIL: usize = usize::MAX;
+
+/// The head of the list, which is before every node on every level
+const HEAD: usize = usize::MAX - 1;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    // the next node on each level that this node is on
+    next: Vec<usize>,
+}
+
+/// An ordered map on a skip list, where every operation threads a single
+/// `Tracker` through all of its comparisons
+///
+/// A search through a skip list moves right while the keys are less than the
+/// query and moves down otherwise, so every key it compares is between the
+/// strongest `Less` and `Greater` keys so far and one tracker serves the whole
+/// traversal. Before descending, the minimum and maximum keys are compared
+/// (see the note on `TreeOrd`), which bounds the tracker on both sides from the
+/// start and lets queries outside of or at the edges of the map skip the
+/// traversal comparisons entirely. A node that a higher level stopped at is not
+/// compared again on the lower levels.
+///
+/// The levels of new nodes are generated from a seedable pseudorandom number
+/// generator, so that the structure and therefore the exact comparisons of
+/// every operation are reproducible. [SkipListMap::new] uses a fixed seed.
+///
+/// Like [crate::search::tree_range], inverted ranges do not panic and are
+/// empty instead.
+///
+/// ```
+/// use tree_ord::collections::SkipListMap;
+///
+/// let key = |s: &str| s.as_bytes().to_vec();
+/// let mut map = SkipListMap::with_seed(42);
+/// for (i, s) in ["common/prefix/b", "common/prefix/a", "common/prefix/c"]
+///     .iter()
+///     .enumerate()
+/// {
+///     map.insert(key(s), i);
+/// }
+/// assert_eq!(map.get(&key("common/prefix/a")), Some(&1));
+/// assert_eq!(map.remove(&key("common/prefix/b")), Some(0));
+/// let range: Vec<_> = map
+///     .range(..=key("common/prefix/b"))
+///     .map(|(_, v)| *v)
+///     .collect();
+/// assert_eq!(range, [1]);
+/// ```
+#[derive(Clone)]
+pub struct SkipListMap<K, V> {
+    // the slots of removed nodes are `None` and are on the `free` list
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    // the first node on each level
+    head: Vec<usize>,
+    // the last node on the bottom level
+    tail: usize,
+    len: usize,
+    rng: u64,
+}
+
+impl<K, V> Default for SkipListMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SkipListMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> SkipListMap<K, V> {
+    /// Creates an empty map with a fixed seed for the levels
+    pub const fn new() -> Self {
+        Self::with_seed(0)
+    }
+
+    /// Creates an empty map whose levels are generated from `seed`
+    pub const fn with_seed(seed: u64) -> Self {
+        Self {
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: Vec::new(),
+            tail: NIL,
+            len: 0,
+            rng: seed,
+        }
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all entries. The state of the level generator is kept.
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.free.clear();
+        self.head.clear();
+        self.tail = NIL;
+        self.len = 0;
+    }
+
+    /// Returns the entry with the minimum key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let node = self.node(*self.head.first()?)?;
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns the entry with the maximum key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let node = self.node(self.tail)?;
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns an iterator over the entries in ascending order of keys
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            map: self,
+            next: self.next(HEAD, 0),
+            len: self.len,
+        }
+    }
+
+    fn node(&self, i: usize) -> Option<&Node<K, V>> {
+        self.nodes.get(i)?.as_ref()
+    }
+
+    fn next(&self, i: usize, level: usize) -> usize {
+        if i == HEAD {
+            self.head.get(level).copied().unwrap_or(NIL)
+        } else {
+            self.nodes[i].as_ref().unwrap().next[level]
+        }
+    }
+
+    fn set_next(&mut self, i: usize, level: usize, next: usize) {
+        if i == HEAD {
+            self.head[level] = next;
+        } else {
+            self.nodes[i].as_mut().unwrap().next[level] = next;
+        }
+    }
+
+    /// Returns the level of a new node, which is `n` with a probability of
+    /// `(1/4)^(n - 1) * (3/4)`
+    fn random_level(&mut self) -> usize {
+        // splitmix64
+        self.rng = self.rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.rng;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        ((z.trailing_zeros() as usize) / 2 + 1).min(MAX_LEVEL)
+    }
+
+    /// Searches for the first node that is not less than `query`. Fills
+    /// `update` with the last node (or `HEAD`) before the query on each level
+    /// and returns the node along with whether it is equal to the query.
+    fn search<Q>(&self, query: &Q, update: &mut [usize; MAX_LEVEL]) -> (usize, bool)
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        update.fill(HEAD);
+        let first = self.next(HEAD, 0);
+        let first_node = match self.node(first) {
+            Some(node) => node,
+            None => return (NIL, false),
+        };
+        let mut tracker = K::Tracker::new();
+        let first_cmp = first_node.key.tree_cmp(query, &mut tracker);
+        if first_cmp != Less {
+            return (first, first_cmp == Equal)
+        }
+        let tail_cmp = if self.tail == first {
+            Less
+        } else {
+            self.node(self.tail)
+                .unwrap()
+                .key
+                .tree_cmp(query, &mut tracker)
+        };
+        // the last node that was not `Less`, which is not compared again when
+        // lower levels reach it
+        let mut stop = (self.tail, tail_cmp);
+        let mut x = HEAD;
+        for level in (0..self.head.len()).rev() {
+            loop {
+                let n = self.next(x, level);
+                if n == NIL {
+                    break
+                }
+                let cmp = if n == first {
+                    Less
+                } else if n == stop.0 {
+                    stop.1
+                } else if tail_cmp != Greater {
+                    // everything before the tail is less
+                    Less
+                } else {
+                    self.node(n).unwrap().key.tree_cmp(query, &mut tracker)
+                };
+                if cmp == Less {
+                    x = n;
+                } else {
+                    stop = (n, cmp);
+                    break
+                }
+            }
+            update[level] = x;
+        }
+        let n = self.next(x, 0);
+        (n, (n != NIL) && (stop == (n, Equal)))
+    }
+
+    fn find<Q>(&self, query: &Q) -> Option<usize>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let mut update = [HEAD; MAX_LEVEL];
+        match self.search(query, &mut update) {
+            (i, true) => Some(i),
+            _ => None,
+        }
+    }
+
+    /// Returns a reference to the value of the key equal to `query`
+    pub fn get<Q>(&self, query: &Q) -> Option<&V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.find(query).map(|i| &self.node(i).unwrap().value)
+    }
+
+    /// Returns the entry of the key equal to `query`
+    pub fn get_key_value<Q>(&self, query: &Q) -> Option<(&K, &V)>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.find(query).map(|i| {
+            let node = self.node(i).unwrap();
+            (&node.key, &node.value)
+        })
+    }
+
+    pub fn contains_key<Q>(&self, query: &Q) -> bool
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.find(query).is_some()
+    }
+
+    /// Returns a mutable reference to the value of the key equal to `query`
+    pub fn get_mut<Q>(&mut self, query: &Q) -> Option<&mut V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let i = self.find(query)?;
+        Some(&mut self.nodes[i].as_mut().unwrap().value)
+    }
+
+    /// Removes the entry of the key equal to `query`, returning its value
+    pub fn remove<Q>(&mut self, query: &Q) -> Option<V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.remove_entry(query).map(|(_, v)| v)
+    }
+
+    /// Removes the entry of the key equal to `query` and returns it
+    pub fn remove_entry<Q>(&mut self, query: &Q) -> Option<(K, V)>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let mut update = [HEAD; MAX_LEVEL];
+        let i = match self.search(query, &mut update) {
+            (i, true) => i,
+            _ => return None,
+        };
+        let node = self.nodes[i].take().unwrap();
+        for (level, next) in node.next.iter().enumerate() {
+            self.set_next(update[level], level, *next);
+        }
+        while self.head.last() == Some(&NIL) {
+            self.head.pop();
+        }
+        if self.tail == i {
+            self.tail = if update[0] == HEAD { NIL } else { update[0] };
+        }
+        self.free.push(i);
+        self.len -= 1;
+        Some((node.key, node.value))
+    }
+
+    /// Returns an iterator over the entries with keys within `range`, in
+    /// ascending order. The start and end bounds are each found with their own
+    /// tracker.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let mut update = [HEAD; MAX_LEVEL];
+        let empty = Range {
+            map: self,
+            next: NIL,
+            last: NIL,
+        };
+        // the last node within the end bound
+        let last = match range.end_bound() {
+            Bound::Included(q) => match self.search(q, &mut update) {
+                (i, true) => i,
+                _ => update[0],
+            },
+            Bound::Excluded(q) => {
+                self.search(q, &mut update);
+                update[0]
+            }
+            Bound::Unbounded => self.tail,
+        };
+        if (last == HEAD) || (last == NIL) {
+            return empty
+        }
+        let next = match range.start_bound() {
+            Bound::Included(q) => self.search(q, &mut update).0,
+            Bound::Excluded(q) => match self.search(q, &mut update) {
+                (i, true) => self.next(i, 0),
+                (i, false) => i,
+            },
+            Bound::Unbounded => self.next(HEAD, 0),
+        };
+        match (self.node(next), self.node(last)) {
+            (Some(first), Some(last_node)) if first.key <= last_node.key => Range {
+                map: self,
+                next,
+                last,
+            },
+            _ => empty,
+        }
+    }
+}
+
+impl<K: TreeOrd, V> SkipListMap<K, V> {
+    /// Inserts an entry, returning the previous value if an equal key was
+    /// already in the map. The key in the map is not updated in that case.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut update = [HEAD; MAX_LEVEL];
+        if let (i, true) = self.search(&key, &mut update) {
+            return Some(mem::replace(
+                &mut self.nodes[i].as_mut().unwrap().value,
+                value,
+            ));
+        }
+        let level = self.random_level();
+        while self.head.len() < level {
+            self.head.push(NIL);
+        }
+        let next = (0..level).map(|l| self.next(update[l], l)).collect();
+        let node = Some(Node { key, value, next });
+        let i = match self.free.pop() {
+            Some(i) => {
+                self.nodes[i] = node;
+                i
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        };
+        for (l, prev) in update.iter().enumerate().take(level) {
+            self.set_next(*prev, l, i);
+        }
+        if self.next(i, 0) == NIL {
+            self.tail = i;
+        }
+        self.len += 1;
+        None
+    }
+}
+
+impl<K: TreeOrd<Q>, V, Q: ?Sized> TreeOrdSearchable<Q> for SkipListMap<K, V> {
+    type Item = V;
+    type RangeIter<'a>
+        = Range<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+
+    fn tree_find(&self, query: &Q) -> Option<&V> {
+        self.get(query)
+    }
+
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> Range<'_, K, V> {
+        self.range(range)
+    }
+}
+
+impl<K: TreeOrd, V> FromIterator<(K, V)> for SkipListMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl<K: TreeOrd, V> Extend<(K, V)> for SkipListMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SkipListMap<K, V> {
+    type IntoIter = Iter<'a, K, V>;
+    type Item = (&'a K, &'a V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the entries of a [SkipListMap] in ascending order of keys
+#[derive(Debug)]
+pub struct Iter<'a, K, V> {
+    map: &'a SkipListMap<K, V>,
+    next: usize,
+    len: usize,
+}
+
+impl<'a, K, V> Clone for Iter<'a, K, V> {
+    fn clone(&self) -> Self {
+        Self { ..*self }
+    }
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.map.node(self.next)?;
+        self.next = node.next[0];
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+/// An iterator over a range of entries of a [SkipListMap], see
+/// [SkipListMap::range]
+#[derive(Debug)]
+pub struct Range<'a, K, V> {
+    map: &'a SkipListMap<K, V>,
+    next: usize,
+    // the last node of the range
+    last: usize,
+}
+
+impl<'a, K, V> Clone for Range<'a, K, V> {
+    fn clone(&self) -> Self {
+        Self { ..*self }
+    }
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.map.node(self.next)?;
+        self.next = if self.next == self.last {
+            NIL
+        } else {
+            node.next[0]
+        };
+        Some((&node.key, &node.value))
+    }
+}
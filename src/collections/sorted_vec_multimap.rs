@@ -0,0 +1,269 @@
+use alloc::vec::Vec;
+use core::{
+    cmp::Ordering::*,
+    iter::Zip,
+    mem,
+    ops::{Range, RangeBounds},
+    slice,
+};
+
+use crate::{
+    merge::tree_sort,
+    search::{
+        tree_equal_range, tree_gallop_search_by, tree_range, tree_upper_bound, TreeOrdSearchable,
+    },
+    KeyValue, Tracker, TreeOrd,
+};
+
+/// An ordered multimap backed by a `Vec` of keys sorted with duplicates and a
+/// parallel `Vec` of values
+///
+/// The values of equal keys form a contiguous group in insertion order, so
+/// the group of a key is returned as a slice found by [tree_equal_range]. That
+/// search carries a single tracker from the lower bound of the group into the
+/// upper bound, so large groups of long keys do not compare the prefixes that
+/// are shared around the group again. Inserts and removes shift the entries
+/// after them like `Vec::insert` and `Vec::drain`, so this is best for maps
+/// that are read much more than they are written.
+///
+/// ```
+/// use tree_ord::collections::SortedVecMultiMap;
+///
+/// let mut map = SortedVecMultiMap::new();
+/// map.insert(b"shared/prefix/b".to_vec(), 0);
+/// map.insert(b"shared/prefix/a".to_vec(), 1);
+/// map.insert(b"shared/prefix/b".to_vec(), 2);
+/// assert_eq!(map.len(), 3);
+/// assert_eq!(map.get_all(&b"shared/prefix/b".to_vec()), &[0, 2]);
+/// let groups: Vec<_> = map.groups().map(|(_, values)| values).collect();
+/// assert_eq!(groups, [&[1][..], &[0, 2][..]]);
+/// assert_eq!(map.remove_key(&b"shared/prefix/b".to_vec()), [0, 2]);
+/// assert!(map.get_all(&b"shared/prefix/b".to_vec()).is_empty());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortedVecMultiMap<K, V> {
+    keys: Vec<K>,
+    values: Vec<V>,
+}
+
+impl<K, V> Default for SortedVecMultiMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> SortedVecMultiMap<K, V> {
+    /// Creates an empty map
+    pub const fn new() -> Self {
+        Self {
+            keys: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Returns the number of entries, counting every value of equal keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Removes all entries
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.values.clear();
+    }
+
+    /// Returns the keys in ascending order, with a key for every entry
+    pub fn keys(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Returns the values in the order of their keys
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    /// Returns the values in the order of their keys
+    pub fn values_mut(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    /// Returns an iterator over the entries in ascending order of keys, with
+    /// the entries of equal keys in insertion order
+    pub fn iter(&self) -> Zip<slice::Iter<'_, K>, slice::Iter<'_, V>> {
+        self.keys.iter().zip(self.values.iter())
+    }
+
+    /// Returns an iterator over the groups of equal keys in ascending order,
+    /// yielding the first key of each group with the values of the group
+    pub fn groups(&self) -> Groups<'_, K, V> {
+        Groups {
+            keys: &self.keys,
+            values: &self.values,
+        }
+    }
+}
+
+impl<K: TreeOrd, V> SortedVecMultiMap<K, V> {
+    /// Creates a map from `entries` in any order, with [tree_sort]. The sort
+    /// is stable, so the values of equal keys keep their order in `entries`.
+    pub fn from_unsorted(entries: Vec<(K, V)>) -> Self {
+        let mut entries: Vec<KeyValue<K, V>> = entries.into_iter().map(KeyValue::from).collect();
+        tree_sort(&mut entries);
+        let (keys, values) = entries.into_iter().map(KeyValue::into_pair).unzip();
+        Self { keys, values }
+    }
+
+    /// Inserts an entry after the entries of any equal keys
+    pub fn insert(&mut self, key: K, value: V) {
+        let i = tree_upper_bound(&self.keys, &key);
+        self.keys.insert(i, key);
+        self.values.insert(i, value);
+    }
+}
+
+impl<K, V> SortedVecMultiMap<K, V> {
+    /// Returns the range of indices of the entries with keys equal to `query`,
+    /// found with [tree_equal_range]
+    pub fn equal_range<Q>(&self, query: &Q) -> Range<usize>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        tree_equal_range(&self.keys, query)
+    }
+
+    pub fn contains_key<Q>(&self, query: &Q) -> bool
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        !self.equal_range(query).is_empty()
+    }
+
+    /// Returns the values of the keys equal to `query` in insertion order
+    pub fn get_all<Q>(&self, query: &Q) -> &[V]
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        &self.values[self.equal_range(query)]
+    }
+
+    /// Returns the values of the keys equal to `query` in insertion order
+    pub fn get_all_mut<Q>(&mut self, query: &Q) -> &mut [V]
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let range = self.equal_range(query);
+        &mut self.values[range]
+    }
+
+    /// Removes the entries with keys equal to `query`, returning their values
+    /// in insertion order
+    pub fn remove_key<Q>(&mut self, query: &Q) -> Vec<V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let range = self.equal_range(query);
+        self.keys.drain(range.clone());
+        self.values.drain(range).collect()
+    }
+
+    /// Returns an iterator over the entries with keys within `range` in
+    /// ascending order, found with [tree_range]
+    pub fn range<Q, R>(&self, range: R) -> Zip<slice::Iter<'_, K>, slice::Iter<'_, V>>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let range = tree_range(&self.keys, range);
+        self.keys[range.clone()]
+            .iter()
+            .zip(self.values[range].iter())
+    }
+}
+
+impl<K: TreeOrd<Q>, V, Q: ?Sized> TreeOrdSearchable<Q> for SortedVecMultiMap<K, V> {
+    type Item = [V];
+    type RangeIter<'a>
+        = Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>
+    where
+        K: 'a,
+        V: 'a;
+
+    /// Returns the values of the keys equal to `query`, or `None` if there
+    /// are none
+    fn tree_find(&self, query: &Q) -> Option<&[V]> {
+        let values = self.get_all(query);
+        (!values.is_empty()).then_some(values)
+    }
+
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> Self::RangeIter<'_> {
+        self.range(range)
+    }
+}
+
+impl<K: TreeOrd, V> FromIterator<(K, V)> for SortedVecMultiMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self::from_unsorted(iter.into_iter().collect())
+    }
+}
+
+impl<K: TreeOrd, V> Extend<(K, V)> for SortedVecMultiMap<K, V> {
+    /// Appends the entries and then sorts the whole map, so the new values of
+    /// a key come after the values already in the map
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let keys = mem::take(&mut self.keys);
+        let values = mem::take(&mut self.values);
+        let mut entries: Vec<(K, V)> = keys.into_iter().zip(values).collect();
+        entries.extend(iter);
+        *self = Self::from_unsorted(entries);
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SortedVecMultiMap<K, V> {
+    type IntoIter = Zip<slice::Iter<'a, K>, slice::Iter<'a, V>>;
+    type Item = (&'a K, &'a V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the groups of equal keys of a [SortedVecMultiMap], see
+/// [SortedVecMultiMap::groups]
+///
+/// The first key of a group is compared with the following keys using a
+/// single tracker, and the end of the group is found by galloping, so small
+/// groups take few comparisons and large groups take a logarithmic number.
+#[derive(Debug, Clone)]
+pub struct Groups<'a, K, V> {
+    keys: &'a [K],
+    values: &'a [V],
+}
+
+impl<'a, K: TreeOrd, V> Iterator for Groups<'a, K, V> {
+    type Item = (&'a K, &'a [V]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (first, rest) = self.keys.split_first()?;
+        let mut tracker = K::Tracker::new();
+        let n = match tree_gallop_search_by(rest, 0, |k| match k.tree_cmp(first, &mut tracker) {
+            Equal => Less,
+            Less | Greater => Greater,
+        }) {
+            Ok(n) | Err(n) => n,
+        };
+        let (values, rest_values) = self.values.split_at(n + 1);
+        self.keys = &rest[n..];
+        self.values = rest_values;
+        Some((first, values))
+    }
+}
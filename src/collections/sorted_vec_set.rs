@@ -0,0 +1,253 @@
+use alloc::vec::{self, Vec};
+use core::{ops::RangeBounds, slice};
+
+use crate::{
+    merge::{tree_dedup, tree_sort},
+    search::{tree_binary_search_with_bounds, tree_range, TreeOrdSearchable},
+    TreeOrd,
+};
+
+/// An ordered set backed by a sorted and deduplicated `Vec`
+///
+/// Every lookup is a binary search with a tracker that first compares the
+/// minimum and maximum keys (see [tree_binary_search_with_bounds]), and bulk
+/// construction uses [tree_sort] and [tree_dedup], so shared prefixes are
+/// skipped throughout. Inserts and removes shift the elements after them like
+/// `Vec::insert` and `Vec::remove`, so this is best for sets that are read
+/// much more than they are written.
+///
+/// Like `BTreeSet`, inserting or extending with a key equal to one already in
+/// the set keeps the key in the set, and building from an iterator keeps the
+/// last of equal keys. Like [crate::search::tree_range], inverted ranges do
+/// not panic and are empty instead.
+///
+/// ```
+/// use tree_ord::collections::SortedVecSet;
+///
+/// let mut set = SortedVecSet::from_unsorted(vec![
+///     b"shared/prefix/c".to_vec(),
+///     b"shared/prefix/a".to_vec(),
+///     b"shared/prefix/c".to_vec(),
+/// ]);
+/// assert_eq!(set.len(), 2);
+/// assert!(set.insert(b"shared/prefix/b".to_vec()));
+/// assert!(!set.insert(b"shared/prefix/b".to_vec()));
+/// assert!(set.contains(&b"shared/prefix/a".to_vec()));
+/// assert!(set.remove(&b"shared/prefix/a".to_vec()));
+/// let range: Vec<_> = set.range(b"shared/prefix/b".to_vec()..).collect();
+/// assert_eq!(range, [b"shared/prefix/b", b"shared/prefix/c"]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SortedVecSet<K> {
+    keys: Vec<K>,
+}
+
+impl<K> Default for SortedVecSet<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> SortedVecSet<K> {
+    /// Creates an empty set
+    pub const fn new() -> Self {
+        Self { keys: Vec::new() }
+    }
+
+    /// Creates a set from `keys` without sorting or deduplicating them. The
+    /// keys must be strictly ascending, otherwise lookups may return wrong
+    /// results. This is checked with a `debug_assert`.
+    pub fn from_sorted_unchecked(keys: Vec<K>) -> Self
+    where
+        K: Ord,
+    {
+        debug_assert!(keys.windows(2).all(|w| w[0] < w[1]));
+        Self { keys }
+    }
+
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Removes all keys
+    pub fn clear(&mut self) {
+        self.keys.clear()
+    }
+
+    /// Returns the keys in ascending order
+    pub fn as_slice(&self) -> &[K] {
+        &self.keys
+    }
+
+    /// Returns the sorted and deduplicated `Vec` of keys
+    pub fn into_vec(self) -> Vec<K> {
+        self.keys
+    }
+
+    /// Returns an iterator over the keys in ascending order
+    pub fn iter(&self) -> slice::Iter<'_, K> {
+        self.keys.iter()
+    }
+
+    /// Returns the minimum key
+    pub fn first(&self) -> Option<&K> {
+        self.keys.first()
+    }
+
+    /// Returns the maximum key
+    pub fn last(&self) -> Option<&K> {
+        self.keys.last()
+    }
+}
+
+impl<K: TreeOrd> SortedVecSet<K> {
+    /// Creates a set from `keys` in any order, with [tree_sort] and
+    /// [tree_dedup]. Like `BTreeSet::from_iter`, the last of equal keys is
+    /// kept.
+    pub fn from_unsorted(mut keys: Vec<K>) -> Self {
+        // the sort is stable, so this puts the last of equal keys first
+        keys.reverse();
+        tree_sort(&mut keys);
+        tree_dedup(&mut keys);
+        Self { keys }
+    }
+
+    /// Inserts `key` if no equal key is in the set, returning if it was
+    /// inserted
+    pub fn insert(&mut self, key: K) -> bool {
+        match tree_binary_search_with_bounds(&self.keys, &key) {
+            Ok(_) => false,
+            Err(i) => {
+                self.keys.insert(i, key);
+                true
+            }
+        }
+    }
+
+    /// Inserts `key`, replacing and returning an equal key if there was one
+    pub fn replace(&mut self, key: K) -> Option<K> {
+        match tree_binary_search_with_bounds(&self.keys, &key) {
+            Ok(i) => Some(core::mem::replace(&mut self.keys[i], key)),
+            Err(i) => {
+                self.keys.insert(i, key);
+                None
+            }
+        }
+    }
+}
+
+impl<K> SortedVecSet<K> {
+    /// Returns the index of the key equal to `query`, or the index where it
+    /// would be inserted
+    pub fn search<Q>(&self, query: &Q) -> Result<usize, usize>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        tree_binary_search_with_bounds(&self.keys, query)
+    }
+
+    pub fn contains<Q>(&self, query: &Q) -> bool
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.search(query).is_ok()
+    }
+
+    /// Returns the key equal to `query`
+    pub fn get<Q>(&self, query: &Q) -> Option<&K>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.search(query).ok().map(|i| &self.keys[i])
+    }
+
+    /// Removes the key equal to `query`, returning if there was one
+    pub fn remove<Q>(&mut self, query: &Q) -> bool
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.take(query).is_some()
+    }
+
+    /// Removes and returns the key equal to `query`
+    pub fn take<Q>(&mut self, query: &Q) -> Option<K>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.search(query).ok().map(|i| self.keys.remove(i))
+    }
+
+    /// Returns an iterator over the keys within `range` in ascending order,
+    /// found with [tree_range]
+    pub fn range<Q, R>(&self, range: R) -> slice::Iter<'_, K>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        self.keys[tree_range(&self.keys, range)].iter()
+    }
+}
+
+impl<K: TreeOrd<Q>, Q: ?Sized> TreeOrdSearchable<Q> for SortedVecSet<K> {
+    type Item = K;
+    type RangeIter<'a>
+        = slice::Iter<'a, K>
+    where
+        K: 'a;
+
+    fn tree_find(&self, query: &Q) -> Option<&K> {
+        self.get(query)
+    }
+
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> slice::Iter<'_, K> {
+        self.range(range)
+    }
+}
+
+impl<K: TreeOrd> FromIterator<K> for SortedVecSet<K> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        Self::from_unsorted(iter.into_iter().collect())
+    }
+}
+
+impl<K: TreeOrd> Extend<K> for SortedVecSet<K> {
+    /// Appends the keys and then sorts and deduplicates the whole set, so
+    /// keys already in the set are kept over equal new keys
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        let len = self.keys.len();
+        self.keys.extend(iter);
+        if self.keys.len() != len {
+            tree_sort(&mut self.keys);
+            tree_dedup(&mut self.keys);
+        }
+    }
+}
+
+impl<K> IntoIterator for SortedVecSet<K> {
+    type IntoIter = vec::IntoIter<K>;
+    type Item = K;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.into_iter()
+    }
+}
+
+impl<'a, K> IntoIterator for &'a SortedVecSet<K> {
+    type IntoIter = slice::Iter<'a, K>;
+    type Item = &'a K;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.keys.iter()
+    }
+}
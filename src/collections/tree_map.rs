@@ -0,0 +1,658 @@
+use alloc::{boxed::Box, vec::Vec};
+use core::{
+    cmp::{max, Ordering},
+    fmt, mem,
+    ops::{Bound, RangeBounds},
+    ptr,
+};
+
+use Ordering::*;
+
+use crate::{search::TreeOrdSearchable, Tracker, TreeOrd};
+
+type Link<K, V> = Option<Box<Node<K, V>>>;
+
+#[derive(Clone)]
+struct Node<K, V> {
+    key: K,
+    value: V,
+    height: u32,
+    left: Link<K, V>,
+    right: Link<K, V>,
+}
+
+impl<K, V> Node<K, V> {
+    fn leaf(key: K, value: V) -> Box<Self> {
+        Box::new(Self {
+            key,
+            value,
+            height: 1,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn leftmost(&self) -> &Self {
+        let mut node = self;
+        while let Some(ref left) = node.left {
+            node = left;
+        }
+        node
+    }
+
+    fn rightmost(&self) -> &Self {
+        let mut node = self;
+        while let Some(ref right) = node.right {
+            node = right;
+        }
+        node
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Node<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Node")
+            .field(&self.key)
+            .field(&self.value)
+            .finish()
+    }
+}
+
+fn height<K, V>(node: &Link<K, V>) -> u32 {
+    node.as_ref().map_or(0, |n| n.height)
+}
+
+fn update_height<K, V>(node: &mut Node<K, V>) {
+    node.height = max(height(&node.left), height(&node.right)) + 1;
+}
+
+fn rotate_left<K, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut right = node.right.take().unwrap();
+    node.right = right.left.take();
+    update_height(&mut node);
+    right.left = Some(node);
+    update_height(&mut right);
+    right
+}
+
+fn rotate_right<K, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    let mut left = node.left.take().unwrap();
+    node.left = left.right.take();
+    update_height(&mut node);
+    left.right = Some(node);
+    update_height(&mut left);
+    left
+}
+
+/// Restores the AVL invariant at `node`, assuming that the subtrees differ in
+/// height by at most 2 and are balanced themselves
+fn balance<K, V>(mut node: Box<Node<K, V>>) -> Box<Node<K, V>> {
+    update_height(&mut node);
+    let (l, r) = (height(&node.left), height(&node.right));
+    if l > (r + 1) {
+        let left = node.left.take().unwrap();
+        node.left = Some(if height(&left.left) < height(&left.right) {
+            rotate_left(left)
+        } else {
+            left
+        });
+        rotate_right(node)
+    } else if r > (l + 1) {
+        let right = node.right.take().unwrap();
+        node.right = Some(if height(&right.right) < height(&right.left) {
+            rotate_right(right)
+        } else {
+            right
+        });
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+/// Removes the minimum node of the subtree, returning it and the rest of the
+/// subtree
+fn pop_min<K, V>(mut node: Box<Node<K, V>>) -> (Box<Node<K, V>>, Link<K, V>) {
+    match node.left.take() {
+        None => {
+            let rest = node.right.take();
+            (node, rest)
+        }
+        Some(left) => {
+            let (min, rest) = pop_min(left);
+            node.left = rest;
+            (min, Some(balance(node)))
+        }
+    }
+}
+
+/// How a single operation descends the tree. The root is compared first and
+/// then the minimum or maximum key on the side that the query is on, as
+/// recommended in the docs of `TreeOrd`, so that the tracker is bounded on
+/// both sides as early as possible even if the queries are at one edge of the
+/// map (such as with ascending inserts). If the query turns out to be outside
+/// of or at the edges of the map, the rest of the descent needs no
+/// comparisons at all.
+enum Route<T> {
+    /// The query is between the minimum and maximum keys, the ordering of the
+    /// root is kept for the first step of the descent
+    Between { root: Option<Ordering>, tracker: T },
+    /// Every key is less than the query
+    AfterMax,
+    /// The query is equal to the maximum key, which is found by always
+    /// descending right
+    AtMax,
+    /// Every key is greater than the query
+    BeforeMin,
+    /// The query is equal to the minimum key
+    AtMin,
+}
+
+impl<T: Tracker> Route<T> {
+    fn new<K, V, Q>(root: &Node<K, V>, query: &Q) -> Self
+    where
+        K: TreeOrd<Q, Tracker = T>,
+        Q: ?Sized,
+    {
+        let mut tracker = T::new();
+        let root_cmp = root.key.tree_cmp(query, &mut tracker);
+        let edge_cmp = match root_cmp {
+            Equal => Equal,
+            Less => match root.right {
+                None => Less,
+                Some(ref right) => right.rightmost().key.tree_cmp(query, &mut tracker),
+            },
+            Greater => match root.left {
+                None => Greater,
+                Some(ref left) => left.leftmost().key.tree_cmp(query, &mut tracker),
+            },
+        };
+        match (root_cmp, edge_cmp) {
+            (Less, Less) => Route::AfterMax,
+            (Less, Equal) => Route::AtMax,
+            (Greater, Greater) => Route::BeforeMin,
+            (Greater, Equal) => Route::AtMin,
+            _ => Route::Between {
+                root: Some(root_cmp),
+                tracker,
+            },
+        }
+    }
+
+    /// Returns the ordering of the key of `node` relative to the query. The
+    /// nodes must be visited in the order of a descent from the root, with
+    /// the direction taken from the previous ordering.
+    fn cmp<K, V, Q>(&mut self, node: &Node<K, V>, query: &Q) -> Ordering
+    where
+        K: TreeOrd<Q, Tracker = T>,
+        Q: ?Sized,
+    {
+        match self {
+            Route::Between { root, tracker } => match root.take() {
+                Some(root_cmp) => root_cmp,
+                None => node.key.tree_cmp(query, tracker),
+            },
+            Route::AfterMax => Less,
+            Route::AtMax => {
+                if node.right.is_none() {
+                    // anything descended into from here is less
+                    *self = Route::AfterMax;
+                    Equal
+                } else {
+                    Less
+                }
+            }
+            Route::BeforeMin => Greater,
+            Route::AtMin => {
+                if node.left.is_none() {
+                    *self = Route::BeforeMin;
+                    Equal
+                } else {
+                    Greater
+                }
+            }
+        }
+    }
+}
+
+fn insert_rec<K: TreeOrd, V>(
+    node: Link<K, V>,
+    key: K,
+    value: V,
+    route: &mut Route<K::Tracker>,
+) -> (Box<Node<K, V>>, Option<V>) {
+    let mut node = match node {
+        None => return (Node::leaf(key, value), None),
+        Some(node) => node,
+    };
+    match route.cmp(&node, &key) {
+        Equal => {
+            let old = mem::replace(&mut node.value, value);
+            (node, Some(old))
+        }
+        Less => {
+            let (right, old) = insert_rec(node.right.take(), key, value, route);
+            node.right = Some(right);
+            (balance(node), old)
+        }
+        Greater => {
+            let (left, old) = insert_rec(node.left.take(), key, value, route);
+            node.left = Some(left);
+            (balance(node), old)
+        }
+    }
+}
+
+fn remove_rec<K, V, Q>(
+    slot: &mut Link<K, V>,
+    query: &Q,
+    route: &mut Route<K::Tracker>,
+) -> Option<(K, V)>
+where
+    K: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    let node = slot.as_mut()?;
+    let res = match route.cmp(node, query) {
+        Less => remove_rec(&mut node.right, query, route),
+        Greater => remove_rec(&mut node.left, query, route),
+        Equal => {
+            let mut node = slot.take().unwrap();
+            *slot = match (node.left.take(), node.right.take()) {
+                (None, rest) | (rest, None) => rest,
+                (Some(left), Some(right)) => {
+                    let (mut succ, rest) = pop_min(right);
+                    succ.left = Some(left);
+                    succ.right = rest;
+                    Some(balance(succ))
+                }
+            };
+            return Some((node.key, node.value))
+        }
+    };
+    if res.is_some() {
+        *slot = Some(balance(slot.take().unwrap()));
+    }
+    res
+}
+
+/// An ordered map on an AVL tree, where every operation threads a single
+/// `Tracker` through all of its comparisons
+///
+/// Each operation first compares the root and then the minimum or maximum key
+/// on the side of the query (see the note on `TreeOrd`), before descending
+/// with the tracker. For keys with long shared prefixes, such as paths or
+/// composite byte keys, this makes each comparison after the first few skip
+/// most of the key. For small keys with `Tracker = ()`, `BTreeMap` is faster
+/// because of its cache friendly nodes.
+///
+/// Like [crate::search::tree_range], inverted ranges do not panic and are
+/// empty instead.
+///
+/// ```
+/// use tree_ord::collections::TreeMap;
+///
+/// let key = |s: &str| s.as_bytes().to_vec();
+/// let mut map = TreeMap::new();
+/// map.insert(key("common/prefix/b"), 2);
+/// map.insert(key("common/prefix/a"), 1);
+/// map.insert(key("common/prefix/c"), 3);
+/// assert_eq!(map.insert(key("common/prefix/b"), 4), Some(2));
+/// assert_eq!(map.get(&key("common/prefix/b")), Some(&4));
+/// assert_eq!(map.remove(&key("common/prefix/a")), Some(1));
+/// let range: Vec<_> = map
+///     .range(key("common/prefix/b")..)
+///     .map(|(_, v)| *v)
+///     .collect();
+/// assert_eq!(range, [4, 3]);
+/// ```
+#[derive(Clone)]
+pub struct TreeMap<K, V> {
+    root: Link<K, V>,
+    len: usize,
+}
+
+impl<K, V> Default for TreeMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for TreeMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> TreeMap<K, V> {
+    /// Creates an empty map
+    pub const fn new() -> Self {
+        Self { root: None, len: 0 }
+    }
+
+    /// Returns the number of entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all entries
+    pub fn clear(&mut self) {
+        self.root = None;
+        self.len = 0;
+    }
+
+    /// Returns the entry with the minimum key
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        let node = self.root.as_ref()?.leftmost();
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns the entry with the maximum key
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        let node = self.root.as_ref()?.rightmost();
+        Some((&node.key, &node.value))
+    }
+
+    /// Returns an iterator over the entries in ascending order of keys
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            len: self.len,
+        };
+        push_left(&mut iter.stack, self.root.as_deref());
+        iter
+    }
+}
+
+impl<K: TreeOrd, V> TreeMap<K, V> {
+    /// Inserts an entry, returning the previous value if an equal key was
+    /// already in the map. The key in the map is not updated in that case.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let mut route = match self.root {
+            None => {
+                self.root = Some(Node::leaf(key, value));
+                self.len = 1;
+                return None
+            }
+            Some(ref root) => Route::new(root, &key),
+        };
+        let (root, old) = insert_rec(self.root.take(), key, value, &mut route);
+        self.root = Some(root);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+}
+
+impl<K, V> TreeMap<K, V> {
+    fn find<Q>(&self, query: &Q) -> Option<&Node<K, V>>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let root = self.root.as_deref()?;
+        let mut route = Route::new(root, query);
+        let mut node = Some(root);
+        while let Some(n) = node {
+            node = match route.cmp(n, query) {
+                Less => n.right.as_deref(),
+                Equal => return Some(n),
+                Greater => n.left.as_deref(),
+            };
+        }
+        None
+    }
+
+    /// Returns a reference to the value of the key equal to `query`
+    pub fn get<Q>(&self, query: &Q) -> Option<&V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.find(query).map(|n| &n.value)
+    }
+
+    /// Returns the entry of the key equal to `query`
+    pub fn get_key_value<Q>(&self, query: &Q) -> Option<(&K, &V)>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.find(query).map(|n| (&n.key, &n.value))
+    }
+
+    pub fn contains_key<Q>(&self, query: &Q) -> bool
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.find(query).is_some()
+    }
+
+    /// Returns a mutable reference to the value of the key equal to `query`
+    pub fn get_mut<Q>(&mut self, query: &Q) -> Option<&mut V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let mut route = Route::new(self.root.as_deref()?, query);
+        let mut node = self.root.as_deref_mut();
+        while let Some(n) = node {
+            node = match route.cmp(n, query) {
+                Less => n.right.as_deref_mut(),
+                Equal => return Some(&mut n.value),
+                Greater => n.left.as_deref_mut(),
+            };
+        }
+        None
+    }
+
+    /// Removes the entry of the key equal to `query`, returning its value
+    pub fn remove<Q>(&mut self, query: &Q) -> Option<V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        self.remove_entry(query).map(|(_, v)| v)
+    }
+
+    /// Removes the entry of the key equal to `query` and returns it
+    pub fn remove_entry<Q>(&mut self, query: &Q) -> Option<(K, V)>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let mut route = Route::new(self.root.as_deref()?, query);
+        let res = remove_rec(&mut self.root, query, &mut route);
+        if res.is_some() {
+            self.len -= 1;
+        }
+        res
+    }
+
+    /// Returns an iterator over the entries with keys within `range`, in
+    /// ascending order. The start and end bounds are each found with their own
+    /// tracker.
+    pub fn range<Q, R>(&self, range: R) -> Range<'_, K, V>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let mut res = Range {
+            stack: Vec::new(),
+            last: None,
+        };
+        let root = match self.root.as_deref() {
+            Some(root) => root,
+            None => return res,
+        };
+        // the last node within the end bound
+        let last = match range.end_bound() {
+            Bound::Included(q) => self.last_before(q, |o| o != Greater),
+            Bound::Excluded(q) => self.last_before(q, |o| o == Less),
+            Bound::Unbounded => Some(root.rightmost()),
+        };
+        let last = match last {
+            Some(last) => last,
+            None => return res,
+        };
+        // the stack of nodes that a descent to the first node within the start
+        // bound went left at
+        match range.start_bound() {
+            Bound::Included(q) => self.first_after(&mut res.stack, q, |o| o != Less),
+            Bound::Excluded(q) => self.first_after(&mut res.stack, q, |o| o == Greater),
+            Bound::Unbounded => push_left(&mut res.stack, Some(root)),
+        }
+        match res.stack.last() {
+            Some(first) if first.key <= last.key => res.last = Some(last),
+            _ => res.stack.clear(),
+        }
+        res
+    }
+
+    fn first_after<'a, Q, F>(&'a self, stack: &mut Vec<&'a Node<K, V>>, query: &Q, go_left: F)
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+        F: Fn(Ordering) -> bool,
+    {
+        let mut node = self.root.as_deref();
+        let mut route = match node {
+            Some(root) => Route::new(root, query),
+            None => return,
+        };
+        while let Some(n) = node {
+            if go_left(route.cmp(n, query)) {
+                stack.push(n);
+                node = n.left.as_deref();
+            } else {
+                node = n.right.as_deref();
+            }
+        }
+    }
+
+    fn last_before<Q, F>(&self, query: &Q, go_right: F) -> Option<&Node<K, V>>
+    where
+        K: TreeOrd<Q>,
+        Q: ?Sized,
+        F: Fn(Ordering) -> bool,
+    {
+        let mut node = self.root.as_deref();
+        let mut route = Route::new(node?, query);
+        let mut res = None;
+        while let Some(n) = node {
+            if go_right(route.cmp(n, query)) {
+                res = Some(n);
+                node = n.right.as_deref();
+            } else {
+                node = n.left.as_deref();
+            }
+        }
+        res
+    }
+}
+
+impl<K: TreeOrd<Q>, V, Q: ?Sized> TreeOrdSearchable<Q> for TreeMap<K, V> {
+    type Item = V;
+    type RangeIter<'a>
+        = Range<'a, K, V>
+    where
+        K: 'a,
+        V: 'a;
+
+    fn tree_find(&self, query: &Q) -> Option<&V> {
+        self.get(query)
+    }
+
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> Range<'_, K, V> {
+        self.range(range)
+    }
+}
+
+impl<K: TreeOrd, V> FromIterator<(K, V)> for TreeMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl<K: TreeOrd, V> Extend<(K, V)> for TreeMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a TreeMap<K, V> {
+    type IntoIter = Iter<'a, K, V>;
+    type Item = (&'a K, &'a V);
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+fn push_left<'a, K, V>(stack: &mut Vec<&'a Node<K, V>>, mut node: Option<&'a Node<K, V>>) {
+    while let Some(n) = node {
+        stack.push(n);
+        node = n.left.as_deref();
+    }
+}
+
+/// Pops the next node of an in order traversal
+fn next_in_order<'a, K, V>(stack: &mut Vec<&'a Node<K, V>>) -> Option<&'a Node<K, V>> {
+    let node = stack.pop()?;
+    push_left(stack, node.right.as_deref());
+    Some(node)
+}
+
+/// An iterator over the entries of a [TreeMap] in ascending order of keys
+#[derive(Debug, Clone)]
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = next_in_order(&mut self.stack)?;
+        self.len -= 1;
+        Some((&node.key, &node.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for Iter<'a, K, V> {}
+
+/// An iterator over a range of entries of a [TreeMap], see [TreeMap::range]
+#[derive(Debug, Clone)]
+pub struct Range<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    // the last node of the range
+    last: Option<&'a Node<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Range<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = next_in_order(&mut self.stack)?;
+        if self.last.is_some_and(|last| ptr::eq(node, last)) {
+            self.stack.clear();
+        }
+        Some((&node.key, &node.value))
+    }
+}
@@ -0,0 +1,278 @@
+//! Deterministic generators of sorted corpora for benchmarking `TreeOrd` impls
+//!
+//! Uniformly random keys are a poor benchmark for `TreeOrd`, because random
+//! keys almost always diverge on their first element and there is no prefix
+//! to skip. The generators here make keys out of a few long "plateaus" of
+//! repeated values at rotated positions, so that neighboring keys of the
+//! sorted corpus share long prefixes and diverge at unpredictable positions,
+//! which is what tree searches over real data (paths, URLs, composite keys)
+//! look like. Every generator returns a sorted `Vec` that is a pure function
+//! of its parameters and the seed, so that benchmarks are reproducible across
+//! runs and platforms.
+//!
+//! ```
+//! use tree_ord::{corpus::ClusteredBytes, Tracker, TreeOrd};
+//!
+//! let space = ClusteredBytes {
+//!     count: 1 << 10,
+//!     max_len: 64,
+//!     plateau_levels: vec![128, 255],
+//! }
+//! .generate(0);
+//! assert!(space.windows(2).all(|w| w[0] <= w[1]));
+//! for query in &space {
+//!     let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+//!     let i = space
+//!         .binary_search_by(|key| key.tree_cmp(query, &mut tracker))
+//!         .unwrap();
+//!     assert_eq!(&space[i], query);
+//! }
+//! ```
+
+use alloc::{vec, vec::Vec};
+
+use rand_xoshiro::{
+    rand_core::{RngCore, SeedableRng},
+    Xoshiro128StarStar,
+};
+
+/// Returns the next random number below `n`, which must not be zero
+fn below(rng: &mut Xoshiro128StarStar, n: usize) -> usize {
+    (rng.next_u64() % (n as u64)) as usize
+}
+
+/// Generates a run of `len` elements that start as `base`. For each of the
+/// `levels` in order, a random number of elements from the front are set to
+/// the level and then the whole run is rotated by a random amount. Later
+/// levels overwrite parts of earlier plateaus, so a run has at most
+/// `levels.len() + 1` distinct values arranged in a few contiguous blocks
+/// (counting wrap around).
+fn clustered_run<T: Copy>(
+    rng: &mut Xoshiro128StarStar,
+    len: usize,
+    base: T,
+    levels: &[T],
+) -> Vec<T> {
+    let mut res = vec![base; len];
+    if len != 0 {
+        for level in levels {
+            for x in &mut res[..below(rng, len)] {
+                *x = *level;
+            }
+            res.rotate_left(below(rng, len));
+        }
+    }
+    res
+}
+
+/// Byte string keys made of zeros and plateaus
+///
+/// The lengths are uniform in `0..max_len`, so the corpus has many keys that
+/// are prefixes of each other. With few plateau levels, most keys share a
+/// prefix of zeros with their neighbors that is about as long as the shorter
+/// key, and the position of divergence is roughly uniform over that prefix.
+/// This is the best case for `TreeOrd` over `Ord`, because the shared
+/// prefixes grow as the search narrows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClusteredBytes {
+    /// The number of keys, including any duplicates
+    pub count: usize,
+    /// The exclusive upper bound on the key lengths, must not be zero
+    pub max_len: usize,
+    /// The byte values of the plateaus, the rest of the bytes are zeros
+    pub plateau_levels: Vec<u8>,
+}
+
+impl Default for ClusteredBytes {
+    fn default() -> Self {
+        Self {
+            count: 1 << 16,
+            max_len: 1 << 10,
+            plateau_levels: vec![u8::MAX / 2, u8::MAX],
+        }
+    }
+}
+
+impl ClusteredBytes {
+    /// Generates the sorted keys from `seed`
+    pub fn generate(&self, seed: u64) -> Vec<Vec<u8>> {
+        let mut rng = Xoshiro128StarStar::seed_from_u64(seed);
+        let mut res = Vec::with_capacity(self.count);
+        for _ in 0..self.count {
+            let len = below(&mut rng, self.max_len);
+            res.push(clustered_run(&mut rng, len, 0, &self.plateau_levels));
+        }
+        res.sort();
+        res
+    }
+}
+
+/// Integer string keys made of zeros and plateaus
+///
+/// This has the same shape as [ClusteredBytes] but with `u64` elements, which
+/// is what `Vec<T>` impls over non byte elements see. Each element comparison
+/// is more expensive relative to the bookkeeping of the tracker than for
+/// bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClusteredInts {
+    /// The number of keys, including any duplicates
+    pub count: usize,
+    /// The exclusive upper bound on the key lengths, must not be zero
+    pub max_len: usize,
+    /// The values of the plateaus, the rest of the elements are zeros
+    pub plateau_levels: Vec<u64>,
+}
+
+impl Default for ClusteredInts {
+    fn default() -> Self {
+        Self {
+            count: 1 << 16,
+            max_len: 1 << 7,
+            plateau_levels: vec![u64::MAX / 2, u64::MAX],
+        }
+    }
+}
+
+impl ClusteredInts {
+    /// Generates the sorted keys from `seed`
+    pub fn generate(&self, seed: u64) -> Vec<Vec<u64>> {
+        let mut rng = Xoshiro128StarStar::seed_from_u64(seed);
+        let mut res = Vec::with_capacity(self.count);
+        for _ in 0..self.count {
+            let len = below(&mut rng, self.max_len);
+            res.push(clustered_run(&mut rng, len, 0, &self.plateau_levels));
+        }
+        res.sort();
+        res
+    }
+}
+
+/// A dynamically nested key generated by [NestedSlices], which can be
+/// converted into concrete nested types through [FromNested]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Nested {
+    Leaf(u64),
+    Slice(Vec<Nested>),
+}
+
+/// Conversion from [Nested] into a concrete type with the same shape
+pub trait FromNested: Sized {
+    /// Returns `None` if the shape of `nested` does not match `Self`
+    fn from_nested(nested: Nested) -> Option<Self>;
+}
+
+macro_rules! impl_from_nested {
+    ($($t:ident)*) => {
+        $(
+            impl FromNested for $t {
+                fn from_nested(nested: Nested) -> Option<Self> {
+                    match nested {
+                        Nested::Leaf(x) => $t::try_from(x).ok(),
+                        Nested::Slice(_) => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_nested!(u8 u16 u32 u64 u128 usize);
+
+impl<T: FromNested> FromNested for Vec<T> {
+    fn from_nested(nested: Nested) -> Option<Self> {
+        match nested {
+            Nested::Leaf(_) => None,
+            Nested::Slice(v) => v.into_iter().map(T::from_nested).collect(),
+        }
+    }
+}
+
+/// Nested slice keys whose innermost slices are clustered runs
+///
+/// Each key has `depth` levels of slices. The outer levels have fixed widths,
+/// so that the keys differ only within their innermost runs, and those runs
+/// are generated like the keys of [ClusteredInts]. This stresses the trackers
+/// of nested `TreeOrd` impls: the outer trackers skip whole subslices that
+/// are proven equal while the inner trackers skip within the subslice where
+/// the keys diverge.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NestedSlices {
+    /// The number of keys, including any duplicates
+    pub count: usize,
+    /// The number of levels of slices in each key, must not be zero
+    pub depth: usize,
+    /// The number of elements of each of the outer `depth - 1` levels,
+    /// outermost first. The last width is reused for any deeper levels.
+    pub widths: Vec<usize>,
+    /// The exclusive upper bound on the innermost run lengths, must not be
+    /// zero
+    pub max_len: usize,
+    /// The values of the plateaus of the innermost runs
+    pub plateau_levels: Vec<u64>,
+}
+
+impl Default for NestedSlices {
+    fn default() -> Self {
+        Self {
+            count: 1 << 15,
+            depth: 2,
+            widths: vec![4],
+            max_len: 1 << 5,
+            plateau_levels: vec![4, 8],
+        }
+    }
+}
+
+impl NestedSlices {
+    fn generate_one(&self, rng: &mut Xoshiro128StarStar, level: usize) -> Nested {
+        if (level + 1) >= self.depth {
+            let len = below(rng, self.max_len);
+            let run = clustered_run(rng, len, 0, &self.plateau_levels);
+            Nested::Slice(run.into_iter().map(Nested::Leaf).collect())
+        } else {
+            let width = *self
+                .widths
+                .get(level)
+                .or(self.widths.last())
+                .expect("`widths` must not be empty when `depth > 1`");
+            Nested::Slice(
+                (0..width)
+                    .map(|_| self.generate_one(rng, level + 1))
+                    .collect(),
+            )
+        }
+    }
+
+    fn generate_unsorted(&self, seed: u64) -> Vec<Nested> {
+        assert_ne!(self.depth, 0);
+        let mut rng = Xoshiro128StarStar::seed_from_u64(seed);
+        (0..self.count)
+            .map(|_| self.generate_one(&mut rng, 0))
+            .collect()
+    }
+
+    /// Generates the sorted keys from `seed`
+    pub fn generate_nested(&self, seed: u64) -> Vec<Nested> {
+        let mut res = self.generate_unsorted(seed);
+        res.sort();
+        res
+    }
+
+    /// Generates the sorted keys from `seed` and converts them into `T`, which
+    /// should be `depth` levels of `Vec`s around an integer type, for example
+    /// `Vec<Vec<u64>>` for a depth of 2. The keys are sorted by the `Ord` of
+    /// `T`.
+    ///
+    /// # Panics
+    ///
+    /// If `T` has a different shape than the generated keys
+    pub fn generate<T: FromNested + Ord>(&self, seed: u64) -> Vec<T> {
+        let mut res: Vec<T> = self
+            .generate_unsorted(seed)
+            .into_iter()
+            .map(|nested| T::from_nested(nested).expect("`T` does not match the shape of the keys"))
+            .collect();
+        res.sort();
+        res
+    }
+}
@@ -0,0 +1,319 @@
+//! Object safe versions of [TreeOrd] and [Tracker], for keys and comparators
+//! that are chosen at runtime and stored as trait objects. [DynTreeOrd] is
+//! implemented for every `T: TreeOrd + 'static`, and [DynKey] wraps a
+//! `Box<dyn DynTreeOrd>` so that boxed keys work with the `search` functions.
+//!
+//! Keys are only comparable with keys of the same concrete type, and trackers
+//! only with the type that created them. Comparing keys of different types or
+//! using a tracker of another type panics with a message naming the types.
+
+use alloc::boxed::Box;
+use core::{
+    any::{type_name, Any},
+    cmp::Ordering,
+    fmt,
+};
+
+use crate::{Tracker, TreeOrd};
+
+/// An object safe mirror of [Tracker], implemented for every `Tracker` with
+/// `'static` state. Snapshots are boxed.
+pub trait DynTracker: Any {
+    /// Returns the name of the concrete tracker type
+    fn tracker_type_name(&self) -> &'static str;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// The same as `Tracker::reset`
+    fn dyn_reset(&mut self);
+
+    /// The same as `Tracker::snapshot`
+    fn dyn_snapshot(&self) -> Box<dyn Any>;
+
+    /// The same as `Tracker::restore`
+    ///
+    /// # Panics
+    ///
+    /// If `snapshot` was not taken from a tracker of the same type
+    fn dyn_restore(&mut self, snapshot: Box<dyn Any>);
+
+    /// The same as `Tracker::proven_prefix`
+    fn dyn_proven_prefix(&self) -> Option<usize>;
+
+    /// The same as `Tracker::fork`
+    fn dyn_fork(&self) -> Box<dyn DynTracker>;
+}
+
+impl<T: Tracker + 'static> DynTracker for T
+where
+    T::Snapshot: 'static,
+{
+    fn tracker_type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn dyn_reset(&mut self) {
+        self.reset();
+    }
+
+    fn dyn_snapshot(&self) -> Box<dyn Any> {
+        Box::new(self.snapshot())
+    }
+
+    fn dyn_restore(&mut self, snapshot: Box<dyn Any>) {
+        match snapshot.downcast::<T::Snapshot>() {
+            Ok(snapshot) => self.restore(*snapshot),
+            Err(_) => panic!(
+                "DynTracker::dyn_restore: the snapshot is not from a `{}`",
+                type_name::<T>()
+            ),
+        }
+    }
+
+    fn dyn_proven_prefix(&self) -> Option<usize> {
+        self.proven_prefix()
+    }
+
+    fn dyn_fork(&self) -> Box<dyn DynTracker> {
+        Box::new(self.fork())
+    }
+}
+
+/// An object safe mirror of [TreeOrd], implemented for every `T: TreeOrd +
+/// 'static` with `'static` tracker state. The right hand sides and trackers
+/// are downcast to `T` and `T::Tracker`.
+///
+/// ```
+/// use std::cmp::Ordering;
+///
+/// use tree_ord::dynamic::DynTreeOrd;
+///
+/// let keys: Vec<Box<dyn DynTreeOrd>> = vec![Box::new(vec![1u8, 2]), Box::new(vec![1u8, 3])];
+/// let query: Box<dyn DynTreeOrd> = Box::new(vec![1u8, 3]);
+/// let mut tracker = query.new_tracker();
+/// assert_eq!(keys[0].dyn_tree_cmp(&*query, &mut *tracker), Ordering::Less);
+/// assert_eq!(
+///     keys[1].dyn_tree_cmp(&*query, &mut *tracker),
+///     Ordering::Equal
+/// );
+/// ```
+pub trait DynTreeOrd: Any {
+    /// Returns the name of the concrete key type
+    fn key_type_name(&self) -> &'static str;
+
+    fn as_any(&self) -> &dyn Any;
+
+    /// Returns a new tracker for comparisons with keys of this type
+    fn new_tracker(&self) -> Box<dyn DynTracker>;
+
+    /// The same as `Ord::cmp`
+    ///
+    /// # Panics
+    ///
+    /// If `rhs` is of a different type
+    fn dyn_cmp(&self, rhs: &dyn DynTreeOrd) -> Ordering;
+
+    /// The same as `TreeOrd::tree_cmp`
+    ///
+    /// # Panics
+    ///
+    /// If `rhs` is of a different type, or if `tracker` was not created for
+    /// this type
+    fn dyn_tree_cmp(&self, rhs: &dyn DynTreeOrd, tracker: &mut dyn DynTracker) -> Ordering;
+
+    /// The same as `TreeOrd::tree_cmp_hereditary`
+    ///
+    /// # Panics
+    ///
+    /// If `rhs` is of a different type, or if `tracker` was not created for
+    /// this type
+    fn dyn_tree_cmp_hereditary(
+        &self,
+        rhs: &dyn DynTreeOrd,
+        tracker: &mut dyn DynTracker,
+    ) -> Ordering;
+}
+
+/// Downcasts the arguments of a `DynTreeOrd` comparison
+fn downcast<'a, T: TreeOrd + 'static>(
+    rhs: &'a dyn DynTreeOrd,
+    tracker: &'a mut dyn DynTracker,
+) -> (&'a T, &'a mut T::Tracker)
+where
+    T::Tracker: 'static,
+{
+    let tracker_name = tracker.tracker_type_name();
+    let Some(tracker) = tracker.as_any_mut().downcast_mut::<T::Tracker>() else {
+        panic!(
+            "DynTreeOrd: a `{tracker_name}` tracker was used for comparing `{}` keys",
+            type_name::<T>()
+        )
+    };
+    (downcast_key(rhs), tracker)
+}
+
+fn downcast_key<T: 'static>(rhs: &dyn DynTreeOrd) -> &T {
+    match rhs.as_any().downcast_ref::<T>() {
+        Some(rhs) => rhs,
+        None => panic!(
+            "DynTreeOrd: compared a `{}` key with a `{}` key",
+            type_name::<T>(),
+            rhs.key_type_name()
+        ),
+    }
+}
+
+impl<T: TreeOrd + 'static> DynTreeOrd for T
+where
+    T::Tracker: 'static,
+    <T::Tracker as Tracker>::Snapshot: 'static,
+{
+    fn key_type_name(&self) -> &'static str {
+        type_name::<T>()
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn new_tracker(&self) -> Box<dyn DynTracker> {
+        Box::new(T::Tracker::new())
+    }
+
+    fn dyn_cmp(&self, rhs: &dyn DynTreeOrd) -> Ordering {
+        self.cmp(downcast_key::<T>(rhs))
+    }
+
+    fn dyn_tree_cmp(&self, rhs: &dyn DynTreeOrd, tracker: &mut dyn DynTracker) -> Ordering {
+        let (rhs, tracker) = downcast::<T>(rhs, tracker);
+        self.tree_cmp(rhs, tracker)
+    }
+
+    fn dyn_tree_cmp_hereditary(
+        &self,
+        rhs: &dyn DynTreeOrd,
+        tracker: &mut dyn DynTracker,
+    ) -> Ordering {
+        let (rhs, tracker) = downcast::<T>(rhs, tracker);
+        self.tree_cmp_hereditary(rhs, tracker)
+    }
+}
+
+/// A boxed [DynTreeOrd] key that implements `TreeOrd`, so that it can be used
+/// with the `search` functions and other generic code. All the keys compared
+/// with each other must have the same concrete type.
+///
+/// This is a wrapper instead of an impl on `Box<dyn DynTreeOrd>`, because the
+/// box would then also be a `DynTreeOrd` itself, and method calls on it would
+/// compare as boxes instead of as the inner type.
+pub struct DynKey(pub Box<dyn DynTreeOrd>);
+
+impl DynKey {
+    pub fn new<T: DynTreeOrd>(key: T) -> Self {
+        Self(Box::new(key))
+    }
+}
+
+impl fmt::Debug for DynKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynKey")
+            .field(&self.0.key_type_name())
+            .finish()
+    }
+}
+
+impl PartialEq for DynKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.dyn_cmp(&*other.0) == Ordering::Equal
+    }
+}
+
+impl Eq for DynKey {}
+
+impl PartialOrd for DynKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DynKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.dyn_cmp(&*other.0)
+    }
+}
+
+impl TreeOrd<Self> for DynKey {
+    type Tracker = DynKeyTracker;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let tracker = tracker.0.get_or_insert_with(|| self.0.new_tracker());
+        self.0.dyn_tree_cmp(&*rhs.0, &mut **tracker)
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let tracker = tracker.0.get_or_insert_with(|| self.0.new_tracker());
+        self.0.dyn_tree_cmp_hereditary(&*rhs.0, &mut **tracker)
+    }
+}
+
+/// The tracker of [DynKey]. The inner tracker is created by the first
+/// comparison, since the key type is not known before that.
+#[derive(Default)]
+pub struct DynKeyTracker(Option<Box<dyn DynTracker>>);
+
+impl DynKeyTracker {
+    /// Returns the inner tracker, if a comparison has created it
+    pub fn inner(&self) -> Option<&dyn DynTracker> {
+        self.0.as_deref()
+    }
+}
+
+impl fmt::Debug for DynKeyTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DynKeyTracker")
+            .field(&self.0.as_ref().map(|t| t.tracker_type_name()))
+            .finish()
+    }
+}
+
+impl Tracker for DynKeyTracker {
+    /// The boxed snapshot of the inner tracker, if it has been created
+    type Snapshot = Option<Box<dyn Any>>;
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = Self(None);
+
+    fn reset(&mut self) {
+        // keeps the allocation
+        if let Some(tracker) = &mut self.0 {
+            tracker.dyn_reset();
+        }
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.0.as_ref().map(|t| t.dyn_snapshot())
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        match (snapshot, &mut self.0) {
+            (Some(snapshot), Some(tracker)) => tracker.dyn_restore(snapshot),
+            // there were no comparisons before the snapshot
+            (None, _) => self.reset(),
+            (Some(_), None) => {
+                panic!("DynKeyTracker::restore: the snapshot is not from this tracker")
+            }
+        }
+    }
+
+    fn proven_prefix(&self) -> Option<usize> {
+        self.0.as_ref().and_then(|t| t.dyn_proven_prefix())
+    }
+
+    fn fork(&self) -> Self {
+        Self(self.0.as_ref().map(|t| t.dyn_fork()))
+    }
+}
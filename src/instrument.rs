@@ -0,0 +1,160 @@
+//! Instrumentation for measuring how many comparisons `TreeOrd` skips for a
+//! given key type and corpus
+//!
+//! The leaf element types of keys are wrapped in [InstrumentedElement], which
+//! counts its comparisons in a thread local, and the whole key is wrapped in
+//! [Instrumented], whose [InstrumentedTracker] attributes the element
+//! comparisons to each `tree_cmp` call. None of this has any cost unless the
+//! wrappers are used.
+
+use core::{cell::Cell, cmp::Ordering};
+
+use crate::{Tracker, TreeOrd};
+
+thread_local! {
+    static ELEMENT_CMPS: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Returns the number of comparisons that [InstrumentedElement]s have made on
+/// this thread
+pub fn element_cmp_count() -> u64 {
+    ELEMENT_CMPS.with(|c| c.get())
+}
+
+fn inc_element_cmps() {
+    ELEMENT_CMPS.with(|c| c.set(c.get().wrapping_add(1)));
+}
+
+/// A wrapper for the leaf element types of keys (e.g. the `u64` in
+/// `Vec<(u64, String)>`) that counts every comparison it makes. This should not
+/// wrap types that themselves contain `InstrumentedElement`s, or else
+/// comparisons will be counted multiple times.
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(transparent)]
+pub struct InstrumentedElement<T>(pub T);
+
+impl<T: PartialEq> PartialEq for InstrumentedElement<T> {
+    fn eq(&self, other: &Self) -> bool {
+        inc_element_cmps();
+        self.0 == other.0
+    }
+}
+
+impl<T: Eq> Eq for InstrumentedElement<T> {}
+
+impl<T: PartialOrd> PartialOrd for InstrumentedElement<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        inc_element_cmps();
+        self.0.partial_cmp(&other.0)
+    }
+}
+
+impl<T: Ord> Ord for InstrumentedElement<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        inc_element_cmps();
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<T: TreeOrd> TreeOrd<Self> for InstrumentedElement<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        inc_element_cmps();
+        self.0.tree_cmp(&rhs.0, tracker)
+    }
+}
+
+/// Statistics gathered by an [InstrumentedTracker]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrackerStats {
+    /// The number of `tree_cmp` calls
+    pub tree_cmps: u64,
+    /// The number of element comparisons made by the `tree_cmp` calls
+    pub element_cmps: u64,
+    /// The number of element comparisons that `Ord::cmp` would have made for
+    /// the same calls
+    pub baseline_element_cmps: u64,
+}
+
+impl TrackerStats {
+    /// Returns the number of element comparisons that were skipped due to
+    /// prefix knowledge. Note that the tracked comparisons can occasionally
+    /// make more comparisons than the baseline, which is not subtracted here.
+    pub fn skipped_element_cmps(&self) -> u64 {
+        self.baseline_element_cmps.saturating_sub(self.element_cmps)
+    }
+}
+
+/// The tracker of [Instrumented], which wraps the tracker of the key and
+/// gathers [TrackerStats]. The statistics are kept across `reset`s and
+/// `restore`s, and forks start with zeroed statistics.
+#[derive(Debug, Clone, Default)]
+pub struct InstrumentedTracker<T: Tracker> {
+    inner: T,
+    stats: TrackerStats,
+}
+
+impl<T: Tracker> InstrumentedTracker<T> {
+    pub fn stats(&self) -> TrackerStats {
+        self.stats
+    }
+
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Tracker> Tracker for InstrumentedTracker<T> {
+    type Snapshot = T::Snapshot;
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = Self {
+        inner: T::NEW,
+        stats: TrackerStats {
+            tree_cmps: 0,
+            element_cmps: 0,
+            baseline_element_cmps: 0,
+        },
+    };
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.inner.snapshot()
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        self.inner.restore(snapshot);
+    }
+}
+
+/// A key wrapper that uses an [InstrumentedTracker]. Every `tree_cmp` also
+/// performs an `Ord::cmp` to measure the baseline, so this is only for
+/// measurement and not for production.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct Instrumented<K>(pub K);
+
+impl<K: TreeOrd> TreeOrd<Self> for Instrumented<K> {
+    type Tracker = InstrumentedTracker<K::Tracker>;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let start = element_cmp_count();
+        let res = self.0.tree_cmp(&rhs.0, &mut tracker.inner);
+        let mid = element_cmp_count();
+        let baseline = self.0.cmp(&rhs.0);
+        let end = element_cmp_count();
+        debug_assert_eq!(res, baseline);
+        let stats = &mut tracker.stats;
+        stats.tree_cmps = stats.tree_cmps.wrapping_add(1);
+        stats.element_cmps = stats.element_cmps.wrapping_add(mid.wrapping_sub(start));
+        stats.baseline_element_cmps = stats
+            .baseline_element_cmps
+            .wrapping_add(end.wrapping_sub(mid));
+        res
+    }
+}
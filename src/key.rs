@@ -0,0 +1,893 @@
+//! Order preserving encoding of composite keys into byte strings, so that keys
+//! can be stored in byte ordered stores and searched with `TreeOrdBytes` while
+//! still being ordered like the tuples of their fields.
+//!
+//! Each field is encoded so that the encodings of a field sort in the same
+//! order as its values and no encoding is a proper prefix of another, which
+//! makes the concatenated fields sort like tuples:
+//!
+//! - Unsigned integers are big endian.
+//! - Signed integers are big endian with the sign bit flipped.
+//! - Floats are big endian, with the sign bit flipped for positive floats and
+//!   all bits inverted for negative floats, so that they sort like `total_cmp`.
+//!   All NaNs are ordered by `total_cmp` as well, and -0.0 and +0.0 are
+//!   distinct but adjacent.
+//! - `bool`s are a 0 or 1 byte.
+//! - Byte strings and `str`s have each 0 byte escaped as `00 ff` and are
+//!   terminated by `00 01`, so that a terminator sorts before any continuation.
+//! - `Option`s are a 0 tag byte for `None` or a 1 tag byte followed by the
+//!   encoding of the value.
+//!
+//! [KeyBuilder] encodes into a `Vec` (requiring the "alloc" feature), and
+//! [KeyWriter] encodes into a caller provided buffer. Both share the same
+//! encoding functions. [KeyReader] decodes the fields again. For a single
+//! integer, [crate::BigEndianKey] has the same encoding as a fixed size array.
+//!
+//! Fields pushed with `push_desc` have all the bytes of their encoding
+//! inverted, which reverses their order. Since no encoding is a proper
+//! prefix of another, the comparison of two inverted encodings is always
+//! decided at a differing byte (for strings, an inverted terminator `ff fe`
+//! sorts after any continuation), so this also works for variable length
+//! fields.
+//!
+//! The [fdb] module has the same interface for the FoundationDB tuple layer
+//! encoding, and the [memcomparable] module for the encoding of TiKV.
+
+pub mod fdb;
+pub mod memcomparable;
+
+#[cfg(feature = "alloc")]
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::convert::Infallible;
+use core::{fmt, str};
+
+/// The error of [KeyWriter] when a field does not fit in the remaining
+/// capacity of the buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CapacityError;
+
+impl fmt::Display for CapacityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("the key field does not fit in the buffer")
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CapacityError {}
+
+/// The output of the encoding functions
+trait Sink {
+    type Error;
+
+    /// Appends `n` bytes and returns them for writing, or returns an error
+    /// without changing the output
+    fn append(&mut self, n: usize) -> Result<&mut [u8], Self::Error>;
+
+    /// Returns the output starting at `start`
+    fn tail_mut(&mut self, start: usize) -> &mut [u8];
+}
+
+#[inline]
+fn put<S: Sink>(sink: &mut S, bytes: &[u8]) -> Result<(), S::Error> {
+    sink.append(bytes.len())?.copy_from_slice(bytes);
+    Ok(())
+}
+
+/// Puts a byte string with its 0 bytes escaped as `00 ff` and the `00 01`
+/// terminator
+fn put_bytes<S: Sink>(sink: &mut S, x: &[u8]) -> Result<(), S::Error> {
+    let zeros = x.iter().filter(|b| **b == 0).count();
+    let out = sink.append(x.len() + zeros + 2)?;
+    let mut j = 0;
+    for b in x {
+        out[j] = *b;
+        j += 1;
+        if *b == 0 {
+            out[j] = 0xff;
+            j += 1;
+        }
+    }
+    out[j] = 0;
+    out[j + 1] = 1;
+    Ok(())
+}
+
+/// Inverts the output starting at `start` to make it descending
+#[inline]
+fn invert_from<S: Sink>(sink: &mut S, start: usize) {
+    for b in sink.tail_mut(start) {
+        *b = !*b;
+    }
+}
+
+/// The field methods of [KeyBuilder] and [KeyWriter], `$pushed` converts the
+/// result of the encoding function into the return type
+macro_rules! push_fields {
+    ($ret:ty, $pushed:ident) => {
+        push_fields!(
+            @ints $ret, $pushed,
+            u8 i8 push_u8 push_i8;
+            u16 i16 push_u16 push_i16;
+            u32 i32 push_u32 push_i32;
+            u64 i64 push_u64 push_i64;
+            u128 i128 push_u128 push_i128;
+        );
+
+        /// Pushes an `f32` so that it sorts like `f32::total_cmp`
+        #[inline]
+        pub fn push_f32(&mut self, x: f32) -> $ret {
+            let res = put(self, &encode_f32(x));
+            self.$pushed(res)
+        }
+
+        /// Pushes an `f64` so that it sorts like `f64::total_cmp`
+        #[inline]
+        pub fn push_f64(&mut self, x: f64) -> $ret {
+            let res = put(self, &encode_f64(x));
+            self.$pushed(res)
+        }
+
+        /// Pushes a `bool` as a 0 or 1 byte
+        #[inline]
+        pub fn push_bool(&mut self, x: bool) -> $ret {
+            self.push_u8(u8::from(x))
+        }
+
+        /// Pushes a byte string with its 0 bytes escaped and a terminator
+        #[inline]
+        pub fn push_bytes(&mut self, x: &[u8]) -> $ret {
+            let res = put_bytes(self, x);
+            self.$pushed(res)
+        }
+
+        /// Pushes a `str` the same way as a byte string
+        #[inline]
+        pub fn push_str(&mut self, x: &str) -> $ret {
+            self.push_bytes(x.as_bytes())
+        }
+    };
+    (@ints $ret:ty, $pushed:ident, $($u:ident $i:ident $push_u:ident $push_i:ident;)*) => {
+        $(
+            /// Pushes an unsigned integer in big endian
+            #[inline]
+            pub fn $push_u(&mut self, x: $u) -> $ret {
+                let res = put(self, &x.to_be_bytes());
+                self.$pushed(res)
+            }
+
+            /// Pushes a signed integer in big endian with the sign bit flipped
+            #[inline]
+            pub fn $push_i(&mut self, x: $i) -> $ret {
+                self.$push_u((x as $u) ^ (1 << ($u::BITS - 1)))
+            }
+        )*
+    };
+}
+
+/// Builds an order preserving encoding of a composite key field by field. The
+/// encodings of two keys compare bytewise in the same order as the tuples of
+/// their fields, as long as both keys push the same types of fields in the same
+/// order.
+///
+/// ```
+/// use tree_ord::key::KeyBuilder;
+///
+/// let a = KeyBuilder::new().push_str("ab").push_u32(7).finish();
+/// let b = KeyBuilder::new().push_str("ab\0").push_u32(0).finish();
+/// assert!(a < b);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyBuilder {
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl Sink for KeyBuilder {
+    type Error = Infallible;
+
+    #[inline]
+    fn append(&mut self, n: usize) -> Result<&mut [u8], Infallible> {
+        let start = self.buf.len();
+        self.buf.resize(start + n, 0);
+        Ok(&mut self.buf[start..])
+    }
+
+    #[inline]
+    fn tail_mut(&mut self, start: usize) -> &mut [u8] {
+        &mut self.buf[start..]
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl KeyBuilder {
+    push_fields!(&mut Self, pushed);
+
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: Vec::with_capacity(capacity),
+        }
+    }
+
+    #[inline]
+    fn pushed(&mut self, res: Result<(), Infallible>) -> &mut Self {
+        match res {
+            Ok(()) => self,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Returns the encoding so far
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Returns the encoding, leaving the builder empty so that it can be reused
+    /// for another key
+    pub fn finish(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.buf)
+    }
+
+    /// Clears the encoding while keeping the allocation
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    /// Pushes the fields pushed by `f` in descending order, by inverting their
+    /// encoding
+    ///
+    /// ```
+    /// use tree_ord::key::KeyBuilder;
+    ///
+    /// let mut key = KeyBuilder::new();
+    /// // newest first, then by ascending id
+    /// let a = key.push_desc(|key| key.push_u64(200)).push_u32(7).finish();
+    /// let b = key.push_desc(|key| key.push_u64(100)).push_u32(3).finish();
+    /// assert!(a < b);
+    /// ```
+    pub fn push_desc(&mut self, f: impl FnOnce(&mut Self) -> &mut Self) -> &mut Self {
+        let start = self.buf.len();
+        f(self);
+        invert_from(self, start);
+        self
+    }
+
+    /// Pushes the tag of an `Option`, and for `Some` then calls `f` to push the
+    /// value
+    ///
+    /// ```
+    /// use tree_ord::key::KeyBuilder;
+    ///
+    /// let mut key = KeyBuilder::new();
+    /// key.push_option(Some(3u16), |key, x| key.push_u16(x));
+    /// assert_eq!(key.as_bytes(), &[1, 0, 3]);
+    /// ```
+    pub fn push_option<T>(
+        &mut self,
+        x: Option<T>,
+        f: impl FnOnce(&mut Self, T) -> &mut Self,
+    ) -> &mut Self {
+        match x {
+            None => self.push_u8(0),
+            Some(x) => f(self.push_u8(1), x),
+        }
+    }
+}
+
+/// Writes the same encoding as [KeyBuilder] into a caller provided buffer,
+/// without allocating. The field methods return the length of the encoding so
+/// far, or a [CapacityError] if the field does not fit, in which case nothing
+/// of the field is written.
+///
+/// ```
+/// use tree_ord::key::{CapacityError, KeyWriter};
+///
+/// let mut buf = [0u8; 8];
+/// let mut key = KeyWriter::new(&mut buf);
+/// assert_eq!(key.push_str("ab"), Ok(4));
+/// assert_eq!(key.push_u32(7), Ok(8));
+/// assert_eq!(key.push_bool(true), Err(CapacityError));
+/// assert_eq!(key.as_bytes(), &[b'a', b'b', 0, 1, 0, 0, 0, 7]);
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct KeyWriter<'a> {
+    buf: &'a mut [u8],
+    len: usize,
+}
+
+impl Sink for KeyWriter<'_> {
+    type Error = CapacityError;
+
+    #[inline]
+    fn append(&mut self, n: usize) -> Result<&mut [u8], CapacityError> {
+        let start = self.len;
+        if n > self.buf.len() - start {
+            return Err(CapacityError)
+        }
+        self.len = start + n;
+        Ok(&mut self.buf[start..self.len])
+    }
+
+    #[inline]
+    fn tail_mut(&mut self, start: usize) -> &mut [u8] {
+        &mut self.buf[start..self.len]
+    }
+}
+
+impl<'a> KeyWriter<'a> {
+    push_fields!(Result<usize, CapacityError>, pushed);
+
+    /// Creates a writer that writes from the start of `buf`
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, len: 0 }
+    }
+
+    #[inline]
+    fn pushed(&mut self, res: Result<(), CapacityError>) -> Result<usize, CapacityError> {
+        res.map(|()| self.len)
+    }
+
+    /// Returns the length of the encoding so far
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the encoding so far
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns the encoded part of the buffer
+    pub fn into_bytes(self) -> &'a mut [u8] {
+        &mut self.buf[..self.len]
+    }
+
+    /// Clears the encoding, so that the buffer can be reused for another key
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Pushes the fields pushed by `f` in descending order, by inverting their
+    /// encoding. If `f` returns an error, everything it wrote is removed.
+    pub fn push_desc(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<usize, CapacityError>,
+    ) -> Result<usize, CapacityError> {
+        let start = self.len;
+        if let Err(e) = f(self) {
+            self.len = start;
+            return Err(e)
+        }
+        invert_from(self, start);
+        Ok(self.len)
+    }
+
+    /// Pushes the tag of an `Option`, and for `Some` then calls `f` to push the
+    /// value. If `f` returns an error, the tag is removed as well.
+    pub fn push_option<T>(
+        &mut self,
+        x: Option<T>,
+        f: impl FnOnce(&mut Self, T) -> Result<usize, CapacityError>,
+    ) -> Result<usize, CapacityError> {
+        match x {
+            None => self.push_u8(0),
+            Some(x) => {
+                let start = self.len;
+                self.push_u8(1)?;
+                f(self, x).inspect_err(|_| self.len = start)
+            }
+        }
+    }
+}
+
+macro_rules! floats {
+    ($($f:ident $u:ident $encode:ident $decode:ident $n:literal);*;) => {
+        $(
+            /// Returns the order preserving encoding of a float, which sorts
+            /// like `total_cmp`
+            #[inline]
+            pub fn $encode(x: $f) -> [u8; $n] {
+                let bits = x.to_bits();
+                let sign = 1 << ($u::BITS - 1);
+                let bits = if (bits & sign) == 0 {
+                    bits ^ sign
+                } else {
+                    !bits
+                };
+                bits.to_be_bytes()
+            }
+
+            /// Decodes a float encoded by the corresponding `encode` function,
+            /// with the exact bits including NaN payloads and the sign of zero
+            #[inline]
+            pub fn $decode(bytes: [u8; $n]) -> $f {
+                let bits = $u::from_be_bytes(bytes);
+                let sign = 1 << ($u::BITS - 1);
+                let bits = if (bits & sign) != 0 {
+                    bits ^ sign
+                } else {
+                    !bits
+                };
+                $f::from_bits(bits)
+            }
+        )*
+    };
+}
+
+floats!(
+    f32 u32 encode_f32 decode_f32 4;
+    f64 u64 encode_f64 decode_f64 8;
+);
+
+/// The kind of a [KeyError]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyErrorKind {
+    /// The key ended within a field
+    UnexpectedEnd,
+    /// A 0 byte within a byte string was not followed by `ff` or `01`
+    InvalidEscape,
+    /// A `bool` byte was not 0 or 1
+    InvalidBool,
+    /// An `Option` tag was not 0 or 1
+    InvalidTag,
+    /// A `str` field was not valid UTF-8
+    InvalidUtf8,
+    /// There were bytes left after the last field
+    TrailingBytes,
+    /// A type code was not the one of the type being read
+    InvalidTypeCode,
+    /// An integer did not fit in the type being read
+    OutOfRange,
+    /// A memcomparable group had an invalid marker or nonzero padding
+    InvalidGroup,
+}
+
+/// The error of [KeyReader] on malformed input
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyError {
+    pub kind: KeyErrorKind,
+    /// The offset of the field (or of the trailing bytes) in the key
+    pub offset: usize,
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self.kind {
+            KeyErrorKind::UnexpectedEnd => "unexpected end of key",
+            KeyErrorKind::InvalidEscape => "invalid escape in byte string",
+            KeyErrorKind::InvalidBool => "invalid bool",
+            KeyErrorKind::InvalidTag => "invalid option tag",
+            KeyErrorKind::InvalidUtf8 => "invalid UTF-8 in string",
+            KeyErrorKind::TrailingBytes => "trailing bytes after the last field",
+            KeyErrorKind::InvalidTypeCode => "invalid type code",
+            KeyErrorKind::OutOfRange => "integer out of range",
+            KeyErrorKind::InvalidGroup => "invalid memcomparable group",
+        };
+        write!(f, "{msg} at offset {}", self.offset)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for KeyError {}
+
+/// A byte string field read by [KeyReader] (or the readers of the other
+/// encodings), which is unescaped (and uninverted, for descending fields)
+/// lazily
+#[derive(Debug, Clone, Copy)]
+pub struct KeyBytes<'a> {
+    /// The encoding without the terminator, or the groups of the memcomparable
+    /// encoding
+    raw: &'a [u8],
+    mask: u8,
+    grouped: bool,
+}
+
+impl<'a> KeyBytes<'a> {
+    /// Returns the bytes without copying if they are stored as they are, which
+    /// is the case for ascending fields without 0 bytes (or within a single
+    /// memcomparable group)
+    pub fn as_slice(&self) -> Option<&'a [u8]> {
+        if self.mask != 0 {
+            None
+        } else if self.grouped {
+            if self.raw.len() == memcomparable::GROUP_LEN + 1 {
+                Some(&self.raw[..self.len()])
+            } else {
+                None
+            }
+        } else if !self.raw.contains(&0) {
+            Some(self.raw)
+        } else {
+            None
+        }
+    }
+
+    /// Returns an iterator over the bytes
+    pub fn iter(&self) -> KeyBytesIter<'a> {
+        if self.grouped {
+            KeyBytesIter {
+                data: [].iter(),
+                groups: self.raw,
+                mask: self.mask,
+                grouped: true,
+            }
+        } else {
+            KeyBytesIter {
+                data: self.raw.iter(),
+                groups: &[],
+                mask: self.mask,
+                grouped: false,
+            }
+        }
+    }
+
+    /// Returns the number of bytes
+    pub fn len(&self) -> usize {
+        if self.grouped {
+            let last = self.raw[self.raw.len() - 1] ^ self.mask;
+            self.raw.len() - self.raw.len() / (memcomparable::GROUP_LEN + 1)
+                + memcomparable::group_data_len(last)
+                - memcomparable::GROUP_LEN
+        } else {
+            self.raw.len() - self.raw.iter().filter(|b| (**b ^ self.mask) == 0).count()
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.iter().collect()
+    }
+}
+
+impl PartialEq for KeyBytes<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for KeyBytes<'_> {}
+
+impl PartialEq<[u8]> for KeyBytes<'_> {
+    fn eq(&self, other: &[u8]) -> bool {
+        self.iter().eq(other.iter().copied())
+    }
+}
+
+impl<'a> IntoIterator for KeyBytes<'a> {
+    type IntoIter = KeyBytesIter<'a>;
+    type Item = u8;
+
+    fn into_iter(self) -> KeyBytesIter<'a> {
+        self.iter()
+    }
+}
+
+/// The iterator of [KeyBytes]
+#[derive(Debug, Clone)]
+pub struct KeyBytesIter<'a> {
+    data: core::slice::Iter<'a, u8>,
+    /// The remaining memcomparable groups
+    groups: &'a [u8],
+    mask: u8,
+    grouped: bool,
+}
+
+impl Iterator for KeyBytesIter<'_> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        if self.grouped {
+            loop {
+                if let Some(b) = self.data.next() {
+                    return Some(b ^ self.mask)
+                }
+                let (group, rest) = self.groups.split_at_checked(memcomparable::GROUP_LEN + 1)?;
+                self.groups = rest;
+                let marker = group[memcomparable::GROUP_LEN] ^ self.mask;
+                self.data = group[..memcomparable::group_data_len(marker)].iter();
+            }
+        }
+        let b = self.data.next()? ^ self.mask;
+        if b == 0 {
+            // the `ff` of the escape
+            self.data.next();
+        }
+        Some(b)
+    }
+}
+
+/// Calls `f` with consecutive pieces of the UTF-8 in `bytes`, decoded through a
+/// small buffer. Returns `None` if `bytes` is not valid UTF-8.
+fn utf8_pieces(
+    mut bytes: impl Iterator<Item = u8>,
+    mut f: impl FnMut(&str) -> fmt::Result,
+) -> Option<fmt::Result> {
+    let mut buf = [0u8; 64];
+    let mut len = 0;
+    loop {
+        let mut end = true;
+        for b in bytes.by_ref() {
+            buf[len] = b;
+            len += 1;
+            if len == buf.len() {
+                end = false;
+                break
+            }
+        }
+        let valid = match str::from_utf8(&buf[..len]) {
+            Ok(s) => s,
+            // an incomplete character at the end of the buffer continues in the
+            // next piece
+            Err(e) if e.error_len().is_none() && !end => {
+                // Safety: the bytes up to `valid_up_to` are valid UTF-8
+                unsafe { str::from_utf8_unchecked(&buf[..e.valid_up_to()]) }
+            }
+            Err(_) => return None,
+        };
+        let valid_len = valid.len();
+        if let Err(e) = f(valid) {
+            return Some(Err(e))
+        }
+        if end {
+            return Some(Ok(()))
+        }
+        buf.copy_within(valid_len..len, 0);
+        len -= valid_len;
+    }
+}
+
+/// A `str` field read by [KeyReader], which is validated as UTF-8 but is
+/// unescaped lazily like [KeyBytes]. `Display` writes the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyStr<'a> {
+    bytes: KeyBytes<'a>,
+}
+
+impl<'a> KeyStr<'a> {
+    /// Returns the string without copying if it is stored as it is
+    pub fn as_str(&self) -> Option<&'a str> {
+        let bytes = self.bytes.as_slice()?;
+        // Safety: the bytes were validated when reading the field
+        Some(unsafe { str::from_utf8_unchecked(bytes) })
+    }
+
+    /// Returns the bytes of the string
+    pub fn as_bytes(&self) -> KeyBytes<'a> {
+        self.bytes
+    }
+}
+
+impl PartialEq<str> for KeyStr<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.bytes == *other.as_bytes()
+    }
+}
+
+impl fmt::Display for KeyStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        utf8_pieces(self.bytes.iter(), |s| f.write_str(s)).unwrap_or(Err(fmt::Error))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<KeyStr<'_>> for String {
+    fn from(s: KeyStr<'_>) -> String {
+        let mut res = String::new();
+        utf8_pieces(s.bytes.iter(), |s| {
+            res.push_str(s);
+            Ok(())
+        });
+        res
+    }
+}
+
+/// Reads the fields of a key encoded by [KeyBuilder] or [KeyWriter]. The
+/// fields must be read with the same types in the same order as they were
+/// pushed, and malformed input returns a [KeyError] instead of panicking.
+///
+/// ```
+/// use tree_ord::key::{KeyReader, KeyWriter};
+///
+/// let mut buf = [0u8; 16];
+/// let mut key = KeyWriter::new(&mut buf);
+/// key.push_str("ab").unwrap();
+/// key.push_desc(|key| key.push_i32(-7)).unwrap();
+/// key.push_option(Some(true), |key, x| key.push_bool(x))
+///     .unwrap();
+///
+/// let mut reader = KeyReader::new(key.as_bytes());
+/// assert_eq!(reader.read_str().unwrap().as_str(), Some("ab"));
+/// assert_eq!(reader.read_desc(|r| r.read_i32()), Ok(-7));
+/// assert_eq!(reader.read_option(|r| r.read_bool()), Ok(Some(true)));
+/// reader.finish().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct KeyReader<'a> {
+    key: &'a [u8],
+    offset: usize,
+    /// `ff` within descending fields
+    mask: u8,
+}
+
+macro_rules! read_ints {
+    ($($u:ident $i:ident $read_u:ident $read_i:ident $n:literal);*;) => {
+        $(
+            #[inline]
+            pub fn $read_u(&mut self) -> Result<$u, KeyError> {
+                Ok($u::from_be_bytes(self.read_array::<$n>()?))
+            }
+
+            #[inline]
+            pub fn $read_i(&mut self) -> Result<$i, KeyError> {
+                Ok((self.$read_u()? ^ (1 << ($u::BITS - 1))) as $i)
+            }
+        )*
+    };
+}
+
+impl<'a> KeyReader<'a> {
+    read_ints!(
+        u8 i8 read_u8 read_i8 1;
+        u16 i16 read_u16 read_i16 2;
+        u32 i32 read_u32 read_i32 4;
+        u64 i64 read_u64 read_i64 8;
+        u128 i128 read_u128 read_i128 16;
+    );
+
+    pub fn new(key: &'a [u8]) -> Self {
+        Self {
+            key,
+            offset: 0,
+            mask: 0,
+        }
+    }
+
+    /// Returns the offset of the next field
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the bytes after the fields read so far
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.key[self.offset..]
+    }
+
+    /// Checks that all of the key has been read
+    pub fn finish(self) -> Result<(), KeyError> {
+        if self.offset == self.key.len() {
+            Ok(())
+        } else {
+            Err(self.error(KeyErrorKind::TrailingBytes))
+        }
+    }
+
+    fn error(&self, kind: KeyErrorKind) -> KeyError {
+        KeyError {
+            kind,
+            offset: self.offset,
+        }
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], KeyError> {
+        let Some(bytes) = self.key.get(self.offset..(self.offset + N)) else {
+            return Err(self.error(KeyErrorKind::UnexpectedEnd))
+        };
+        let mut res = [0u8; N];
+        for (x, b) in res.iter_mut().zip(bytes) {
+            *x = *b ^ self.mask;
+        }
+        self.offset += N;
+        Ok(res)
+    }
+
+    #[inline]
+    pub fn read_f32(&mut self) -> Result<f32, KeyError> {
+        Ok(decode_f32(self.read_array()?))
+    }
+
+    #[inline]
+    pub fn read_f64(&mut self) -> Result<f64, KeyError> {
+        Ok(decode_f64(self.read_array()?))
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, KeyError> {
+        let offset = self.offset;
+        match self.read_u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => {
+                self.offset = offset;
+                Err(self.error(KeyErrorKind::InvalidBool))
+            }
+        }
+    }
+
+    /// Reads a byte string, validating its escapes and terminator
+    pub fn read_bytes(&mut self) -> Result<KeyBytes<'a>, KeyError> {
+        let rest = &self.key[self.offset..];
+        let mut i = 0;
+        loop {
+            let Some(b) = rest.get(i) else {
+                return Err(self.error(KeyErrorKind::UnexpectedEnd))
+            };
+            if (b ^ self.mask) == 0 {
+                match rest.get(i + 1).map(|b| b ^ self.mask) {
+                    Some(0xff) => (),
+                    Some(1) => break,
+                    Some(_) => return Err(self.error(KeyErrorKind::InvalidEscape)),
+                    None => return Err(self.error(KeyErrorKind::UnexpectedEnd)),
+                }
+                i += 1;
+            }
+            i += 1;
+        }
+        self.offset += i + 2;
+        Ok(KeyBytes {
+            raw: &rest[..i],
+            mask: self.mask,
+            grouped: false,
+        })
+    }
+
+    /// Reads a `str`, validating it as UTF-8
+    pub fn read_str(&mut self) -> Result<KeyStr<'a>, KeyError> {
+        let offset = self.offset;
+        let bytes = self.read_bytes()?;
+        if utf8_pieces(bytes.iter(), |_| Ok(())).is_none() {
+            self.offset = offset;
+            return Err(self.error(KeyErrorKind::InvalidUtf8))
+        }
+        Ok(KeyStr { bytes })
+    }
+
+    /// Reads the tag of an `Option`, and for `Some` then calls `f` to read the
+    /// value
+    pub fn read_option<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, KeyError>,
+    ) -> Result<Option<T>, KeyError> {
+        let offset = self.offset;
+        match self.read_u8()? {
+            0 => Ok(None),
+            1 => f(self).map(Some),
+            _ => {
+                self.offset = offset;
+                Err(self.error(KeyErrorKind::InvalidTag))
+            }
+        }
+    }
+
+    /// Reads the fields read by `f` as descending fields. If `f` returns an
+    /// error, the reader is moved back to the start of the descending fields.
+    pub fn read_desc<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, KeyError>,
+    ) -> Result<T, KeyError> {
+        let offset = self.offset;
+        self.mask = !self.mask;
+        let res = f(self);
+        self.mask = !self.mask;
+        if res.is_err() {
+            self.offset = offset;
+        }
+        res
+    }
+}
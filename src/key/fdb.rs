@@ -0,0 +1,531 @@
+//! The FoundationDB tuple layer encoding, for keys that have to interoperate
+//! with other tuple layer implementations. [TupleBuilder] and [TupleReader]
+//! have the same interface as `KeyBuilder` and `KeyReader`, except that there
+//! are no descending fields, and there are nulls and nested tuples instead.
+//!
+//! Every element starts with a type code, and the elements of the same type
+//! are ordered by value:
+//!
+//! - Null is `00` (`00 ff` within nested tuples), and `None` is encoded as
+//!   null.
+//! - Byte strings are `01` and `str`s are `02`, followed by the bytes with each
+//!   0 byte escaped as `00 ff` and a `00` terminator.
+//! - Nested tuples are `05`, followed by the elements and a `00` terminator.
+//! - Integers of all the Rust integer types share one encoding. Zero is `14`,
+//!   positive integers of `n <= 8` big endian bytes are `14 + n` followed by
+//!   the bytes, and negative integers are `14 - n` followed by the one's
+//!   complement bytes. Integers of more bytes are `1d` followed by the number
+//!   of bytes, or `0b` followed by the inverted number of bytes for negative
+//!   integers.
+//! - `f32`s are `20` and `f64`s are `21`, followed by the same order preserving
+//!   encoding as `encode_f32` and `encode_f64`.
+//! - `false` is `26` and `true` is `27`.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use super::{decode_f32, decode_f64, utf8_pieces, KeyBytes, KeyError, KeyErrorKind, KeyStr};
+#[cfg(feature = "alloc")]
+use super::{encode_f32, encode_f64};
+
+const NULL: u8 = 0x00;
+const BYTES: u8 = 0x01;
+const STRING: u8 = 0x02;
+const NESTED: u8 = 0x05;
+const NEG_INT_START: u8 = 0x0b;
+const INT_ZERO: u8 = 0x14;
+const POS_INT_END: u8 = 0x1d;
+const FLOAT: u8 = 0x20;
+const DOUBLE: u8 = 0x21;
+const FALSE: u8 = 0x26;
+const TRUE: u8 = 0x27;
+/// The byte following a 0 byte that marks an escape, or a null within a
+/// nested tuple
+const ESCAPE: u8 = 0xff;
+
+/// Builds a key in the tuple layer encoding element by element
+///
+/// ```
+/// use tree_ord::key::fdb::TupleBuilder;
+///
+/// let key = TupleBuilder::new()
+///     .push_str("foo")
+///     .push_i64(-5551212)
+///     .finish();
+/// assert_eq!(key, b"\x02foo\x00\x11\xab\x4b\x93");
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TupleBuilder {
+    buf: Vec<u8>,
+    /// The nesting depth of tuples
+    depth: usize,
+}
+
+#[cfg(feature = "alloc")]
+macro_rules! push_ints {
+    ($($push_u:ident $u:ident $push_i:ident $i:ident);*;) => {
+        $(
+            #[inline]
+            pub fn $push_u(&mut self, x: $u) -> &mut Self {
+                self.push_int(false, u128::from(x))
+            }
+
+            #[inline]
+            pub fn $push_i(&mut self, x: $i) -> &mut Self {
+                self.push_int(x < 0, u128::from(x.unsigned_abs()))
+            }
+        )*
+    };
+}
+
+#[cfg(feature = "alloc")]
+impl TupleBuilder {
+    push_ints!(
+        push_u8 u8 push_i8 i8;
+        push_u16 u16 push_i16 i16;
+        push_u32 u32 push_i32 i32;
+        push_u64 u64 push_i64 i64;
+        push_u128 u128 push_i128 i128;
+    );
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the encoding so far
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Returns the encoding, leaving the builder empty so that it can be reused
+    /// for another key
+    ///
+    /// # Panics
+    ///
+    /// If called within `push_nested`
+    pub fn finish(&mut self) -> Vec<u8> {
+        assert_eq!(self.depth, 0);
+        core::mem::take(&mut self.buf)
+    }
+
+    /// Clears the encoding while keeping the allocation
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.depth = 0;
+    }
+
+    /// Pushes an integer with the sign and magnitude
+    fn push_int(&mut self, negative: bool, magnitude: u128) -> &mut Self {
+        if magnitude == 0 {
+            self.buf.push(INT_ZERO);
+            return self
+        }
+        let n = 16 - (magnitude.leading_zeros() / 8) as usize;
+        let bytes = &magnitude.to_be_bytes()[(16 - n)..];
+        if negative {
+            if n <= 8 {
+                self.buf.push(INT_ZERO - n as u8);
+            } else {
+                self.buf.extend_from_slice(&[NEG_INT_START, !(n as u8)]);
+            }
+            self.buf.extend(bytes.iter().map(|b| !b));
+        } else {
+            if n <= 8 {
+                self.buf.push(INT_ZERO + n as u8);
+            } else {
+                self.buf.extend_from_slice(&[POS_INT_END, n as u8]);
+            }
+            self.buf.extend_from_slice(bytes);
+        }
+        self
+    }
+
+    /// Pushes a null
+    #[inline]
+    pub fn push_null(&mut self) -> &mut Self {
+        self.buf.push(NULL);
+        if self.depth > 0 {
+            self.buf.push(ESCAPE);
+        }
+        self
+    }
+
+    #[inline]
+    pub fn push_f32(&mut self, x: f32) -> &mut Self {
+        self.buf.push(FLOAT);
+        self.buf.extend_from_slice(&encode_f32(x));
+        self
+    }
+
+    #[inline]
+    pub fn push_f64(&mut self, x: f64) -> &mut Self {
+        self.buf.push(DOUBLE);
+        self.buf.extend_from_slice(&encode_f64(x));
+        self
+    }
+
+    #[inline]
+    pub fn push_bool(&mut self, x: bool) -> &mut Self {
+        self.buf.push(if x { TRUE } else { FALSE });
+        self
+    }
+
+    fn push_escaped(&mut self, code: u8, x: &[u8]) -> &mut Self {
+        self.buf.push(code);
+        for b in x {
+            self.buf.push(*b);
+            if *b == 0 {
+                self.buf.push(ESCAPE);
+            }
+        }
+        self.buf.push(0);
+        self
+    }
+
+    /// Pushes a byte string
+    #[inline]
+    pub fn push_bytes(&mut self, x: &[u8]) -> &mut Self {
+        self.push_escaped(BYTES, x)
+    }
+
+    /// Pushes a unicode string
+    #[inline]
+    pub fn push_str(&mut self, x: &str) -> &mut Self {
+        self.push_escaped(STRING, x.as_bytes())
+    }
+
+    /// Pushes null for `None`, or calls `f` to push the value for `Some`
+    pub fn push_option<T>(
+        &mut self,
+        x: Option<T>,
+        f: impl FnOnce(&mut Self, T) -> &mut Self,
+    ) -> &mut Self {
+        match x {
+            None => self.push_null(),
+            Some(x) => f(self, x),
+        }
+    }
+
+    /// Pushes a nested tuple of the elements pushed by `f`
+    pub fn push_nested(&mut self, f: impl FnOnce(&mut Self) -> &mut Self) -> &mut Self {
+        self.buf.push(NESTED);
+        self.depth += 1;
+        f(self);
+        self.depth -= 1;
+        self.buf.push(0);
+        self
+    }
+}
+
+/// Reads the elements of a key in the tuple layer encoding. Integers can be
+/// read as any integer type they fit in.
+///
+/// ```
+/// use tree_ord::key::fdb::TupleReader;
+///
+/// let mut reader = TupleReader::new(b"\x05\x15\x07\x00\xff\x00\x27");
+/// let nested = reader.read_nested(|r| Ok((r.read_u8()?, r.read_option(|r| r.read_u8())?)));
+/// assert_eq!(nested, Ok((7, None)));
+/// assert_eq!(reader.read_bool(), Ok(true));
+/// reader.finish().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct TupleReader<'a> {
+    key: &'a [u8],
+    offset: usize,
+    /// The nesting depth of tuples
+    depth: usize,
+}
+
+macro_rules! read_ints {
+    ($($read:ident $t:ident);*;) => {
+        $(
+            #[inline]
+            pub fn $read(&mut self) -> Result<$t, KeyError> {
+                let offset = self.offset;
+                let x = self.read_i128_or_u128()?;
+                let res = match x {
+                    Ok(x) => $t::try_from(x).ok(),
+                    Err(x) => $t::try_from(x).ok(),
+                };
+                res.ok_or_else(|| {
+                    self.offset = offset;
+                    self.error(KeyErrorKind::OutOfRange)
+                })
+            }
+        )*
+    };
+}
+
+impl<'a> TupleReader<'a> {
+    read_ints!(
+        read_u8 u8;
+        read_i8 i8;
+        read_u16 u16;
+        read_i16 i16;
+        read_u32 u32;
+        read_i32 i32;
+        read_u64 u64;
+        read_i64 i64;
+        read_u128 u128;
+        read_i128 i128;
+    );
+
+    pub fn new(key: &'a [u8]) -> Self {
+        Self {
+            key,
+            offset: 0,
+            depth: 0,
+        }
+    }
+
+    /// Returns the offset of the next element
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the bytes after the elements read so far
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.key[self.offset..]
+    }
+
+    /// Returns the type code of the next element
+    pub fn peek_type_code(&self) -> Option<u8> {
+        self.key.get(self.offset).copied()
+    }
+
+    /// Checks that all of the key has been read
+    pub fn finish(self) -> Result<(), KeyError> {
+        if self.offset == self.key.len() {
+            Ok(())
+        } else {
+            Err(self.error(KeyErrorKind::TrailingBytes))
+        }
+    }
+
+    fn error(&self, kind: KeyErrorKind) -> KeyError {
+        KeyError {
+            kind,
+            offset: self.offset,
+        }
+    }
+
+    /// Takes the next `n` bytes
+    fn take(&mut self, n: usize) -> Result<&'a [u8], KeyError> {
+        let Some(bytes) = self.key.get(self.offset..(self.offset + n)) else {
+            return Err(self.error(KeyErrorKind::UnexpectedEnd))
+        };
+        self.offset += n;
+        Ok(bytes)
+    }
+
+    /// Takes the type code `code`, leaving the offset unchanged on error
+    fn expect(&mut self, code: u8) -> Result<(), KeyError> {
+        match self.peek_type_code() {
+            Some(c) if c == code => {
+                self.offset += 1;
+                Ok(())
+            }
+            Some(_) => Err(self.error(KeyErrorKind::InvalidTypeCode)),
+            None => Err(self.error(KeyErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    /// Reads an integer as an `i128` if it is negative, or as a `u128`
+    /// otherwise
+    fn read_i128_or_u128(&mut self) -> Result<Result<i128, u128>, KeyError> {
+        let offset = self.offset;
+        let res = self.read_int_parts();
+        if res.is_err() {
+            self.offset = offset;
+        }
+        let (negative, magnitude) = res?;
+        if !negative {
+            Ok(Err(magnitude))
+        } else if magnitude <= (1 << 127) {
+            Ok(Ok((magnitude as i128).wrapping_neg()))
+        } else {
+            self.offset = offset;
+            Err(self.error(KeyErrorKind::OutOfRange))
+        }
+    }
+
+    /// Reads the sign and magnitude of an integer
+    fn read_int_parts(&mut self) -> Result<(bool, u128), KeyError> {
+        let Some(code) = self.peek_type_code() else {
+            return Err(self.error(KeyErrorKind::UnexpectedEnd))
+        };
+        let (negative, n) = match code {
+            NEG_INT_START => {
+                self.offset += 1;
+                (true, usize::from(!self.take(1)?[0]))
+            }
+            0x0c..=0x13 => {
+                self.offset += 1;
+                (true, usize::from(INT_ZERO - code))
+            }
+            INT_ZERO..=0x1c => {
+                self.offset += 1;
+                (false, usize::from(code - INT_ZERO))
+            }
+            POS_INT_END => {
+                self.offset += 1;
+                (false, usize::from(self.take(1)?[0]))
+            }
+            _ => return Err(self.error(KeyErrorKind::InvalidTypeCode)),
+        };
+        let bytes = self.take(n)?;
+        // leading zero bytes are allowed, but the rest must fit
+        let zeros = bytes
+            .iter()
+            .take_while(|b| **b == if negative { 0xff } else { 0 })
+            .count();
+        let bytes = &bytes[zeros..];
+        if bytes.len() > 16 {
+            return Err(self.error(KeyErrorKind::OutOfRange))
+        }
+        let mut magnitude = 0u128;
+        for b in bytes {
+            let b = if negative { !b } else { *b };
+            magnitude = (magnitude << 8) | u128::from(b);
+        }
+        Ok((negative, magnitude))
+    }
+
+    /// Reads a null
+    pub fn read_null(&mut self) -> Result<(), KeyError> {
+        if self.read_if_null() {
+            Ok(())
+        } else if self.offset == self.key.len() {
+            Err(self.error(KeyErrorKind::UnexpectedEnd))
+        } else {
+            Err(self.error(KeyErrorKind::InvalidTypeCode))
+        }
+    }
+
+    /// Takes a null if the next element is one
+    fn read_if_null(&mut self) -> bool {
+        let rest = self.remaining();
+        let len = if self.depth == 0 {
+            if rest.first() != Some(&NULL) {
+                return false
+            }
+            1
+        } else {
+            if !rest.starts_with(&[NULL, ESCAPE]) {
+                return false
+            }
+            2
+        };
+        self.offset += len;
+        true
+    }
+
+    pub fn read_f32(&mut self) -> Result<f32, KeyError> {
+        self.expect(FLOAT)?;
+        let bytes = self.take(4).inspect_err(|_| self.offset -= 1)?;
+        Ok(decode_f32(bytes.try_into().unwrap()))
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, KeyError> {
+        self.expect(DOUBLE)?;
+        let bytes = self.take(8).inspect_err(|_| self.offset -= 1)?;
+        Ok(decode_f64(bytes.try_into().unwrap()))
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, KeyError> {
+        match self.peek_type_code() {
+            Some(FALSE) => {
+                self.offset += 1;
+                Ok(false)
+            }
+            Some(TRUE) => {
+                self.offset += 1;
+                Ok(true)
+            }
+            Some(_) => Err(self.error(KeyErrorKind::InvalidTypeCode)),
+            None => Err(self.error(KeyErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn read_escaped(&mut self, code: u8) -> Result<KeyBytes<'a>, KeyError> {
+        self.expect(code)?;
+        let rest = &self.key[self.offset..];
+        let mut i = 0;
+        loop {
+            match rest.get(i) {
+                Some(0) => {
+                    if rest.get(i + 1) == Some(&ESCAPE) {
+                        i += 1;
+                    } else {
+                        break
+                    }
+                }
+                Some(_) => (),
+                None => {
+                    self.offset -= 1;
+                    return Err(self.error(KeyErrorKind::UnexpectedEnd))
+                }
+            }
+            i += 1;
+        }
+        self.offset += i + 1;
+        Ok(KeyBytes {
+            raw: &rest[..i],
+            mask: 0,
+            grouped: false,
+        })
+    }
+
+    /// Reads a byte string
+    pub fn read_bytes(&mut self) -> Result<KeyBytes<'a>, KeyError> {
+        self.read_escaped(BYTES)
+    }
+
+    /// Reads a unicode string, validating it as UTF-8
+    pub fn read_str(&mut self) -> Result<KeyStr<'a>, KeyError> {
+        let offset = self.offset;
+        let bytes = self.read_escaped(STRING)?;
+        if utf8_pieces(bytes.iter(), |_| Ok(())).is_none() {
+            self.offset = offset;
+            return Err(self.error(KeyErrorKind::InvalidUtf8))
+        }
+        Ok(KeyStr { bytes })
+    }
+
+    /// Reads `None` for a null, or calls `f` to read the value otherwise
+    pub fn read_option<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, KeyError>,
+    ) -> Result<Option<T>, KeyError> {
+        if self.read_if_null() {
+            Ok(None)
+        } else {
+            f(self).map(Some)
+        }
+    }
+
+    /// Reads a nested tuple, whose elements are read by `f`
+    pub fn read_nested<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, KeyError>,
+    ) -> Result<T, KeyError> {
+        self.expect(NESTED)?;
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        let res = res?;
+        let rest = self.remaining();
+        match (rest.first(), rest.get(1)) {
+            (Some(0), Some(&ESCAPE)) => Err(self.error(KeyErrorKind::TrailingBytes)),
+            (Some(0), _) => {
+                self.offset += 1;
+                Ok(res)
+            }
+            (Some(_), _) => Err(self.error(KeyErrorKind::TrailingBytes)),
+            (None, _) => Err(self.error(KeyErrorKind::UnexpectedEnd)),
+        }
+    }
+}
@@ -0,0 +1,249 @@
+//! The memcomparable encoding of TiKV (and of the MySQL compatible stores built
+//! on it), for keys that have to interoperate with them. [MemcomparableBuilder]
+//! and [MemcomparableReader] have the same interface as `KeyBuilder` and
+//! `KeyReader`, restricted to the types of the encoding:
+//!
+//! - `u64`s are big endian, and `i64`s are big endian with the sign bit
+//!   flipped.
+//! - `f64`s are the same as `encode_f64`.
+//! - Byte strings and `str`s are split into groups of 8 bytes, and each group
+//!   is followed by a marker byte. A full group that is followed by more bytes
+//!   has the marker `ff`, and the last group is padded with 0 bytes to 8 bytes
+//!   and has the marker `ff - n` where `n` is the number of padding bytes. If
+//!   the length is a multiple of 8, this means that there is a last group of
+//!   only padding with the marker `f7`.
+//! - Descending fields have all the bytes of their encoding inverted.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+#[cfg(feature = "alloc")]
+use super::encode_f64;
+use super::{decode_f64, utf8_pieces, KeyBytes, KeyError, KeyErrorKind, KeyStr};
+
+/// The number of bytes in a group
+pub(super) const GROUP_LEN: usize = 8;
+/// The marker of a full group that is followed by more groups
+const FULL_MARKER: u8 = 0xff;
+
+/// Returns the number of bytes of a group that are not padding, for a valid
+/// uninverted `marker`
+#[inline]
+pub(super) fn group_data_len(marker: u8) -> usize {
+    GROUP_LEN - usize::from(FULL_MARKER - marker)
+}
+
+/// Builds a key in the memcomparable encoding field by field
+///
+/// ```
+/// use tree_ord::key::memcomparable::MemcomparableBuilder;
+///
+/// let key = MemcomparableBuilder::new()
+///     .push_bytes(&[1, 2, 3])
+///     .push_i64(-1)
+///     .finish();
+/// assert_eq!(key[..9], [1, 2, 3, 0, 0, 0, 0, 0, 0xfa]);
+/// assert_eq!(key[9..], [0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]);
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MemcomparableBuilder {
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "alloc")]
+impl MemcomparableBuilder {
+    pub fn new() -> Self {
+        Self { buf: Vec::new() }
+    }
+
+    /// Returns the encoding so far
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.buf
+    }
+
+    /// Returns the encoding, leaving the builder empty so that it can be reused
+    /// for another key
+    pub fn finish(&mut self) -> Vec<u8> {
+        core::mem::take(&mut self.buf)
+    }
+
+    /// Clears the encoding while keeping the allocation
+    pub fn clear(&mut self) {
+        self.buf.clear();
+    }
+
+    #[inline]
+    pub fn push_u64(&mut self, x: u64) -> &mut Self {
+        self.buf.extend_from_slice(&x.to_be_bytes());
+        self
+    }
+
+    #[inline]
+    pub fn push_i64(&mut self, x: i64) -> &mut Self {
+        self.push_u64((x as u64) ^ (1 << 63))
+    }
+
+    #[inline]
+    pub fn push_f64(&mut self, x: f64) -> &mut Self {
+        self.buf.extend_from_slice(&encode_f64(x));
+        self
+    }
+
+    /// Pushes a byte string in groups of 8 bytes
+    pub fn push_bytes(&mut self, x: &[u8]) -> &mut Self {
+        let mut chunks = x.chunks_exact(GROUP_LEN);
+        for chunk in chunks.by_ref() {
+            self.buf.extend_from_slice(chunk);
+            self.buf.push(FULL_MARKER);
+        }
+        let rest = chunks.remainder();
+        let pad = GROUP_LEN - rest.len();
+        self.buf.extend_from_slice(rest);
+        self.buf.extend_from_slice(&[0; GROUP_LEN][..pad]);
+        self.buf.push(FULL_MARKER - pad as u8);
+        self
+    }
+
+    /// Pushes a `str` the same way as a byte string
+    #[inline]
+    pub fn push_str(&mut self, x: &str) -> &mut Self {
+        self.push_bytes(x.as_bytes())
+    }
+
+    /// Pushes the fields pushed by `f` in descending order, by inverting their
+    /// encoding
+    pub fn push_desc(&mut self, f: impl FnOnce(&mut Self) -> &mut Self) -> &mut Self {
+        let start = self.buf.len();
+        f(self);
+        for b in &mut self.buf[start..] {
+            *b = !*b;
+        }
+        self
+    }
+}
+
+/// Reads the fields of a key in the memcomparable encoding
+///
+/// ```
+/// use tree_ord::key::memcomparable::MemcomparableReader;
+///
+/// let mut reader = MemcomparableReader::new(&[1, 2, 3, 0, 0, 0, 0, 0, 0xfa]);
+/// assert_eq!(
+///     reader.read_bytes().unwrap().as_slice(),
+///     Some(&[1, 2, 3][..])
+/// );
+/// reader.finish().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct MemcomparableReader<'a> {
+    key: &'a [u8],
+    offset: usize,
+    /// `ff` within descending fields
+    mask: u8,
+}
+
+impl<'a> MemcomparableReader<'a> {
+    pub fn new(key: &'a [u8]) -> Self {
+        Self {
+            key,
+            offset: 0,
+            mask: 0,
+        }
+    }
+
+    /// Returns the offset of the next field
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Returns the bytes after the fields read so far
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.key[self.offset..]
+    }
+
+    /// Checks that all of the key has been read
+    pub fn finish(self) -> Result<(), KeyError> {
+        if self.offset == self.key.len() {
+            Ok(())
+        } else {
+            Err(self.error(KeyErrorKind::TrailingBytes))
+        }
+    }
+
+    fn error(&self, kind: KeyErrorKind) -> KeyError {
+        KeyError {
+            kind,
+            offset: self.offset,
+        }
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, KeyError> {
+        let Some(bytes) = self.key.get(self.offset..(self.offset + 8)) else {
+            return Err(self.error(KeyErrorKind::UnexpectedEnd))
+        };
+        self.offset += 8;
+        Ok(u64::from_be_bytes(bytes.try_into().unwrap()) ^ u64::from_ne_bytes([self.mask; 8]))
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, KeyError> {
+        Ok((self.read_u64()? ^ (1 << 63)) as i64)
+    }
+
+    pub fn read_f64(&mut self) -> Result<f64, KeyError> {
+        Ok(decode_f64(self.read_u64()?.to_be_bytes()))
+    }
+
+    /// Reads a byte string, validating its group markers and padding
+    pub fn read_bytes(&mut self) -> Result<KeyBytes<'a>, KeyError> {
+        let rest = &self.key[self.offset..];
+        let mut len = 0;
+        loop {
+            let Some(group) = rest.get(len..(len + GROUP_LEN + 1)) else {
+                return Err(self.error(KeyErrorKind::UnexpectedEnd))
+            };
+            len += GROUP_LEN + 1;
+            let marker = group[GROUP_LEN] ^ self.mask;
+            if marker == FULL_MARKER {
+                continue
+            }
+            if marker < FULL_MARKER - GROUP_LEN as u8 {
+                return Err(self.error(KeyErrorKind::InvalidGroup))
+            }
+            let padding = &group[group_data_len(marker)..GROUP_LEN];
+            if padding.iter().any(|b| *b != self.mask) {
+                return Err(self.error(KeyErrorKind::InvalidGroup))
+            }
+            break
+        }
+        self.offset += len;
+        Ok(KeyBytes {
+            raw: &rest[..len],
+            mask: self.mask,
+            grouped: true,
+        })
+    }
+
+    /// Reads a `str`, validating it as UTF-8
+    pub fn read_str(&mut self) -> Result<KeyStr<'a>, KeyError> {
+        let offset = self.offset;
+        let bytes = self.read_bytes()?;
+        if utf8_pieces(bytes.iter(), |_| Ok(())).is_none() {
+            self.offset = offset;
+            return Err(self.error(KeyErrorKind::InvalidUtf8))
+        }
+        Ok(KeyStr { bytes })
+    }
+
+    /// Reads the fields read by `f` as descending fields
+    pub fn read_desc<T>(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<T, KeyError>,
+    ) -> Result<T, KeyError> {
+        self.mask = !self.mask;
+        let res = f(self);
+        self.mask = !self.mask;
+        res
+    }
+}
@@ -1,28 +1,102 @@
-//! Note that there are "alloc" and "std" feature flags that can be turned off
+//! Note that there are "alloc" and "std" feature flags that can be turned off.
+//! The other feature flags are:
+//!
+//! - "rayon": the `par` module of parallel batch searches
+//! - "checked": keeps the `checked::CheckedTreeOrd` assertions in release
+//! - "simd": uses `core::simd` in `TreeOrdBytes`, requires nightly
+//! - "nightly-specialization": specializes `[u8]`, requires nightly
+//! - "aligned-chunks": aligns `TreeOrdBytes` chunks to the left hand side
+//! - "stats": the `stats` module of process wide comparison counters
+//! - "unicode-case": `CaseFolded`, embeds a case folding table
+//! - "unicode-normalization": `AccentInsensitive` and `Nfc`, embeds tables
+//! - "testing": the `testing` module for counting comparisons, enables "std"
+//! - "corpus": the `corpus` module of deterministic benchmark corpora
+//! - "collections": the `TreeMap` of the `collections` module
+//!
+//! The `key` module has order preserving encodings of composite keys, and its
+//! `KeyBuilder` requires "alloc". The `dynamic` module has object safe versions
+//! of `TreeOrd` and `Tracker` and also requires "alloc". The `collections`
+//! module has containers that thread trackers through their operations, and its
+//! `SortedVecSet` requires "alloc".
 
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
+#![cfg_attr(feature = "nightly-specialization", feature(min_specialization))]
 
 use core::{
     any::TypeId,
+    borrow::Borrow,
     cell::{Cell, RefCell},
     cmp::min,
+    hash::{Hash, Hasher},
     marker::PhantomData,
+    mem::size_of,
     num::{
         NonZeroI128, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroIsize, NonZeroU128,
         NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU8, NonZeroUsize,
     },
-    ops::Deref,
+    ops::{BitOr, BitXor, Deref},
     pin::Pin,
 };
 #[cfg(feature = "alloc")]
 extern crate alloc;
 use core::{cmp::Ordering, time::Duration};
 
-use utils::{LexicographicTracker, ResultTracker};
+#[cfg(not(feature = "nightly-specialization"))]
+use utils::as_bytes_tree_cmp;
+#[cfg(feature = "alloc")]
+use utils::BoxedTracker;
+use utils::{
+    case_tiebreak_tree_cmp, lexicographic_diverged, lexicographic_divergence,
+    lexicographic_len_cmp, lexicographic_tree_cmp, lexicographic_tree_cmp_hereditary,
+    lexicographic_tree_eq, mapped_bytes_tree_cmp, shortlex_tree_cmp, suffix_tree_cmp,
+    AdaptiveBytesTracker, CaseTiebreakTracker, LexicographicTracker, MaybeTrackedTracker,
+    NaturalTracker, ResultTracker, ShortlexTracker,
+};
 use Ordering::*;
+#[cfg(feature = "unicode-normalization")]
+mod accents;
+#[cfg(feature = "unicode-case")]
+mod case_fold;
+pub mod checked;
+#[cfg(feature = "alloc")]
+pub mod collections;
+#[cfg(feature = "corpus")]
+pub mod corpus;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+#[cfg(feature = "std")]
+pub mod instrument;
+pub mod key;
+pub mod merge;
+#[cfg(feature = "unicode-normalization")]
+mod normalize;
+#[cfg(feature = "rayon")]
+pub mod par;
+#[cfg(feature = "alloc")]
+pub mod pool;
+pub mod prefix;
+#[cfg(feature = "std")]
+pub mod scoped;
+pub mod search;
+#[cfg(feature = "stats")]
+pub mod stats;
+#[cfg(not(feature = "stats"))]
+mod stats {
+    #[inline(always)]
+    pub(crate) fn record(_: usize) {}
+}
+// in a separate file so that stable does not parse the specialization syntax
+#[cfg(feature = "nightly-specialization")]
+mod specialization;
+#[cfg(feature = "testing")]
+pub mod testing;
 pub mod utils;
 
 /// A trait for structs used in `TreeOrd` impls to store prefix information
+///
+/// The `Tracker`s in this crate implement `Clone`, `Debug`, and `Default`
+/// (equivalent to `Tracker::new`) whenever their subtrackers do.
 pub trait Tracker {
     /// Indicates if the `Tracker` is a no-op that does no prefix tracking or
     /// anything to help `TreeOrd` with. This can be used by `TreeOrd` impls to
@@ -31,14 +105,101 @@ pub trait Tracker {
     /// tracking state themselves or not.
     const IS_NOOP: bool;
 
+    /// The state saved by `snapshot`
+    type Snapshot;
+
+    /// A new `Tracker` that starts with no known prefix, usable in const
+    /// contexts such as `static` items
+    const NEW: Self;
+
     /// Creates a new `Tracker` that starts with no known prefix
-    fn new() -> Self;
+    #[inline]
+    fn new() -> Self
+    where
+        Self: Sized,
+    {
+        Self::NEW
+    }
+
+    /// Resets the `Tracker` to a state equivalent to `Self::new()`, so that it
+    /// can be reused for another search. Implementations may do this more
+    /// cheaply than reconstructing the whole `Tracker`.
+    #[inline]
+    fn reset(&mut self)
+    where
+        Self: Sized,
+    {
+        *self = Self::new();
+    }
+
+    /// Saves the state of the `Tracker`, so that a backtracking search can
+    /// later `restore` it.
+    ///
+    /// Every comparison can strengthen the bounds of the `Tracker`, and the
+    /// bounds are only valid for keys between the keys of the comparisons that
+    /// established them. If a search backs up to an earlier point and then
+    /// compares with a key that is outside of the bounds established by
+    /// comparisons made after that point (e.g. going back up a tree to an
+    /// ancestor and then descending into a sibling subtree), it must first
+    /// `restore` a snapshot taken at that point, or else comparisons may return
+    /// wrong results. Continuing with keys that are still within the current
+    /// bounds does not require a restore.
+    fn snapshot(&self) -> Self::Snapshot;
+
+    /// Restores the state saved by `snapshot`
+    fn restore(&mut self, snapshot: Self::Snapshot);
+
+    /// Returns the number of leading elements of the query (bytes for
+    /// `TreeOrdBytes` and `TreeOrdVec`) that are proven to be shared with
+    /// every key within the current bounds, that is every key between the keys
+    /// of the strongest `Less` and `Greater` comparisons so far (or between the
+    /// keys of the known prefix, for trackers constructed with one). Radix
+    /// trees and tries can use this to skip rechecking prefixes while
+    /// descending. Returns `None` for no-op trackers and trackers that do not
+    /// track a sequence prefix.
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        None
+    }
+
+    /// Returns an independent copy of the `Tracker` for continuing a search
+    /// in another branch, such as descending into both subtrees of a node when
+    /// collecting all equal keys. This is only valid for continuing with keys
+    /// within the bounds established so far.
+    ///
+    /// The state of a `Tracker` only depends on the comparisons made with it,
+    /// and each comparison returning `Less` or `Greater` only claims that the
+    /// query shares some prefix with every key between the key of that
+    /// comparison and the query. Every key within the current bounds is
+    /// between the query and the keys of the strongest `Less` and `Greater`
+    /// comparisons, so the prefix knowledge accumulated before the fork holds
+    /// for all of them, no matter which branch they are reached from. Each
+    /// branch then only strengthens its own copy, so the comparisons of one
+    /// branch can never invalidate the other.
+    fn fork(&self) -> Self
+    where
+        Self: Sized,
+    {
+        let mut res = Self::new();
+        res.restore(self.snapshot());
+        res
+    }
 }
 
 impl Tracker for () {
+    type Snapshot = ();
+
     const IS_NOOP: bool = true;
+    const NEW: Self = ();
+
+    #[inline]
+    fn reset(&mut self) {}
 
-    fn new() -> Self {}
+    #[inline]
+    fn snapshot(&self) {}
+
+    #[inline]
+    fn restore(&mut self, _: ()) {}
 }
 
 /// An ordering trait for faster comparisons in binary tree searches
@@ -123,16 +284,16 @@ impl Tracker for () {
 /// equal keys for one move and would need to be redirected, which couldn't
 /// happen if `Equal` made all future returns `Equal` or something like that.
 /// Additionally, the last comparison of any valid series of comparisons is
-/// allowed to be repeated any number of times.
+/// allowed to be repeated any number of times. `TreeOrd::tree_cmp_hereditary`
+/// is an opt-in for searches that can never leave the group of equal keys.
 ///
 /// Note: When using `TreeOrd` for a datastructure with fast access to the
 /// minimum and maximum keys of a tree, the minimum or maximum keys should be
 /// `tree_cmp`ed with after the root node, because if there is some bias where
 /// insertions are happening close to one edge of the tree, then the tracker
 /// can't optimize things like lots of leading zero bytes early because it needs
-/// both `Less` and `Greater` cases. In the future we may have better
-/// specializations that are aware of the absolute minimum and maximum values of
-/// `T`.
+/// both `Less` and `Greater` cases. `search::tracker_with_bounds` does this.
+/// For types with absolute minimum and maximum values, see [TreeOrdBounded].
 pub trait TreeOrd<Rhs = Self>
 where
     Self: Ord,
@@ -141,6 +302,49 @@ where
     type Tracker: Tracker;
 
     fn tree_cmp(&self, rhs: &Rhs, tracker: &mut Self::Tracker) -> Ordering;
+
+    /// An opt-in version of `tree_cmp` for hereditary searches, where an
+    /// `Equal` result is recorded in the tracker like a `Greater` result with
+    /// the whole key being equal. This lets keys between the strongest `Less`
+    /// comparison and the equal key skip the prefix shared with the `Less`
+    /// key, which would otherwise need a `Greater` comparison to be proven. The
+    /// default implementation just calls `tree_cmp`, sequence types override
+    /// it.
+    ///
+    /// This is only sound if after every `Equal` result, the search either
+    /// stops or only continues with keys less than or equal to the query that
+    /// are above all keys that returned `Less`. This is the case for a lower
+    /// bound binary search that treats `Equal` like `Greater` (e.g.
+    /// `search::tree_lower_bound_hereditary`), or for descending a binary
+    /// search tree where equal keys are always found in the left subtree of
+    /// an equal key. Searching for an upper bound, scanning forwards past
+    /// an equal key, or using the tracker with nonhereditary trees where
+    /// the search can leave the group of equal keys may return wrong
+    /// orderings. Use a fresh tracker or one `restore`d from before the
+    /// first `Equal` for those.
+    ///
+    /// Unlike `tree_cmp`, the side of the query matters: `self` must be the
+    /// key and `rhs` the query, as in the searches of this crate. The tracker
+    /// does not know which side the query is on, and with the query on the
+    /// left hand side the `Equal` is recorded on the side of the keys greater
+    /// than the query. [checked::Checked] panics if the query is on the left
+    /// hand side.
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Rhs, tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp(rhs, tracker)
+    }
+
+    /// Returns the same as `tree_cmp` along with the index of the first
+    /// element where `self` and `rhs` differ, or the length of the shorter
+    /// one if one is a prefix of the other (the common length for `Equal`).
+    /// The index is absolute and counts the prefix that the tracker let the
+    /// comparison skip. Slices, `str`, and the byte types implement this from
+    /// the lengths they record in the tracker anyway, so it costs nothing
+    /// extra. The default implementation returns `usize::MAX` as the index.
+    #[inline]
+    fn tree_cmp_detail(&self, rhs: &Rhs, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        (self.tree_cmp(rhs, tracker), usize::MAX)
+    }
 }
 
 impl TreeOrd<Self> for () {
@@ -176,293 +380,3803 @@ impl_simple_tree_ord!(
 // TODO when stabilized in core
 //IpAddr SocketAddr Ipv4Addr Ipv6Addr SocketAddrV4 SocketAddrV6
 
-/// Wrapper that implements `TreeOrd` with a no-op `Tracker` for any `T: Ord`.
-/// It may be important to implement `TreeOrd` manually for large and
-/// complicated `T`.
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-#[repr(transparent)]
-pub struct OrdToTreeOrd<T: Ord>(pub T);
+/// A `TreeOrd` type with absolute minimum and maximum values. No search can
+/// find keys outside of them, so they can act as the bounds of a search before
+/// any real keys are compared, see [search::tracker_with_extremes]. This helps
+/// when queries are at the edge of a structure (such as with ascending
+/// inserts) and the tracker would otherwise only ever get one side bounded.
+/// The trackers of slices can use this through
+/// [LexicographicTracker::with_extremes].
+pub trait TreeOrdBounded: TreeOrd + Sized {
+    /// The value that every value is greater than or equal to
+    const MIN: Self;
+    /// The value that every value is less than or equal to
+    const MAX: Self;
+}
 
-impl<T: Ord> TreeOrd<Self> for OrdToTreeOrd<T> {
-    type Tracker = ();
+macro_rules! impl_tree_ord_bounded {
+    ($($t:ident)*) => {
+        $(
+            impl TreeOrdBounded for $t {
+                const MIN: Self = $t::MIN;
+                const MAX: Self = $t::MAX;
+            }
+        )*
+    };
+}
 
-    #[inline]
-    fn tree_cmp(&self, rhs: &Self, _: &mut Self::Tracker) -> Ordering {
-        self.0.cmp(&rhs.0)
-    }
+impl_tree_ord_bounded!(usize u8 u16 u32 u64 u128 isize i8 i16 i32 i64 i128 char);
+
+impl TreeOrdBounded for bool {
+    const MAX: Self = true;
+    const MIN: Self = false;
 }
 
-/// Like [core::cmp::Reverse] except for `TreeOrd`
-#[derive(PartialEq, Eq, PartialOrd, Ord)]
-#[repr(transparent)]
-pub struct TreeOrdReverse<T: TreeOrd>(pub T);
+impl TreeOrdBounded for () {
+    const MAX: Self = ();
+    const MIN: Self = ();
+}
 
-impl<T: TreeOrd> TreeOrd<Self> for TreeOrdReverse<T> {
-    type Tracker = T::Tracker;
+impl<T: TreeOrdBounded> TreeOrdBounded for Option<T> {
+    const MAX: Self = Some(T::MAX);
+    const MIN: Self = None;
+}
 
-    #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        self.0.tree_cmp(&rhs.0, tracker).reverse()
-    }
+macro_rules! impl_tree_ord_bounded_tuple {
+    ($($($t:ident)+;)*) => {
+        $(
+            impl<$($t: TreeOrdBounded,)+> TreeOrdBounded for ($($t,)+) {
+                const MIN: Self = ($($t::MIN,)+);
+                const MAX: Self = ($($t::MAX,)+);
+            }
+        )*
+    };
 }
 
-impl<T: TreeOrd> TreeOrd<Self> for &T {
+impl_tree_ord_bounded_tuple!(
+    A;
+    A B;
+    A B C;
+    A B C D;
+    A B C D E;
+    A B C D E F;
+    A B C D E F G;
+    A B C D E F G H;
+    A B C D E F G H I;
+    A B C D E F G H I J;
+    A B C D E F G H I J K;
+    A B C D E F G H I J K L;
+);
+
+/// A version of [TreeOrd] for types that are only `PartialOrd`, such as
+/// floats. `partial_tree_cmp` returns `None` if `self` and `rhs` are
+/// unordered. Implementations must only update the tracker on `Some` results,
+/// so that an unordered comparison leaves the tracker as it was before it.
+///
+/// Every `TreeOrd` type implements this by wrapping its `tree_cmp` in `Some`.
+/// `f32` and `f64` return `None` if either side is NaN. See
+/// [search::partial_tree_binary_search] for searching.
+pub trait PartialTreeOrd<Rhs = Self>
+where
+    Self: PartialOrd,
+    Rhs: ?Sized,
+{
+    type Tracker: Tracker;
+
+    fn partial_tree_cmp(&self, rhs: &Rhs, tracker: &mut Self::Tracker) -> Option<Ordering>;
+}
+
+impl<T, Rhs> PartialTreeOrd<Rhs> for T
+where
+    T: TreeOrd<Rhs> + ?Sized,
+    Rhs: ?Sized,
+{
     type Tracker = T::Tracker;
 
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        TreeOrd::tree_cmp(*self, *rhs, tracker)
+    fn partial_tree_cmp(&self, rhs: &Rhs, tracker: &mut Self::Tracker) -> Option<Ordering> {
+        Some(self.tree_cmp(rhs, tracker))
     }
 }
 
-impl<T: TreeOrd> TreeOrd<Self> for &mut T {
-    type Tracker = T::Tracker;
+macro_rules! impl_partial_tree_ord_float {
+    ($($t:ident)*) => {
+        $(
+            impl PartialTreeOrd<Self> for $t {
+                type Tracker = ();
+
+                #[inline]
+                fn partial_tree_cmp(&self, rhs: &Self, _: &mut Self::Tracker) -> Option<Ordering> {
+                    self.partial_cmp(rhs)
+                }
+            }
+        )*
+    };
+}
+
+impl_partial_tree_ord_float!(f32 f64);
 
+/// Equality checks in the middle of a search, for things like duplicate
+/// detection where no ordering is needed. `tree_eq` skips the prefix that
+/// `tracker` has proven to be shared by `rhs` and all keys within the bounds
+/// of the search, and sequence types return early if the lengths differ.
+///
+/// The tracker is only read and never updated, so it stays valid for the
+/// ongoing search whatever the result is. The same contract as
+/// `TreeOrd::tree_cmp` applies, `self` must be within the current bounds and
+/// `rhs` must be the query of the search. The default implementation calls
+/// `tree_cmp` with a `Tracker::fork` of the tracker.
+///
+/// ```
+/// use tree_ord::{Tracker, TreeEq, TreeOrd};
+///
+/// let query = vec![1u8, 2, 3];
+/// let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+/// vec![1u8, 2, 0].tree_cmp(&query, &mut tracker);
+/// vec![1u8, 2, 4].tree_cmp(&query, &mut tracker);
+/// // only the last element is compared
+/// assert!(vec![1u8, 2, 3].tree_eq(&query, &tracker));
+/// assert!(!vec![1u8, 2, 3, 0].tree_eq(&query, &tracker));
+/// ```
+pub trait TreeEq<Rhs = Self>: TreeOrd<Rhs>
+where
+    Rhs: ?Sized,
+{
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        TreeOrd::tree_cmp(*self, *rhs, tracker)
+    fn tree_eq(&self, rhs: &Rhs, tracker: &Self::Tracker) -> bool {
+        self.tree_cmp(rhs, &mut tracker.fork()) == Ordering::Equal
     }
 }
 
-impl<T: TreeOrd + Copy> TreeOrd<Self> for Cell<T> {
-    type Tracker = T::Tracker;
-
+impl TreeEq<Self> for () {
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        TreeOrd::tree_cmp(&self.get(), &rhs.get(), tracker)
+    fn tree_eq(&self, _: &Self, _: &Self::Tracker) -> bool {
+        true
     }
 }
 
-impl<T: TreeOrd + ?Sized> TreeOrd<Self> for RefCell<T> {
-    type Tracker = T::Tracker;
+macro_rules! impl_simple_tree_eq {
+    ($($t:ident)*) => {
+        $(
+            impl TreeEq<Self> for $t {
+                #[inline]
+                fn tree_eq(&self, rhs: &Self, _: &Self::Tracker) -> bool {
+                    self == rhs
+                }
+            }
+        )*
+    };
+}
+
+impl_simple_tree_eq!(
+    usize u8 u16 u32 u64 u128 NonZeroUsize NonZeroU8 NonZeroU16 NonZeroU32 NonZeroU64 NonZeroU128
+    isize i8 i16 i32 i64 i128 NonZeroIsize NonZeroI8 NonZeroI16 NonZeroI32 NonZeroI64 NonZeroI128
+    bool char
+    Ordering TypeId Duration
+);
 
+impl<T: TreeOrd> TreeEq<Self> for [T] {
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        self.borrow().tree_cmp(&*rhs.borrow(), tracker)
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        lexicographic_tree_eq(self, rhs, tracker)
     }
 }
 
-impl<T: ?Sized> TreeOrd<Self> for PhantomData<T> {
-    type Tracker = ();
+impl TreeEq<Self> for str {
+    #[inline]
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        self.as_bytes().tree_eq(rhs.as_bytes(), tracker)
+    }
+}
 
+impl<T: TreeOrd, const N: usize> TreeEq<Self> for [T; N] {
     #[inline]
-    fn tree_cmp(&self, _: &Self, _: &mut Self::Tracker) -> Ordering {
-        Ordering::Equal
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        self.as_slice().tree_eq(rhs.as_slice(), tracker)
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<T: TreeOrd + ?Sized> TreeOrd<Self> for alloc::boxed::Box<T> {
-    type Tracker = T::Tracker;
-
+impl<T: TreeOrd> TreeEq<Self> for alloc::vec::Vec<T> {
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        TreeOrd::tree_cmp(self.as_ref(), rhs.as_ref(), tracker)
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        self.as_slice().tree_eq(rhs.as_slice(), tracker)
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<T: TreeOrd + ?Sized> TreeOrd<Self> for alloc::rc::Rc<T> {
-    type Tracker = T::Tracker;
+impl TreeEq<Self> for alloc::string::String {
+    #[inline]
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        self.as_bytes().tree_eq(rhs.as_bytes(), tracker)
+    }
+}
 
+impl<'a> TreeEq<Self> for TreeOrdBytes<'a> {
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        TreeOrd::tree_cmp(self.as_ref(), rhs.as_ref(), tracker)
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        self.0.tree_eq(rhs.0, tracker)
     }
 }
 
-#[cfg(feature = "alloc")]
-impl<T: TreeOrd + ?Sized> TreeOrd<Self> for alloc::sync::Arc<T> {
-    type Tracker = T::Tracker;
+impl<T: TreeEq> TreeEq<Self> for &T {
+    #[inline]
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        TreeEq::tree_eq(*self, *rhs, tracker)
+    }
+}
 
+#[cfg(feature = "alloc")]
+impl<T: TreeEq + ?Sized> TreeEq<Self> for alloc::boxed::Box<T> {
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        TreeOrd::tree_cmp(self.as_ref(), rhs.as_ref(), tracker)
+    fn tree_eq(&self, rhs: &Self, tracker: &Self::Tracker) -> bool {
+        TreeEq::tree_eq(self.as_ref(), rhs.as_ref(), tracker)
     }
 }
 
-// TODO for `Saturating` and `Wrapping` when impls become stable
+macro_rules! impl_tree_eq_tuple {
+    ($($($t:ident)+;)*) => {
+        $(
+            // the default implementation, the fork still skips the locked
+            // fields
+            impl<$($t: TreeOrd,)+> TreeEq<Self> for ($($t,)+) {}
+        )*
+    };
+}
 
-impl<P> TreeOrd<Self> for Pin<P>
-where
-    P: Deref,
-    <P as Deref>::Target: TreeOrd,
-{
-    type Tracker = <<P as Deref>::Target as TreeOrd>::Tracker;
+impl_tree_eq_tuple!(
+    A;
+    A B;
+    A B C;
+    A B C D;
+    A B C D E;
+    A B C D E F;
+    A B C D E F G;
+    A B C D E F G H;
+    A B C D E F G H I;
+    A B C D E F G H I J;
+    A B C D E F G H I J K;
+    A B C D E F G H I J K L;
+);
+
+/// Wrapper that implements `TreeOrd` with a no-op `Tracker` for any `T: Ord`.
+/// It may be important to implement `TreeOrd` manually for large and
+/// complicated `T`.
+///
+/// `Eq`, `Ord`, and `Hash` are the same as those of `T`, so this also
+/// implements `Borrow<T>` and can be looked up by `&T` in `HashMap`s and
+/// `BTreeMap`s.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct OrdToTreeOrd<T: Ord>(pub T);
 
+impl<T: Ord> OrdToTreeOrd<T> {
+    /// Reinterprets a reference without copying
     #[inline]
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        self.deref().tree_cmp(rhs.deref(), tracker)
+    pub fn from_ref(t: &T) -> &Self {
+        // Safety: `OrdToTreeOrd<T>` is `repr(transparent)` over `T`
+        unsafe { &*(t as *const T as *const Self) }
     }
-}
 
-impl<T: TreeOrd> TreeOrd<Self> for Option<T> {
-    type Tracker = T::Tracker;
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
 
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        match (self, rhs) {
-            (None, None) => Equal,
-            (None, Some(_)) => Less,
-            (Some(_), None) => Greater,
-            (Some(lhs), Some(rhs)) => lhs.tree_cmp(rhs, tracker),
-        }
+    #[inline]
+    pub fn as_inner(&self) -> &T {
+        &self.0
     }
 }
 
-impl<T: TreeOrd, E: TreeOrd> TreeOrd<Self> for Result<T, E> {
-    type Tracker = ResultTracker<T, E>;
-
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        match (self, rhs) {
-            (Ok(lhs), Ok(rhs)) => lhs.tree_cmp(rhs, &mut tracker.t),
-            (Ok(_), Err(_)) => Less,
-            (Err(_), Ok(_)) => Greater,
-            (Err(lhs), Err(rhs)) => lhs.tree_cmp(rhs, &mut tracker.e),
-        }
+impl<T: Ord> From<T> for OrdToTreeOrd<T> {
+    #[inline]
+    fn from(x: T) -> Self {
+        Self(x)
     }
 }
 
-impl<T: TreeOrd> TreeOrd<Self> for [T] {
-    type Tracker = LexicographicTracker<T>;
+impl<T: Ord> Deref for OrdToTreeOrd<T> {
+    type Target = T;
 
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        // use this, because otherwise the compiler would not optimize stuff away since
-        // `tracker` is an external thing to the function
-        let not_noop = !<T as TreeOrd>::Tracker::IS_NOOP;
-        let start = min(tracker.min_eq_len, tracker.max_eq_len);
-        let end = min(self.len(), rhs.len());
-        if start >= end {
-            return self.len().cmp(&rhs.len())
-        }
-        let len = end.wrapping_sub(start);
-        // enable bound check elmination in the compiler
-        let x = &self[start..end];
-        let y = &rhs[start..end];
-        // unroll first iter to handle subtracker which tracks only the `start` element
-        let i = start;
-        if not_noop && (i != tracker.subtracker_i) {
-            tracker.subtracker = <T as TreeOrd>::Tracker::new();
-            tracker.subtracker_i = i;
-        }
-        match x[0].tree_cmp(&y[0], &mut tracker.subtracker) {
-            Less => return Less,
-            Equal => (),
-            Greater => return Greater,
-        }
-        for j in 1..len {
-            let i = j.wrapping_add(start);
-            match x[j].cmp(&y[j]) {
-                Less => {
-                    tracker.max_eq_len = i;
-                    return Less
-                }
-                Equal => (),
-                Greater => {
-                    tracker.min_eq_len = i;
-                    return Greater
-                }
-            }
-        }
-        self.len().cmp(&rhs.len())
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
     }
 }
 
-impl TreeOrd<Self> for str {
-    type Tracker = <[u8] as TreeOrd>::Tracker;
-
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        self.as_bytes().tree_cmp(rhs.as_bytes(), tracker)
+impl<T: Ord> AsRef<T> for OrdToTreeOrd<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        &self.0
     }
 }
 
-impl<T: TreeOrd, const N: usize> TreeOrd<Self> for [T; N] {
-    type Tracker = <[T] as TreeOrd>::Tracker;
-
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        self.as_slice().tree_cmp(rhs.as_slice(), tracker)
+impl<T: Ord> Borrow<T> for OrdToTreeOrd<T> {
+    #[inline]
+    fn borrow(&self) -> &T {
+        &self.0
     }
 }
 
-#[cfg(feature = "alloc")]
-impl<T: TreeOrd> TreeOrd<Self> for alloc::vec::Vec<T> {
-    type Tracker = <[T] as TreeOrd>::Tracker;
+impl<T: Ord> TreeOrd<Self> for OrdToTreeOrd<T> {
+    type Tracker = ();
 
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        self.as_slice().tree_cmp(rhs.as_slice(), tracker)
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, _: &mut Self::Tracker) -> Ordering {
+        self.0.cmp(&rhs.0)
     }
 }
 
-#[cfg(feature = "alloc")]
-impl TreeOrd<Self> for alloc::string::String {
-    type Tracker = <[u8] as TreeOrd>::Tracker;
+/// Like [core::cmp::Reverse] except for `TreeOrd`
+///
+/// This does not implement `Borrow<T>`, because the ordering is the reverse of
+/// that of `T` and `Borrow` requires them to be the same.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct TreeOrdReverse<T: TreeOrd>(pub T);
 
-    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        self.as_bytes().tree_cmp(rhs.as_bytes(), tracker)
+impl<T: TreeOrd> TreeOrdReverse<T> {
+    /// Reinterprets a reference without copying
+    #[inline]
+    pub fn from_ref(t: &T) -> &Self {
+        // Safety: `TreeOrdReverse<T>` is `repr(transparent)` over `T`
+        unsafe { &*(t as *const T as *const Self) }
     }
-}
 
-/// The generic `[T]` impl is not performant for `[u8]`. We can't specialize the
-/// `[T]` impl on stable, so this exists to compare bytes in chunks of bytes.
-/// However, it seems this is only more performant for very long slices and deep
-/// trees, you should benchmark to see if this is faster for your usecase.
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct TreeOrdBytes<'a>(pub &'a [u8]);
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
 
-impl<'a> TreeOrd<Self> for TreeOrdBytes<'a> {
-    type Tracker = LexicographicTracker<u8>;
+    #[inline]
+    pub fn as_inner(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: TreeOrd> From<T> for TreeOrdReverse<T> {
+    #[inline]
+    fn from(x: T) -> Self {
+        Self(x)
+    }
+}
+
+impl<T: TreeOrd> From<core::cmp::Reverse<T>> for TreeOrdReverse<T> {
+    #[inline]
+    fn from(x: core::cmp::Reverse<T>) -> Self {
+        Self(x.0)
+    }
+}
+
+impl<T: TreeOrd> From<TreeOrdReverse<T>> for core::cmp::Reverse<T> {
+    #[inline]
+    fn from(x: TreeOrdReverse<T>) -> Self {
+        Self(x.0)
+    }
+}
+
+impl<T: TreeOrd> Deref for TreeOrdReverse<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: TreeOrd> AsRef<T> for TreeOrdReverse<T> {
+    #[inline]
+    fn as_ref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T: TreeOrd> PartialOrd for TreeOrdReverse<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: TreeOrd> Ord for TreeOrdReverse<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0).reverse()
+    }
+}
+
+// `tree_cmp_hereditary` is not forwarded, since `T` would record an `Equal`
+// like a `Greater` of `T`, which is a `Less` of `TreeOrdReverse<T>`. The
+// hereditary search continues with keys above the query in the order of `T`,
+// which the bound from the `Equal` says nothing about.
+impl<T: TreeOrd> TreeOrd<Self> for TreeOrdReverse<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.0.tree_cmp(&rhs.0, tracker).reverse()
+    }
+}
+
+/// Combinators for the fields of composite keys, so that a key can be built
+/// like `(user_id, timestamp.tree_reversed())`. This is implemented for every
+/// sized `TreeOrd` type.
+///
+/// The wrappers keep the trackers of tuple keys composing as usual. A tuple
+/// has a subtracker for each field and skips the fields that are proven to be
+/// shared, and
+/// - [TreeOrdExt::tree_reversed] keeps the tracker of the field, because the
+///   prefix shared by the keys within the bounds of a search is the same no
+///   matter which way the field is ordered
+/// - [TreeOrdExt::untracked] has the no-op `()` tracker, so the field is
+///   compared with `Ord` every time it is reached. This can be faster for small
+///   or cheap fields, while the fields after it are still tracked once it is
+///   proven to be shared.
+///
+/// The `_ref` versions return transparent wrappers of references, for keys
+/// that borrow their fields. Note that calling the by value versions on a `&T`
+/// wraps the reference itself, because `&T` is also `TreeOrd`.
+///
+/// ```
+/// use std::cmp::Ordering::*;
+///
+/// use tree_ord::{Tracker, TreeOrd, TreeOrdExt};
+///
+/// // newest first within each user
+/// let a = (1u64, 200u64.tree_reversed());
+/// let b = (1u64, 100u64.tree_reversed());
+/// let c = (2u64, 300u64.tree_reversed());
+/// let mut tracker = <(u64, _) as TreeOrd>::Tracker::new();
+/// assert_eq!(a.tree_cmp(&b, &mut tracker), Less);
+/// assert_eq!(a.tree_cmp(&c, &mut tracker), Less);
+///
+/// let name = String::from("name");
+/// let d = (name.untracked_ref(), 7u8.tree_reversed());
+/// let e = (name.untracked_ref(), 8u8.tree_reversed());
+/// assert_eq!(d.tree_cmp(&e, &mut Tracker::new()), Greater);
+/// ```
+pub trait TreeOrdExt: TreeOrd + Sized {
+    /// Reverses the ordering, see [TreeOrdReverse]
+    #[inline]
+    fn tree_reversed(self) -> TreeOrdReverse<Self> {
+        TreeOrdReverse(self)
+    }
+
+    /// Opts out of tracking, see [OrdToTreeOrd]
+    #[inline]
+    fn untracked(self) -> OrdToTreeOrd<Self>
+    where
+        Self: Ord,
+    {
+        OrdToTreeOrd(self)
+    }
+
+    /// The same as [TreeOrdExt::tree_reversed] without moving `self`
+    #[inline]
+    fn tree_reversed_ref(&self) -> &TreeOrdReverse<Self> {
+        TreeOrdReverse::from_ref(self)
+    }
+
+    /// The same as [TreeOrdExt::untracked] without moving `self`
+    #[inline]
+    fn untracked_ref(&self) -> &OrdToTreeOrd<Self>
+    where
+        Self: Ord,
+    {
+        OrdToTreeOrd::from_ref(self)
+    }
+}
+
+impl<T: TreeOrd> TreeOrdExt for T {}
+
+/// An `Option<T>` that orders `None` after every `Some`, like `NULLS LAST` in
+/// SQL, where `Option<T>` itself orders `None` first. `Some` values are
+/// compared with `T::Tracker`, so this can be used as a field of tuple keys
+/// such as `(UserId, NullsLast<Timestamp>)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct NullsLast<T>(pub Option<T>);
+
+impl<T> From<Option<T>> for NullsLast<T> {
+    #[inline]
+    fn from(x: Option<T>) -> Self {
+        Self(x)
+    }
+}
+
+impl<T> From<NullsLast<T>> for Option<T> {
+    #[inline]
+    fn from(x: NullsLast<T>) -> Self {
+        x.0
+    }
+}
+
+impl<T: Ord> PartialOrd for NullsLast<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for NullsLast<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (&self.0, &other.0) {
+            (None, None) => Equal,
+            (None, Some(_)) => Greater,
+            (Some(_), None) => Less,
+            (Some(lhs), Some(rhs)) => lhs.cmp(rhs),
+        }
+    }
+}
+
+impl<T: TreeOrd> TreeOrd<Self> for NullsLast<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        match (&self.0, &rhs.0) {
+            (Some(lhs), Some(rhs)) => lhs.tree_cmp(rhs, tracker),
+            _ => self.cmp(rhs),
+        }
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        match (&self.0, &rhs.0) {
+            (Some(lhs), Some(rhs)) => lhs.tree_cmp_hereditary(rhs, tracker),
+            _ => self.cmp(rhs),
+        }
+    }
+}
+
+/// Uses a [MaybeTrackedTracker] for `T`, so that tracking can be turned off at
+/// runtime for searches where it does not pay off. Disabled comparisons are
+/// plain `Ord` comparisons.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct MaybeTracked<T: TreeOrd>(pub T);
+
+impl<T: TreeOrd> TreeOrd<Self> for MaybeTracked<T> {
+    type Tracker = MaybeTrackedTracker<T::Tracker>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        if tracker.is_enabled() {
+            self.0.tree_cmp(&rhs.0, tracker.inner_mut())
+        } else {
+            self.0.cmp(&rhs.0)
+        }
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        if tracker.is_enabled() {
+            self.0.tree_cmp_hereditary(&rhs.0, tracker.inner_mut())
+        } else {
+            self.0.cmp(&rhs.0)
+        }
+    }
+}
+
+/// Uses a [BoxedTracker] for `T`, for keys with deeply nested trackers that
+/// would otherwise take up a lot of stack space in every search frame
+#[cfg(feature = "alloc")]
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct TreeOrdBoxed<T: TreeOrd>(pub T);
+
+#[cfg(feature = "alloc")]
+impl<T: TreeOrd> TreeOrd<Self> for TreeOrdBoxed<T> {
+    type Tracker = BoxedTracker<T::Tracker>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        if Self::Tracker::IS_NOOP {
+            self.0.tree_cmp(&rhs.0, &mut T::Tracker::new())
+        } else {
+            self.0.tree_cmp(&rhs.0, tracker.get_mut())
+        }
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        if Self::Tracker::IS_NOOP {
+            self.0.tree_cmp_hereditary(&rhs.0, &mut T::Tracker::new())
+        } else {
+            self.0.tree_cmp_hereditary(&rhs.0, tracker.get_mut())
+        }
+    }
+}
+
+/// A key-value pair that is ordered and tracked only by its key, for sorted
+/// `Vec`s with map semantics. Note that this means `Eq` only considers the key,
+/// so two `KeyValue`s with equal keys and different values are equal.
+///
+/// This also implements `TreeOrd<K>`, so that a sorted slice of `KeyValue`s can
+/// be searched with a bare key.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct KeyValue<K, V> {
+    pub key: K,
+    pub value: V,
+}
+
+impl<K, V> KeyValue<K, V> {
+    pub fn new(key: K, value: V) -> Self {
+        Self { key, value }
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn value(&self) -> &V {
+        &self.value
+    }
+
+    pub fn value_mut(&mut self) -> &mut V {
+        &mut self.value
+    }
+
+    pub fn into_pair(self) -> (K, V) {
+        (self.key, self.value)
+    }
+}
+
+impl<K, V> From<(K, V)> for KeyValue<K, V> {
+    fn from((key, value): (K, V)) -> Self {
+        Self { key, value }
+    }
+}
+
+impl<K: PartialEq, V> PartialEq for KeyValue<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl<K: Eq, V> Eq for KeyValue<K, V> {}
+
+impl<K: PartialOrd, V> PartialOrd for KeyValue<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.key.partial_cmp(&other.key)
+    }
+}
+
+impl<K: Ord, V> Ord for KeyValue<K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key)
+    }
+}
+
+impl<K: TreeOrd, V> TreeOrd<Self> for KeyValue<K, V> {
+    type Tracker = K::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.key.tree_cmp(&rhs.key, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.key.tree_cmp_hereditary(&rhs.key, tracker)
+    }
+}
+
+impl<K: TreeOrd, V> TreeOrd<K> for KeyValue<K, V> {
+    type Tracker = K::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &K, tracker: &mut Self::Tracker) -> Ordering {
+        self.key.tree_cmp(rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &K, tracker: &mut Self::Tracker) -> Ordering {
+        self.key.tree_cmp_hereditary(rhs, tracker)
+    }
+}
+
+/// A trait for types that are ordered by a key extracted from them, used by
+/// [ByKey]
+pub trait TreeOrdKey {
+    type Key: TreeOrd + ?Sized;
+
+    fn tree_key(&self) -> &Self::Key;
+}
+
+/// A wrapper that implements `Ord` and `TreeOrd` by delegating to the key
+/// extracted by [TreeOrdKey], using the key's tracker
+#[derive(Debug, Clone, Copy, Default)]
+#[repr(transparent)]
+pub struct ByKey<T: TreeOrdKey>(pub T);
+
+impl<T: TreeOrdKey> ByKey<T> {
+    /// Reinterprets a reference without copying
+    pub fn from_ref(t: &T) -> &Self {
+        // Safety: `ByKey<T>` is `repr(transparent)` over `T`
+        unsafe { &*(t as *const T as *const Self) }
+    }
+
+    /// Reinterprets a slice without copying
+    pub fn from_slice(s: &[T]) -> &[Self] {
+        // Safety: `ByKey<T>` is `repr(transparent)` over `T`, so the slices
+        // have the same layout
+        unsafe { &*(s as *const [T] as *const [Self]) }
+    }
+}
+
+impl<T: TreeOrdKey> PartialEq for ByKey<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.tree_key() == other.0.tree_key()
+    }
+}
+
+impl<T: TreeOrdKey> Eq for ByKey<T> {}
+
+impl<T: TreeOrdKey> PartialOrd for ByKey<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: TreeOrdKey> Ord for ByKey<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.tree_key().cmp(other.0.tree_key())
+    }
+}
+
+impl<T: TreeOrdKey> TreeOrd<Self> for ByKey<T> {
+    type Tracker = <T::Key as TreeOrd>::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.0.tree_key().tree_cmp(rhs.0.tree_key(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.0
+            .tree_key()
+            .tree_cmp_hereditary(rhs.0.tree_key(), tracker)
+    }
+}
+
+impl<T: TreeOrd> TreeOrd<Self> for &T {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp(*self, *rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp_hereditary(*self, *rhs, tracker)
+    }
+}
+
+impl<T: TreeOrd> TreeOrd<Self> for &mut T {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp(*self, *rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp_hereditary(*self, *rhs, tracker)
+    }
+}
+
+impl<T: TreeOrd + Copy> TreeOrd<Self> for Cell<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp(&self.get(), &rhs.get(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp_hereditary(&self.get(), &rhs.get(), tracker)
+    }
+}
+
+impl<T: TreeOrd + ?Sized> TreeOrd<Self> for RefCell<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.borrow().tree_cmp(&*rhs.borrow(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.borrow().tree_cmp_hereditary(&*rhs.borrow(), tracker)
+    }
+}
+
+impl<T: ?Sized> TreeOrd<Self> for PhantomData<T> {
+    type Tracker = ();
+
+    #[inline]
+    fn tree_cmp(&self, _: &Self, _: &mut Self::Tracker) -> Ordering {
+        Ordering::Equal
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: TreeOrd + ?Sized> TreeOrd<Self> for alloc::boxed::Box<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp(self.as_ref(), rhs.as_ref(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp_hereditary(self.as_ref(), rhs.as_ref(), tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: TreeOrd + ?Sized> TreeOrd<Self> for alloc::rc::Rc<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp(self.as_ref(), rhs.as_ref(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp_hereditary(self.as_ref(), rhs.as_ref(), tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: TreeOrd + ?Sized> TreeOrd<Self> for alloc::sync::Arc<T> {
+    type Tracker = T::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp(self.as_ref(), rhs.as_ref(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrd::tree_cmp_hereditary(self.as_ref(), rhs.as_ref(), tracker)
+    }
+}
+
+/// An `Rc<T>` or `Arc<T>` whose comparisons first check if both sides point to
+/// the same allocation, and return `Equal` without comparing the contents if
+/// they do. This is for heavily deduplicated keys where the query is often the
+/// same allocation as the key it is compared with. The ordering is the same as
+/// that of `T`, only the extra branch differs from plain `Rc<T>` and `Arc<T>`.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct PtrEqFast<P>(pub P);
+
+#[cfg(feature = "alloc")]
+impl<P> From<P> for PtrEqFast<P> {
+    #[inline]
+    fn from(p: P) -> Self {
+        Self(p)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<P> Deref for PtrEqFast<P> {
+    type Target = P;
+
+    #[inline]
+    fn deref(&self) -> &P {
+        &self.0
+    }
+}
+
+macro_rules! impl_ptr_eq_fast {
+    ($($m:ident::$ptr:ident)*) => {$(
+        #[cfg(feature = "alloc")]
+        impl<T: Ord + ?Sized> PartialOrd for PtrEqFast<alloc::$m::$ptr<T>> {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        impl<T: Ord + ?Sized> Ord for PtrEqFast<alloc::$m::$ptr<T>> {
+            #[inline]
+            fn cmp(&self, other: &Self) -> Ordering {
+                if alloc::$m::$ptr::ptr_eq(&self.0, &other.0) {
+                    Equal
+                } else {
+                    self.0.as_ref().cmp(other.0.as_ref())
+                }
+            }
+        }
+
+        #[cfg(feature = "alloc")]
+        impl<T: TreeOrd + ?Sized> TreeOrd<Self> for PtrEqFast<alloc::$m::$ptr<T>> {
+            type Tracker = T::Tracker;
+
+            #[inline]
+            fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                if alloc::$m::$ptr::ptr_eq(&self.0, &rhs.0) {
+                    Equal
+                } else {
+                    TreeOrd::tree_cmp(self.0.as_ref(), rhs.0.as_ref(), tracker)
+                }
+            }
+
+            /// Identical pointers return `Equal` without recording it in the
+            /// tracker, which only means that less is proven
+            #[inline]
+            fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                if alloc::$m::$ptr::ptr_eq(&self.0, &rhs.0) {
+                    Equal
+                } else {
+                    TreeOrd::tree_cmp_hereditary(self.0.as_ref(), rhs.0.as_ref(), tracker)
+                }
+            }
+        }
+    )*};
+}
+
+impl_ptr_eq_fast!(rc::Rc sync::Arc);
+
+// TODO for `Saturating` and `Wrapping` when impls become stable
+
+impl<P> TreeOrd<Self> for Pin<P>
+where
+    P: Deref,
+    <P as Deref>::Target: TreeOrd,
+{
+    type Tracker = <<P as Deref>::Target as TreeOrd>::Tracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.deref().tree_cmp(rhs.deref(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.deref().tree_cmp_hereditary(rhs.deref(), tracker)
+    }
+}
+
+impl<T: TreeOrd> TreeOrd<Self> for Option<T> {
+    type Tracker = T::Tracker;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        match (self, rhs) {
+            (None, None) => Equal,
+            (None, Some(_)) => Less,
+            (Some(_), None) => Greater,
+            (Some(lhs), Some(rhs)) => lhs.tree_cmp(rhs, tracker),
+        }
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        match (self, rhs) {
+            (Some(lhs), Some(rhs)) => lhs.tree_cmp_hereditary(rhs, tracker),
+            _ => self.cmp(rhs),
+        }
+    }
+}
+
+impl<T: TreeOrd, E: TreeOrd> TreeOrd<Self> for Result<T, E> {
+    type Tracker = ResultTracker<T, E>;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        match (self, rhs) {
+            (Ok(lhs), Ok(rhs)) => {
+                if <Self::Tracker as Tracker>::IS_NOOP {
+                    lhs.tree_cmp(rhs, &mut T::Tracker::new())
+                } else {
+                    lhs.tree_cmp(rhs, tracker.ok())
+                }
+            }
+            (Ok(_), Err(_)) => Less,
+            (Err(_), Ok(_)) => Greater,
+            (Err(lhs), Err(rhs)) => {
+                if <Self::Tracker as Tracker>::IS_NOOP {
+                    lhs.tree_cmp(rhs, &mut E::Tracker::new())
+                } else {
+                    lhs.tree_cmp(rhs, tracker.err())
+                }
+            }
+        }
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        match (self, rhs) {
+            (Ok(lhs), Ok(rhs)) => {
+                if <Self::Tracker as Tracker>::IS_NOOP {
+                    lhs.tree_cmp_hereditary(rhs, &mut T::Tracker::new())
+                } else {
+                    lhs.tree_cmp_hereditary(rhs, tracker.ok())
+                }
+            }
+            (Err(lhs), Err(rhs)) => {
+                if <Self::Tracker as Tracker>::IS_NOOP {
+                    lhs.tree_cmp_hereditary(rhs, &mut E::Tracker::new())
+                } else {
+                    lhs.tree_cmp_hereditary(rhs, tracker.err())
+                }
+            }
+            _ => self.cmp(rhs),
+        }
+    }
+}
+
+#[cfg(not(feature = "nightly-specialization"))]
+impl<T: TreeOrd> TreeOrd<Self> for [T] {
+    type Tracker = LexicographicTracker<T>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        // `[u8]` gets the chunked comparison of `TreeOrdBytes`, which has the
+        // same tracker and records the same prefixes
+        if let Some((lhs, rhs, tracker)) = as_bytes_tree_cmp(self, rhs, tracker) {
+            return TreeOrdBytes(lhs).tree_cmp(&TreeOrdBytes(rhs), tracker)
+        }
+        lexicographic_tree_cmp(self, rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        if let Some((lhs, rhs, tracker)) = as_bytes_tree_cmp(self, rhs, tracker) {
+            return TreeOrdBytes(lhs).tree_cmp_hereditary(&TreeOrdBytes(rhs), tracker)
+        }
+        lexicographic_tree_cmp_hereditary(self, rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_detail(&self, rhs: &Self, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        let res = self.tree_cmp(rhs, tracker);
+        (res, lexicographic_divergence(res, self.len(), tracker))
+    }
+}
+
+impl TreeOrd<Self> for str {
+    type Tracker = <[u8] as TreeOrd>::Tracker;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_bytes().tree_cmp(rhs.as_bytes(), tracker)
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_bytes().tree_cmp_hereditary(rhs.as_bytes(), tracker)
+    }
+
+    fn tree_cmp_detail(&self, rhs: &Self, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        self.as_bytes().tree_cmp_detail(rhs.as_bytes(), tracker)
+    }
+}
+
+impl<T: TreeOrd, const N: usize> TreeOrd<Self> for [T; N] {
+    type Tracker = <[T] as TreeOrd>::Tracker;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_slice().tree_cmp(rhs.as_slice(), tracker)
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_slice().tree_cmp_hereditary(rhs.as_slice(), tracker)
+    }
+
+    fn tree_cmp_detail(&self, rhs: &Self, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        self.as_slice().tree_cmp_detail(rhs.as_slice(), tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: TreeOrd> TreeOrd<Self> for alloc::vec::Vec<T> {
+    type Tracker = <[T] as TreeOrd>::Tracker;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_slice().tree_cmp(rhs.as_slice(), tracker)
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_slice().tree_cmp_hereditary(rhs.as_slice(), tracker)
+    }
+
+    fn tree_cmp_detail(&self, rhs: &Self, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        self.as_slice().tree_cmp_detail(rhs.as_slice(), tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TreeOrd<Self> for alloc::string::String {
+    type Tracker = <[u8] as TreeOrd>::Tracker;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_bytes().tree_cmp(rhs.as_bytes(), tracker)
+    }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_bytes().tree_cmp_hereditary(rhs.as_bytes(), tracker)
+    }
+
+    fn tree_cmp_detail(&self, rhs: &Self, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        self.as_bytes().tree_cmp_detail(rhs.as_bytes(), tracker)
+    }
+}
+
+/// Compares bytes in chunks of bytes. The tracker still records the exact byte
+/// where keys diverge, so the proven prefix is skipped with byte granularity.
+/// However, it seems this is only more performant for very long slices and deep
+/// trees, you should benchmark to see if this is faster for your usecase.
+///
+/// The `[T]` impl detects `T = u8` with a `TypeId` check that the compiler
+/// folds away, and uses this same comparison, so `[u8]`, `Vec<u8>`, `str`, and
+/// `String` keys get it automatically, and `TreeOrdBytes` and `TreeOrdVec`
+/// are kept for compatibility.
+///
+/// `Eq`, `Ord`, and `Hash` are the same as those of `[u8]`, so this also
+/// implements `Borrow<[u8]>`.
+///
+/// This also implements `TreeOrd<[u8]>` and `TreeOrd<TreeOrdVec>` with the
+/// same tracker and comparison, so a space of borrowed keys can be searched
+/// with owned or plain byte queries and the other way around.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TreeOrdBytes<'a>(pub &'a [u8]);
+
+impl<'a> TreeOrdBytes<'a> {
+    #[inline]
+    pub fn into_inner(self) -> &'a [u8] {
+        self.0
+    }
+
+    #[inline]
+    pub fn as_inner(&self) -> &'a [u8] {
+        self.0
+    }
+}
+
+impl<'a> From<&'a [u8]> for TreeOrdBytes<'a> {
+    #[inline]
+    fn from(s: &'a [u8]) -> Self {
+        Self(s)
+    }
+}
+
+impl<'a> From<&'a str> for TreeOrdBytes<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self(s.as_bytes())
+    }
+}
+
+impl<'a> From<TreeOrdBytes<'a>> for &'a [u8] {
+    #[inline]
+    fn from(x: TreeOrdBytes<'a>) -> Self {
+        x.0
+    }
+}
+
+impl Deref for TreeOrdBytes<'_> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl AsRef<[u8]> for TreeOrdBytes<'_> {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl Borrow<[u8]> for TreeOrdBytes<'_> {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl TreeOrdBytes<'_> {
+    /// The number of bytes compared at a time
+    pub const CHUNK_LEN: usize = 32;
+    /// When fewer bytes than this remain after the proven prefix, they are
+    /// compared as 8 byte words instead of in chunks
+    pub const SHORT_LEN: usize = 40;
+
+    /// Returns a tracker for `TreeOrdBytes` or `TreeOrdVec` that starts with
+    /// the first `len` bytes known to be common to the query and all keys. The
+    /// same contract as [LexicographicTracker::with_known_prefix] applies.
+    pub fn tracker_with_known_prefix(len: usize) -> LexicographicTracker<u8> {
+        LexicographicTracker::with_known_prefix(len)
+    }
+}
+
+impl<'a> TreeOrd<Self> for TreeOrdBytes<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp_impl::<false>(rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp_impl::<true>(rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_detail(&self, rhs: &Self, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        self.tree_cmp_detail(rhs.0, tracker)
+    }
+}
+
+impl<'a> TreeOrd<[u8]> for TreeOrdBytes<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &[u8], tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp_impl::<false>(rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &[u8], tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp_impl::<true>(rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_detail(&self, rhs: &[u8], tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        let res = self.tree_cmp_impl::<false>(rhs, tracker);
+        (res, lexicographic_divergence(res, self.0.len(), tracker))
+    }
+}
+
+impl TreeOrdBytes<'_> {
+    #[inline]
+    fn tree_cmp_impl<const HEREDITARY: bool>(
+        &self,
+        rhs: &[u8],
+        tracker: &mut LexicographicTracker<u8>,
+    ) -> Ordering {
+        let start = min(tracker.proven_prefix_len(), min(self.0.len(), rhs.len()));
+        stats::record(start);
+        bytes_tree_cmp::<HEREDITARY>(self.0, rhs, start, tracker)
+    }
+}
+
+/// Compares `lhs` and `rhs` in chunks of `TreeOrdBytes::CHUNK_LEN` bytes
+/// starting at byte `start`, which must not be beyond either end and not beyond
+/// the proven prefix
+#[inline]
+fn bytes_tree_cmp<const HEREDITARY: bool>(
+    lhs: &[u8],
+    rhs: &[u8],
+    mut start: usize,
+    tracker: &mut LexicographicTracker<u8>,
+) -> Ordering {
+    // byte comparison is greatly sped up internally by
+    // `core::intrinsics::compare_bytes` when we do it in chunks
+    const CHUNK_LEN: usize = TreeOrdBytes::CHUNK_LEN;
+    // benchmarks have not shown aligning to be a win on x86_64, so it is
+    // behind a feature flag
+    const ALIGN_CHUNKS: bool = cfg!(feature = "aligned-chunks");
+    let end_bytes = min(lhs.len(), rhs.len());
+    if end_bytes.wrapping_sub(start) < TreeOrdBytes::SHORT_LEN {
+        return short_bytes_tree_cmp::<HEREDITARY>(lhs, rhs, start, tracker)
+    }
+    // the chunks start right after the proven prefix, with "aligned-chunks" the
+    // first chunk is shortened so that the later chunks are aligned in `lhs`,
+    // and the last chunk may be partial
+    let mut end = if ALIGN_CHUNKS {
+        let addr = (lhs.as_ptr() as usize).wrapping_add(start);
+        start.wrapping_add(CHUNK_LEN.wrapping_sub(addr % CHUNK_LEN))
+    } else {
+        start.wrapping_add(CHUNK_LEN)
+    };
+    while start < end_bytes {
+        end = min(end, end_bytes);
+        let x = &lhs[start..end];
+        let y = &rhs[start..end];
+        if let Some(i) = first_difference(x, y) {
+            // record the exact byte where the keys diverge, so that later
+            // comparisons can start in the middle of this chunk
+            let diverge = start.wrapping_add(i);
+            let res = lhs[diverge].cmp(&rhs[diverge]);
+            if res == Less {
+                tracker.set_max_eq_len(diverge);
+            } else {
+                tracker.set_min_eq_len(diverge);
+            }
+            return res
+        }
+        start = end;
+        end = start.wrapping_add(CHUNK_LEN);
+    }
+    lexicographic_len_cmp::<u8, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+}
+
+/// The same as `bytes_tree_cmp` but compares 8 byte big-endian words at a time,
+/// which is faster for short remainders than the chunks
+#[inline]
+fn short_bytes_tree_cmp<const HEREDITARY: bool>(
+    lhs: &[u8],
+    rhs: &[u8],
+    mut start: usize,
+    tracker: &mut LexicographicTracker<u8>,
+) -> Ordering {
+    let end_bytes = min(lhs.len(), rhs.len());
+    while start < end_bytes {
+        let end = start.wrapping_add(8);
+        if end > end_bytes {
+            // The last word is loaded so that it ends at `end_bytes`, or zero
+            // padded for keys shorter than a word (the padding is the same on
+            // both sides). The bytes before `start` are masked off, so that the
+            // proven prefix is never compared.
+            let (a, b, first) = if end_bytes >= 8 {
+                let first = end_bytes.wrapping_sub(8);
+                (
+                    u64::from_be_bytes(lhs[first..end_bytes].try_into().unwrap()),
+                    u64::from_be_bytes(rhs[first..end_bytes].try_into().unwrap()),
+                    first,
+                )
+            } else {
+                let mut a = [0u8; 8];
+                let mut b = [0u8; 8];
+                a[..end_bytes].copy_from_slice(&lhs[..end_bytes]);
+                b[..end_bytes].copy_from_slice(&rhs[..end_bytes]);
+                (u64::from_be_bytes(a), u64::from_be_bytes(b), 0)
+            };
+            let mask = u64::MAX >> (start.wrapping_sub(first).wrapping_mul(8));
+            let (a, b) = (a & mask, b & mask);
+            if a != b {
+                let diverge = first.wrapping_add(((a ^ b).leading_zeros() / 8) as usize);
+                return lexicographic_diverged(diverge, a.cmp(&b), tracker)
+            }
+            break
+        }
+        let a = u64::from_be_bytes(lhs[start..end].try_into().unwrap());
+        let b = u64::from_be_bytes(rhs[start..end].try_into().unwrap());
+        if a != b {
+            // the first byte is the most significant in big-endian
+            let diverge = start.wrapping_add(((a ^ b).leading_zeros() / 8) as usize);
+            return lexicographic_diverged(diverge, a.cmp(&b), tracker)
+        }
+        start = end;
+    }
+    lexicographic_len_cmp::<u8, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+}
+
+/// Returns the index of the first byte that differs between `x` and `y`, which
+/// must have the same length, or `None` if they are equal
+#[inline]
+fn first_difference(x: &[u8], y: &[u8]) -> Option<usize> {
+    // whole chunks are compared with one vector, the partial chunks at the ends
+    // of keys use the scalar path
+    #[cfg(feature = "simd")]
+    if x.len() == TreeOrdBytes::CHUNK_LEN {
+        use core::simd::{cmp::SimdPartialEq, Simd};
+        type Chunk = Simd<u8, { TreeOrdBytes::CHUNK_LEN }>;
+        let ne = Chunk::from_slice(x).simd_ne(Chunk::from_slice(y));
+        // lane `i` is bit `i` of the bitmask
+        let mask = ne.to_bitmask();
+        return if mask == 0 {
+            None
+        } else {
+            Some(mask.trailing_zeros() as usize)
+        }
+    }
+    // `memcmp` is faster for the common case of equal chunks
+    if x == y {
+        return None
+    }
+    let mut i: usize = 0;
+    while i.wrapping_add(8) <= x.len() {
+        let end = i.wrapping_add(8);
+        let a = u64::from_le_bytes(x[i..end].try_into().unwrap());
+        let b = u64::from_le_bytes(y[i..end].try_into().unwrap());
+        if a != b {
+            // the first differing byte is the least significant differing byte
+            return Some(i.wrapping_add(((a ^ b).trailing_zeros() / 8) as usize))
+        }
+        i = end;
+    }
+    Some(i.wrapping_add(prefix::common_prefix_len(&x[i..], &y[i..])))
+}
+
+/// Compares `lhs` and `rhs` in chunks of `CHUNK_LEN` elements after the proven
+/// prefix, and then resolves the first differing element. The tracker counts
+/// elements.
+#[inline]
+fn chunked_tree_cmp<
+    T: TreeOrd + Ord + Copy + Default + BitOr<Output = T> + BitXor<Output = T>,
+    const CHUNK_LEN: usize,
+    const HEREDITARY: bool,
+>(
+    lhs: &[T],
+    rhs: &[T],
+    tracker: &mut LexicographicTracker<T>,
+) -> Ordering {
+    let end_elems = min(lhs.len(), rhs.len());
+    let mut start = min(tracker.proven_prefix_len(), end_elems);
+    stats::record(start);
+    // keys usually diverge right after the proven prefix, so the first element
+    // is checked on its own
+    if start < end_elems {
+        let res = lhs[start].cmp(&rhs[start]);
+        if res == Less {
+            tracker.set_max_eq_len(start);
+            return res
+        } else if res == Greater {
+            tracker.set_min_eq_len(start);
+            return res
+        }
+        start = start.wrapping_add(1);
+    }
+    // the XOR of fixed size chunks is folded without branches, and only the
+    // element orderings are used to resolve, so the native endianness never
+    // affects the result
+    while start.wrapping_add(CHUNK_LEN) <= end_elems {
+        let end = start.wrapping_add(CHUNK_LEN);
+        let x: &[T; CHUNK_LEN] = lhs[start..end].try_into().unwrap();
+        let y: &[T; CHUNK_LEN] = rhs[start..end].try_into().unwrap();
+        let mut diff = T::default();
+        for i in 0..CHUNK_LEN {
+            diff = diff | (x[i] ^ y[i]);
+        }
+        if diff != T::default() {
+            break
+        }
+        start = end;
+    }
+    while start < end_elems {
+        let res = lhs[start].cmp(&rhs[start]);
+        if res == Less {
+            tracker.set_max_eq_len(start);
+            return res
+        } else if res == Greater {
+            tracker.set_min_eq_len(start);
+            return res
+        }
+        start = start.wrapping_add(1);
+    }
+    lexicographic_len_cmp::<T, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+}
+
+macro_rules! tree_ord_ints {
+    ($($name:ident $t:ident $chunk_len:literal),*) => {
+        $(
+            #[doc = concat!(
+                "Compares `[", stringify!($t), "]` slices in chunks of `CHUNK_LEN` elements like ",
+                "`TreeOrdBytes`, with the same ordering as the slice. The tracker is the same as ",
+                "that of `[", stringify!($t), "]` and counts elements."
+            )]
+            #[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+            pub struct $name<'a>(pub &'a [$t]);
+
+            impl $name<'_> {
+                /// The number of elements compared at a time
+                pub const CHUNK_LEN: usize = $chunk_len;
+            }
+
+            impl<'a> TreeOrd<Self> for $name<'a> {
+                type Tracker = LexicographicTracker<$t>;
+
+                #[inline]
+                fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                    chunked_tree_cmp::<$t, $chunk_len, false>(self.0, rhs.0, tracker)
+                }
+
+                #[inline]
+                fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                    chunked_tree_cmp::<$t, $chunk_len, true>(self.0, rhs.0, tracker)
+                }
+            }
+        )*
+    };
+}
+
+// the same number of bytes as `TreeOrdBytes::CHUNK_LEN`
+tree_ord_ints!(TreeOrdU16s u16 16, TreeOrdU32s u32 8, TreeOrdU64s u64 4);
+
+/// The same as `TreeOrdBytes` but for an owned `Vec<u8>`. This also
+/// implements `TreeOrd<TreeOrdBytes>` and `TreeOrd<[u8]>`, so a space of owned
+/// keys can be searched with borrowed queries without copying them.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TreeOrdVec(pub alloc::vec::Vec<u8>);
+
+#[cfg(feature = "alloc")]
+impl TreeOrdVec {
+    #[inline]
+    pub fn into_inner(self) -> alloc::vec::Vec<u8> {
+        self.0
+    }
+
+    #[inline]
+    pub fn as_inner(&self) -> &alloc::vec::Vec<u8> {
+        &self.0
+    }
+
+    /// Returns the borrowed form
+    #[inline]
+    pub fn as_borrowed(&self) -> TreeOrdBytes<'_> {
+        TreeOrdBytes(&self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<alloc::vec::Vec<u8>> for TreeOrdVec {
+    #[inline]
+    fn from(v: alloc::vec::Vec<u8>) -> Self {
+        Self(v)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<&[u8]> for TreeOrdVec {
+    #[inline]
+    fn from(s: &[u8]) -> Self {
+        Self(s.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<TreeOrdBytes<'_>> for TreeOrdVec {
+    #[inline]
+    fn from(s: TreeOrdBytes<'_>) -> Self {
+        Self(s.0.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<TreeOrdVec> for alloc::vec::Vec<u8> {
+    #[inline]
+    fn from(x: TreeOrdVec) -> Self {
+        x.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Deref for TreeOrdVec {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsRef<[u8]> for TreeOrdVec {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Borrow<[u8]> for TreeOrdVec {
+    #[inline]
+    fn borrow(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TreeOrd<Self> for TreeOrdVec {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(&self.0).tree_cmp(&TreeOrdBytes(&rhs.0), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(&self.0).tree_cmp_hereditary(&TreeOrdBytes(&rhs.0), tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> TreeOrd<TreeOrdBytes<'a>> for TreeOrdVec {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &TreeOrdBytes<'a>, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(&self.0).tree_cmp(rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &TreeOrdBytes<'a>, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(&self.0).tree_cmp_hereditary(rhs.0, tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TreeOrd<[u8]> for TreeOrdVec {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &[u8], tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(&self.0).tree_cmp(rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &[u8], tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(&self.0).tree_cmp_hereditary(rhs, tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a> TreeOrd<TreeOrdVec> for TreeOrdBytes<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &TreeOrdVec, tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp(rhs.0.as_slice(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &TreeOrdVec, tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp_hereditary(rhs.0.as_slice(), tracker)
+    }
+}
+
+/// The same as `TreeOrdBytes`, except that its tracker adapts to long keys.
+/// Once the proven prefix reaches `AdaptiveBytesTracker::ADAPT_LEN` bytes,
+/// equal bytes are skipped in strides of `AdaptiveBytesTracker::LARGE_STRIDE`
+/// bytes for the rest of the search, before the differing stride is resolved
+/// like in `TreeOrdBytes`. Short keys never reach the threshold and are
+/// compared exactly like `TreeOrdBytes`. The tracker still counts bytes.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TreeOrdBytesAdaptive<'a>(pub &'a [u8]);
+
+impl<'a> TreeOrd<Self> for TreeOrdBytesAdaptive<'a> {
+    type Tracker = AdaptiveBytesTracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp_impl::<false>(rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.tree_cmp_impl::<true>(rhs, tracker)
+    }
+}
+
+impl TreeOrdBytesAdaptive<'_> {
+    #[inline]
+    fn tree_cmp_impl<const HEREDITARY: bool>(
+        &self,
+        rhs: &Self,
+        tracker: &mut AdaptiveBytesTracker,
+    ) -> Ordering {
+        let (lhs, rhs) = (self.0, rhs.0);
+        let end_bytes = min(lhs.len(), rhs.len());
+        let stride = tracker.adapt();
+        let mut start = min(tracker.inner().proven_prefix_len(), end_bytes);
+        stats::record(start);
+        if stride > TreeOrdBytes::CHUNK_LEN {
+            // skip whole equal strides with one `memcmp` each, the stride that
+            // differs is resolved in small chunks
+            while start.wrapping_add(stride) <= end_bytes {
+                let end = start.wrapping_add(stride);
+                if lhs[start..end] != rhs[start..end] {
+                    break
+                }
+                start = end;
+            }
+        }
+        bytes_tree_cmp::<HEREDITARY>(lhs, rhs, start, tracker.inner_mut())
+    }
+}
+
+/// A `str` that is compared with the chunked comparison of `TreeOrdBytes`.
+/// UTF-8 preserves the ordering of `char`s in its bytes, so the ordering is
+/// the same as that of `str`. `str` itself already uses this comparison
+/// through the `TypeId` check of `[T]`, this wrapper makes it explicit and
+/// also implements `TreeOrd<str>`, so that it can be searched with a plain
+/// `&str`.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TreeOrdStr<'a>(pub &'a str);
+
+impl<'a> Deref for TreeOrdStr<'a> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for TreeOrdStr<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self(s)
+    }
+}
+
+impl<'a> TreeOrd<Self> for TreeOrdStr<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(self.0.as_bytes()).tree_cmp(&TreeOrdBytes(rhs.0.as_bytes()), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(self.0.as_bytes())
+            .tree_cmp_hereditary(&TreeOrdBytes(rhs.0.as_bytes()), tracker)
+    }
+}
+
+impl<'a> TreeOrd<str> for TreeOrdStr<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &str, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(self.0.as_bytes()).tree_cmp(&TreeOrdBytes(rhs.as_bytes()), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &str, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(self.0.as_bytes()).tree_cmp_hereditary(&TreeOrdBytes(rhs.as_bytes()), tracker)
+    }
+}
+
+/// The same as `TreeOrdStr` but for an owned `String`
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TreeOrdString(pub alloc::string::String);
+
+#[cfg(feature = "alloc")]
+impl Deref for TreeOrdString {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<&str> for TreeOrdString {
+    #[inline]
+    fn from(s: &str) -> Self {
+        Self(s.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<alloc::string::String> for TreeOrdString {
+    #[inline]
+    fn from(s: alloc::string::String) -> Self {
+        Self(s)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TreeOrd<Self> for TreeOrdString {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdStr(&self.0).tree_cmp(rhs.0.as_str(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdStr(&self.0).tree_cmp_hereditary(rhs.0.as_str(), tracker)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TreeOrd<str> for TreeOrdString {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &str, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdStr(&self.0).tree_cmp(rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &str, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdStr(&self.0).tree_cmp_hereditary(rhs, tracker)
+    }
+}
+
+/// A `str` that is ordered by its UTF-16 code units, like strings in
+/// JavaScript, Java, and Windows. This only differs from the order of `str`
+/// for supplementary `char`s (above U+FFFF), which are encoded with surrogates
+/// in U+D800..U+DFFF and therefore sort before U+E000..U+FFFF in UTF-16, while
+/// UTF-8 sorts them after everything in the BMP.
+///
+/// The comparison works directly on the UTF-8 bytes without transcoding the
+/// whole string. The first differing byte is found like `TreeOrdBytes` would,
+/// and only the `char`s containing it are compared by their UTF-16 order. The
+/// tracker counts UTF-8 bytes, but only ever records prefixes that end on a
+/// `char` boundary, so the proven prefix is always a whole number of equal
+/// code units.
+///
+/// ```
+/// use tree_ord::{Tracker, TreeOrd, Utf16Ordered};
+///
+/// // U+1F600 is encoded as the surrogates D83D DE00
+/// assert!("\u{1F600}" > "\u{FF5E}");
+/// assert!(Utf16Ordered("\u{1F600}") < Utf16Ordered("\u{FF5E}"));
+/// let mut tracker = <Utf16Ordered as TreeOrd>::Tracker::new();
+/// assert!(Utf16Ordered("key/\u{1F600}")
+///     .tree_cmp(&Utf16Ordered("key/\u{FF5E}"), &mut tracker)
+///     .is_lt());
+/// ```
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Utf16Ordered<'a>(pub &'a str);
+
+impl<'a> Deref for Utf16Ordered<'a> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for Utf16Ordered<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self(s)
+    }
+}
+
+impl PartialOrd for Utf16Ordered<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Utf16Ordered<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.encode_utf16().cmp(other.0.encode_utf16())
+    }
+}
+
+impl<'a> TreeOrd<Self> for Utf16Ordered<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        utf16_str_tree_cmp::<false>(self.0, rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        utf16_str_tree_cmp::<true>(self.0, rhs.0, tracker)
+    }
+}
+
+/// Maps a `char` to a key with the same order as its UTF-16 encoding. The
+/// supplementary planes are moved between U+D7FF and U+E000 where their
+/// surrogates are, and U+E000..=U+FFFF is moved above them.
+#[inline]
+fn utf16_order_key(c: char) -> u32 {
+    let c = u32::from(c);
+    match c {
+        0..=0xd7ff => c,
+        0xe000..=0xffff => c.wrapping_add(0x10_0000),
+        _ => c.wrapping_sub(0x2000),
+    }
+}
+
+/// Compares `lhs` and `rhs` by UTF-16 code units after the proven prefix,
+/// which always ends on a `char` boundary
+#[inline]
+fn utf16_str_tree_cmp<const HEREDITARY: bool>(
+    lhs: &str,
+    rhs: &str,
+    tracker: &mut LexicographicTracker<u8>,
+) -> Ordering {
+    let end = min(lhs.len(), rhs.len());
+    let start = min(tracker.proven_prefix_len(), end);
+    stats::record(start);
+    match first_difference(&lhs.as_bytes()[start..end], &rhs.as_bytes()[start..end]) {
+        Some(i) => {
+            // the bytes before the difference are equal, so the `char`
+            // boundaries before it are the same in both strings
+            let mut diverge = start.wrapping_add(i);
+            while !lhs.is_char_boundary(diverge) {
+                diverge = diverge.wrapping_sub(1);
+            }
+            let a = lhs[diverge..].chars().next().unwrap();
+            let b = rhs[diverge..].chars().next().unwrap();
+            lexicographic_diverged(
+                diverge,
+                utf16_order_key(a).cmp(&utf16_order_key(b)),
+                tracker,
+            )
+        }
+        None => lexicographic_len_cmp::<u8, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker),
+    }
+}
+
+/// UTF-16 code units that are compared like `TreeOrdU16s`, which is the same
+/// order as [Utf16Ordered] for well formed UTF-16. Unpaired surrogates are
+/// allowed and are simply compared as code units. The tracker counts code
+/// units.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Utf16Units<'a>(pub &'a [u16]);
+
+impl<'a> Deref for Utf16Units<'a> {
+    type Target = [u16];
+
+    #[inline]
+    fn deref(&self) -> &[u16] {
+        self.0
+    }
+}
+
+impl<'a> From<&'a [u16]> for Utf16Units<'a> {
+    #[inline]
+    fn from(s: &'a [u16]) -> Self {
+        Self(s)
+    }
+}
+
+impl<'a> TreeOrd<Self> for Utf16Units<'a> {
+    type Tracker = LexicographicTracker<u16>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdU16s(self.0).tree_cmp(&TreeOrdU16s(rhs.0), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdU16s(self.0).tree_cmp_hereditary(&TreeOrdU16s(rhs.0), tracker)
+    }
+}
+
+/// A `Vec<T>` that uses `u32` indices in its `LexicographicTracker`, which
+/// saves memory for deeply nested trackers. Prefixes longer than
+/// `u32::MAX - 1` elements just stop being skipped beyond that length.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct NarrowVec<T>(pub alloc::vec::Vec<T>);
+
+#[cfg(feature = "alloc")]
+impl<T: TreeOrd> TreeOrd<Self> for NarrowVec<T> {
+    type Tracker = LexicographicTracker<T, u32>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        lexicographic_tree_cmp(&self.0, &rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        lexicographic_tree_cmp_hereditary(&self.0, &rhs.0, tracker)
+    }
+}
+
+/// A slice-like key (anything that derefs to `[E]`, such as `&[E]`, `Vec<E>`,
+/// and `TreeOrdVec`) that is ordered by only its first `N` elements, for
+/// bucketing and coarse indexes. Keys shorter than `N` are compared by all of
+/// their elements and then by length, exactly like the truncated slices. The
+/// tracker is that of `[E]`, whose proven prefix never grows beyond `N`.
+///
+/// Note that this coarsens equality: `Eq` and `Hash` also only consider the
+/// first `N` elements, so distinct keys that share them are the same key, and
+/// a set of `PrefixKey`s keeps only one of them.
+///
+/// This also implements `TreeOrd<[E]>`, which truncates the query the same
+/// way, so the bucket of an untruncated key can be looked up directly.
+///
+/// ```
+/// use tree_ord::{search::tree_binary_search, PrefixKey};
+///
+/// fn bucket(key: &[u8]) -> PrefixKey<3, &[u8]> {
+///     PrefixKey(key)
+/// }
+///
+/// let buckets = [bucket(b"ab"), bucket(b"abc"), bucket(b"abd")];
+/// assert_eq!(bucket(b"abcx"), bucket(b"abcy"));
+/// assert_ne!(bucket(b"ab"), bucket(b"abc"));
+/// assert_eq!(tree_binary_search(&buckets, &b"abcdefg"[..]), Ok(1));
+/// assert_eq!(tree_binary_search(&buckets, &b"ab"[..]), Ok(0));
+/// assert_eq!(tree_binary_search(&buckets, &b"abb"[..]), Err(1));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrefixKey<const N: usize, T>(pub T);
+
+impl<const N: usize, T> PrefixKey<N, T> {
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    #[inline]
+    pub fn as_inner(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<const N: usize, T> From<T> for PrefixKey<N, T> {
+    #[inline]
+    fn from(x: T) -> Self {
+        Self(x)
+    }
+}
+
+impl<const N: usize, T: Deref<Target = [E]>, E> PrefixKey<N, T> {
+    /// Returns the first `N` elements, or all of them if there are fewer
+    #[inline]
+    pub fn prefix(&self) -> &[E] {
+        truncated::<N, E>(&self.0)
+    }
+}
+
+#[inline]
+fn truncated<const N: usize, E>(x: &[E]) -> &[E] {
+    &x[..min(N, x.len())]
+}
+
+impl<const N: usize, T: Deref<Target = [E]>, E: PartialEq> PartialEq for PrefixKey<N, T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.prefix() == other.prefix()
+    }
+}
+
+impl<const N: usize, T: Deref<Target = [E]>, E: Eq> Eq for PrefixKey<N, T> {}
+
+impl<const N: usize, T: Deref<Target = [E]>, E: Ord> PartialOrd for PrefixKey<N, T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<const N: usize, T: Deref<Target = [E]>, E: Ord> Ord for PrefixKey<N, T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.prefix().cmp(other.prefix())
+    }
+}
+
+impl<const N: usize, T: Deref<Target = [E]>, E: Hash> Hash for PrefixKey<N, T> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.prefix().hash(state)
+    }
+}
+
+impl<const N: usize, T: Deref<Target = [E]>, E: TreeOrd> TreeOrd<Self> for PrefixKey<N, T> {
+    type Tracker = LexicographicTracker<E>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.prefix().tree_cmp(rhs.prefix(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.prefix().tree_cmp_hereditary(rhs.prefix(), tracker)
+    }
+}
+
+impl<const N: usize, T: Deref<Target = [E]>, E: TreeOrd> TreeOrd<[E]> for PrefixKey<N, T> {
+    type Tracker = LexicographicTracker<E>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &[E], tracker: &mut Self::Tracker) -> Ordering {
+        self.prefix().tree_cmp(truncated::<N, E>(rhs), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &[E], tracker: &mut Self::Tracker) -> Ordering {
+        self.prefix()
+            .tree_cmp_hereditary(truncated::<N, E>(rhs), tracker)
+    }
+}
+
+/// A `u128` whose tracker records how many of the leading big-endian bytes are
+/// proven equal, so that only the remaining low bytes are compared. This helps
+/// with clustered keys such as IPv6 addresses and UUIDs that share high bytes.
+/// The ordering is the same as that of `u128`.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TreeOrdU128(pub u128);
+
+impl TreeOrd<Self> for TreeOrdU128 {
+    type Tracker = LexicographicTracker<u8, u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        u128_tree_cmp::<false>(self.0, rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        u128_tree_cmp::<true>(self.0, rhs.0, tracker)
+    }
+}
+
+/// The same as `TreeOrdU128` but for `i128`. The bytes are those of the value
+/// with the sign bit flipped, so that the ordering is the same as that of
+/// `i128`.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TreeOrdI128(pub i128);
+
+impl TreeOrdI128 {
+    #[inline]
+    const fn biased(self) -> u128 {
+        (self.0 as u128) ^ (1 << 127)
+    }
+}
+
+impl TreeOrd<Self> for TreeOrdI128 {
+    type Tracker = LexicographicTracker<u8, u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        u128_tree_cmp::<false>(self.biased(), rhs.biased(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        u128_tree_cmp::<true>(self.biased(), rhs.biased(), tracker)
+    }
+}
+
+/// Compares the bytes of `lhs` and `rhs` after the proven big-endian prefix
+#[inline]
+fn u128_tree_cmp<const HEREDITARY: bool>(
+    lhs: u128,
+    rhs: u128,
+    tracker: &mut LexicographicTracker<u8, u8>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    stats::record(start);
+    if start >= 16 {
+        return Equal
+    }
+    let mask = u128::MAX >> (start.wrapping_mul(8));
+    let diff = (lhs ^ rhs) & mask;
+    if diff == 0 {
+        if HEREDITARY {
+            // recorded like a `Greater`, which assumes the key is `lhs`
+            tracker.set_min_eq_len(16);
+        }
+        return Equal
+    }
+    let diverge = (diff.leading_zeros() / 8) as usize;
+    let res = (lhs & mask).cmp(&(rhs & mask));
+    if res == Less {
+        tracker.set_max_eq_len(diverge);
+    } else {
+        tracker.set_min_eq_len(diverge);
+    }
+    res
+}
+
+/// An integer that is ordered and tracked through its big-endian byte form,
+/// with the sign bit flipped for signed integers. This is the same encoding as
+/// the integer fields of [key::KeyBuilder], and the bytes compare in the same
+/// order as the integers, so they can be stored in byte ordered stores or
+/// compared as `TreeOrdBytes`. `Eq`, `Ord`, and `Hash` are the same as those
+/// of `T`, and the tracker records how many of the leading bytes are proven
+/// equal like that of [TreeOrdU128].
+///
+/// ```
+/// use tree_ord::BigEndianKey;
+///
+/// assert_eq!(BigEndianKey(-1i16).as_bytes(), [0x7f, 0xff]);
+/// assert_eq!(BigEndianKey(0x1234u16).as_bytes(), [0x12, 0x34]);
+/// assert_eq!(BigEndianKey::<i16>::from_bytes([0x80, 0]), BigEndianKey(0));
+/// assert!(BigEndianKey(-1i32).as_bytes() < BigEndianKey(0i32).as_bytes());
+/// ```
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[repr(transparent)]
+pub struct BigEndianKey<T>(pub T);
+
+impl<T> BigEndianKey<T> {
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for BigEndianKey<T> {
+    #[inline]
+    fn from(x: T) -> Self {
+        Self(x)
+    }
+}
+
+macro_rules! big_endian_keys {
+    ($($t:ident $u:ident),*) => {
+        $(
+            impl BigEndianKey<$t> {
+                /// The number of bytes of the byte form
+                pub const LEN: usize = size_of::<$t>();
+
+                /// Returns the big-endian bytes, with the sign bit flipped for
+                /// signed integers
+                #[inline]
+                pub const fn as_bytes(&self) -> [u8; size_of::<$t>()] {
+                    self.biased().to_be_bytes()
+                }
+
+                /// The inverse of [BigEndianKey::as_bytes]
+                #[inline]
+                pub const fn from_bytes(bytes: [u8; size_of::<$t>()]) -> Self {
+                    Self(($u::from_be_bytes(bytes) ^ ($t::MIN as $u)) as $t)
+                }
+
+                /// `MIN` maps to 0, which only flips the sign bit of signed
+                /// integers
+                #[inline]
+                const fn biased(&self) -> $u {
+                    (self.0 as $u) ^ ($t::MIN as $u)
+                }
+
+                /// The bytes moved to the top of a `u128`, so that the byte
+                /// indexes of the tracker are the same
+                #[inline]
+                const fn aligned(&self) -> u128 {
+                    (self.biased() as u128) << (128 - $u::BITS)
+                }
+            }
+
+            impl TreeOrd<Self> for BigEndianKey<$t> {
+                type Tracker = LexicographicTracker<u8, u8>;
+
+                #[inline]
+                fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                    u128_tree_cmp::<false>(self.aligned(), rhs.aligned(), tracker)
+                }
+
+                #[inline]
+                fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                    u128_tree_cmp::<true>(self.aligned(), rhs.aligned(), tracker)
+                }
+            }
+        )*
+    };
+}
+
+big_endian_keys!(
+    u8 u8, u16 u16, u32 u32, u64 u64, u128 u128, usize usize,
+    i8 u8, i16 u16, i32 u32, i64 u64, i128 u128, isize usize
+);
+
+/// A `str` that is ordered by its ASCII lowercase bytes, without allocating a
+/// lowercase copy. Non-ASCII bytes are compared as they are.
+///
+/// Note that this changes which keys are equal: `Eq` and `Hash` are also case
+/// insensitive, so "Content-Type" and "content-type" are the same key.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AsciiCaseInsensitive<'a>(pub &'a str);
+
+impl AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn lowercase_bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.0.bytes().map(|b| b.to_ascii_lowercase())
+    }
+}
+
+impl<'a> Deref for AsciiCaseInsensitive<'a> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for AsciiCaseInsensitive<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self(s)
+    }
+}
+
+impl PartialEq for AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(other.0)
+    }
+}
+
+impl Eq for AsciiCaseInsensitive<'_> {}
+
+impl PartialOrd for AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for AsciiCaseInsensitive<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.lowercase_bytes().cmp(other.lowercase_bytes())
+    }
+}
+
+impl Hash for AsciiCaseInsensitive<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.lowercase_bytes() {
+            state.write_u8(b);
+        }
+        // the same terminator as `str` uses so that concatenations differ
+        state.write_u8(0xff);
+    }
+}
+
+impl<'a> TreeOrd<Self> for AsciiCaseInsensitive<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        mapped_bytes_tree_cmp::<_, false>(
+            self.0.as_bytes(),
+            rhs.0.as_bytes(),
+            |b| b.to_ascii_lowercase(),
+            tracker,
+        )
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        mapped_bytes_tree_cmp::<_, true>(
+            self.0.as_bytes(),
+            rhs.0.as_bytes(),
+            |b| b.to_ascii_lowercase(),
+            tracker,
+        )
+    }
+}
+
+/// The same as `AsciiCaseInsensitive` but for an owned `String`
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default)]
+pub struct AsciiCaseInsensitiveString(pub alloc::string::String);
+
+#[cfg(feature = "alloc")]
+impl AsciiCaseInsensitiveString {
+    /// Returns the borrowed form
+    #[inline]
+    pub fn as_borrowed(&self) -> AsciiCaseInsensitive<'_> {
+        AsciiCaseInsensitive(&self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Deref for AsciiCaseInsensitiveString {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<&str> for AsciiCaseInsensitiveString {
+    #[inline]
+    fn from(s: &str) -> Self {
+        Self(s.into())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<alloc::string::String> for AsciiCaseInsensitiveString {
+    #[inline]
+    fn from(s: alloc::string::String) -> Self {
+        Self(s)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl PartialEq for AsciiCaseInsensitiveString {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.as_borrowed() == other.as_borrowed()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Eq for AsciiCaseInsensitiveString {}
+
+#[cfg(feature = "alloc")]
+impl PartialOrd for AsciiCaseInsensitiveString {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Ord for AsciiCaseInsensitiveString {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_borrowed().cmp(&other.as_borrowed())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Hash for AsciiCaseInsensitiveString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_borrowed().hash(state)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl TreeOrd<Self> for AsciiCaseInsensitiveString {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_borrowed().tree_cmp(&rhs.as_borrowed(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.as_borrowed()
+            .tree_cmp_hereditary(&rhs.as_borrowed(), tracker)
+    }
+}
+
+/// A string that is ordered by the simple Unicode case folding of its `char`s,
+/// which is folded on the fly without allocating. Every `char` folds to exactly
+/// one `char`, so the tracker counts `char`s. This is not locale aware, for
+/// example the Turkish dotless 'ı' is its own letter while 'I' folds to 'i'.
+/// Folds to multiple `char`s are not applied, so 'ß' stays 'ß' (and 'ẞ' folds
+/// to it) instead of becoming "ss".
+///
+/// Note that this changes which keys are equal: `Eq` and `Hash` also use the
+/// case folding, so "STRAẞE" and "straße" are the same key.
+#[cfg(feature = "unicode-case")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CaseFolded<S: AsRef<str>>(pub S);
+
+#[cfg(feature = "unicode-case")]
+impl<S: AsRef<str>> CaseFolded<S> {
+    #[inline]
+    fn folded_chars(&self) -> case_fold::FoldedChars<'_> {
+        case_fold::FoldedChars::new(self.0.as_ref())
+    }
+}
+
+#[cfg(feature = "unicode-case")]
+impl<S: AsRef<str>> PartialEq for CaseFolded<S> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.folded_chars().eq(other.folded_chars())
+    }
+}
+
+#[cfg(feature = "unicode-case")]
+impl<S: AsRef<str>> Eq for CaseFolded<S> {}
+
+#[cfg(feature = "unicode-case")]
+impl<S: AsRef<str>> PartialOrd for CaseFolded<S> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "unicode-case")]
+impl<S: AsRef<str>> Ord for CaseFolded<S> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.folded_chars().cmp(other.folded_chars())
+    }
+}
+
+#[cfg(feature = "unicode-case")]
+impl<S: AsRef<str>> Hash for CaseFolded<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for c in self.folded_chars() {
+            state.write_u32(u32::from(c));
+        }
+        state.write_u8(0xff);
+    }
+}
+
+#[cfg(feature = "unicode-case")]
+impl<S: AsRef<str>> TreeOrd<Self> for CaseFolded<S> {
+    type Tracker = LexicographicTracker<char>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        case_folded_tree_cmp::<false>(self.0.as_ref(), rhs.0.as_ref(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        case_folded_tree_cmp::<true>(self.0.as_ref(), rhs.0.as_ref(), tracker)
+    }
+}
+
+/// Compares the case folded `char`s of `lhs` and `rhs` after the proven prefix
+/// of `char`s
+#[cfg(feature = "unicode-case")]
+fn case_folded_tree_cmp<const HEREDITARY: bool>(
+    lhs: &str,
+    rhs: &str,
+    tracker: &mut LexicographicTracker<char>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    let mut x = case_fold::FoldedChars::new(lhs);
+    let mut y = case_fold::FoldedChars::new(rhs);
+    x.skip_folded(start);
+    y.skip_folded(start);
+    transformed_tree_cmp::<_, HEREDITARY>(x, y, start, tracker)
+}
+
+/// Compares streams of transformed `char`s that have already been advanced
+/// past the proven prefix `start`
+#[cfg(any(feature = "unicode-case", feature = "unicode-normalization"))]
+fn transformed_tree_cmp<I: Iterator<Item = char>, const HEREDITARY: bool>(
+    mut x: I,
+    mut y: I,
+    start: usize,
+    tracker: &mut LexicographicTracker<char>,
+) -> Ordering {
+    stats::record(start);
+    let mut i = start;
+    loop {
+        // the transformed lengths are not known ahead of time, only which side
+        // ends first
+        match (x.next(), y.next()) {
+            (Some(a), Some(b)) => {
+                if a != b {
+                    return lexicographic_diverged(i, a.cmp(&b), tracker)
+                }
+            }
+            (None, None) => return lexicographic_len_cmp::<char, usize, HEREDITARY>(i, i, tracker),
+            (None, Some(_)) => {
+                return lexicographic_len_cmp::<char, usize, HEREDITARY>(
+                    i,
+                    i.wrapping_add(1),
+                    tracker,
+                )
+            }
+            (Some(_), None) => {
+                return lexicographic_len_cmp::<char, usize, HEREDITARY>(
+                    i.wrapping_add(1),
+                    i,
+                    tracker,
+                )
+            }
+        }
+        i = i.wrapping_add(1);
+    }
+}
+
+/// A string that is ordered with accents stripped, that is after NFD
+/// normalization with nonspacing marks removed, so that "é", "e\u{301}", and
+/// "e" are all equal. This is transformed on the fly without allocating, and
+/// the tracker counts transformed `char`s. Case is unaffected.
+///
+/// Note that this changes which keys are equal: `Eq` and `Hash` also strip
+/// accents, so "résumé" and "resume" are the same key.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AccentInsensitive<S: AsRef<str>>(pub S);
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> AccentInsensitive<S> {
+    #[inline]
+    fn stripped_chars(&self) -> accents::StrippedChars<'_> {
+        accents::StrippedChars::new(self.0.as_ref())
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> PartialEq for AccentInsensitive<S> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.stripped_chars().eq(other.stripped_chars())
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> Eq for AccentInsensitive<S> {}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> PartialOrd for AccentInsensitive<S> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> Ord for AccentInsensitive<S> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.stripped_chars().cmp(other.stripped_chars())
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> Hash for AccentInsensitive<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for c in self.stripped_chars() {
+            state.write_u32(u32::from(c));
+        }
+        state.write_u8(0xff);
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> TreeOrd<Self> for AccentInsensitive<S> {
+    type Tracker = LexicographicTracker<char>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        accent_insensitive_tree_cmp::<false>(self.0.as_ref(), rhs.0.as_ref(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        accent_insensitive_tree_cmp::<true>(self.0.as_ref(), rhs.0.as_ref(), tracker)
+    }
+}
+
+/// Compares the accent stripped `char`s of `lhs` and `rhs` after the proven
+/// prefix of stripped `char`s
+#[cfg(feature = "unicode-normalization")]
+fn accent_insensitive_tree_cmp<const HEREDITARY: bool>(
+    lhs: &str,
+    rhs: &str,
+    tracker: &mut LexicographicTracker<char>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    let mut x = accents::StrippedChars::new(lhs);
+    let mut y = accents::StrippedChars::new(rhs);
+    x.skip_stripped(start);
+    y.skip_stripped(start);
+    transformed_tree_cmp::<_, HEREDITARY>(x, y, start, tracker)
+}
+
+/// A string that is ordered by its NFC normalized `char`s, which are normalized
+/// on the fly without allocating. The tracker counts normalized `char`s.
+///
+/// Note that this changes which keys are equal: canonically equivalent strings
+/// are equal, so `Nfc("e\u{301}") == Nfc("\u{e9}")`, and `Hash` is consistent
+/// with this. Runs of more than 30 combining marks in a row (which are outside
+/// of the Stream-Safe Text Format of UAX #15) are normalized in pieces.
+#[cfg(feature = "unicode-normalization")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Nfc<S: AsRef<str>>(pub S);
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> Nfc<S> {
+    #[inline]
+    fn nfc_chars(&self) -> normalize::NfcChars<'_> {
+        normalize::NfcChars::new(self.0.as_ref())
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> PartialEq for Nfc<S> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.nfc_chars().eq(other.nfc_chars())
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> Eq for Nfc<S> {}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> PartialOrd for Nfc<S> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> Ord for Nfc<S> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.nfc_chars().cmp(other.nfc_chars())
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> Hash for Nfc<S> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for c in self.nfc_chars() {
+            state.write_u32(u32::from(c));
+        }
+        state.write_u8(0xff);
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+impl<S: AsRef<str>> TreeOrd<Self> for Nfc<S> {
+    type Tracker = LexicographicTracker<char>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        nfc_tree_cmp::<false>(self.0.as_ref(), rhs.0.as_ref(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        nfc_tree_cmp::<true>(self.0.as_ref(), rhs.0.as_ref(), tracker)
+    }
+}
+
+/// Compares the NFC normalized `char`s of `lhs` and `rhs` after the proven
+/// prefix of normalized `char`s
+#[cfg(feature = "unicode-normalization")]
+fn nfc_tree_cmp<const HEREDITARY: bool>(
+    lhs: &str,
+    rhs: &str,
+    tracker: &mut LexicographicTracker<char>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    let mut x = normalize::NfcChars::new(lhs);
+    let mut y = normalize::NfcChars::new(rhs);
+    x.skip_normalized(start);
+    y.skip_normalized(start);
+    transformed_tree_cmp::<_, HEREDITARY>(x, y, start, tracker)
+}
+
+/// A slice that is ordered lexicographically from its end, for keys that
+/// cluster by suffix such as file extensions or unreversed domain names. The
+/// tracker tracks the common suffix length.
+///
+/// If one slice is a suffix of the other, the shorter one is `Less`, the same
+/// as for prefixes in the usual lexicographic ordering. This makes the
+/// ordering that of the reversed slices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuffixLexicographic<'a, T>(pub &'a [T]);
+
+impl<T: Ord> PartialOrd for SuffixLexicographic<'_, T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> Ord for SuffixLexicographic<'_, T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl<'a, T: TreeOrd> TreeOrd<Self> for SuffixLexicographic<'a, T> {
+    type Tracker = LexicographicTracker<T>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        suffix_tree_cmp::<T, false>(self.0, rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        suffix_tree_cmp::<T, true>(self.0, rhs.0, tracker)
+    }
+}
+
+/// The same ordering as `SuffixLexicographic<u8>`, but comparing 8 bytes at a
+/// time from the end
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuffixLexicographicBytes<'a>(pub &'a [u8]);
+
+impl PartialOrd for SuffixLexicographicBytes<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SuffixLexicographicBytes<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.iter().rev().cmp(other.0.iter().rev())
+    }
+}
+
+impl<'a> TreeOrd<Self> for SuffixLexicographicBytes<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        suffix_bytes_tree_cmp::<false>(self.0, rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        suffix_bytes_tree_cmp::<true>(self.0, rhs.0, tracker)
+    }
+}
+
+/// A `str` that is ordered like `SuffixLexicographicBytes` of its bytes. Note
+/// that for non-ASCII strings this is not the same as comparing `char`s from
+/// the end, but it is a consistent total ordering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SuffixLexicographicStr<'a>(pub &'a str);
+
+impl PartialOrd for SuffixLexicographicStr<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SuffixLexicographicStr<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        SuffixLexicographicBytes(self.0.as_bytes())
+            .cmp(&SuffixLexicographicBytes(other.0.as_bytes()))
+    }
+}
+
+impl<'a> TreeOrd<Self> for SuffixLexicographicStr<'a> {
+    type Tracker = LexicographicTracker<u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        suffix_bytes_tree_cmp::<false>(self.0.as_bytes(), rhs.0.as_bytes(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        suffix_bytes_tree_cmp::<true>(self.0.as_bytes(), rhs.0.as_bytes(), tracker)
+    }
+}
+
+/// Compares `lhs` and `rhs` from their ends after the proven common suffix.
+/// Little endian words put the byte closest to the end in the most significant
+/// position, so whole words can be compared at once.
+#[inline]
+fn suffix_bytes_tree_cmp<const HEREDITARY: bool>(
+    lhs: &[u8],
+    rhs: &[u8],
+    tracker: &mut LexicographicTracker<u8>,
+) -> Ordering {
+    let end = min(lhs.len(), rhs.len());
+    let mut i = min(tracker.proven_prefix_len(), end);
+    stats::record(i);
+    while i.wrapping_add(8) <= end {
+        let x_end = lhs.len().wrapping_sub(i);
+        let y_end = rhs.len().wrapping_sub(i);
+        let x = u64::from_le_bytes(lhs[x_end.wrapping_sub(8)..x_end].try_into().unwrap());
+        let y = u64::from_le_bytes(rhs[y_end.wrapping_sub(8)..y_end].try_into().unwrap());
+        if x != y {
+            let diverge = i.wrapping_add(((x ^ y).leading_zeros() / 8) as usize);
+            return lexicographic_diverged(diverge, x.cmp(&y), tracker)
+        }
+        i = i.wrapping_add(8);
+    }
+    while i < end {
+        let a = lhs[lhs.len().wrapping_sub(1).wrapping_sub(i)];
+        let b = rhs[rhs.len().wrapping_sub(1).wrapping_sub(i)];
+        if a != b {
+            return lexicographic_diverged(i, a.cmp(&b), tracker)
+        }
+        i = i.wrapping_add(1);
+    }
+    lexicographic_len_cmp::<u8, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+}
+
+/// A `str` in natural sort order, so that "file2" < "file10". The string is
+/// split into segments that are runs of ASCII digits or runs of other
+/// characters, and the segments are compared in order:
+///
+/// - Digit segments are compared by numeric value (of any length), and equal
+///   values are ordered by their number of leading zeros, so "1" < "01".
+/// - Other segments are compared by bytes.
+/// - A digit segment and another segment are compared by their first bytes.
+/// - If one string runs out of segments first, it is `Less`.
+///
+/// Segments are only equal if they are equal in bytes, so `Eq` is plain string
+/// equality. The tracker tracks the number of equal leading segments, see
+/// [NaturalTracker].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct NaturalOrdStr<'a>(pub &'a str);
+
+impl PartialOrd for NaturalOrdStr<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for NaturalOrdStr<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        natural_tree_cmp::<false>(self.0, other.0, &mut NaturalTracker::new())
+    }
+}
+
+impl<'a> TreeOrd<Self> for NaturalOrdStr<'a> {
+    type Tracker = NaturalTracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        natural_tree_cmp::<false>(self.0, rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        natural_tree_cmp::<true>(self.0, rhs.0, tracker)
+    }
+}
+
+/// Returns the end of the segment starting at `start`
+#[inline]
+fn natural_segment_end(s: &[u8], start: usize) -> usize {
+    let digits = s[start].is_ascii_digit();
+    let len = s[start..]
+        .iter()
+        .take_while(|b| b.is_ascii_digit() == digits)
+        .count();
+    start.wrapping_add(len)
+}
+
+/// Compares two segments
+#[inline]
+fn natural_segment_cmp(x: &[u8], y: &[u8]) -> Ordering {
+    if x[0].is_ascii_digit() && y[0].is_ascii_digit() {
+        let x_zeros = x.iter().take_while(|b| **b == b'0').count();
+        let y_zeros = y.iter().take_while(|b| **b == b'0').count();
+        let (x_value, y_value) = (&x[x_zeros..], &y[y_zeros..]);
+        x_value
+            .len()
+            .cmp(&y_value.len())
+            .then_with(|| x_value.cmp(y_value))
+            .then_with(|| x_zeros.cmp(&y_zeros))
+    } else {
+        x.cmp(y)
+    }
+}
+
+/// Compares the segments of `lhs` and `rhs` after the proven segments
+fn natural_tree_cmp<const HEREDITARY: bool>(
+    lhs: &str,
+    rhs: &str,
+    tracker: &mut NaturalTracker,
+) -> Ordering {
+    let (x, y) = (lhs.as_bytes(), rhs.as_bytes());
+    let mut segments = tracker.proven_segments();
+    stats::record(segments);
+    // ASCII digits never occur within multibyte UTF-8, so segments can be found
+    // in bytes
+    let mut i = min(tracker.proven_bytes(), min(x.len(), y.len()));
+    loop {
+        match (i < x.len(), i < y.len()) {
+            (true, true) => (),
+            (false, false) => {
+                if HEREDITARY {
+                    tracker.set_min_eq(segments, i);
+                }
+                return Equal
+            }
+            (false, true) => {
+                tracker.set_max_eq(segments, i);
+                return Less
+            }
+            (true, false) => {
+                tracker.set_min_eq(segments, i);
+                return Greater
+            }
+        }
+        let x_end = natural_segment_end(x, i);
+        let y_end = natural_segment_end(y, i);
+        match natural_segment_cmp(&x[i..x_end], &y[i..y_end]) {
+            Less => {
+                tracker.set_max_eq(segments, i);
+                return Less
+            }
+            // equal segments have equal lengths
+            Equal => (),
+            Greater => {
+                tracker.set_min_eq(segments, i);
+                return Greater
+            }
+        }
+        i = x_end;
+        segments = segments.wrapping_add(1);
+    }
+}
+
+/// A dotted version string such as "1.10.0" or "2.0.0-rc.2+build5", so that
+/// "1.9.0" < "1.10.0" without parsing into a version type. The string is split
+/// at every `.` into segments, and the segments are compared in order:
+///
+/// - Segments that are nonempty and only ASCII digits are numeric, and are
+///   compared by value (of any length). Equal values are ordered by their
+///   number of leading zeros, so "1.0" < "1.00".
+/// - Numeric segments are `Less` than other segments, and other segments are
+///   compared by bytes, so "1.2" < "1.2a" < "1.2b". Empty segments are `Less`
+///   than both, so "1." < "1.0".
+/// - If one string runs out of segments first, it is `Less`, so "1.0" <
+///   "1.0.0".
+///
+/// Note that this is not semver precedence, "1.0.0" < "1.0.0-rc.1" because
+/// "0" < "0-rc". Segments are only equal if they are equal in bytes, so `Eq` is
+/// plain string equality. The tracker tracks the number of equal leading
+/// segments, see [NaturalTracker].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct VersionStr<'a>(pub &'a str);
+
+impl PartialOrd for VersionStr<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for VersionStr<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        version_tree_cmp::<false>(self.0, other.0, &mut NaturalTracker::new())
+    }
+}
+
+impl<'a> TreeOrd<Self> for VersionStr<'a> {
+    type Tracker = NaturalTracker;
 
     #[inline]
     fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        // byte comparison is greatly sped up internally by
-        // `core::intrinsics::compare_bytes` when we do it in chunks
-        const CHUNK_LEN: usize = 32;
-        let start_chunks = min(tracker.min_eq_len, tracker.max_eq_len);
-        let start_bytes = start_chunks.wrapping_mul(CHUNK_LEN);
-        let end_bytes = min(self.0.len(), rhs.0.len());
-        let end_chunks = end_bytes.wrapping_div(CHUNK_LEN);
-        if start_chunks >= end_chunks {
-            if start_bytes >= end_bytes {
-                return self.0.len().cmp(&rhs.0.len())
-            } else {
-                let x = &self.0[start_bytes..];
-                let y = &rhs.0[start_bytes..];
-                return x.cmp(y)
-            }
-        }
-        let len_chunks = end_chunks.wrapping_sub(start_chunks);
-        for i in 0..len_chunks {
-            let start = start_chunks.wrapping_add(i).wrapping_mul(CHUNK_LEN);
-            let end = start.wrapping_add(CHUNK_LEN);
-            let x = &self.0[start..end];
-            let y = &rhs.0[start..end];
-            match x.cmp(y) {
-                Less => {
-                    tracker.max_eq_len = i;
-                    return Less
+        version_tree_cmp::<false>(self.0, rhs.0, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        version_tree_cmp::<true>(self.0, rhs.0, tracker)
+    }
+}
+
+/// Compares two dot separated segments
+#[inline]
+fn version_segment_cmp(x: &[u8], y: &[u8]) -> Ordering {
+    // empty, numeric, or other
+    let class = |s: &[u8]| match s {
+        [] => 0,
+        _ if s.iter().all(u8::is_ascii_digit) => 1,
+        _ => 2,
+    };
+    let (x_class, y_class) = (class(x), class(y));
+    if x_class != y_class {
+        x_class.cmp(&y_class)
+    } else if x_class == 1 {
+        natural_segment_cmp(x, y)
+    } else {
+        x.cmp(y)
+    }
+}
+
+/// Compares the segments of `lhs` and `rhs` after the proven segments. Segment
+/// starts are after the `.` of the previous segment, so a string has no more
+/// segments once the start is past its end.
+fn version_tree_cmp<const HEREDITARY: bool>(
+    lhs: &str,
+    rhs: &str,
+    tracker: &mut NaturalTracker,
+) -> Ordering {
+    let (x, y) = (lhs.as_bytes(), rhs.as_bytes());
+    let mut segments = tracker.proven_segments();
+    stats::record(segments);
+    let mut i = min(
+        tracker.proven_bytes(),
+        min(x.len(), y.len()).wrapping_add(1),
+    );
+    loop {
+        match (i <= x.len(), i <= y.len()) {
+            (true, true) => (),
+            (false, false) => {
+                if HEREDITARY {
+                    tracker.set_min_eq(segments, i);
                 }
-                Equal => (),
-                Greater => {
-                    tracker.min_eq_len = i;
-                    return Greater
+                return Equal
+            }
+            (false, true) => {
+                tracker.set_max_eq(segments, i);
+                return Less
+            }
+            (true, false) => {
+                tracker.set_min_eq(segments, i);
+                return Greater
+            }
+        }
+        let segment_end = |s: &[u8]| {
+            s[i..]
+                .iter()
+                .position(|b| *b == b'.')
+                .map_or(s.len(), |len| i.wrapping_add(len))
+        };
+        let (x_end, y_end) = (segment_end(x), segment_end(y));
+        match version_segment_cmp(&x[i..x_end], &y[i..y_end]) {
+            Less => {
+                tracker.set_max_eq(segments, i);
+                return Less
+            }
+            // equal segments have equal lengths
+            Equal => (),
+            Greater => {
+                tracker.set_min_eq(segments, i);
+                return Greater
+            }
+        }
+        i = x_end.wrapping_add(1);
+        segments = segments.wrapping_add(1);
+    }
+}
+
+/// A DNS name in presentation format (such as "www.Example.com.") in the
+/// canonical DNS name order of RFC 4034 section 6.1, as used by DNSSEC. Names
+/// are compared label by label starting from the rightmost label. Labels are
+/// compared as byte strings with ASCII letters lowercased, and a label that is
+/// a prefix of another is `Less`. A name whose labels are a suffix of the
+/// labels of another name is `Less`, so a zone apex sorts before the names in
+/// the zone.
+///
+/// The name is split at every `.`, escapes like "\." and "\001" are not
+/// interpreted, so raw bytes should be used for labels with dots or binary
+/// data in them. A single trailing `.` is ignored, so "example.com." and
+/// "example.com" are equal, and "." and "" are both the root name with no
+/// labels. Any other empty labels are kept, and sort before every nonempty
+/// label.
+///
+/// Note that this changes which keys are equal: `Eq` and `Hash` also ignore
+/// ASCII case and the trailing `.`. The tracker tracks the number of equal
+/// labels from the right, see [NaturalTracker], so names in a zone skip the
+/// labels of the zone once they are proven.
+///
+/// ```
+/// use tree_ord::DnsName;
+///
+/// // the example from RFC 4034, with the escaped labels as raw bytes
+/// let names: [&[u8]; 9] = [
+///     b"example",
+///     b"a.example",
+///     b"yljkjljk.a.example",
+///     b"Z.a.example",
+///     b"zABC.a.EXAMPLE",
+///     b"z.example",
+///     b"\x01.z.example",
+///     b"*.z.example",
+///     b"\x80.z.example",
+/// ];
+/// assert!(names.windows(2).all(|w| DnsName(w[0]) < DnsName(w[1])));
+/// assert_eq!(DnsName::from("Example.COM."), DnsName::from("example.com"));
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsName<'a>(pub &'a [u8]);
+
+impl DnsName<'_> {
+    /// Returns the name without the trailing `.`
+    #[inline]
+    fn trimmed(&self) -> &[u8] {
+        self.0.strip_suffix(b".").unwrap_or(self.0)
+    }
+}
+
+impl<'a> Deref for DnsName<'a> {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+impl<'a> From<&'a [u8]> for DnsName<'a> {
+    #[inline]
+    fn from(s: &'a [u8]) -> Self {
+        Self(s)
+    }
+}
+
+impl<'a> From<&'a str> for DnsName<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self(s.as_bytes())
+    }
+}
+
+impl PartialEq for DnsName<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.trimmed().eq_ignore_ascii_case(other.trimmed())
+    }
+}
+
+impl Eq for DnsName<'_> {}
+
+impl PartialOrd for DnsName<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for DnsName<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        dns_tree_cmp::<false>(self.trimmed(), other.trimmed(), &mut NaturalTracker::new())
+    }
+}
+
+impl Hash for DnsName<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for b in self.trimmed() {
+            state.write_u8(b.to_ascii_lowercase());
+        }
+        state.write_u8(0xff);
+    }
+}
+
+impl<'a> TreeOrd<Self> for DnsName<'a> {
+    type Tracker = NaturalTracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        dns_tree_cmp::<false>(self.trimmed(), rhs.trimmed(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        dns_tree_cmp::<true>(self.trimmed(), rhs.trimmed(), tracker)
+    }
+}
+
+/// Returns the label of `name` that ends `i` bytes before the end
+#[inline]
+fn dns_label(name: &[u8], i: usize) -> &[u8] {
+    let end = name.len().wrapping_sub(i);
+    let start = name[..end]
+        .iter()
+        .rposition(|b| *b == b'.')
+        .map_or(0, |j| j.wrapping_add(1));
+    &name[start..end]
+}
+
+/// Compares the labels of the trimmed names `lhs` and `rhs` from the right,
+/// after the proven labels. The tracker counts bytes from the end, and label
+/// ends are before the `.` of the previous label, so a name has no more labels
+/// once the offset is past its length (and the root name has none at all).
+fn dns_tree_cmp<const HEREDITARY: bool>(
+    lhs: &[u8],
+    rhs: &[u8],
+    tracker: &mut NaturalTracker,
+) -> Ordering {
+    let mut labels = tracker.proven_segments();
+    stats::record(labels);
+    let mut i = min(
+        tracker.proven_bytes(),
+        min(lhs.len(), rhs.len()).wrapping_add(1),
+    );
+    let has_label = |s: &[u8], i: usize| (i <= s.len()) && !s.is_empty();
+    loop {
+        match (has_label(lhs, i), has_label(rhs, i)) {
+            (true, true) => (),
+            (false, false) => {
+                if HEREDITARY {
+                    tracker.set_min_eq(labels, i);
                 }
+                return Equal
+            }
+            (false, true) => {
+                tracker.set_max_eq(labels, i);
+                return Less
+            }
+            (true, false) => {
+                tracker.set_min_eq(labels, i);
+                return Greater
+            }
+        }
+        let (x, y) = (dns_label(lhs, i), dns_label(rhs, i));
+        let res = x
+            .iter()
+            .map(u8::to_ascii_lowercase)
+            .cmp(y.iter().map(u8::to_ascii_lowercase));
+        match res {
+            Less => {
+                tracker.set_max_eq(labels, i);
+                return Less
+            }
+            // equal labels have equal lengths
+            Equal => (),
+            Greater => {
+                tracker.set_min_eq(labels, i);
+                return Greater
             }
         }
-        let extra_start = end_chunks.wrapping_mul(CHUNK_LEN);
-        self.0[extra_start..].cmp(&rhs.0[extra_start..])
+        i = i.wrapping_add(x.len()).wrapping_add(1);
+        labels = labels.wrapping_add(1);
     }
 }
 
-/// The same as `TreeOrdBytes` but for an owned `Vec<u8>`
-#[cfg(feature = "alloc")]
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub struct TreeOrdVec(pub alloc::vec::Vec<u8>);
+/// A `/` separated path that is ordered component by component, so that the
+/// paths of a directory are grouped together. Byte order puts "foo-bar/x"
+/// between "foo/a" and "foo/z" because `-` is less than `/`, while here "foo"
+/// is a prefix of "foo-bar" and every path in "foo/" comes first.
+///
+/// The policy for the edge cases is:
+///
+/// - Empty components are ignored, so repeated and trailing slashes make no
+///   difference: "a//b/" and "a/b" are equal.
+/// - An absolute path starts with a root component that is less than any other
+///   component, so every absolute path sorts before every relative path, and
+///   "/" is the least absolute path.
+/// - "." and ".." are ordinary components and are not normalized away, because
+///   resolving ".." correctly requires following symlinks on the filesystem.
+///   "a/./b" and "a/b" are different keys.
+/// - Only `/` is a separator, Windows paths with `\` should be normalized
+///   first.
+///
+/// Note that this changes which keys are equal: `Eq` and `Hash` also ignore
+/// empty components. The tracker is a `LexicographicTracker` that counts
+/// components, and the proven components are skipped over without being
+/// compared. Because of the ignored empty components, the byte offset after
+/// the proven components can differ between keys, so the separators before it
+/// are still scanned.
+///
+/// ```
+/// use tree_ord::ComponentOrderedPath;
+///
+/// let p = ComponentOrderedPath;
+/// assert!("foo-bar/x" < "foo/z");
+/// assert!(p("foo/z") < p("foo-bar/x"));
+/// assert_eq!(p("foo//bar/"), p("foo/bar"));
+/// assert!(p("/z") < p("a"));
+/// let components: Vec<&str> = p("/usr//lib/").components().collect();
+/// assert_eq!(components, ["", "usr", "lib"]);
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComponentOrderedPath<'a>(pub &'a str);
 
-#[cfg(feature = "alloc")]
-impl TreeOrd<Self> for TreeOrdVec {
+impl<'a> ComponentOrderedPath<'a> {
+    /// Returns the components of the path, starting with an empty component
+    /// for the root of absolute paths
+    #[inline]
+    pub fn components(&self) -> impl Iterator<Item = &'a str> + Clone {
+        let root = self.0.starts_with('/').then_some("");
+        root.into_iter()
+            .chain(self.0.split('/').filter(|c| !c.is_empty()))
+    }
+
+    /// Returns `None` if `path` is not valid UTF-8
+    #[cfg(feature = "std")]
+    pub fn from_path(path: &'a std::path::Path) -> Option<Self> {
+        path.to_str().map(Self)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn as_path(&self) -> &'a std::path::Path {
+        std::path::Path::new(self.0)
+    }
+}
+
+impl<'a> Deref for ComponentOrderedPath<'a> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for ComponentOrderedPath<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self(s)
+    }
+}
+
+impl PartialEq for ComponentOrderedPath<'_> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.components().eq(other.components())
+    }
+}
+
+impl Eq for ComponentOrderedPath<'_> {}
+
+impl PartialOrd for ComponentOrderedPath<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ComponentOrderedPath<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.components().cmp(other.components())
+    }
+}
+
+impl Hash for ComponentOrderedPath<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        // `str` writes a terminator after each component
+        for c in self.components() {
+            c.hash(state);
+        }
+    }
+}
+
+impl<'a> TreeOrd<Self> for ComponentOrderedPath<'a> {
     type Tracker = LexicographicTracker<u8>;
 
     #[inline]
     fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
-        TreeOrdBytes(&self.0).tree_cmp(&TreeOrdBytes(&rhs.0), tracker)
+        path_tree_cmp::<false>(self, rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        path_tree_cmp::<true>(self, rhs, tracker)
+    }
+}
+
+/// Compares the components of `lhs` and `rhs` after the proven components
+fn path_tree_cmp<const HEREDITARY: bool>(
+    lhs: &ComponentOrderedPath,
+    rhs: &ComponentOrderedPath,
+    tracker: &mut LexicographicTracker<u8>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    stats::record(start);
+    let mut x = lhs.components().skip(start);
+    let mut y = rhs.components().skip(start);
+    let mut i = start;
+    loop {
+        match (x.next(), y.next()) {
+            (Some(a), Some(b)) => {
+                let res = a.cmp(b);
+                if res != Equal {
+                    return lexicographic_diverged(i, res, tracker)
+                }
+            }
+            (None, None) => return lexicographic_len_cmp::<u8, usize, HEREDITARY>(i, i, tracker),
+            (None, Some(_)) => {
+                return lexicographic_len_cmp::<u8, usize, HEREDITARY>(i, i.wrapping_add(1), tracker)
+            }
+            (Some(_), None) => {
+                return lexicographic_len_cmp::<u8, usize, HEREDITARY>(i.wrapping_add(1), i, tracker)
+            }
+        }
+        i = i.wrapping_add(1);
+    }
+}
+
+/// A sequence in shortlex order, where shorter sequences are `Less` and
+/// sequences of the same length are ordered lexicographically. This is
+/// implemented for `&[T]`, `&str`, `Vec<T>`, and `String`, and lengths are in
+/// elements or bytes. See [ShortlexTracker] for the tracking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Shortlex<T>(pub T);
+
+macro_rules! impl_shortlex {
+    ($($(#[$attr:meta])* [$($g:tt)*] $ty:ty => $content:ty $(, $t:ident)?;)*) => {$(
+        $(#[$attr])*
+        impl<$($g)*> PartialOrd for Shortlex<$ty> where $($t: Ord)? {
+            #[inline]
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        $(#[$attr])*
+        impl<$($g)*> Ord for Shortlex<$ty> where $($t: Ord)? {
+            #[inline]
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.len().cmp(&other.0.len()).then_with(|| self.0.cmp(&other.0))
+            }
+        }
+
+        $(#[$attr])*
+        impl<$($g)*> TreeOrd<Self> for Shortlex<$ty> where $($t: TreeOrd)? {
+            type Tracker = ShortlexTracker<<$content as TreeOrd>::Tracker>;
+
+            #[inline]
+            fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                shortlex_tree_cmp::<$content, false>(
+                    &self.0[..],
+                    self.0.len(),
+                    &rhs.0[..],
+                    rhs.0.len(),
+                    tracker,
+                )
+            }
+
+            #[inline]
+            fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                shortlex_tree_cmp::<$content, true>(
+                    &self.0[..],
+                    self.0.len(),
+                    &rhs.0[..],
+                    rhs.0.len(),
+                    tracker,
+                )
+            }
+        }
+    )*};
+}
+
+impl_shortlex!(
+    ['a, T] &'a [T] => [T], T;
+    ['a] &'a str => str;
+    #[cfg(feature = "alloc")]
+    [T] alloc::vec::Vec<T> => [T], T;
+    #[cfg(feature = "alloc")]
+    [] alloc::string::String => str;
+);
+
+/// A `str` in dictionary order, compared by its ASCII lowercase bytes first,
+/// with the original bytes only breaking ties between strings that differ just
+/// in case, so "apple" < "Apricot" and "Apple" < "apple". Unlike
+/// [AsciiCaseInsensitive], `Eq` and `Hash` are case sensitive. Nothing is
+/// allocated, see [CaseTiebreakTracker] for the tracking.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CaseTiebreak<'a>(pub &'a str);
+
+impl<'a> Deref for CaseTiebreak<'a> {
+    type Target = str;
+
+    #[inline]
+    fn deref(&self) -> &str {
+        self.0
+    }
+}
+
+impl<'a> From<&'a str> for CaseTiebreak<'a> {
+    #[inline]
+    fn from(s: &'a str) -> Self {
+        Self(s)
+    }
+}
+
+impl PartialOrd for CaseTiebreak<'_> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CaseTiebreak<'_> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        AsciiCaseInsensitive(self.0)
+            .cmp(&AsciiCaseInsensitive(other.0))
+            .then_with(|| self.0.cmp(other.0))
+    }
+}
+
+impl<'a> TreeOrd<Self> for CaseTiebreak<'a> {
+    type Tracker = CaseTiebreakTracker;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        case_tiebreak_tree_cmp::<false>(self.0.as_bytes(), rhs.0.as_bytes(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        case_tiebreak_tree_cmp::<true>(self.0.as_bytes(), rhs.0.as_bytes(), tracker)
+    }
+}
+
+/// `(x, y)` coordinates in Z-order, the order of the integer whose bits
+/// alternate between those of `y` and `x` starting with the most significant
+/// bit of `y`. Range queries over rectangles map to few key ranges in this
+/// order. The tracker records how many of the leading bit pairs of the
+/// interleaved integer are proven equal, so that the high bits of clustered
+/// coordinates are not compared again.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Morton2(pub u32, pub u32);
+
+impl Morton2 {
+    /// Returns the interleaved integer, which has the same order and can be
+    /// stored instead of the coordinates
+    #[inline]
+    pub const fn interleaved(self) -> u64 {
+        spread2(self.0) | (spread2(self.1) << 1)
+    }
+
+    /// The inverse of [Morton2::interleaved]
+    #[inline]
+    pub const fn from_interleaved(z: u64) -> Self {
+        Self(compact2(z), compact2(z >> 1))
+    }
+}
+
+impl From<(u32, u32)> for Morton2 {
+    #[inline]
+    fn from((x, y): (u32, u32)) -> Self {
+        Self(x, y)
+    }
+}
+
+impl PartialOrd for Morton2 {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Morton2 {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.interleaved().cmp(&other.interleaved())
+    }
+}
+
+impl TreeOrd<Self> for Morton2 {
+    type Tracker = LexicographicTracker<u8, u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let (lhs, rhs) = (self.interleaved().into(), rhs.interleaved().into());
+        morton_tree_cmp::<2, false>(lhs, rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        let (lhs, rhs) = (self.interleaved().into(), rhs.interleaved().into());
+        morton_tree_cmp::<2, true>(lhs, rhs, tracker)
+    }
+}
+
+/// `(x, y, z)` coordinates in Z-order, the same as [Morton2] but with bit
+/// triples ordered `z`, `y`, `x`
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+pub struct Morton3(pub u32, pub u32, pub u32);
+
+impl Morton3 {
+    /// Returns the interleaved integer in the low 96 bits, which has the same
+    /// order and can be stored instead of the coordinates
+    #[inline]
+    pub const fn interleaved(self) -> u128 {
+        spread3(self.0) | (spread3(self.1) << 1) | (spread3(self.2) << 2)
+    }
+
+    /// The inverse of [Morton3::interleaved], the bits above the low 96 are
+    /// ignored
+    #[inline]
+    pub const fn from_interleaved(z: u128) -> Self {
+        Self(compact3(z), compact3(z >> 1), compact3(z >> 2))
+    }
+}
+
+impl From<(u32, u32, u32)> for Morton3 {
+    #[inline]
+    fn from((x, y, z): (u32, u32, u32)) -> Self {
+        Self(x, y, z)
+    }
+}
+
+impl PartialOrd for Morton3 {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Morton3 {
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.interleaved().cmp(&other.interleaved())
+    }
+}
+
+impl TreeOrd<Self> for Morton3 {
+    type Tracker = LexicographicTracker<u8, u8>;
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        morton_tree_cmp::<3, false>(self.interleaved(), rhs.interleaved(), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        morton_tree_cmp::<3, true>(self.interleaved(), rhs.interleaved(), tracker)
+    }
+}
+
+/// Moves bit `i` to bit `2 * i`
+#[inline]
+const fn spread2(x: u32) -> u64 {
+    let mut x = x as u64;
+    x = (x | (x << 16)) & 0x0000_ffff_0000_ffff;
+    x = (x | (x << 8)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x << 4)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x << 2)) & 0x3333_3333_3333_3333;
+    (x | (x << 1)) & 0x5555_5555_5555_5555
+}
+
+/// The inverse of `spread2`, ignoring the odd bits
+#[inline]
+const fn compact2(x: u64) -> u32 {
+    let mut x = x & 0x5555_5555_5555_5555;
+    x = (x | (x >> 1)) & 0x3333_3333_3333_3333;
+    x = (x | (x >> 2)) & 0x0f0f_0f0f_0f0f_0f0f;
+    x = (x | (x >> 4)) & 0x00ff_00ff_00ff_00ff;
+    x = (x | (x >> 8)) & 0x0000_ffff_0000_ffff;
+    (x | (x >> 16)) as u32
+}
+
+/// Moves bit `i` to bit `3 * i`. The halves are spread separately so that
+/// the steps fit in 64 bits.
+#[inline]
+const fn spread3(x: u32) -> u128 {
+    const fn spread(x: u32) -> u64 {
+        let mut x = (x & 0xffff) as u64;
+        x = (x | (x << 16)) & 0x0000_ff00_00ff;
+        x = (x | (x << 8)) & 0x00f0_0f00_f00f;
+        x = (x | (x << 4)) & 0x0c30_c30c_30c3;
+        (x | (x << 2)) & 0x2492_4924_9249
+    }
+    (spread(x) as u128) | ((spread(x >> 16) as u128) << 48)
+}
+
+/// The inverse of `spread3`, ignoring the other bits
+#[inline]
+const fn compact3(x: u128) -> u32 {
+    const fn compact(x: u64) -> u32 {
+        let mut x = x & 0x2492_4924_9249;
+        x = (x | (x >> 2)) & 0x0c30_c30c_30c3;
+        x = (x | (x >> 4)) & 0x00f0_0f00_f00f;
+        x = (x | (x >> 8)) & 0x0000_ff00_00ff;
+        ((x | (x >> 16)) & 0xffff) as u32
+    }
+    compact(x as u64 & 0xffff_ffff_ffff) | (compact((x >> 48) as u64) << 16)
+}
+
+/// Compares interleaved integers of `32 * D` bits after the proven prefix of
+/// `D` bit groups
+#[inline]
+fn morton_tree_cmp<const D: usize, const HEREDITARY: bool>(
+    lhs: u128,
+    rhs: u128,
+    tracker: &mut LexicographicTracker<u8, u8>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    stats::record(start);
+    if start >= 32 {
+        return Equal
+    }
+    let mask = (u128::MAX >> (128 - 32 * D)) >> (start * D);
+    let diff = (lhs ^ rhs) & mask;
+    if diff == 0 {
+        if HEREDITARY {
+            tracker.set_min_eq_len(32);
+        }
+        return Equal
+    }
+    let diverge = (diff.leading_zeros() as usize - (128 - 32 * D)) / D;
+    let res = (lhs & mask).cmp(&(rhs & mask));
+    if res == Less {
+        tracker.set_max_eq_len(diverge);
+    } else {
+        tracker.set_min_eq_len(diverge);
     }
+    res
 }
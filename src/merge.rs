@@ -0,0 +1,768 @@
+//! Merge-like algorithms over sorted sequences that carry prefix knowledge
+//! between comparisons
+
+#[cfg(feature = "alloc")]
+use alloc::{vec, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::cmp::min;
+use core::{cmp::Ordering, ops::Range};
+
+use Ordering::*;
+
+#[cfg(feature = "alloc")]
+use crate::search::tree_binary_search_by;
+use crate::{
+    search::{gallop_search_indices, tree_gallop_search_by, tree_search_fn},
+    Tracker, TreeOrd,
+};
+
+/// Iterator returned by [tree_merge_join]
+#[derive(Debug, Clone)]
+pub struct TreeMergeJoin<'a, K, I> {
+    space: &'a [K],
+    queries: I,
+    // all keys before this index are known to be less than the remaining
+    // queries
+    frontier: usize,
+}
+
+impl<'a, K, I> Iterator for TreeMergeJoin<'a, K, I>
+where
+    I: Iterator,
+    K: TreeOrd<I::Item>,
+{
+    type Item = (I::Item, Option<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let query = self.queries.next()?;
+        let space = &self.space[self.frontier..];
+        let mut f = tree_search_fn(&query);
+        // the index of the last key that compared `Equal`, later `Equal` keys
+        // are always at lower indexes
+        let mut last_equal = None;
+        // `Equal` is mapped to `Greater` so that the search finds the first
+        // equal key, the search is still valid since `Equal` never strengthens
+        // any bounds
+        let lower = match gallop_search_indices(space.len(), 0, |i| match f(&space[i]) {
+            Less => Less,
+            Equal => {
+                last_equal = Some(i);
+                Greater
+            }
+            Greater => Greater,
+        }) {
+            Ok(i) | Err(i) => i,
+        };
+        drop(f);
+        // If the key at `lower` is equal to the query, it must have been probed
+        // (otherwise the search could not have excluded the keys before it) and
+        // it would be the last `Equal`, so we do not need another comparison
+        let found = last_equal == Some(lower);
+        self.frontier = self.frontier.wrapping_add(lower);
+        Some((query, if found { Some(self.frontier) } else { None }))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.queries.size_hint()
+    }
+}
+
+/// Merge-joins the sorted `queries` against the sorted `space`, yielding each
+/// query along with the index of the first key in `space` equal to it (if
+/// any).
+///
+/// Instead of independent binary searches over the whole space, each query is
+/// gallop searched (see [tree_gallop_search_by]) starting from where the
+/// previous query landed, so nearby queries only take a few comparisons.
+/// Each query uses its own tracker. Duplicate queries yield the same index. If
+/// `queries` is not sorted, the results for queries that are less than a
+/// previous query are unspecified.
+pub fn tree_merge_join<K, I>(space: &[K], queries: I) -> TreeMergeJoin<'_, K, I::IntoIter>
+where
+    I: IntoIterator,
+    K: TreeOrd<I::Item>,
+{
+    TreeMergeJoin {
+        space,
+        queries: queries.into_iter(),
+        frontier: 0,
+    }
+}
+
+/// Which input of a merge an output range comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MergeSide {
+    A,
+    B,
+}
+
+/// After this many consecutive outputs from the same side, merging switches to
+/// galloping
+const MIN_GALLOP: usize = 7;
+
+/// Merges the sorted `a` and `b` by calling `emit` with ranges of indices into
+/// `a` or `b` in the order that the corresponding elements appear in the
+/// merged output. Ranges are never empty. The merge is stable, elements of `a`
+/// come before equal elements of `b`.
+///
+/// Unlike a tree search, a merge compares two heads that both change over
+/// time, so a tracker cannot be kept across the whole merge. The argument we
+/// use instead is that while one side keeps winning, the head of the other
+/// side stays fixed and is compared against a sequence of increasing keys,
+/// where each key that lost is a lower bound for all later keys from the same
+/// side. This is a valid comparison sequence for a tracker tied to the fixed
+/// head, so a single tracker is kept until the winning side changes. Only
+/// lower bounds are established this way, so once one side wins
+/// `MIN_GALLOP` times in a row, the merge switches to galloping (see
+/// [crate::search::tree_gallop_search_by]) with the same tracker. The gallop
+/// probes overshoot the fixed head and establish an upper bound, after which
+/// the prefix shared by the bounds is skipped. For clustered inputs with long
+/// runs this skips most of the comparison work.
+///
+/// All comparisons are of the form `a[i].tree_cmp(&b[j], ..)` so that the
+/// tracker never sees a changing orientation.
+pub fn tree_merge_ranges<K, F>(a: &[K], b: &[K], mut emit: F)
+where
+    K: TreeOrd,
+    F: FnMut(MergeSide, Range<usize>),
+{
+    let mut i = 0;
+    let mut j = 0;
+    let mut tracker = K::Tracker::new();
+    // the side that most recently won and how many consecutive times
+    let mut last = MergeSide::A;
+    let mut streak = 0usize;
+    while (i < a.len()) && (j < b.len()) {
+        if streak >= MIN_GALLOP {
+            match last {
+                MergeSide::A => {
+                    // `b[j]` is fixed, find the number of `a` keys not greater than it
+                    let y = &b[j];
+                    let n = match tree_gallop_search_by(&a[i..], 0, |x| {
+                        match x.tree_cmp(y, &mut tracker) {
+                            Less | Equal => Less,
+                            Greater => Greater,
+                        }
+                    }) {
+                        Ok(n) | Err(n) => n,
+                    };
+                    if n != 0 {
+                        emit(MergeSide::A, i..i.wrapping_add(n));
+                        i = i.wrapping_add(n);
+                    }
+                    if i >= a.len() {
+                        break
+                    }
+                    // the gallop determined that `a[i] > b[j]`
+                    emit(MergeSide::B, j..j.wrapping_add(1));
+                    j = j.wrapping_add(1);
+                    last = MergeSide::B;
+                    streak = if n >= MIN_GALLOP { MIN_GALLOP } else { 1 };
+                }
+                MergeSide::B => {
+                    // `a[i]` is fixed, find the number of `b` keys less than it
+                    let x = &a[i];
+                    let n = match tree_gallop_search_by(&b[j..], 0, |y| {
+                        match x.tree_cmp(y, &mut tracker) {
+                            Less | Equal => Greater,
+                            Greater => Less,
+                        }
+                    }) {
+                        Ok(n) | Err(n) => n,
+                    };
+                    if n != 0 {
+                        emit(MergeSide::B, j..j.wrapping_add(n));
+                        j = j.wrapping_add(n);
+                    }
+                    if j >= b.len() {
+                        break
+                    }
+                    // the gallop determined that `a[i] <= b[j]`
+                    emit(MergeSide::A, i..i.wrapping_add(1));
+                    i = i.wrapping_add(1);
+                    last = MergeSide::A;
+                    streak = if n >= MIN_GALLOP { MIN_GALLOP } else { 1 };
+                }
+            }
+            // the fixed head changed
+            tracker = K::Tracker::new();
+        } else {
+            let winner = match a[i].tree_cmp(&b[j], &mut tracker) {
+                Less | Equal => {
+                    emit(MergeSide::A, i..i.wrapping_add(1));
+                    i = i.wrapping_add(1);
+                    MergeSide::A
+                }
+                Greater => {
+                    emit(MergeSide::B, j..j.wrapping_add(1));
+                    j = j.wrapping_add(1);
+                    MergeSide::B
+                }
+            };
+            if winner == last {
+                streak = streak.wrapping_add(1);
+            } else {
+                // the previously fixed head was just output
+                last = winner;
+                streak = 1;
+                tracker = K::Tracker::new();
+            }
+        }
+    }
+    if i < a.len() {
+        emit(MergeSide::A, i..a.len());
+    }
+    if j < b.len() {
+        emit(MergeSide::B, j..b.len());
+    }
+}
+
+/// Merges the sorted `a` and `b` into `out` by cloning. See
+/// [tree_merge_ranges] for more.
+#[cfg(feature = "alloc")]
+pub fn tree_merge<K: TreeOrd + Clone>(a: &[K], b: &[K], out: &mut Vec<K>) {
+    out.reserve(a.len().saturating_add(b.len()));
+    tree_merge_ranges(a, b, |side, range| match side {
+        MergeSide::A => out.extend_from_slice(&a[range]),
+        MergeSide::B => out.extend_from_slice(&b[range]),
+    })
+}
+
+/// An output of [tree_set_ranges]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SetRange {
+    /// Keys of `a` that are not in `b`
+    OnlyA(Range<usize>),
+    /// Keys of `b` that are not in `a`
+    OnlyB(Range<usize>),
+    /// `a[i]` and `b[j]` are equal keys
+    Both(usize, usize),
+}
+
+/// Walks the sorted and deduplicated `a` and `b` in merged order, calling
+/// `emit` with the ranges of keys that are only in one of the inputs and with
+/// the pairs of keys that are in both. Ranges are never empty. This is the
+/// basis of [tree_union], [tree_intersection], and [tree_difference].
+///
+/// The tracker reasoning is the same as [tree_merge_ranges], except that
+/// when a key is in both inputs both heads change and the tracker is reset.
+/// If the inputs are not deduplicated, the results are unspecified.
+pub fn tree_set_ranges<K, F>(a: &[K], b: &[K], mut emit: F)
+where
+    K: TreeOrd,
+    F: FnMut(SetRange),
+{
+    let mut i = 0;
+    let mut j = 0;
+    let mut tracker = K::Tracker::new();
+    let mut last = MergeSide::A;
+    let mut streak = 0usize;
+    while (i < a.len()) && (j < b.len()) {
+        if streak >= MIN_GALLOP {
+            // the index (relative to the head) of the last key that compared
+            // `Equal`, later `Equal` keys are always at lower indexes
+            let mut last_equal = None;
+            let n = match last {
+                MergeSide::A => {
+                    // `b[j]` is fixed, find the number of `a` keys less than it
+                    let y = &b[j];
+                    let rest = &a[i..];
+                    let n = match gallop_search_indices(rest.len(), 0, |k| {
+                        match rest[k].tree_cmp(y, &mut tracker) {
+                            Less => Less,
+                            Equal => {
+                                last_equal = Some(k);
+                                Greater
+                            }
+                            Greater => Greater,
+                        }
+                    }) {
+                        Ok(n) | Err(n) => n,
+                    };
+                    if n != 0 {
+                        emit(SetRange::OnlyA(i..i.wrapping_add(n)));
+                        i = i.wrapping_add(n);
+                    }
+                    if i >= a.len() {
+                        break
+                    }
+                    // if `a[i]` is equal it must have been probed and was the last
+                    // `Equal`
+                    if last_equal == Some(n) {
+                        emit(SetRange::Both(i, j));
+                        i = i.wrapping_add(1);
+                    } else {
+                        emit(SetRange::OnlyB(j..j.wrapping_add(1)));
+                    }
+                    j = j.wrapping_add(1);
+                    last = MergeSide::B;
+                    n
+                }
+                MergeSide::B => {
+                    // `a[i]` is fixed, find the number of `b` keys less than it
+                    let x = &a[i];
+                    let rest = &b[j..];
+                    let n = match gallop_search_indices(rest.len(), 0, |k| {
+                        match x.tree_cmp(&rest[k], &mut tracker) {
+                            Less => Greater,
+                            Equal => {
+                                last_equal = Some(k);
+                                Greater
+                            }
+                            Greater => Less,
+                        }
+                    }) {
+                        Ok(n) | Err(n) => n,
+                    };
+                    if n != 0 {
+                        emit(SetRange::OnlyB(j..j.wrapping_add(n)));
+                        j = j.wrapping_add(n);
+                    }
+                    if j >= b.len() {
+                        break
+                    }
+                    if last_equal == Some(n) {
+                        emit(SetRange::Both(i, j));
+                        j = j.wrapping_add(1);
+                    } else {
+                        emit(SetRange::OnlyA(i..i.wrapping_add(1)));
+                    }
+                    i = i.wrapping_add(1);
+                    last = MergeSide::A;
+                    n
+                }
+            };
+            streak = if n >= MIN_GALLOP { MIN_GALLOP } else { 1 };
+            tracker = K::Tracker::new();
+        } else {
+            let winner = match a[i].tree_cmp(&b[j], &mut tracker) {
+                Less => {
+                    emit(SetRange::OnlyA(i..i.wrapping_add(1)));
+                    i = i.wrapping_add(1);
+                    Some(MergeSide::A)
+                }
+                Equal => {
+                    emit(SetRange::Both(i, j));
+                    i = i.wrapping_add(1);
+                    j = j.wrapping_add(1);
+                    None
+                }
+                Greater => {
+                    emit(SetRange::OnlyB(j..j.wrapping_add(1)));
+                    j = j.wrapping_add(1);
+                    Some(MergeSide::B)
+                }
+            };
+            match winner {
+                Some(winner) if winner == last => streak = streak.wrapping_add(1),
+                Some(winner) => {
+                    last = winner;
+                    streak = 1;
+                    tracker = K::Tracker::new();
+                }
+                None => {
+                    // both heads changed
+                    streak = 0;
+                    tracker = K::Tracker::new();
+                }
+            }
+        }
+    }
+    if i < a.len() {
+        emit(SetRange::OnlyA(i..a.len()));
+    }
+    if j < b.len() {
+        emit(SetRange::OnlyB(j..b.len()));
+    }
+}
+
+/// Clones the sorted union of the sorted and deduplicated `a` and `b` into
+/// `out`. Keys in both inputs are cloned from `a`.
+#[cfg(feature = "alloc")]
+pub fn tree_union<K: TreeOrd + Clone>(a: &[K], b: &[K], out: &mut Vec<K>) {
+    tree_set_ranges(a, b, |range| match range {
+        SetRange::OnlyA(range) => out.extend_from_slice(&a[range]),
+        SetRange::OnlyB(range) => out.extend_from_slice(&b[range]),
+        SetRange::Both(i, _) => out.push(a[i].clone()),
+    })
+}
+
+/// Clones the sorted intersection of the sorted and deduplicated `a` and `b`
+/// into `out`. The keys are cloned from `a`.
+#[cfg(feature = "alloc")]
+pub fn tree_intersection<K: TreeOrd + Clone>(a: &[K], b: &[K], out: &mut Vec<K>) {
+    tree_set_ranges(a, b, |range| {
+        if let SetRange::Both(i, _) = range {
+            out.push(a[i].clone())
+        }
+    })
+}
+
+/// Clones the keys of the sorted and deduplicated `a` that are not in the
+/// sorted and deduplicated `b` into `out`
+#[cfg(feature = "alloc")]
+pub fn tree_difference<K: TreeOrd + Clone>(a: &[K], b: &[K], out: &mut Vec<K>) {
+    tree_set_ranges(a, b, |range| {
+        if let SetRange::OnlyA(range) = range {
+            out.extend_from_slice(&a[range])
+        }
+    })
+}
+
+/// An iterator that merges any number of sorted runs using a loser tree.
+/// Equal keys are output in the order of their runs, so the merge is stable
+/// with respect to the order of `runs`.
+///
+/// The replays up the loser tree use plain `Ord` comparisons. The candidates
+/// compared against a stored loser only ever come from below it until one of
+/// them displaces it, so a tracker there could never be bounded on both sides
+/// and would not be able to skip any prefix.
+///
+/// When the same run wins `MIN_GALLOP` times in a row, the best head among
+/// the other runs (the runner-up) is found, and the winning run is gallop
+/// searched against it with a single tracker, after which the keys of the
+/// winning run before the runner-up are output without any comparisons.
+#[cfg(feature = "alloc")]
+pub struct TreeKWayMerge<'a, K: TreeOrd> {
+    runs: Vec<&'a [K]>,
+    pos: Vec<usize>,
+    // `losers[0]` is the current winner and `losers[1..]` are the losers stored
+    // at the internal nodes. The leaf of run `r` is the node `runs.len() + r`.
+    losers: Vec<usize>,
+    // if the winner needs to be replayed up the tree
+    dirty: bool,
+    // the number of keys that can be output from the winner without replaying
+    free: usize,
+    streak: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K: TreeOrd> TreeKWayMerge<'a, K> {
+    pub fn new(runs: Vec<&'a [K]>) -> Self {
+        let k = runs.len();
+        let mut res = Self {
+            pos: vec![0; k],
+            losers: vec![usize::MAX; k],
+            runs,
+            dirty: false,
+            free: 0,
+            streak: 0,
+        };
+        for r in 0..k {
+            // the first run to arrive at a node waits there for the second
+            let mut cand = r;
+            let mut node = k.wrapping_add(r) / 2;
+            loop {
+                if node == 0 {
+                    res.losers[0] = cand;
+                    break
+                }
+                if res.losers[node] == usize::MAX {
+                    res.losers[node] = cand;
+                    break
+                }
+                if res.stored_beats(node, cand) {
+                    cand = res.replace_stored(node, cand);
+                }
+                node /= 2;
+            }
+        }
+        res
+    }
+
+    fn head(&self, r: usize) -> Option<&'a K> {
+        self.runs[r].get(self.pos[r])
+    }
+
+    /// Returns if the loser stored at `node` beats the candidate run `cand`
+    fn stored_beats(&self, node: usize, cand: usize) -> bool {
+        let stored = self.losers[node];
+        match (self.head(stored), self.head(cand)) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(s), Some(c)) => match c.cmp(s) {
+                Less => false,
+                Equal => stored < cand,
+                Greater => true,
+            },
+        }
+    }
+
+    /// Stores `cand` at `node` and returns the previously stored loser
+    fn replace_stored(&mut self, node: usize, cand: usize) -> usize {
+        core::mem::replace(&mut self.losers[node], cand)
+    }
+
+    /// Replays the current winner up the tree after its head changed
+    fn replay(&mut self) {
+        let k = self.runs.len();
+        let mut cand = self.losers[0];
+        let mut node = k.wrapping_add(cand) / 2;
+        while node != 0 {
+            if self.stored_beats(node, cand) {
+                cand = self.replace_stored(node, cand);
+            }
+            node /= 2;
+        }
+        self.losers[0] = cand;
+    }
+
+    /// Returns the number of keys starting at the head of the winning run that
+    /// come before the heads of all other runs
+    fn gallop(&mut self) -> usize {
+        let k = self.runs.len();
+        let w = self.losers[0];
+        // the best of the losers along the path of the winner is the runner-up
+        let mut runner: Option<usize> = None;
+        let mut node = k.wrapping_add(w) / 2;
+        while node != 0 {
+            let stored = self.losers[node];
+            if let Some(s) = self.head(stored) {
+                runner = match runner {
+                    Some(r) => match s.cmp(self.head(r).unwrap()) {
+                        Less => Some(stored),
+                        Equal if stored < r => Some(stored),
+                        _ => Some(r),
+                    },
+                    None => Some(stored),
+                }
+            }
+            node /= 2;
+        }
+        let rest = &self.runs[w][self.pos[w]..];
+        let runner = match runner {
+            Some(runner) => runner,
+            None => return rest.len(),
+        };
+        let runner_key = self.head(runner).unwrap();
+        let mut tracker = K::Tracker::new();
+        match tree_gallop_search_by(rest, 0, |x| match x.tree_cmp(runner_key, &mut tracker) {
+            Less => Less,
+            Equal if w < runner => Less,
+            _ => Greater,
+        }) {
+            Ok(n) | Err(n) => n,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K: TreeOrd> Iterator for TreeKWayMerge<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<&'a K> {
+        if self.runs.is_empty() {
+            return None
+        }
+        if self.free != 0 {
+            self.free -= 1;
+            let w = self.losers[0];
+            let key = self.head(w);
+            self.pos[w] = self.pos[w].wrapping_add(1);
+            return key
+        }
+        if self.dirty {
+            let prev = self.losers[0];
+            self.replay();
+            if self.losers[0] == prev {
+                self.streak = self.streak.wrapping_add(1);
+            } else {
+                self.streak = 1;
+            }
+        }
+        let w = self.losers[0];
+        let key = self.head(w)?;
+        self.pos[w] = self.pos[w].wrapping_add(1);
+        self.dirty = true;
+        if self.streak >= MIN_GALLOP {
+            self.free = self.gallop();
+            self.streak = 0;
+        }
+        Some(key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self
+            .runs
+            .iter()
+            .zip(self.pos.iter())
+            .fold(0usize, |acc, (run, pos)| acc.wrapping_add(run.len() - pos));
+        (len, Some(len))
+    }
+}
+
+/// Runs shorter than this are extended with binary insertion sort
+#[cfg(feature = "alloc")]
+const MIN_RUN: usize = 16;
+
+/// Rearranges `s` such that `s[k]` becomes the element previously at
+/// `s[perm[k]]`. `perm` is used as scratch space.
+#[cfg(feature = "alloc")]
+fn apply_permutation<K>(s: &mut [K], perm: &mut [usize]) {
+    for start in 0..perm.len() {
+        if perm[start] == usize::MAX {
+            continue
+        }
+        // follow the cycle starting at `start`, after each swap `s[cur]` is
+        // finished and the element from `start` moves to `src`
+        let mut cur = start;
+        loop {
+            let src = perm[cur];
+            perm[cur] = usize::MAX;
+            if src == start {
+                break
+            }
+            s.swap(cur, src);
+            cur = src;
+        }
+    }
+}
+
+/// A stable merge sort of `s` that uses [tree_merge_ranges] for the merge
+/// step, so that clustered keys get their shared prefixes skipped. Equal
+/// elements keep their relative order.
+///
+/// Natural nondescending runs are found first, and short runs are extended to
+/// a minimum length with a binary insertion sort that uses a tracker per
+/// insertion. Adjacent runs are then merged pairwise until the whole slice is
+/// sorted. The merges produce a permutation that is applied in place with
+/// swaps, so `K` does not need to be `Clone` and only a `Vec<usize>` scratch
+/// space is allocated.
+#[cfg(feature = "alloc")]
+pub fn tree_sort<K: TreeOrd>(s: &mut [K]) {
+    let len = s.len();
+    if len < 2 {
+        return
+    }
+    // find the run boundaries
+    let mut bounds = vec![0usize];
+    let mut start = 0;
+    while start < len {
+        let mut end = start.wrapping_add(1);
+        while (end < len) && (s[end - 1] <= s[end]) {
+            end = end.wrapping_add(1);
+        }
+        if (end.wrapping_sub(start) < MIN_RUN) && (end < len) {
+            let min_end = min(start.wrapping_add(MIN_RUN), len);
+            for i in end..min_end {
+                let mut tracker = K::Tracker::new();
+                let pos = match tree_binary_search_by(&s[start..i], |x| {
+                    // an upper bound keeps the sort stable
+                    match x.tree_cmp(&s[i], &mut tracker) {
+                        Less | Equal => Less,
+                        Greater => Greater,
+                    }
+                }) {
+                    Ok(pos) | Err(pos) => pos,
+                };
+                s[start.wrapping_add(pos)..=i].rotate_right(1);
+            }
+            end = min_end;
+        }
+        bounds.push(end);
+        start = end;
+    }
+    let mut perm: Vec<usize> = Vec::with_capacity(len);
+    while bounds.len() > 2 {
+        let mut next_bounds = vec![0usize];
+        let mut r = 0usize;
+        while r.wrapping_add(2) < bounds.len() {
+            let (lo, mid, hi) = (bounds[r], bounds[r + 1], bounds[r + 2]);
+            perm.clear();
+            tree_merge_ranges(&s[lo..mid], &s[mid..hi], |side, range| {
+                let offset = match side {
+                    MergeSide::A => 0,
+                    MergeSide::B => mid - lo,
+                };
+                perm.extend(range.map(|i| i.wrapping_add(offset)));
+            });
+            apply_permutation(&mut s[lo..hi], &mut perm);
+            next_bounds.push(hi);
+            r = r.wrapping_add(2);
+        }
+        if r.wrapping_add(1) < bounds.len() {
+            // odd run out
+            next_bounds.push(bounds[r + 1]);
+        }
+        bounds = next_bounds;
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn tree_dedup_core<T, Tr, F>(v: &mut Vec<T>, mut cmp: F)
+where
+    Tr: Tracker,
+    F: FnMut(&T, &T, &mut Tr) -> Ordering,
+{
+    let len = v.len();
+    let mut kept = 0usize;
+    // the start of the current group of equal elements
+    let mut start = 0;
+    let mut tracker = Tr::new();
+    while start < len {
+        // the first element of the group is the query, the `Equal` results of
+        // the linear scan leave the tracker unchanged
+        tracker.reset();
+        let mut end = start.wrapping_add(1);
+        let mut streak = 0usize;
+        while end < len {
+            if streak >= MIN_GALLOP {
+                let (head, rest) = v.split_at(end);
+                let first = &head[start];
+                if !Tr::IS_NOOP && (kept > 0) {
+                    // the kept element of the previous group is less than
+                    // `first` and every element of the group, which bounds
+                    // the tracker from below
+                    cmp(&head[kept.wrapping_sub(1)], first, &mut tracker);
+                }
+                let n =
+                    match tree_gallop_search_by(rest, 0, |x| match cmp(x, first, &mut tracker) {
+                        Equal => Less,
+                        Less | Greater => Greater,
+                    }) {
+                        Ok(n) | Err(n) => n,
+                    };
+                end = end.wrapping_add(n);
+                break
+            }
+            if cmp(&v[end], &v[start], &mut tracker) == Equal {
+                end = end.wrapping_add(1);
+                streak = streak.wrapping_add(1);
+            } else {
+                break
+            }
+        }
+        // `kept <= start`, and everything in `kept..start` is a removed element
+        v.swap(kept, start);
+        kept = kept.wrapping_add(1);
+        start = end;
+    }
+    v.truncate(kept);
+}
+
+/// Removes consecutive equal elements of the sorted `v`, keeping the first
+/// element of each group like [Vec::dedup].
+///
+/// The first element of a group is compared with the following elements, and
+/// once a group is found to be long, the end of the group is found by
+/// galloping (see [crate::search::tree_gallop_search_by]). The gallop uses a
+/// tracker bounded by the kept element of the previous group and the first
+/// greater element it finds, so its binary search skips the prefix shared by
+/// both groups and the next one. Because of this, the result is only the same
+/// as [Vec::dedup] if `v` is sorted.
+#[cfg(feature = "alloc")]
+pub fn tree_dedup<K: TreeOrd>(v: &mut Vec<K>) {
+    tree_dedup_core(v, |x: &K, y: &K, tracker| x.tree_cmp(y, tracker))
+}
+
+/// Like [tree_dedup] except that elements are compared by the keys that
+/// `key` returns, like [Vec::dedup_by_key]. `v` should be sorted by the keys.
+/// `key` may be called any number of times for any element.
+#[cfg(feature = "alloc")]
+pub fn tree_dedup_by_key<T, K, F>(v: &mut Vec<T>, mut key: F)
+where
+    K: TreeOrd,
+    F: FnMut(&T) -> K,
+{
+    tree_dedup_core(v, |x: &T, y: &T, tracker| key(x).tree_cmp(&key(y), tracker))
+}
@@ -0,0 +1,270 @@
+//! Streaming NFC normalization, using the tables of Unicode 14.0 in `tables`
+//! which are shared with `accents`
+
+pub(crate) mod tables;
+
+use core::{cmp::Ordering, str::Chars};
+
+use tables::{CCC, COMPOSITIONS, DECOMPOSITIONS};
+
+const HANGUL_S_BASE: u32 = 0xac00;
+const HANGUL_L_BASE: u32 = 0x1100;
+const HANGUL_V_BASE: u32 = 0x1161;
+const HANGUL_T_BASE: u32 = 0x11a7;
+const HANGUL_L_COUNT: u32 = 19;
+const HANGUL_V_COUNT: u32 = 21;
+const HANGUL_T_COUNT: u32 = 28;
+const HANGUL_N_COUNT: u32 = 588;
+const HANGUL_S_COUNT: u32 = 11172;
+
+/// The maximum number of `char`s in a segment of a starter and the non-starters
+/// that follow it. This is enough for the Stream-Safe Text Format of UAX #15,
+/// which allows 30 non-starters in a row, longer runs are normalized in pieces.
+const SEGMENT_LEN: usize = 32;
+
+/// Returns the canonical combining class of `c`
+#[inline]
+fn ccc(c: char) -> u8 {
+    if c.is_ascii() {
+        return 0
+    }
+    match CCC.binary_search_by(|(lo, hi, _)| {
+        if *hi < c {
+            Ordering::Less
+        } else if *lo > c {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }) {
+        Ok(i) => CCC[i].2,
+        Err(_) => 0,
+    }
+}
+
+/// Returns the canonical decomposition of the Hangul syllable `c` into two or
+/// three jamo, Hangul syllables decompose algorithmically
+#[inline]
+pub(crate) fn decompose_hangul(c: char) -> Option<impl Iterator<Item = char>> {
+    let s_index = u32::from(c).wrapping_sub(HANGUL_S_BASE);
+    if s_index >= HANGUL_S_COUNT {
+        return None
+    }
+    let l = HANGUL_L_BASE + (s_index / HANGUL_N_COUNT);
+    let v = HANGUL_V_BASE + ((s_index % HANGUL_N_COUNT) / HANGUL_T_COUNT);
+    let t = s_index % HANGUL_T_COUNT;
+    let t = (t != 0).then(|| HANGUL_T_BASE + t);
+    let jamo = [Some(l), Some(v), t].into_iter().flatten();
+    Some(jamo.map(|c| char::from_u32(c).unwrap()))
+}
+
+/// Returns the full canonical decomposition of `c` if it is not a Hangul
+/// syllable and decomposes
+#[inline]
+pub(crate) fn decomposition(c: char) -> Option<&'static str> {
+    match DECOMPOSITIONS.binary_search_by_key(&c, |(c, _)| *c) {
+        Ok(i) => Some(DECOMPOSITIONS[i].1),
+        Err(_) => None,
+    }
+}
+
+/// Returns the primary composite of `a` and `b` if there is one
+#[inline]
+fn compose(a: char, b: char) -> Option<char> {
+    let (a_u, b_u) = (u32::from(a), u32::from(b));
+    let l_index = a_u.wrapping_sub(HANGUL_L_BASE);
+    let v_index = b_u.wrapping_sub(HANGUL_V_BASE);
+    if (l_index < HANGUL_L_COUNT) && (v_index < HANGUL_V_COUNT) {
+        let s = HANGUL_S_BASE + ((l_index * HANGUL_N_COUNT) + (v_index * HANGUL_T_COUNT));
+        return char::from_u32(s)
+    }
+    let s_index = a_u.wrapping_sub(HANGUL_S_BASE);
+    let t_index = b_u.wrapping_sub(HANGUL_T_BASE);
+    if (s_index < HANGUL_S_COUNT)
+        && ((s_index % HANGUL_T_COUNT) == 0)
+        && (t_index > 0)
+        && (t_index < HANGUL_T_COUNT)
+    {
+        return char::from_u32(a_u + t_index)
+    }
+    match COMPOSITIONS.binary_search_by(|(x, y, _)| (*x, *y).cmp(&(a, b))) {
+        Ok(i) => Some(COMPOSITIONS[i].2),
+        Err(_) => None,
+    }
+}
+
+/// An iterator over the NFC normalized `char`s of a `str`, which normalizes on
+/// the fly without allocating
+#[derive(Debug, Clone)]
+pub(crate) struct NfcChars<'a> {
+    chars: Chars<'a>,
+    /// The remaining `char`s of a canonical decomposition
+    decomposed: [char; 4],
+    decomposed_start: u8,
+    decomposed_end: u8,
+    /// A decomposed `char` that starts the next segment
+    peeked: Option<char>,
+    /// The normalized segment
+    segment: [char; SEGMENT_LEN],
+    segment_start: u8,
+    segment_end: u8,
+}
+
+impl<'a> NfcChars<'a> {
+    #[inline]
+    pub(crate) fn new(s: &'a str) -> Self {
+        Self {
+            chars: s.chars(),
+            decomposed: ['\0'; 4],
+            decomposed_start: 0,
+            decomposed_end: 0,
+            peeked: None,
+            segment: ['\0'; SEGMENT_LEN],
+            segment_start: 0,
+            segment_end: 0,
+        }
+    }
+
+    /// Skips `n` normalized `char`s. Runs of ASCII are skipped without
+    /// normalizing, except for a last ASCII `char` that a following
+    /// non-ASCII `char` could compose with.
+    #[inline]
+    pub(crate) fn skip_normalized(&mut self, mut n: usize) {
+        while n > 0 {
+            if (self.segment_start == self.segment_end)
+                && (self.decomposed_start == self.decomposed_end)
+                && self.peeked.is_none()
+            {
+                let rest = self.chars.as_str();
+                let mut ascii = rest.bytes().take_while(|b| b.is_ascii()).count();
+                if ascii < rest.len() {
+                    ascii = ascii.saturating_sub(1);
+                }
+                let ascii = ascii.min(n);
+                if ascii > 0 {
+                    self.chars = rest[ascii..].chars();
+                    n = n.wrapping_sub(ascii);
+                    continue
+                }
+            }
+            if self.next().is_none() {
+                break
+            }
+            n = n.wrapping_sub(1);
+        }
+    }
+
+    /// Returns the next `char` of the canonical decomposition
+    #[inline]
+    fn next_decomposed(&mut self) -> Option<char> {
+        if let Some(c) = self.peeked.take() {
+            return Some(c)
+        }
+        if self.decomposed_start < self.decomposed_end {
+            let c = self.decomposed[usize::from(self.decomposed_start)];
+            self.decomposed_start = self.decomposed_start.wrapping_add(1);
+            return Some(c)
+        }
+        let c = self.chars.next()?;
+        if c.is_ascii() {
+            return Some(c)
+        }
+        self.decomposed_start = 0;
+        self.decomposed_end = 0;
+        if let Some(jamo) = decompose_hangul(c) {
+            for c in jamo {
+                self.decomposed[usize::from(self.decomposed_end)] = c;
+                self.decomposed_end = self.decomposed_end.wrapping_add(1);
+            }
+        } else if let Some(decomposition) = decomposition(c) {
+            for c in decomposition.chars() {
+                self.decomposed[usize::from(self.decomposed_end)] = c;
+                self.decomposed_end = self.decomposed_end.wrapping_add(1);
+            }
+        } else {
+            return Some(c)
+        }
+        self.next_decomposed()
+    }
+
+    /// Sorts the non-starters of the segment by combining class and then
+    /// composes them with the starter where possible, returns the new length
+    fn compose_segment(&mut self, len: usize) -> usize {
+        let segment = &mut self.segment[..len];
+        // stable insertion sort, the starter has class 0 and stays in front
+        for i in 1..segment.len() {
+            let mut j = i;
+            while (j > 0) && (ccc(segment[j.wrapping_sub(1)]) > ccc(segment[j])) {
+                segment.swap(j.wrapping_sub(1), j);
+                j = j.wrapping_sub(1);
+            }
+        }
+        if ccc(segment[0]) != 0 {
+            return len
+        }
+        let mut end = 1;
+        // the class of the last `char` left between the starter and the next
+        let mut last_ccc = None;
+        for i in 1..segment.len() {
+            let c = segment[i];
+            let c_ccc = ccc(c);
+            let blocked = match last_ccc {
+                None => false,
+                Some(last_ccc) => last_ccc >= c_ccc,
+            };
+            if !blocked {
+                if let Some(composed) = compose(segment[0], c) {
+                    segment[0] = composed;
+                    continue
+                }
+            }
+            last_ccc = Some(c_ccc);
+            segment[end] = c;
+            end = end.wrapping_add(1);
+        }
+        end
+    }
+}
+
+impl Iterator for NfcChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.segment_start < self.segment_end {
+            let c = self.segment[usize::from(self.segment_start)];
+            self.segment_start = self.segment_start.wrapping_add(1);
+            return Some(c)
+        }
+        let first = self.next_decomposed()?;
+        self.segment[0] = first;
+        let mut len = 1;
+        while let Some(c) = self.next_decomposed() {
+            if ccc(c) != 0 {
+                if len == SEGMENT_LEN {
+                    self.peeked = Some(c);
+                    break
+                }
+                self.segment[len] = c;
+                len = len.wrapping_add(1);
+                continue
+            }
+            // a starter can only compose with a directly preceding starter,
+            // such as Hangul jamo
+            len = self.compose_segment(len);
+            if len == 1 {
+                if let Some(composed) = compose(self.segment[0], c) {
+                    self.segment[0] = composed;
+                    continue
+                }
+            }
+            self.peeked = Some(c);
+            self.segment_start = 1;
+            self.segment_end = len as u8;
+            return Some(self.segment[0])
+        }
+        len = self.compose_segment(len);
+        self.segment_start = 1;
+        self.segment_end = len as u8;
+        Some(self.segment[0])
+    }
+}
@@ -0,0 +1,34 @@
+//! Parallel batch searches using `rayon`
+
+use rayon::prelude::*;
+
+use crate::{search::tree_binary_search, TreeOrd};
+
+/// Batches with fewer queries than this are searched sequentially
+const PAR_THRESHOLD: usize = 1024;
+
+/// The minimum number of queries given to a single worker
+const MIN_CHUNK: usize = 256;
+
+/// Binary searches the sorted `space` for each of the `queries`, returning the
+/// same as calling [tree_binary_search] on each query in order. The queries are
+/// split across `rayon` worker threads, and every query gets its own tracker.
+/// Small batches are searched on the current thread.
+pub fn par_tree_binary_search_batch<K, Q>(space: &[K], queries: &[Q]) -> Vec<Result<usize, usize>>
+where
+    K: TreeOrd<Q> + Sync,
+    Q: Sync,
+{
+    if queries.len() < PAR_THRESHOLD {
+        queries
+            .iter()
+            .map(|query| tree_binary_search(space, query))
+            .collect()
+    } else {
+        queries
+            .par_iter()
+            .with_min_len(MIN_CHUNK)
+            .map(|query| tree_binary_search(space, query))
+            .collect()
+    }
+}
@@ -0,0 +1,103 @@
+//! Reuse of trackers across many searches
+
+use alloc::vec::Vec;
+use core::{
+    cell::{Cell, RefCell},
+    mem,
+    ops::{Deref, DerefMut},
+};
+
+use crate::Tracker;
+
+/// A free list of trackers for hot search loops, so that trackers are only
+/// constructed once and then reused. Any number of trackers can be acquired at
+/// the same time, and each one is reset and returned to the free list when its
+/// guard is dropped.
+#[derive(Debug, Clone)]
+pub struct TrackerPool<T: Tracker> {
+    free: RefCell<Vec<T>>,
+    created: Cell<usize>,
+    acquired: Cell<usize>,
+}
+
+impl<T: Tracker> Default for TrackerPool<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Tracker> TrackerPool<T> {
+    /// Creates an empty pool, trackers are constructed by `acquire` whenever
+    /// the free list is empty
+    pub const fn new() -> Self {
+        Self {
+            free: RefCell::new(Vec::new()),
+            created: Cell::new(0),
+            acquired: Cell::new(0),
+        }
+    }
+
+    /// Returns a tracker in the state of `T::new()`, which is reset and goes
+    /// back to the pool when the returned guard is dropped
+    #[inline]
+    pub fn acquire(&self) -> PooledTracker<'_, T> {
+        self.acquired.set(self.acquired.get().wrapping_add(1));
+        let tracker = match self.free.borrow_mut().pop() {
+            Some(tracker) => tracker,
+            None => {
+                self.created.set(self.created.get().wrapping_add(1));
+                T::new()
+            }
+        };
+        PooledTracker {
+            pool: self,
+            tracker,
+        }
+    }
+
+    /// Returns the number of trackers that have been constructed by the pool
+    pub fn created(&self) -> usize {
+        self.created.get()
+    }
+
+    /// Returns the number of times `acquire` has been called
+    pub fn acquired(&self) -> usize {
+        self.acquired.get()
+    }
+
+    /// Returns the number of trackers in the free list
+    pub fn available(&self) -> usize {
+        self.free.borrow().len()
+    }
+}
+
+/// A tracker acquired from a [TrackerPool], which dereferences to the tracker
+#[derive(Debug)]
+pub struct PooledTracker<'a, T: Tracker> {
+    pool: &'a TrackerPool<T>,
+    tracker: T,
+}
+
+impl<T: Tracker> Deref for PooledTracker<'_, T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        &self.tracker
+    }
+}
+
+impl<T: Tracker> DerefMut for PooledTracker<'_, T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.tracker
+    }
+}
+
+impl<T: Tracker> Drop for PooledTracker<'_, T> {
+    fn drop(&mut self) {
+        let mut tracker = mem::replace(&mut self.tracker, T::NEW);
+        tracker.reset();
+        self.pool.free.borrow_mut().push(tracker);
+    }
+}
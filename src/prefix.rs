@@ -0,0 +1,46 @@
+//! Longest common prefix utilities
+
+use core::cmp::min;
+
+use crate::TreeOrdBytes;
+
+/// Returns the length of the longest common prefix of `a` and `b`
+pub fn common_prefix_len<T: Eq>(a: &[T], b: &[T]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// The same as [common_prefix_len] but for bytes, comparing in chunks like
+/// [TreeOrdBytes] does
+pub fn bytes_common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    const CHUNK_LEN: usize = TreeOrdBytes::CHUNK_LEN;
+    let len = min(a.len(), b.len());
+    let mut start: usize = 0;
+    while start.wrapping_add(CHUNK_LEN) <= len {
+        let end = start.wrapping_add(CHUNK_LEN);
+        if a[start..end] != b[start..end] {
+            break
+        }
+        start = end;
+    }
+    start.wrapping_add(common_prefix_len(&a[start..len], &b[start..len]))
+}
+
+/// Returns the length of the longest prefix common to all the keys in
+/// `sorted`, which must be sorted lexicographically. Only the first and last
+/// keys need to be compared, because any prefix shared by the first and last
+/// keys is shared by every key between them. Returns 0 if `sorted` is empty.
+pub fn range_common_prefix_len<T: Eq, S: AsRef<[T]>>(sorted: &[S]) -> usize {
+    match (sorted.first(), sorted.last()) {
+        (Some(first), Some(last)) => common_prefix_len(first.as_ref(), last.as_ref()),
+        _ => 0,
+    }
+}
+
+/// The same as [range_common_prefix_len] but using
+/// [bytes_common_prefix_len]
+pub fn bytes_range_common_prefix_len<S: AsRef<[u8]>>(sorted: &[S]) -> usize {
+    match (sorted.first(), sorted.last()) {
+        (Some(first), Some(last)) => bytes_common_prefix_len(first.as_ref(), last.as_ref()),
+        _ => 0,
+    }
+}
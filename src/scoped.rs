@@ -0,0 +1,79 @@
+//! One-line searches that keep their trackers in a thread local, for quick
+//! scripts and tests that do not want to construct trackers themselves
+
+use core::any::{Any, TypeId};
+use std::{cell::RefCell, collections::HashMap};
+
+use crate::{search, Tracker, TreeOrd};
+
+/// The maximum number of tracker types kept at once, the map is cleared if it
+/// would exceed this
+const MAX_TRACKERS: usize = 64;
+
+thread_local! {
+    static TRACKERS: RefCell<HashMap<TypeId, Box<dyn Any>>> = RefCell::new(HashMap::new());
+}
+
+/// Calls `f` with the thread local tracker of type `T`, which is reset to the
+/// state of `T::new()` beforehand. The tracker is taken out of the thread local
+/// for the duration of `f`, so nested calls (even with the same tracker type)
+/// work and simply get their own tracker.
+pub fn with_tracker<T: Tracker + 'static, R, F: FnOnce(&mut T) -> R>(f: F) -> R {
+    let id = TypeId::of::<T>();
+    let mut tracker: Box<T> = match TRACKERS.with(|m| m.borrow_mut().remove(&id)) {
+        Some(tracker) => {
+            let mut tracker = tracker.downcast::<T>().unwrap();
+            *tracker = T::new();
+            tracker
+        }
+        None => Box::new(T::new()),
+    };
+    let res = f(&mut tracker);
+    TRACKERS.with(|m| {
+        let mut m = m.borrow_mut();
+        if m.len() >= MAX_TRACKERS {
+            m.clear();
+        }
+        m.insert(id, tracker);
+    });
+    res
+}
+
+/// Binary searches the sorted `space` for `query`. Returns the same as
+/// [slice::binary_search].
+pub fn tree_binary_search<K, Q>(space: &[K], query: &Q) -> Result<usize, usize>
+where
+    K: TreeOrd<Q>,
+    K::Tracker: 'static,
+    Q: ?Sized,
+{
+    with_tracker(|tracker: &mut K::Tracker| {
+        search::tree_binary_search_by(space, |k| k.tree_cmp(query, tracker))
+    })
+}
+
+/// Returns the index of the first key in the sorted `space` that is not less
+/// than `query`
+pub fn tree_lower_bound<K, Q>(space: &[K], query: &Q) -> usize
+where
+    K: TreeOrd<Q>,
+    K::Tracker: 'static,
+    Q: ?Sized,
+{
+    with_tracker(|tracker: &mut K::Tracker| {
+        space.partition_point(|k| k.tree_cmp(query, tracker).is_lt())
+    })
+}
+
+/// Returns the index of the first key in the sorted `space` that is greater
+/// than `query`
+pub fn tree_upper_bound<K, Q>(space: &[K], query: &Q) -> usize
+where
+    K: TreeOrd<Q>,
+    K::Tracker: 'static,
+    Q: ?Sized,
+{
+    with_tracker(|tracker: &mut K::Tracker| {
+        space.partition_point(|k| k.tree_cmp(query, tracker).is_le())
+    })
+}
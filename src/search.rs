@@ -0,0 +1,1036 @@
+//! Search helpers that handle the `Tracker` internally
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+use core::{
+    cmp::{min, Ordering},
+    fmt,
+    ops::{Bound, Range, RangeBounds},
+    slice,
+};
+
+use Ordering::*;
+
+use crate::{PartialTreeOrd, Tracker, TreeOrd, TreeOrdBounded, TreeOrdBytes};
+
+/// Returns a comparator closure that can be passed to
+/// [slice::binary_search_by], [slice::partition_point] (with a `== Less`
+/// mapping), or any other function that takes a `FnMut(&T) -> Ordering`
+/// comparator that is called during a single search.
+///
+/// The closure owns a fresh tracker, so a new closure should be created for
+/// every search. Note that comparators like the one `binary_search_by` takes
+/// are called with the element of the space as the left hand side and expect
+/// the ordering of that element relative to the target, so the closure calls
+/// `element.tree_cmp(query, ..)` and not `query.tree_cmp(element, ..)`.
+///
+/// ```
+/// use tree_ord::search::tree_search_fn;
+///
+/// let space = [[1u8, 2, 3], [1, 2, 4], [1, 3, 0], [2, 0, 0]];
+/// assert_eq!(space.binary_search_by(tree_search_fn(&[1, 3, 0])), Ok(2));
+/// assert_eq!(space.binary_search_by(tree_search_fn(&[1, 2, 5])), Err(2));
+/// ```
+pub fn tree_search_fn<'q, Q, T>(query: &'q Q) -> impl FnMut(&T) -> Ordering + 'q
+where
+    Q: ?Sized,
+    T: TreeOrd<Q> + ?Sized + 'q,
+{
+    let mut tracker = <T as TreeOrd<Q>>::Tracker::new();
+    move |lhs: &T| lhs.tree_cmp(query, &mut tracker)
+}
+
+/// Sorted storage that can be searched by the functions in this module. This
+/// allows searching over keys that are not in a single contiguous slice.
+pub trait TreeIndexable {
+    type Key: ?Sized;
+
+    /// Returns the number of keys
+    fn len(&self) -> usize;
+
+    /// Returns the key at index `i`
+    ///
+    /// # Panics
+    ///
+    /// If `i >= self.len()`
+    fn get(&self, i: usize) -> &Self::Key;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K> TreeIndexable for [K] {
+    type Key = K;
+
+    #[inline]
+    fn len(&self) -> usize {
+        <[K]>::len(self)
+    }
+
+    #[inline]
+    fn get(&self, i: usize) -> &K {
+        &self[i]
+    }
+}
+
+impl<K, const N: usize> TreeIndexable for [K; N] {
+    type Key = K;
+
+    #[inline]
+    fn len(&self) -> usize {
+        N
+    }
+
+    #[inline]
+    fn get(&self, i: usize) -> &K {
+        &self[i]
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K> TreeIndexable for Vec<K> {
+    type Key = K;
+
+    #[inline]
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    #[inline]
+    fn get(&self, i: usize) -> &K {
+        &self[i]
+    }
+}
+
+/// A [TreeIndexable] adapter over a sequence of sorted segments, such that the
+/// concatenation of the segments is sorted. Translating an index takes
+/// `O(log segments)` time.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct Segmented<'a, K> {
+    segments: &'a [&'a [K]],
+    // the cumulative end index of each segment
+    ends: Vec<usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K> Segmented<'a, K> {
+    pub fn new(segments: &'a [&'a [K]]) -> Self {
+        let mut ends = Vec::with_capacity(segments.len());
+        let mut end = 0usize;
+        for segment in segments {
+            end = end.checked_add(segment.len()).unwrap();
+            ends.push(end);
+        }
+        Self { segments, ends }
+    }
+
+    pub fn segments(&self) -> &'a [&'a [K]] {
+        self.segments
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, K> TreeIndexable for Segmented<'a, K> {
+    type Key = K;
+
+    fn len(&self) -> usize {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    fn get(&self, i: usize) -> &K {
+        // empty segments are skipped over because their end equals the end of
+        // the previous segment
+        let j = self.ends.partition_point(|end| *end <= i);
+        let start = if j == 0 { 0 } else { self.ends[j - 1] };
+        &self.segments[j][i - start]
+    }
+}
+
+/// A [TreeIndexable] adapter that treats `buf` as a sequence of fixed width
+/// records of `stride` bytes each. A trailing partial record (if `buf.len()` is
+/// not a multiple of `stride`) is ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedStride<'a> {
+    buf: &'a [u8],
+    stride: usize,
+}
+
+impl<'a> FixedStride<'a> {
+    /// # Panics
+    ///
+    /// If `stride == 0`
+    pub fn new(buf: &'a [u8], stride: usize) -> Self {
+        assert!(stride != 0, "`FixedStride` requires a nonzero stride");
+        Self { buf, stride }
+    }
+
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    /// Returns the bytes of the trailing partial record, which is empty if
+    /// there is no partial record
+    pub fn remainder(&self) -> &'a [u8] {
+        &self.buf[(self.buf.len() - (self.buf.len() % self.stride))..]
+    }
+}
+
+impl<'a> TreeIndexable for FixedStride<'a> {
+    type Key = [u8];
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.buf.len() / self.stride
+    }
+
+    #[inline]
+    fn get(&self, i: usize) -> &[u8] {
+        let start = i.checked_mul(self.stride).unwrap();
+        &self.buf[start..(start + self.stride)]
+    }
+}
+
+/// Binary searches `[left, right)` with the probing order documented on
+/// [tree_binary_search_by]
+fn binary_search_range<S, F>(space: &S, left: usize, right: usize, mut f: F) -> Result<usize, usize>
+where
+    S: TreeIndexable + ?Sized,
+    F: FnMut(&S::Key) -> Ordering,
+{
+    binary_search_indices(left, right, |i| f(space.get(i)))
+}
+
+/// The same as `binary_search_range` except that `f` is given the index to
+/// probe
+fn binary_search_indices<F>(mut left: usize, mut right: usize, mut f: F) -> Result<usize, usize>
+where
+    F: FnMut(usize) -> Ordering,
+{
+    let mut size = right.wrapping_sub(left);
+    while left < right {
+        let mid = left.wrapping_add(size / 2);
+        let cmp = f(mid);
+        left = if cmp == Less {
+            mid.wrapping_add(1)
+        } else {
+            left
+        };
+        right = if cmp == Greater { mid } else { right };
+        if cmp == Equal {
+            return Ok(mid)
+        }
+        size = right.wrapping_sub(left);
+    }
+    Err(left)
+}
+
+/// Returns the first index in `[left, right)` for which `pred` returns false
+fn partition_point_range<S, P>(space: &S, mut left: usize, mut right: usize, mut pred: P) -> usize
+where
+    S: TreeIndexable + ?Sized,
+    P: FnMut(&S::Key) -> bool,
+{
+    while left < right {
+        let mid = left.wrapping_add(right.wrapping_sub(left) / 2);
+        if pred(space.get(mid)) {
+            left = mid.wrapping_add(1);
+        } else {
+            right = mid;
+        }
+    }
+    left
+}
+
+/// The same as [slice::binary_search_by], except that the exact sequence of
+/// comparisons is fixed by this crate. The standard library is free to change
+/// its probing order between versions, which matters when counting comparisons
+/// or when reproducing a tracker's behavior.
+///
+/// `f` should return the ordering of the probed element relative to the
+/// target. If there are multiple matches, any one of them may be returned.
+pub fn tree_binary_search_by<S, F>(space: &S, f: F) -> Result<usize, usize>
+where
+    S: TreeIndexable + ?Sized,
+    F: FnMut(&S::Key) -> Ordering,
+{
+    binary_search_range(space, 0, space.len(), f)
+}
+
+/// Binary searches the sorted `space` for `query`, using a single tracker for
+/// the whole search. Returns the same as [slice::binary_search].
+pub fn tree_binary_search<S, Q>(space: &S, query: &Q) -> Result<usize, usize>
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    tree_binary_search_by(space, tree_search_fn(query))
+}
+
+/// The error of the `partial_tree_binary_search` functions, returned when a
+/// key was unordered with the query
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Unordered {
+    /// The index of the unordered key
+    pub index: usize,
+}
+
+impl fmt::Display for Unordered {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the key at index {} is unordered with the query",
+            self.index
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Unordered {}
+
+/// The same as [tree_binary_search_by] with the same probing order, except
+/// that `f` returns `None` for unordered keys, which stops the search with an
+/// [Unordered] error
+pub fn partial_tree_binary_search_by<S, F>(
+    space: &S,
+    mut f: F,
+) -> Result<Result<usize, usize>, Unordered>
+where
+    S: TreeIndexable + ?Sized,
+    F: FnMut(&S::Key) -> Option<Ordering>,
+{
+    let mut left = 0;
+    let mut right = space.len();
+    let mut size = right;
+    while left < right {
+        let mid = left.wrapping_add(size / 2);
+        let Some(cmp) = f(space.get(mid)) else {
+            return Err(Unordered { index: mid })
+        };
+        left = if cmp == Less {
+            mid.wrapping_add(1)
+        } else {
+            left
+        };
+        right = if cmp == Greater { mid } else { right };
+        if cmp == Equal {
+            return Ok(Ok(mid))
+        }
+        size = right.wrapping_sub(left);
+    }
+    Ok(Err(left))
+}
+
+/// Binary searches the sorted `space` for `query` with
+/// [PartialTreeOrd::partial_tree_cmp], using a single tracker for the whole
+/// search. Returns an [Unordered] error for the first probed key that is
+/// unordered with `query`, or else the same as [slice::binary_search].
+///
+/// ```
+/// use tree_ord::search::{partial_tree_binary_search, Unordered};
+///
+/// let space = [0.5f64, 1.0, 2.5, 4.0];
+/// assert_eq!(partial_tree_binary_search(&space[..], &2.5), Ok(Ok(2)));
+/// assert_eq!(partial_tree_binary_search(&space[..], &3.0), Ok(Err(3)));
+/// assert_eq!(
+///     partial_tree_binary_search(&space[..], &f64::NAN),
+///     Err(Unordered { index: 2 })
+/// );
+/// ```
+pub fn partial_tree_binary_search<S, Q>(
+    space: &S,
+    query: &Q,
+) -> Result<Result<usize, usize>, Unordered>
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: PartialTreeOrd<Q>,
+    Q: ?Sized,
+{
+    let mut tracker = <S::Key as PartialTreeOrd<Q>>::Tracker::new();
+    partial_tree_binary_search_by(space, |k| k.partial_tree_cmp(query, &mut tracker))
+}
+
+/// Returns the index of the first key in the sorted `space` that is not less
+/// than `query`, using a single tracker for the whole search
+pub fn tree_lower_bound<S, Q>(space: &S, query: &Q) -> usize
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    let mut f = tree_search_fn(query);
+    partition_point_range(space, 0, space.len(), |k| f(k) == Less)
+}
+
+/// The same as [tree_lower_bound], except that it uses
+/// [TreeOrd::tree_cmp_hereditary]. A lower bound search only continues towards
+/// smaller keys after an `Equal`, so keys in a run of equal keys can skip the
+/// prefix they share with the keys below the run.
+pub fn tree_lower_bound_hereditary<S, Q>(space: &S, query: &Q) -> usize
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    let mut tracker = <S::Key as TreeOrd<Q>>::Tracker::new();
+    partition_point_range(space, 0, space.len(), |k| {
+        k.tree_cmp_hereditary(query, &mut tracker) == Less
+    })
+}
+
+/// Returns the index of the first key in the sorted `space` that is greater
+/// than `query`, using a single tracker for the whole search
+pub fn tree_upper_bound<S, Q>(space: &S, query: &Q) -> usize
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    let mut f = tree_search_fn(query);
+    partition_point_range(space, 0, space.len(), |k| f(k) != Greater)
+}
+
+/// Returns the range of indices of keys in the sorted `space` that are equal
+/// to `query`, using a single tracker for the whole search.
+///
+/// The lower bound is found first while remembering the leftmost key that was
+/// found to be greater than the query. Every key between the lower bound and
+/// that key is within the bounds of the tracker, so the upper bound search
+/// continues with the same tracker instead of starting over. This means that
+/// long runs of equal keys only compare the part of the key that is not
+/// shared with the keys around the run.
+pub fn tree_equal_range<S, Q>(space: &S, query: &Q) -> Range<usize>
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    let mut tracker = <S::Key as TreeOrd<Q>>::Tracker::new();
+    // the leftmost key seen so far that compared `Greater`, which bounds the
+    // upper bound
+    let mut greater = space.len();
+    let mut left = 0;
+    let mut right = space.len();
+    while left < right {
+        let mid = left.wrapping_add(right.wrapping_sub(left) / 2);
+        match space.get(mid).tree_cmp(query, &mut tracker) {
+            Less => left = mid.wrapping_add(1),
+            Equal => right = mid,
+            Greater => {
+                right = mid;
+                greater = mid;
+            }
+        }
+    }
+    let lower = left;
+    let upper = partition_point_range(space, lower, greater, |k| {
+        k.tree_cmp(query, &mut tracker) != Greater
+    });
+    lower..upper
+}
+
+/// Returns a tracker for `query` that has already been used to compare
+/// `min_key` and `max_key` (the minimum and maximum keys of some structure)
+/// against the `query`, along with the orderings of those comparisons. If the
+/// query is strictly between the bounds, the tracker then carries the prefix
+/// common to every key in the structure, which a search that only starts with
+/// the root key cannot learn early if the queries are biased towards one edge.
+///
+/// The orderings are of the keys relative to the query, as in the comparators
+/// of [tree_binary_search_by]. If `min_key` is `Greater` than the query,
+/// `max_key` is not compared and `Greater` is returned for it.
+pub fn tracker_with_bounds<K, Q>(
+    query: &Q,
+    min_key: &K,
+    max_key: &K,
+) -> (K::Tracker, Ordering, Ordering)
+where
+    K: TreeOrd<Q> + ?Sized,
+    Q: ?Sized,
+{
+    let mut tracker = K::Tracker::new();
+    let min_cmp = min_key.tree_cmp(query, &mut tracker);
+    let max_cmp = if min_cmp == Greater {
+        Greater
+    } else {
+        max_key.tree_cmp(query, &mut tracker)
+    };
+    (tracker, min_cmp, max_cmp)
+}
+
+/// Returns a tracker for `query` that has already been used to compare
+/// `K::MIN` and `K::MAX` against the `query`. Every key is within these, so the
+/// tracker starts with both sides bounded by the leading fields that the query
+/// shares with them. For example, with `(u8, u8, u32)` keys and a `(255, 255,
+/// x)` query, the first two fields are never compared once any key less than
+/// the query and starting with `(255, 255)` is found, even if no key greater
+/// than the query exists. For slices, use
+/// [crate::utils::LexicographicTracker::with_extremes].
+///
+/// The extremes are compared as `K::MIN.tree_cmp(query)`, so the tracker must
+/// keep being used with the key on the left hand side, as in the searches of
+/// this crate. [crate::checked::Checked] panics if the query is on the left
+/// hand side.
+pub fn tracker_with_extremes<K: TreeOrdBounded>(query: &K) -> K::Tracker {
+    let mut tracker = K::Tracker::new();
+    if !K::Tracker::IS_NOOP {
+        K::MIN.tree_cmp(query, &mut tracker);
+        K::MAX.tree_cmp(query, &mut tracker);
+    }
+    tracker
+}
+
+/// The same as [tree_binary_search], except that the first and last keys are
+/// compared first with [tracker_with_bounds]. This is much faster when queries
+/// are often outside of or close to the ends of the space, and when all the
+/// keys share a long prefix. If there are multiple matches, any one of them may
+/// be returned.
+pub fn tree_binary_search_with_bounds<S, Q>(space: &S, query: &Q) -> Result<usize, usize>
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    let len = space.len();
+    if len == 0 {
+        return Err(0)
+    }
+    let last = len.wrapping_sub(1);
+    // this is `tracker_with_bounds` except that the last key is also not
+    // compared if the first key is already equal
+    let mut tracker = <S::Key as TreeOrd<Q>>::Tracker::new();
+    match space.get(0).tree_cmp(query, &mut tracker) {
+        Less => (),
+        Equal => return Ok(0),
+        Greater => return Err(0),
+    }
+    match space.get(last).tree_cmp(query, &mut tracker) {
+        Less => Err(len),
+        Equal => Ok(last),
+        Greater => binary_search_range(space, 1, last, |k| k.tree_cmp(query, &mut tracker)),
+    }
+}
+
+/// Returns the range of indices of keys in the sorted `space` that are
+/// contained in `range`, similar to `BTreeMap::range`. The start and end bounds
+/// are each found with their own tracker, and the end is only searched for
+/// among the keys at or after the start. Unlike `BTreeMap::range`, inverted
+/// ranges do not panic and instead return an empty range (starting at the
+/// index where the start bound would be).
+pub fn tree_range<S, Q, R>(space: &S, range: R) -> Range<usize>
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+    R: RangeBounds<Q>,
+{
+    let start = match range.start_bound() {
+        Bound::Included(q) => tree_lower_bound(space, q),
+        Bound::Excluded(q) => tree_upper_bound(space, q),
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(q) => {
+            let mut f = tree_search_fn(q);
+            partition_point_range(space, start, space.len(), |k| f(k) != Greater)
+        }
+        Bound::Excluded(q) => {
+            let mut f = tree_search_fn(q);
+            partition_point_range(space, start, space.len(), |k| f(k) == Less)
+        }
+        Bound::Unbounded => space.len(),
+    };
+    start..end
+}
+
+/// Ordered containers that can be searched by `Q`, for generic code that does
+/// not care which container it is given. The tracker of each search is created
+/// and dropped inside of the methods, so callers never see it.
+///
+/// ```
+/// use tree_ord::{collections::SortedVecSet, search::TreeOrdSearchable};
+///
+/// fn count_found<S: TreeOrdSearchable<u32> + ?Sized>(s: &S, queries: &[u32]) -> usize {
+///     queries.iter().filter(|q| s.tree_find(q).is_some()).count()
+/// }
+///
+/// let keys = vec![2u32, 3, 5, 7, 11];
+/// let set: SortedVecSet<u32> = keys.iter().copied().collect();
+/// assert_eq!(count_found(&keys[..], &[3, 4, 5]), 2);
+/// assert_eq!(count_found(&set, &[3, 4, 5]), 2);
+/// assert_eq!(set.tree_range(4..).count(), 3);
+/// ```
+pub trait TreeOrdSearchable<Q: ?Sized> {
+    /// What a successful search finds. This is the key for sets and slices,
+    /// and the value (or group of values) for maps.
+    type Item: ?Sized;
+
+    /// The iterator over the part of the container within some range
+    type RangeIter<'a>: Iterator
+    where
+        Self: 'a;
+
+    /// Returns the item of the key equal to `query`
+    fn tree_find(&self, query: &Q) -> Option<&Self::Item>;
+
+    /// Returns an iterator over the part of the container with keys within
+    /// `range` in ascending order. Inverted ranges are empty.
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> Self::RangeIter<'_>;
+}
+
+impl<K: TreeOrd<Q>, Q: ?Sized> TreeOrdSearchable<Q> for [K] {
+    type Item = K;
+    type RangeIter<'a>
+        = slice::Iter<'a, K>
+    where
+        K: 'a;
+
+    fn tree_find(&self, query: &Q) -> Option<&K> {
+        tree_binary_search_with_bounds(self, query)
+            .ok()
+            .map(|i| &self[i])
+    }
+
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> slice::Iter<'_, K> {
+        self[tree_range(self, range)].iter()
+    }
+}
+
+impl<K: TreeOrd<Q>, Q: ?Sized, const N: usize> TreeOrdSearchable<Q> for [K; N] {
+    type Item = K;
+    type RangeIter<'a>
+        = slice::Iter<'a, K>
+    where
+        K: 'a;
+
+    fn tree_find(&self, query: &Q) -> Option<&K> {
+        self[..].tree_find(query)
+    }
+
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> slice::Iter<'_, K> {
+        self[..].tree_range(range)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: TreeOrd<Q>, Q: ?Sized> TreeOrdSearchable<Q> for Vec<K> {
+    type Item = K;
+    type RangeIter<'a>
+        = slice::Iter<'a, K>
+    where
+        K: 'a;
+
+    fn tree_find(&self, query: &Q) -> Option<&K> {
+        self[..].tree_find(query)
+    }
+
+    fn tree_range<R: RangeBounds<Q>>(&self, range: R) -> slice::Iter<'_, K> {
+        self[..].tree_range(range)
+    }
+}
+
+/// Like [tree_binary_search_by], except that the search starts at `start_hint`
+/// and exponentially expands a window in the direction of the target before
+/// binary searching within the window. This takes `O(log d)` comparisons
+/// where `d` is the distance between `start_hint` and the result, which makes
+/// it suitable for merge-like algorithms where consecutive searches usually
+/// land close to each other.
+///
+/// Every probe is within the bounds established by the previous probes, so
+/// `f` may be a single tracker-using comparator (see [tree_search_fn]) for the
+/// entire search. `start_hint` is clamped to `space.len()`.
+pub fn tree_gallop_search_by<S, F>(space: &S, start_hint: usize, mut f: F) -> Result<usize, usize>
+where
+    S: TreeIndexable + ?Sized,
+    F: FnMut(&S::Key) -> Ordering,
+{
+    gallop_search_indices(space.len(), start_hint, |i| f(space.get(i)))
+}
+
+/// The same as [tree_gallop_search_by] over `len` keys, except that `f` is
+/// given the index to probe, for callers that need to know which keys were
+/// probed
+pub(crate) fn gallop_search_indices<F>(
+    len: usize,
+    start_hint: usize,
+    mut f: F,
+) -> Result<usize, usize>
+where
+    F: FnMut(usize) -> Ordering,
+{
+    let hint = min(start_hint, len);
+    // Determine which side of the hint the target is on. `hint == len` is
+    // treated as if there were a `Greater` element at the end.
+    let hint_cmp = if hint < len { f(hint) } else { Greater };
+    // the window `[lo, hi)` that the target is determined to be within
+    let (lo, hi) = match hint_cmp {
+        Less => {
+            // `prev` is always an index known to be `Less`
+            let mut prev = hint;
+            let mut step = 1usize;
+            loop {
+                let probe = prev.saturating_add(step);
+                if probe >= len {
+                    break (prev.wrapping_add(1), len)
+                }
+                match f(probe) {
+                    Less => {
+                        prev = probe;
+                        step = step.saturating_mul(2);
+                    }
+                    Equal => return Ok(probe),
+                    Greater => break (prev.wrapping_add(1), probe),
+                }
+            }
+        }
+        Equal => return Ok(hint),
+        Greater => {
+            // `prev` is always an index known to be `Greater` (or `len`)
+            let mut prev = hint;
+            let mut step = 1usize;
+            loop {
+                if step > prev {
+                    break (0, prev)
+                }
+                let probe = prev.wrapping_sub(step);
+                match f(probe) {
+                    Less => break (probe.wrapping_add(1), prev),
+                    Equal => return Ok(probe),
+                    Greater => {
+                        prev = probe;
+                        step = step.saturating_mul(2);
+                    }
+                }
+            }
+        }
+    };
+    binary_search_indices(lo, hi, f)
+}
+
+/// Gallop searches the sorted `space` for `query` starting from `start_hint`,
+/// using a single tracker for the whole search. See [tree_gallop_search_by].
+pub fn tree_gallop_search<S, Q>(space: &S, start_hint: usize, query: &Q) -> Result<usize, usize>
+where
+    S: TreeIndexable + ?Sized,
+    S::Key: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    tree_gallop_search_by(space, start_hint, tree_search_fn(query))
+}
+
+/// What a [TreeSearch] needs next from the caller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProbeRequest {
+    /// The key at this index needs to be fetched and passed to
+    /// [TreeSearch::feed]
+    Probe(usize),
+    /// The search is finished with the same kind of result as
+    /// [slice::binary_search]
+    Done(Result<usize, usize>),
+}
+
+/// The outcome of feeding a key to a [TreeSearch]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchStep {
+    /// The query is less than the fed key, the search continues to the left
+    Left,
+    /// The query is greater than the fed key, the search continues to the right
+    Right,
+    /// The fed key is equal to the query and is at this index
+    Found(usize),
+    /// There are no more keys to probe, and the query would be inserted at
+    /// this index
+    NotFound(usize),
+}
+
+/// A sans-io binary search over `len` sorted keys that are fetched by the
+/// caller one at a time, for storage where the keys cannot be accessed
+/// synchronously (e.g. paged or asynchronous storage). The tracker is owned
+/// internally, and the probing order is the same as [tree_binary_search].
+///
+/// ```
+/// use tree_ord::search::{ProbeRequest, SearchStep, TreeSearch};
+///
+/// let space = [1u64, 3, 5, 7, 9, 11];
+/// let mut search = TreeSearch::<u64, u64>::new(&7, space.len());
+/// loop {
+///     match search.next_probe() {
+///         // fetch the key (possibly asynchronously) and feed it back
+///         ProbeRequest::Probe(i) => match search.feed(&space[i]) {
+///             SearchStep::Left | SearchStep::Right => (),
+///             SearchStep::Found(i) => {
+///                 assert_eq!(i, 3);
+///                 break
+///             }
+///             SearchStep::NotFound(_) => unreachable!(),
+///         },
+///         ProbeRequest::Done(res) => unreachable!(),
+///     }
+/// }
+/// assert_eq!(search.next_probe(), ProbeRequest::Done(Ok(3)));
+/// ```
+pub struct TreeSearch<'q, K, Q>
+where
+    K: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    query: &'q Q,
+    tracker: <K as TreeOrd<Q>>::Tracker,
+    left: usize,
+    right: usize,
+    result: Option<Result<usize, usize>>,
+}
+
+impl<'q, K, Q> TreeSearch<'q, K, Q>
+where
+    K: TreeOrd<Q>,
+    Q: ?Sized,
+{
+    /// Starts a new search for `query` among `len` sorted keys
+    pub fn new(query: &'q Q, len: usize) -> Self {
+        Self {
+            query,
+            tracker: <K as TreeOrd<Q>>::Tracker::new(),
+            left: 0,
+            right: len,
+            result: if len == 0 { Some(Err(0)) } else { None },
+        }
+    }
+
+    /// Returns the query this search was started with
+    pub fn query(&self) -> &'q Q {
+        self.query
+    }
+
+    fn mid(&self) -> usize {
+        self.left
+            .wrapping_add(self.right.wrapping_sub(self.left) / 2)
+    }
+
+    /// Returns the index of the key that should be passed to the next
+    /// [TreeSearch::feed] call, or the result if the search is finished
+    pub fn next_probe(&self) -> ProbeRequest {
+        match self.result {
+            Some(res) => ProbeRequest::Done(res),
+            None => ProbeRequest::Probe(self.mid()),
+        }
+    }
+
+    /// Compares the query with `node_key`, which must be the key at the index
+    /// last returned by [TreeSearch::next_probe]. Feeding any other key
+    /// breaks the comparison sequence that the tracker relies on, and the
+    /// result of the search is unspecified.
+    ///
+    /// # Panics
+    ///
+    /// If the search is already finished
+    pub fn feed(&mut self, node_key: &K) -> SearchStep {
+        if self.result.is_some() {
+            panic!("called `TreeSearch::feed` on a finished search")
+        }
+        let mid = self.mid();
+        match node_key.tree_cmp(self.query, &mut self.tracker) {
+            Less => self.left = mid.wrapping_add(1),
+            Equal => {
+                self.result = Some(Ok(mid));
+                return SearchStep::Found(mid)
+            }
+            Greater => self.right = mid,
+        }
+        if self.left >= self.right {
+            self.result = Some(Err(self.left));
+            SearchStep::NotFound(self.left)
+        } else if self.right == mid {
+            SearchStep::Left
+        } else {
+            SearchStep::Right
+        }
+    }
+}
+
+/// Binary searches `buf` as a sorted sequence of `stride` byte records (see
+/// [FixedStride]), comparing records with the chunked comparison of
+/// [TreeOrdBytes] and a single tracker. Returns a record index in the same way
+/// as [slice::binary_search].
+///
+/// A trailing partial record is ignored. `query` does not need to have a
+/// length of `stride`, records and the query are compared with the usual
+/// lexicographic ordering of byte slices, so a query that is a strict prefix of
+/// a record is less than that record and a query that has a record as a
+/// strict prefix is greater than that record.
+///
+/// # Panics
+///
+/// If `stride == 0`
+pub fn fixed_stride_tree_search(buf: &[u8], stride: usize, query: &[u8]) -> Result<usize, usize> {
+    let records = FixedStride::new(buf, stride);
+    let query = TreeOrdBytes(query);
+    let mut tracker = <TreeOrdBytes as TreeOrd>::Tracker::new();
+    tree_binary_search_by(&records, |record| {
+        TreeOrdBytes(record).tree_cmp(&query, &mut tracker)
+    })
+}
+
+/// A cache for temporally local searches over the same sorted space, such as
+/// consecutive lookups of keys under the same namespace prefix.
+///
+/// After each search, the cache remembers the smallest window of indices seen
+/// during the search that still has at least `min_window` keys. The next search
+/// first compares the query against the two keys just outside of the cached
+/// window, using the same tracker as the rest of the search. If they bound the
+/// query, the tracker has then learned the prefix that the query shares with
+/// all the keys in the window, and the binary search continues within the
+/// window. If they do not, the search continues on the side of the space that
+/// the query falls in. In every case the comparisons form a valid sequence of
+/// probes for a single tracker, so the cache can never cause a wrong result,
+/// even if the space is changed between searches (as long as it stays sorted).
+#[derive(Debug, Clone)]
+pub struct PrefixCache {
+    min_window: usize,
+    window: Option<Range<usize>>,
+}
+
+impl Default for PrefixCache {
+    fn default() -> Self {
+        Self::new(16)
+    }
+}
+
+impl PrefixCache {
+    /// Creates an empty cache that will remember windows of at least
+    /// `min_window` keys. Larger windows are more likely to contain the next
+    /// query, but leave more of the search to do within them.
+    pub fn new(min_window: usize) -> Self {
+        Self {
+            min_window,
+            window: None,
+        }
+    }
+
+    /// Returns the currently cached window
+    pub fn window(&self) -> Option<Range<usize>> {
+        self.window.clone()
+    }
+
+    /// Forgets the cached window
+    pub fn clear(&mut self) {
+        self.window = None;
+    }
+
+    /// Searches the sorted `space` for `query` with the same semantics as
+    /// [tree_binary_search], using and then updating the cached window
+    pub fn search<S, Q>(&mut self, space: &S, query: &Q) -> Result<usize, usize>
+    where
+        S: TreeIndexable + ?Sized,
+        S::Key: TreeOrd<Q>,
+        Q: ?Sized,
+    {
+        let mut tracker = <S::Key as TreeOrd<Q>>::Tracker::new();
+        let mut left = 0;
+        let mut right = space.len();
+        if let Some(window) = self.window.take() {
+            if (window.start <= window.end) && (window.end <= right) {
+                if window.start > 0 {
+                    let i = window.start.wrapping_sub(1);
+                    match space.get(i).tree_cmp(query, &mut tracker) {
+                        Less => left = window.start,
+                        Equal => {
+                            self.window = Some(window);
+                            return Ok(i)
+                        }
+                        Greater => right = i,
+                    }
+                }
+                if window.end < right {
+                    let i = window.end;
+                    match space.get(i).tree_cmp(query, &mut tracker) {
+                        Less => left = i.wrapping_add(1),
+                        Equal => {
+                            self.window = Some(window);
+                            return Ok(i)
+                        }
+                        Greater => right = i,
+                    }
+                }
+            }
+        }
+        while left < right {
+            let size = right.wrapping_sub(left);
+            if size >= self.min_window {
+                self.window = Some(left..right);
+            }
+            let mid = left.wrapping_add(size / 2);
+            match space.get(mid).tree_cmp(query, &mut tracker) {
+                Less => left = mid.wrapping_add(1),
+                Equal => return Ok(mid),
+                Greater => right = mid,
+            }
+        }
+        Err(left)
+    }
+}
+
+/// A cursor for bulk loading keys into a sorted `Vec`. It remembers the index
+/// of the last insertion, and each new key is first compared against the last
+/// inserted key and its neighbor before galloping outward, so nearly sorted
+/// streams of keys take about two comparisons per insertion. All the
+/// comparisons of a single insertion share a tracker.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone)]
+pub struct InsertCursor<K> {
+    vec: Vec<K>,
+    last: usize,
+}
+
+#[cfg(feature = "alloc")]
+impl<K: TreeOrd> Default for InsertCursor<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<K: TreeOrd> InsertCursor<K> {
+    /// Creates a cursor over an empty `Vec`
+    pub fn new() -> Self {
+        Self {
+            vec: Vec::new(),
+            last: 0,
+        }
+    }
+
+    /// Creates a cursor over `vec`, which must already be sorted
+    pub fn from_sorted(vec: Vec<K>) -> Self {
+        Self { vec, last: 0 }
+    }
+
+    /// Inserts `key` after any keys that are equal to it, and returns the
+    /// index it was inserted at
+    pub fn insert(&mut self, key: K) -> usize {
+        let mut tracker = K::Tracker::new();
+        // mapping `Equal` to `Less` finds the upper bound
+        let i = match tree_gallop_search_by(self.vec.as_slice(), self.last, |k| {
+            match k.tree_cmp(&key, &mut tracker) {
+                Equal => Less,
+                o => o,
+            }
+        }) {
+            Ok(i) | Err(i) => i,
+        };
+        self.vec.insert(i, key);
+        self.last = i;
+        i
+    }
+
+    /// Returns the sorted keys
+    pub fn as_slice(&self) -> &[K] {
+        &self.vec
+    }
+
+    /// Returns the sorted `Vec`
+    pub fn into_inner(self) -> Vec<K> {
+        self.vec
+    }
+}
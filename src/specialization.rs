@@ -0,0 +1,45 @@
+//! `min_specialization` impls for the "nightly-specialization" feature
+
+use core::cmp::Ordering;
+
+use crate::{
+    utils::{
+        lexicographic_divergence, lexicographic_tree_cmp, lexicographic_tree_cmp_hereditary,
+        LexicographicTracker,
+    },
+    TreeOrd, TreeOrdBytes,
+};
+
+impl<T: TreeOrd> TreeOrd<Self> for [T] {
+    type Tracker = LexicographicTracker<T>;
+
+    #[inline]
+    default fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        lexicographic_tree_cmp(self, rhs, tracker)
+    }
+
+    #[inline]
+    default fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        lexicographic_tree_cmp_hereditary(self, rhs, tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_detail(&self, rhs: &Self, tracker: &mut Self::Tracker) -> (Ordering, usize) {
+        let res = self.tree_cmp(rhs, tracker);
+        (res, lexicographic_divergence(res, self.len(), tracker))
+    }
+}
+
+/// Uses the chunked comparison of `TreeOrdBytes`, keeping the tracker type of
+/// the `[T]` impl
+impl TreeOrd<Self> for [u8] {
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(self).tree_cmp(&TreeOrdBytes(rhs), tracker)
+    }
+
+    #[inline]
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        TreeOrdBytes(self).tree_cmp_hereditary(&TreeOrdBytes(rhs), tracker)
+    }
+}
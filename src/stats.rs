@@ -0,0 +1,39 @@
+//! Process wide counters of the comparisons done by the slice, tuple, and byte
+//! `TreeOrd` impls of this crate, for tuning. The counters are relaxed atomics
+//! that only exist with the "stats" feature flag.
+
+use core::sync::atomic::{AtomicU64, Ordering::Relaxed};
+
+static COMPARISONS: AtomicU64 = AtomicU64::new(0);
+static SKIPPED: AtomicU64 = AtomicU64::new(0);
+
+/// A snapshot of the counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// The number of tracked comparisons
+    pub comparisons: u64,
+    /// The number of leading elements (bytes for byte strings) that were
+    /// skipped because of proven prefixes
+    pub skipped: u64,
+}
+
+/// Returns the current counters
+pub fn snapshot() -> Stats {
+    Stats {
+        comparisons: COMPARISONS.load(Relaxed),
+        skipped: SKIPPED.load(Relaxed),
+    }
+}
+
+/// Sets the counters to zero
+pub fn reset() {
+    COMPARISONS.store(0, Relaxed);
+    SKIPPED.store(0, Relaxed);
+}
+
+/// Records a comparison that skipped `skipped` leading elements
+#[inline]
+pub(crate) fn record(skipped: usize) {
+    COMPARISONS.fetch_add(1, Relaxed);
+    SKIPPED.fetch_add(skipped as u64, Relaxed);
+}
@@ -0,0 +1,775 @@
+//! Helpers for testing `TreeOrd` impls by counting comparisons
+//!
+//! [CountingOrd] wraps an `Ord` type and counts every `cmp` and `partial_cmp`
+//! in a thread local counter, and a [CountScope] measures how much the counter
+//! has increased since it was created. Because the counter is thread local and
+//! only ever read relative to a scope, tests running in parallel do not need
+//! any global initialization or resetting.
+//!
+//! ```
+//! use tree_ord::{
+//!     testing::{CountScope, CountingOrd},
+//!     Tracker, TreeOrd,
+//! };
+//!
+//! let key = vec![CountingOrd(1u64), CountingOrd(2), CountingOrd(3)];
+//! let mut tracker = <Vec<CountingOrd<u64>> as TreeOrd>::Tracker::new();
+//! let scope = CountScope::new();
+//! key.tree_cmp(
+//!     &vec![CountingOrd(1), CountingOrd(2), CountingOrd(4)],
+//!     &mut tracker,
+//! );
+//! key.tree_cmp(
+//!     &vec![CountingOrd(1), CountingOrd(2), CountingOrd(2)],
+//!     &mut tracker,
+//! );
+//! assert_eq!(scope.count(), 6);
+//! // the first two elements are proven equal on both sides and are skipped
+//! let inner = CountScope::new();
+//! key.tree_cmp(
+//!     &vec![CountingOrd(1), CountingOrd(2), CountingOrd(3)],
+//!     &mut tracker,
+//! );
+//! assert_eq!(inner.finish(), 1);
+//! assert_eq!(scope.finish(), 7);
+//! ```
+//!
+//! [check_tree_ord] checks that a `TreeOrd` impl agrees with its `Ord` impl
+//! over many simulated tree searches, [check_tree_ord_exhaustive] does the
+//! same for every possible search of a small domain of keys (such as those of
+//! [all_sequences]), and [ReferenceBst] runs real searches over a nonhereditary
+//! binary search tree.
+
+use core::{
+    any::type_name,
+    cell::Cell,
+    cmp::{min, Ordering},
+    fmt::Write,
+    ops::Range,
+};
+
+use Ordering::*;
+
+use crate::{Tracker, TreeOrd, TreeOrdBounded};
+
+thread_local! {
+    static CMP_COUNT: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Returns the number of comparisons counted on this thread so far. Prefer
+/// [CountScope] to taking differences of this manually.
+pub fn cmp_count() -> u64 {
+    CMP_COUNT.with(|c| c.get())
+}
+
+/// Increments the comparison counter of this thread, for custom wrappers that
+/// count something other than `Ord` comparisons (e.g. equality checks)
+pub fn inc_cmp_count() {
+    CMP_COUNT.with(|c| c.set(c.get().checked_add(1).unwrap()));
+}
+
+/// A wrapper whose `Ord::cmp` and `PartialOrd::partial_cmp` increment the
+/// comparison counter of the current thread. `PartialEq` is not counted. The
+/// `TreeOrd` impl has no tracker and passes through to `Ord::cmp`, so it counts
+/// exactly the element comparisons that a containing key makes.
+#[derive(Debug, Clone, Copy, Default, Hash, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct CountingOrd<T>(pub T);
+
+#[allow(clippy::non_canonical_partial_ord_impl)]
+impl<T: Ord> PartialOrd for CountingOrd<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        inc_cmp_count();
+        Some(self.0.cmp(&other.0))
+    }
+}
+
+impl<T: Ord> Ord for CountingOrd<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        inc_cmp_count();
+        self.0.cmp(&other.0)
+    }
+}
+
+impl<T: Ord> TreeOrd<Self> for CountingOrd<T> {
+    type Tracker = ();
+
+    #[inline]
+    fn tree_cmp(&self, rhs: &Self, _: &mut Self::Tracker) -> Ordering {
+        self.cmp(rhs)
+    }
+}
+
+impl<T: TreeOrdBounded + Ord> TreeOrdBounded for CountingOrd<T> {
+    const MAX: Self = CountingOrd(T::MAX);
+    const MIN: Self = CountingOrd(T::MIN);
+}
+
+#[cfg(feature = "corpus")]
+impl<T: crate::corpus::FromNested> crate::corpus::FromNested for CountingOrd<T> {
+    fn from_nested(nested: crate::corpus::Nested) -> Option<Self> {
+        T::from_nested(nested).map(CountingOrd)
+    }
+}
+
+/// Measures the comparisons counted on the current thread since the scope was
+/// created. Scopes can be nested and overlapped freely, since each one only
+/// remembers the counter value at its creation.
+#[derive(Debug)]
+pub struct CountScope {
+    start: u64,
+}
+
+impl CountScope {
+    /// Starts counting from the current value of the counter
+    pub fn new() -> Self {
+        Self { start: cmp_count() }
+    }
+
+    /// Returns the number of comparisons since this scope was created
+    pub fn count(&self) -> u64 {
+        cmp_count() - self.start
+    }
+
+    /// Ends the scope, returning the number of comparisons since it was
+    /// created
+    pub fn finish(self) -> u64 {
+        self.count()
+    }
+}
+
+impl Default for CountScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The maximum number of comparisons in a sequence generated by
+/// [check_tree_ord]
+const MAX_SEQUENCE_LEN: usize = 128;
+
+/// The number of sequences that [check_tree_ord] generates for each query
+const SEQUENCES_PER_QUERY: usize = 4;
+
+/// The `splitmix64` generator, so that the checker needs no dependencies
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..n`, `n` must be nonzero
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % (n as u64)) as usize
+    }
+}
+
+/// Generates a random valid sequence of comparisons with `query`, as indices
+/// into the sorted and deduplicated `keys`. Every key is strictly within the
+/// bounds established by the previous comparisons, except for repeats of the
+/// last comparison, and sequences may continue after an `Equal` like a
+/// nonhereditary search.
+fn gen_sequence<T: Ord + ?Sized>(rng: &mut Rng, keys: &[&T], query: &T) -> Vec<usize> {
+    // 0 is a binary search, 1 walks inwards from the edges like a search that
+    // starts from the minimum or maximum key, and 2 picks keys at random
+    let walk = rng.below(3);
+    // the range of keys within the current bounds
+    let mut start = 0;
+    let mut end = keys.len();
+    let mut after_equal = false;
+    let mut seq = vec![];
+    while seq.len() < MAX_SEQUENCE_LEN {
+        if let Some(&last) = seq.last() {
+            if rng.below(4) == 0 {
+                seq.push(last);
+                continue
+            }
+        }
+        if start >= end {
+            break
+        }
+        let len = end - start;
+        let i = if after_equal || (walk == 2) {
+            start + rng.below(len)
+        } else if walk == 0 {
+            start + (len / 2)
+        } else {
+            let step = min(rng.below(4), len - 1);
+            if rng.below(2) == 0 {
+                start + step
+            } else {
+                end - 1 - step
+            }
+        };
+        seq.push(i);
+        after_equal = false;
+        match keys[i].cmp(query) {
+            Less => start = i + 1,
+            Greater => end = i,
+            Equal => {
+                if rng.below(2) == 0 {
+                    break
+                }
+                // `Equal` does not strengthen any bounds
+                after_equal = true;
+            }
+        }
+    }
+    seq
+}
+
+/// A comparison where `tree_cmp` disagreed with `cmp`
+#[derive(Debug, Clone, Copy)]
+struct Mismatch {
+    step: usize,
+    key_on_lhs: bool,
+    tree_res: Ordering,
+    res: Ordering,
+}
+
+impl Mismatch {
+    fn describe(&self) -> String {
+        format!(
+            "`tree_cmp` returned `{:?}` but `cmp` returned `{:?}` at comparison {} with the key \
+             on the {} hand side",
+            self.tree_res,
+            self.res,
+            self.step,
+            if self.key_on_lhs { "left" } else { "right" }
+        )
+    }
+}
+
+/// Runs the comparisons of `query` with `keys` with a fresh tracker, once with
+/// the keys on the left hand side and once with the query on the left hand
+/// side
+fn find_mismatch<T: TreeOrd + ?Sized>(query: &T, keys: &[&T]) -> Option<Mismatch> {
+    for key_on_lhs in [true, false] {
+        let mut tracker = T::Tracker::new();
+        for (step, key) in keys.iter().enumerate() {
+            let (tree_res, res) = if key_on_lhs {
+                (key.tree_cmp(query, &mut tracker), key.cmp(&query))
+            } else {
+                (query.tree_cmp(key, &mut tracker), query.cmp(key))
+            };
+            if tree_res != res {
+                return Some(Mismatch {
+                    step,
+                    key_on_lhs,
+                    tree_res,
+                    res,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Checks that `tree_cmp` agrees with `cmp` over one sequence of comparisons
+/// with `query`, which must be a valid sequence for a tree search (see the
+/// notes on [TreeOrd]). The sequence is run with a fresh tracker, once with the
+/// keys on the left hand side and once with the query on the left hand side.
+/// This is mainly for replaying the failures of [check_tree_ord].
+///
+/// # Panics
+///
+/// If any `tree_cmp` disagrees with `cmp`
+pub fn check_tree_cmp_sequence<T: TreeOrd + ?Sized>(query: &T, keys: &[&T]) {
+    if let Some(mismatch) = find_mismatch(query, keys) {
+        panic!("{}", mismatch.describe());
+    }
+}
+
+/// Checks that `tree_cmp` agrees with `cmp` over many randomized tree searches
+/// of the keys of `space`, which does not need to be sorted or deduplicated.
+///
+/// Each of the `queries` and each key of `space` is used as a query for
+/// several searches that are seeded from `rng_seed`, including binary
+/// searches, walks from the edges of the space, comparisons with random keys
+/// within the current bounds, repeats of the last comparison, and
+/// continuations after `Equal` results. Every search is run with the keys on
+/// both sides of `tree_cmp`, see [check_tree_cmp_sequence]. Keys should
+/// share long prefixes with each other and with the queries, or else most of
+/// the prefix tracking is not exercised.
+///
+/// # Panics
+///
+/// If any `tree_cmp` disagrees with `cmp`. The message includes the offending
+/// sequence as a call to [check_tree_cmp_sequence] that replays it.
+pub fn check_tree_ord<T: TreeOrd>(space: &[T], queries: &[T], rng_seed: u64) {
+    let sorted = sorted_dedup(space);
+    let keys: Vec<&T> = sorted.iter().map(|i| &space[*i]).collect();
+    let mut rng = Rng(rng_seed);
+    let all_queries = queries
+        .iter()
+        .enumerate()
+        .map(|(i, query)| ("queries", i, query))
+        .chain(space.iter().enumerate().map(|(i, key)| ("space", i, key)));
+    for (name, i, query) in all_queries {
+        for _ in 0..SEQUENCES_PER_QUERY {
+            let seq = gen_sequence(&mut rng, &keys, query);
+            let seq_keys: Vec<&T> = seq.iter().map(|j| keys[*j]).collect();
+            if let Some(mismatch) = find_mismatch(query, &seq_keys) {
+                let seq: Vec<usize> = seq.iter().map(|j| sorted[*j]).collect();
+                replay_panic::<T>("check_tree_ord", (name, i), "space", &seq, mismatch)
+            }
+        }
+    }
+}
+
+/// Returns the indices of the keys of `space` in sorted order, without the
+/// indices of keys equal to a previous key
+fn sorted_dedup<T: Ord>(space: &[T]) -> Vec<usize> {
+    let mut sorted: Vec<usize> = (0..space.len()).collect();
+    sorted.sort_by(|a, b| space[*a].cmp(&space[*b]));
+    sorted.dedup_by(|a, b| space[*a].cmp(&space[*b]) == Equal);
+    sorted
+}
+
+/// Panics with the failure of `checker`, formatting the sequence of indices
+/// into the `space` slice as a call to [check_tree_cmp_sequence]
+fn replay_panic<T: ?Sized>(
+    checker: &str,
+    (name, i): (&str, usize),
+    space: &str,
+    seq: &[usize],
+    mismatch: Mismatch,
+) -> ! {
+    let mut indices = String::new();
+    for (k, j) in seq.iter().enumerate() {
+        if k != 0 {
+            indices.push_str(", ");
+        }
+        write!(indices, "{j}").unwrap();
+    }
+    panic!(
+        "{checker} failed for `{}` with the query `{name}[{i}]`: {}. Replay with \
+         `check_tree_cmp_sequence(&{name}[{i}], &[{indices}].map(|i| &{space}[i]))`",
+        type_name::<T>(),
+        mismatch.describe()
+    )
+}
+
+/// Returns every sequence of up to `max_len` elements of `alphabet`, ordered by
+/// length. There are `(n^(max_len + 1) - 1) / (n - 1)` of them for an alphabet
+/// of `n >= 2` elements.
+pub fn all_sequences<T: Clone>(alphabet: &[T], max_len: usize) -> Vec<Vec<T>> {
+    let mut res = vec![vec![]];
+    let mut start = 0;
+    for _ in 0..max_len {
+        let end = res.len();
+        for i in start..end {
+            for x in alphabet {
+                let mut seq = res[i].clone();
+                seq.push(x.clone());
+                res.push(seq);
+            }
+        }
+        start = end;
+    }
+    res
+}
+
+/// Checks that `tree_cmp` agrees with `cmp` for every query in `domain`, over
+/// every valid sequence of up to `max_depth` comparisons with keys of
+/// `domain`.
+///
+/// Whether a sequence is valid only depends on the keys it compares with, so
+/// this covers the searches of every sorted space that can be formed from a
+/// subset of `domain`. Every key of a sequence is strictly within the bounds
+/// established by the previous comparisons, and sequences continue after
+/// `Equal` results. Every comparison is also repeated once on the side, and
+/// every sequence is run with the keys on both sides of `tree_cmp`. The
+/// branches share prefixes of the sequences through [Tracker::fork], so this
+/// also checks that forks behave like the trackers they were forked from.
+///
+/// The number of sequences grows exponentially with `max_depth`, at most 3 or 4
+/// is practical for domains of tens of keys, and at least 3 is needed for the
+/// bounds on both sides of a query to be established before a comparison.
+///
+/// # Panics
+///
+/// If any `tree_cmp` disagrees with `cmp`. The message includes the offending
+/// sequence as a call to [check_tree_cmp_sequence] that replays it.
+pub fn check_tree_ord_exhaustive<T: TreeOrd>(domain: &[T], max_depth: usize) {
+    let sorted = sorted_dedup(domain);
+    let keys: Vec<&T> = sorted.iter().map(|i| &domain[*i]).collect();
+    let mut seq = vec![];
+    for (q, query) in keys.iter().enumerate() {
+        for key_on_lhs in [true, false] {
+            let mut exhaustive = Exhaustive {
+                keys: &keys,
+                query,
+                key_on_lhs,
+                seq: &mut seq,
+            };
+            if let Some(mismatch) = exhaustive.descend(&T::Tracker::new(), 0..keys.len(), max_depth)
+            {
+                let seq: Vec<usize> = seq.iter().map(|j| sorted[*j]).collect();
+                replay_panic::<T>(
+                    "check_tree_ord_exhaustive",
+                    ("domain", sorted[q]),
+                    "domain",
+                    &seq,
+                    mismatch,
+                )
+            }
+        }
+    }
+}
+
+/// The state of the depth first search of [check_tree_ord_exhaustive]
+struct Exhaustive<'a, T> {
+    keys: &'a [&'a T],
+    query: &'a T,
+    key_on_lhs: bool,
+    // the current sequence, which is left as the offending sequence on failure
+    seq: &'a mut Vec<usize>,
+}
+
+impl<T: TreeOrd> Exhaustive<'_, T> {
+    /// Compares with the key at `i`, returning the ordering of the key
+    /// relative to the query
+    fn compare(&mut self, i: usize, tracker: &mut T::Tracker) -> Result<Ordering, Mismatch> {
+        let key = self.keys[i];
+        self.seq.push(i);
+        let (tree_res, res) = if self.key_on_lhs {
+            (key.tree_cmp(self.query, tracker), key.cmp(self.query))
+        } else {
+            (self.query.tree_cmp(key, tracker), self.query.cmp(key))
+        };
+        if tree_res != res {
+            return Err(Mismatch {
+                step: self.seq.len() - 1,
+                key_on_lhs: self.key_on_lhs,
+                tree_res,
+                res,
+            });
+        }
+        Ok(if self.key_on_lhs { res } else { res.reverse() })
+    }
+
+    /// Runs every sequence of up to `depth` comparisons with the keys at
+    /// `bounds`, continuing from `tracker`
+    fn descend(
+        &mut self,
+        tracker: &T::Tracker,
+        bounds: Range<usize>,
+        depth: usize,
+    ) -> Option<Mismatch> {
+        if depth == 0 {
+            return None
+        }
+        for i in bounds.clone() {
+            let mut fork = tracker.fork();
+            let key_ord = match self.compare(i, &mut fork) {
+                Ok(key_ord) => key_ord,
+                Err(mismatch) => return Some(mismatch),
+            };
+            let mut repeat = fork.fork();
+            if let Err(mismatch) = self.compare(i, &mut repeat) {
+                return Some(mismatch)
+            }
+            self.seq.pop();
+            let next = match key_ord {
+                Less => (i + 1)..bounds.end,
+                Equal => bounds.clone(),
+                Greater => bounds.start..i,
+            };
+            if let Some(mismatch) = self.descend(&fork, next, depth - 1) {
+                return Some(mismatch)
+            }
+            self.seq.pop();
+        }
+        None
+    }
+}
+
+/// A node of a [ReferenceBst]
+#[derive(Debug, Clone)]
+struct Node<K> {
+    key: K,
+    left: Option<usize>,
+    right: Option<usize>,
+}
+
+/// A small unbalanced binary search tree for conformance testing of `TreeOrd`
+/// impls in the nonhereditary setting.
+///
+/// Keys equal to a node can be inserted into either of its subtrees, so the
+/// equal keys of a query can be scattered across several subtrees with unequal
+/// keys in between. The searches use a single tracker each (forked for the
+/// branches of `find_all`) and continue after `Equal` results, stepping out of
+/// the group of equal keys and back in, which is only correct if `tree_cmp`
+/// does not treat `Equal` as strengthening any bounds. [ReferenceBst::check]
+/// checks that the keys are in order and cross-checks every search against a
+/// linear scan of them.
+///
+/// The shape of the tree only depends on the insertion order, so adversarial
+/// shapes such as spines (sorted insertions) and zig-zags (insertions
+/// alternating between the ends of a sorted sequence) are easy to build.
+///
+/// ```
+/// use tree_ord::testing::ReferenceBst;
+///
+/// let mut bst = ReferenceBst::new();
+/// let keys = [
+///     ("b", false),
+///     ("a", false),
+///     ("b", true),
+///     ("c", false),
+///     ("b", false),
+/// ];
+/// for (key, equal_right) in keys {
+///     bst.insert(key.to_owned(), equal_right);
+/// }
+/// // the last "b" is in the right subtree of "a"
+/// assert_eq!(bst.find_all(&"b".to_owned()), vec![4, 0, 2]);
+/// bst.check(&"b".to_owned());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReferenceBst<K> {
+    nodes: Vec<Node<K>>,
+    root: Option<usize>,
+}
+
+impl<K: TreeOrd> ReferenceBst<K> {
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![],
+            root: None,
+        }
+    }
+
+    /// Returns the number of keys
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Returns the key with the id `id`, which is the number of keys that
+    /// were inserted before it
+    ///
+    /// # Panics
+    ///
+    /// If `id >= self.len()`
+    pub fn get(&self, id: usize) -> &K {
+        &self.nodes[id].key
+    }
+
+    /// Inserts `key` and returns its id. If an equal key is encountered while
+    /// descending, `key` goes into its right subtree if `equal_right` and into
+    /// its left subtree otherwise.
+    pub fn insert(&mut self, key: K, equal_right: bool) -> usize {
+        let id = self.nodes.len();
+        let mut tracker = K::Tracker::new();
+        let mut link = self.root;
+        let mut parent = None;
+        while let Some(i) = link {
+            let right = match self.nodes[i].key.tree_cmp(&key, &mut tracker) {
+                Less => true,
+                Equal => equal_right,
+                Greater => false,
+            };
+            parent = Some((i, right));
+            link = if right {
+                self.nodes[i].right
+            } else {
+                self.nodes[i].left
+            };
+        }
+        match parent {
+            None => self.root = Some(id),
+            Some((i, true)) => self.nodes[i].right = Some(id),
+            Some((i, false)) => self.nodes[i].left = Some(id),
+        }
+        self.nodes.push(Node {
+            key,
+            left: None,
+            right: None,
+        });
+        id
+    }
+
+    /// Returns the ids of all keys in order
+    pub fn in_order(&self) -> Vec<usize> {
+        let mut res = vec![];
+        let mut stack = vec![];
+        let mut link = self.root;
+        loop {
+            while let Some(i) = link {
+                stack.push(i);
+                link = self.nodes[i].left;
+            }
+            match stack.pop() {
+                Some(i) => {
+                    res.push(i);
+                    link = self.nodes[i].right;
+                }
+                None => return res,
+            }
+        }
+    }
+
+    /// Returns the number of nodes on the longest path from the root
+    pub fn height(&self) -> usize {
+        let mut res = 0;
+        let mut stack: Vec<(usize, usize)> = self.root.map(|i| (i, 1)).into_iter().collect();
+        while let Some((i, depth)) = stack.pop() {
+            res = res.max(depth);
+            for child in [self.nodes[i].left, self.nodes[i].right]
+                .into_iter()
+                .flatten()
+            {
+                stack.push((child, depth + 1));
+            }
+        }
+        res
+    }
+
+    /// Returns the id of the first key equal to `query` found while descending
+    pub fn find_any(&self, query: &K) -> Option<usize> {
+        let mut tracker = K::Tracker::new();
+        let mut link = self.root;
+        while let Some(i) = link {
+            link = match self.nodes[i].key.tree_cmp(query, &mut tracker) {
+                Less => self.nodes[i].right,
+                Equal => return Some(i),
+                Greater => self.nodes[i].left,
+            };
+        }
+        None
+    }
+
+    /// Returns the id of the first key in order that is equal to `query`. The
+    /// search continues into the left subtree of every equal key.
+    pub fn find_first(&self, query: &K) -> Option<usize> {
+        let mut tracker = K::Tracker::new();
+        let mut res = None;
+        let mut link = self.root;
+        while let Some(i) = link {
+            link = match self.nodes[i].key.tree_cmp(query, &mut tracker) {
+                Less => self.nodes[i].right,
+                Equal => {
+                    res = Some(i);
+                    self.nodes[i].left
+                }
+                Greater => self.nodes[i].left,
+            };
+        }
+        res
+    }
+
+    /// Returns the id of the last key in order that is equal to `query`. The
+    /// search continues into the right subtree of every equal key.
+    pub fn find_last(&self, query: &K) -> Option<usize> {
+        let mut tracker = K::Tracker::new();
+        let mut res = None;
+        let mut link = self.root;
+        while let Some(i) = link {
+            link = match self.nodes[i].key.tree_cmp(query, &mut tracker) {
+                Less => self.nodes[i].right,
+                Equal => {
+                    res = Some(i);
+                    self.nodes[i].right
+                }
+                Greater => self.nodes[i].left,
+            };
+        }
+        res
+    }
+
+    /// Returns the ids of all keys equal to `query` in order. Both subtrees of
+    /// every equal key are searched, each with a fork of the tracker.
+    pub fn find_all(&self, query: &K) -> Vec<usize> {
+        let mut res = vec![];
+        self.find_all_recursive(self.root, query, &mut K::Tracker::new(), &mut res);
+        res
+    }
+
+    fn find_all_recursive(
+        &self,
+        mut link: Option<usize>,
+        query: &K,
+        tracker: &mut K::Tracker,
+        res: &mut Vec<usize>,
+    ) {
+        while let Some(i) = link {
+            link = match self.nodes[i].key.tree_cmp(query, tracker) {
+                Less => self.nodes[i].right,
+                Equal => {
+                    let mut fork = tracker.fork();
+                    self.find_all_recursive(self.nodes[i].left, query, &mut fork, res);
+                    res.push(i);
+                    self.nodes[i].right
+                }
+                Greater => self.nodes[i].left,
+            };
+        }
+    }
+
+    /// Checks every search for `query` against a linear scan of the keys in
+    /// order
+    ///
+    /// # Panics
+    ///
+    /// If the keys are out of order or any search disagrees with the linear
+    /// scan, which means that `tree_cmp` is inconsistent with `cmp`
+    pub fn check(&self, query: &K) {
+        let ty = type_name::<K>();
+        let in_order = self.in_order();
+        for w in in_order.windows(2) {
+            assert!(
+                self.nodes[w[0]].key <= self.nodes[w[1]].key,
+                "the keys of the tree are out of order for `{ty}`, an insertion got a wrong \
+                 result from `tree_cmp`"
+            );
+        }
+        let expected: Vec<usize> = in_order
+            .into_iter()
+            .filter(|i| self.nodes[*i].key == *query)
+            .collect();
+        let all = self.find_all(query);
+        assert_eq!(
+            all, expected,
+            "`find_all` disagreed with a linear scan for `{ty}`"
+        );
+        let first = self.find_first(query);
+        assert_eq!(
+            first,
+            expected.first().copied(),
+            "`find_first` disagreed with a linear scan for `{ty}`"
+        );
+        let last = self.find_last(query);
+        assert_eq!(
+            last,
+            expected.last().copied(),
+            "`find_last` disagreed with a linear scan for `{ty}`"
+        );
+        match self.find_any(query) {
+            Some(i) => assert!(
+                expected.contains(&i),
+                "`find_any` found an unequal key for `{ty}`"
+            ),
+            None => assert!(
+                expected.is_empty(),
+                "`find_any` did not find an equal key for `{ty}`"
+            ),
+        }
+    }
+}
+
+impl<K: TreeOrd> Default for ReferenceBst<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,8 +1,14 @@
-use core::cmp::{min, Ordering};
+#[cfg(not(feature = "nightly-specialization"))]
+use core::{any::TypeId, marker::PhantomData};
+use core::{
+    cmp::{max, min, Ordering},
+    fmt,
+    mem::size_of,
+};
 
 use Ordering::*;
 
-use crate::{Tracker, TreeOrd};
+use crate::{stats, Tracker, TreeOrd, TreeOrdBounded, TreeOrdBytes};
 
 /// Minimize debug info for enum matching branches that should be impossible no
 /// matter what
@@ -11,48 +17,1250 @@ pub fn tree_cmp_unreachable() -> ! {
     unreachable!()
 }
 
-/// Used by the trackers of things such as slices
-pub struct LexicographicTracker<T: TreeOrd> {
-    /// Stores a `Tracker` for individual elements
-    pub subtracker: <T as TreeOrd>::Tracker,
-    /// Element to which `subtracker` corresponds
-    pub subtracker_i: usize,
+/// An index type for [LexicographicTracker]. Prefix lengths saturate at
+/// `Self::MAX_LEN`, which just means that no further skipping can happen
+/// beyond that length.
+pub trait TrackerIndex: Copy + Eq + fmt::Debug {
+    /// The maximum prefix length, one less than the maximum value so that the
+    /// maximum value can be reserved
+    const MAX_LEN: usize;
+
+    /// The reserved value that is never a valid prefix length
+    const INVALID: Self;
+
+    const ZERO: Self;
+
+    /// Converts from `usize`, saturating at `MAX_LEN`
+    fn from_usize_saturating(x: usize) -> Self;
+
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_tracker_index {
+    ($($t:ident)*) => {
+        $(
+            impl TrackerIndex for $t {
+                const INVALID: Self = $t::MAX;
+                const MAX_LEN: usize = ($t::MAX - 1) as usize;
+                const ZERO: Self = 0;
+
+                #[inline]
+                fn from_usize_saturating(x: usize) -> Self {
+                    if x > Self::MAX_LEN {
+                        Self::MAX_LEN as $t
+                    } else {
+                        x as $t
+                    }
+                }
+
+                #[inline]
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_tracker_index!(u8 u16 u32 usize);
+
+/// Used by the trackers of things such as slices. The fields are private so
+/// that the layout can change, `proven_prefix_len` and the other accessors can
+/// be used to observe the state.
+///
+/// The index type `I` can be made smaller than `usize` to save memory in deeply
+/// nested trackers, see [TrackerIndex].
+pub struct LexicographicTracker<T: TreeOrd, I: TrackerIndex = usize> {
+    /// Stores a `Tracker` for the element at the proven prefix length. This is
+    /// reset whenever the proven prefix moves, so that no index needs to be
+    /// stored and the whole tracker is just the two lengths for no-op
+    /// subtrackers.
+    subtracker: <T as TreeOrd>::Tracker,
     /// Length of lower bounding prefix
-    pub min_eq_len: usize,
+    min_eq_len: I,
     /// Length of upper bounding prefix
-    pub max_eq_len: usize,
+    max_eq_len: I,
 }
 
-impl<T: TreeOrd> Tracker for LexicographicTracker<T> {
-    const IS_NOOP: bool = false;
+// the impls are written manually so that they are only conditional on the
+// subtrackers and not on `T` itself
+
+impl<T: TreeOrd, I: TrackerIndex> Clone for LexicographicTracker<T, I>
+where
+    T::Tracker: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            subtracker: self.subtracker.clone(),
+            min_eq_len: self.min_eq_len,
+            max_eq_len: self.max_eq_len,
+        }
+    }
+}
+
+impl<T: TreeOrd, I: TrackerIndex> fmt::Debug for LexicographicTracker<T, I>
+where
+    T::Tracker: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LexicographicTracker")
+            .field("subtracker", &self.subtracker)
+            .field("min_eq_len", &self.min_eq_len)
+            .field("max_eq_len", &self.max_eq_len)
+            .finish()
+    }
+}
+
+impl<T: TreeOrd, I: TrackerIndex> Default for LexicographicTracker<T, I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: TreeOrd, I: TrackerIndex> LexicographicTracker<T, I> {
+    /// Returns a tracker that starts with the first `len` elements known to be
+    /// equal between the query and every key that will be compared, for when
+    /// the caller knows that all keys in a structure share a prefix. Those
+    /// elements are never compared.
+    ///
+    /// If `len` overstates the true common prefix, the results of comparisons
+    /// are unspecified (but memory safe).
+    pub fn with_known_prefix(len: usize) -> Self {
+        let len = I::from_usize_saturating(len);
+        Self {
+            subtracker: <T as TreeOrd>::Tracker::new(),
+            min_eq_len: len,
+            max_eq_len: len,
+        }
+    }
 
-    fn new() -> Self {
-        LexicographicTracker {
+    /// Returns a tracker for searching for `query`, bounded by the absolute
+    /// maximum of the keys. Keys greater than the query must share its leading
+    /// `T::MAX` elements, so they start out proven on the `Greater` side. The
+    /// `Less` side always starts at 0, because proper prefixes of the query are
+    /// less than it.
+    ///
+    /// Like [crate::search::tracker_with_extremes], the tracker must be used
+    /// with the key on the left hand side, `key.tree_cmp(query)`, as in the
+    /// searches of this crate. The tracker does not know which side the query
+    /// is on, and with the query on the left hand side the seeded prefix ends
+    /// up on the side of the keys less than the query.
+    pub fn with_extremes(query: &[T]) -> Self
+    where
+        T: TreeOrdBounded,
+    {
+        let len = query.iter().take_while(|x| **x == T::MAX).count();
+        Self {
             subtracker: <T as TreeOrd>::Tracker::new(),
-            subtracker_i: 0,
-            min_eq_len: 0,
-            max_eq_len: 0,
+            min_eq_len: I::from_usize_saturating(len),
+            max_eq_len: I::ZERO,
+        }
+    }
+
+    /// Returns the length of the prefix that is currently proven to be shared
+    /// by the query and all keys within the bounds of the search. This is in
+    /// the units of the `TreeOrd` impl using the tracker, which is elements for
+    /// slices and bytes for `TreeOrdBytes`. This is the same as
+    /// `Tracker::proven_prefix`.
+    #[inline]
+    pub fn proven_prefix_len(&self) -> usize {
+        min(self.min_eq_len.to_usize(), self.max_eq_len.to_usize())
+    }
+
+    /// Returns the length of the prefix that was equal in the last comparison
+    /// that returned `Greater`
+    #[inline]
+    pub fn min_eq_len(&self) -> usize {
+        self.min_eq_len.to_usize()
+    }
+
+    /// Returns the length of the prefix that was equal in the last comparison
+    /// that returned `Less`
+    #[inline]
+    pub fn max_eq_len(&self) -> usize {
+        self.max_eq_len.to_usize()
+    }
+
+    #[inline]
+    pub(crate) fn set_min_eq_len(&mut self, len: usize) {
+        let prefix = self.proven_prefix_len();
+        self.min_eq_len = I::from_usize_saturating(len);
+        self.reset_subtracker_if_moved(prefix);
+    }
+
+    #[inline]
+    pub(crate) fn set_max_eq_len(&mut self, len: usize) {
+        let prefix = self.proven_prefix_len();
+        self.max_eq_len = I::from_usize_saturating(len);
+        self.reset_subtracker_if_moved(prefix);
+    }
+
+    /// The subtracker belongs to the element at the proven prefix length, so it
+    /// is stale once that moves away from `prev_prefix`
+    #[inline]
+    fn reset_subtracker_if_moved(&mut self, prev_prefix: usize) {
+        if !<T as TreeOrd>::Tracker::IS_NOOP && (self.proven_prefix_len() != prev_prefix) {
+            self.subtracker.reset();
+        }
+    }
+
+    /// Sets the subtracker for the element at the current proven prefix length.
+    /// This must be called after the bounds are updated.
+    #[inline]
+    pub(crate) fn set_subtracker(&mut self, subtracker: <T as TreeOrd>::Tracker) {
+        self.subtracker = subtracker;
+    }
+
+    /// Returns the subtracker for the element at the proven prefix length
+    #[inline]
+    pub(crate) fn subtracker(&mut self) -> &mut <T as TreeOrd>::Tracker {
+        &mut self.subtracker
+    }
+}
+
+impl<T: TreeOrd, I: TrackerIndex> Tracker for LexicographicTracker<T, I> {
+    /// The subtracker snapshot, `min_eq_len`, and `max_eq_len`
+    type Snapshot = (<<T as TreeOrd>::Tracker as Tracker>::Snapshot, I, I);
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = LexicographicTracker {
+        subtracker: <T as TreeOrd>::Tracker::NEW,
+        min_eq_len: I::ZERO,
+        max_eq_len: I::ZERO,
+    };
+
+    #[inline]
+    fn reset(&mut self) {
+        self.subtracker.reset();
+        self.min_eq_len = I::ZERO;
+        self.max_eq_len = I::ZERO;
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.subtracker.snapshot(), self.min_eq_len, self.max_eq_len)
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        self.subtracker.restore(snapshot.0);
+        self.min_eq_len = snapshot.1;
+        self.max_eq_len = snapshot.2;
+    }
+
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        Some(self.proven_prefix_len())
+    }
+}
+
+/// The tracker of `TreeOrdBytesAdaptive`. This is a byte
+/// `LexicographicTracker` plus the stride that equal bytes are currently
+/// skipped with, which grows once the proven prefix gets long.
+#[derive(Debug, Clone)]
+pub struct AdaptiveBytesTracker {
+    inner: LexicographicTracker<u8>,
+    stride: usize,
+}
+
+impl Default for AdaptiveBytesTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveBytesTracker {
+    /// The proven prefix length in bytes at which the stride grows
+    pub const ADAPT_LEN: usize = 256;
+    /// The stride after the proven prefix reaches `ADAPT_LEN`
+    pub const LARGE_STRIDE: usize = 512;
+    /// The stride before the proven prefix reaches `ADAPT_LEN`
+    pub const SMALL_STRIDE: usize = TreeOrdBytes::CHUNK_LEN;
+
+    /// The same as [LexicographicTracker::with_known_prefix] in bytes
+    pub fn with_known_prefix(len: usize) -> Self {
+        Self {
+            inner: LexicographicTracker::with_known_prefix(len),
+            stride: Self::SMALL_STRIDE,
+        }
+    }
+
+    /// Returns the underlying byte tracker
+    #[inline]
+    pub fn inner(&self) -> &LexicographicTracker<u8> {
+        &self.inner
+    }
+
+    /// Returns the current stride in bytes
+    #[inline]
+    pub fn stride(&self) -> usize {
+        self.stride
+    }
+
+    #[inline]
+    pub(crate) fn inner_mut(&mut self) -> &mut LexicographicTracker<u8> {
+        &mut self.inner
+    }
+
+    /// Grows the stride if the proven prefix has become long enough, and
+    /// returns the stride
+    #[inline]
+    pub(crate) fn adapt(&mut self) -> usize {
+        if self.inner.proven_prefix_len() >= Self::ADAPT_LEN {
+            self.stride = Self::LARGE_STRIDE;
+        }
+        self.stride
+    }
+}
+
+impl Tracker for AdaptiveBytesTracker {
+    /// The byte tracker snapshot and the stride
+    type Snapshot = (<LexicographicTracker<u8> as Tracker>::Snapshot, usize);
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = AdaptiveBytesTracker {
+        inner: LexicographicTracker::NEW,
+        stride: Self::SMALL_STRIDE,
+    };
+
+    #[inline]
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.stride = Self::SMALL_STRIDE;
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.inner.snapshot(), self.stride)
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        self.inner.restore(snapshot.0);
+        self.stride = snapshot.1;
+    }
+
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        Some(self.inner.proven_prefix_len())
+    }
+}
+
+/// The tracker of [crate::NaturalOrdStr] and [crate::VersionStr], which tracks
+/// the number of leading segments (runs of digits or of other characters, or
+/// dot separated parts) that are proven equal. Equal segments are equal in
+/// bytes, so the byte offset after the proven segments is the same for every
+/// key within the bounds and is stored alongside, so that comparisons can
+/// resume parsing there.
+///
+/// [crate::DnsName] uses this for its labels, which are compared from the
+/// right, so its segments are trailing labels and its byte offsets are counted
+/// from the end of the name.
+#[derive(Debug, Clone, Copy)]
+pub struct NaturalTracker {
+    min_eq_segments: usize,
+    min_eq_bytes: usize,
+    max_eq_segments: usize,
+    max_eq_bytes: usize,
+}
+
+impl Default for NaturalTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NaturalTracker {
+    /// Returns the number of leading segments that are proven to be shared by
+    /// the query and all keys within the bounds of the search
+    #[inline]
+    pub fn proven_segments(&self) -> usize {
+        min(self.min_eq_segments, self.max_eq_segments)
+    }
+
+    /// Returns the byte offset after the proven segments
+    #[inline]
+    pub fn proven_bytes(&self) -> usize {
+        if self.min_eq_segments < self.max_eq_segments {
+            self.min_eq_bytes
+        } else {
+            self.max_eq_bytes
         }
     }
+
+    #[inline]
+    pub(crate) fn set_min_eq(&mut self, segments: usize, bytes: usize) {
+        self.min_eq_segments = segments;
+        self.min_eq_bytes = bytes;
+    }
+
+    #[inline]
+    pub(crate) fn set_max_eq(&mut self, segments: usize, bytes: usize) {
+        self.max_eq_segments = segments;
+        self.max_eq_bytes = bytes;
+    }
+}
+
+impl Tracker for NaturalTracker {
+    type Snapshot = Self;
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = Self {
+        min_eq_segments: 0,
+        min_eq_bytes: 0,
+        max_eq_segments: 0,
+        max_eq_bytes: 0,
+    };
+
+    #[inline]
+    fn snapshot(&self) -> Self {
+        *self
+    }
+
+    #[inline]
+    fn restore(&mut self, snapshot: Self) {
+        *self = snapshot;
+    }
+
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        Some(self.proven_segments())
+    }
+}
+
+/// The tracker of [crate::Shortlex]. The lengths are compared first, and once
+/// the keys of both bounds are proven to have the length of the query, only the
+/// content is compared. The content tracker is only advanced by comparisons
+/// with keys of the same length as the query, so it stays valid while the
+/// lengths are locking in.
+#[derive(Debug, Clone, Copy)]
+pub struct ShortlexTracker<C> {
+    min_eq_len: bool,
+    max_eq_len: bool,
+    content: C,
 }
 
-// for small enums we store the subtrackers in parallel
-pub struct ResultTracker<T: TreeOrd, E: TreeOrd> {
-    pub t: <T as TreeOrd>::Tracker,
-    pub e: <E as TreeOrd>::Tracker,
+impl<C: Tracker> Default for ShortlexTracker<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Tracker> ShortlexTracker<C> {
+    /// Returns if the keys of both bounds have the same length as the query,
+    /// in which case the lengths are no longer compared
+    #[inline]
+    pub fn len_locked(&self) -> bool {
+        self.min_eq_len && self.max_eq_len
+    }
+
+    /// Returns the tracker of the content
+    #[inline]
+    pub fn content(&self) -> &C {
+        &self.content
+    }
+}
+
+impl<C: Tracker> Tracker for ShortlexTracker<C> {
+    type Snapshot = (bool, bool, C::Snapshot);
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = Self {
+        min_eq_len: false,
+        max_eq_len: false,
+        content: C::NEW,
+    };
+
+    #[inline]
+    fn reset(&mut self) {
+        self.min_eq_len = false;
+        self.max_eq_len = false;
+        self.content.reset();
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.min_eq_len, self.max_eq_len, self.content.snapshot())
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        let (min_eq_len, max_eq_len, content) = snapshot;
+        self.min_eq_len = min_eq_len;
+        self.max_eq_len = max_eq_len;
+        self.content.restore(content);
+    }
+
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        if self.len_locked() {
+            self.content.proven_prefix()
+        } else {
+            Some(0)
+        }
+    }
+}
+
+/// Compares by length and then by content
+#[inline]
+pub(crate) fn shortlex_tree_cmp<C: TreeOrd + ?Sized, const HEREDITARY: bool>(
+    x: &C,
+    x_len: usize,
+    y: &C,
+    y_len: usize,
+    tracker: &mut ShortlexTracker<C::Tracker>,
+) -> Ordering {
+    if !tracker.len_locked() {
+        match x_len.cmp(&y_len) {
+            Less => return Less,
+            Equal => (),
+            Greater => return Greater,
+        }
+    }
+    let res = if HEREDITARY {
+        x.tree_cmp_hereditary(y, &mut tracker.content)
+    } else {
+        x.tree_cmp(y, &mut tracker.content)
+    };
+    match res {
+        Less => tracker.max_eq_len = true,
+        Equal => {
+            if HEREDITARY {
+                tracker.min_eq_len = true;
+            }
+        }
+        Greater => tracker.min_eq_len = true,
+    }
+    res
+}
+
+/// Compares `lhs` and `rhs` byte by byte after mapping each byte with `f`,
+/// starting after the proven prefix. `f` must preserve equality, that is equal
+/// bytes must map to equal bytes.
+#[inline]
+pub(crate) fn mapped_bytes_tree_cmp<F: Fn(u8) -> u8, const HEREDITARY: bool>(
+    lhs: &[u8],
+    rhs: &[u8],
+    f: F,
+    tracker: &mut LexicographicTracker<u8>,
+) -> Ordering {
+    let end = min(lhs.len(), rhs.len());
+    let start = min(tracker.proven_prefix_len(), end);
+    stats::record(start);
+    let x = &lhs[start..end];
+    let y = &rhs[start..end];
+    for (j, (a, b)) in x.iter().zip(y.iter()).enumerate() {
+        // most bytes are exactly equal, so `f` is only applied to the others
+        if a != b {
+            let res = f(*a).cmp(&f(*b));
+            if res != Equal {
+                return lexicographic_diverged(start.wrapping_add(j), res, tracker)
+            }
+        }
+    }
+    lexicographic_len_cmp::<u8, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+}
+
+/// The tracker of [crate::CaseTiebreak]. The folded tracker tracks the prefix
+/// that is equal up to ASCII case, and once the keys of both bounds are proven
+/// equal to the query up to case, the search has degenerated into the tiebreak
+/// pass and only the original bytes are compared.
+#[derive(Debug, Clone)]
+pub struct CaseTiebreakTracker {
+    folded: LexicographicTracker<u8>,
+    tiebreak: LexicographicTracker<u8>,
+    min_eq_folded: bool,
+    max_eq_folded: bool,
+}
+
+impl Default for CaseTiebreakTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CaseTiebreakTracker {
+    /// Returns if the keys of both bounds are equal to the query up to case,
+    /// in which case only the tiebreak pass is done
+    #[inline]
+    pub fn in_tiebreak(&self) -> bool {
+        self.min_eq_folded && self.max_eq_folded
+    }
+
+    /// Returns the tracker of the case insensitive pass
+    #[inline]
+    pub fn folded(&self) -> &LexicographicTracker<u8> {
+        &self.folded
+    }
+
+    /// Returns the tracker of the case sensitive tiebreak pass
+    #[inline]
+    pub fn tiebreak(&self) -> &LexicographicTracker<u8> {
+        &self.tiebreak
+    }
+}
+
+impl Tracker for CaseTiebreakTracker {
+    type Snapshot = (
+        <LexicographicTracker<u8> as Tracker>::Snapshot,
+        <LexicographicTracker<u8> as Tracker>::Snapshot,
+        bool,
+        bool,
+    );
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = Self {
+        folded: LexicographicTracker::NEW,
+        tiebreak: LexicographicTracker::NEW,
+        min_eq_folded: false,
+        max_eq_folded: false,
+    };
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (
+            self.folded.snapshot(),
+            self.tiebreak.snapshot(),
+            self.min_eq_folded,
+            self.max_eq_folded,
+        )
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        let (folded, tiebreak, min_eq_folded, max_eq_folded) = snapshot;
+        self.folded.restore(folded);
+        self.tiebreak.restore(tiebreak);
+        self.min_eq_folded = min_eq_folded;
+        self.max_eq_folded = max_eq_folded;
+    }
+
+    /// Returns the number of leading bytes that are proven equal up to ASCII
+    /// case
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        Some(self.folded.proven_prefix_len())
+    }
+}
+
+/// Compares case insensitively, and then case sensitively if that is `Equal`
+#[inline]
+pub(crate) fn case_tiebreak_tree_cmp<const HEREDITARY: bool>(
+    lhs: &[u8],
+    rhs: &[u8],
+    tracker: &mut CaseTiebreakTracker,
+) -> Ordering {
+    if !tracker.in_tiebreak() {
+        match mapped_bytes_tree_cmp::<_, false>(
+            lhs,
+            rhs,
+            |b| b.to_ascii_lowercase(),
+            &mut tracker.folded,
+        ) {
+            Less => return Less,
+            Equal => (),
+            Greater => return Greater,
+        }
+    }
+    // the keys reaching here are equal to the query up to case, so the tiebreak
+    // tracker is only ever advanced by them
+    let res = if HEREDITARY {
+        lhs.tree_cmp_hereditary(rhs, &mut tracker.tiebreak)
+    } else {
+        lhs.tree_cmp(rhs, &mut tracker.tiebreak)
+    };
+    match res {
+        Less => {
+            tracker.max_eq_folded = true;
+            tracker.folded.set_max_eq_len(rhs.len());
+        }
+        Equal => {
+            if HEREDITARY {
+                tracker.min_eq_folded = true;
+                tracker.folded.set_min_eq_len(rhs.len());
+            }
+        }
+        Greater => {
+            tracker.min_eq_folded = true;
+            tracker.folded.set_min_eq_len(rhs.len());
+        }
+    }
+    res
+}
+
+/// The tracker of [crate::MaybeTracked], which can turn tracking off at
+/// runtime. When disabled, comparisons use plain `Ord` and never touch the
+/// inner tracker.
+#[derive(Debug, Clone)]
+pub struct MaybeTrackedTracker<T: Tracker> {
+    enabled: bool,
+    inner: T,
+}
+
+impl<T: Tracker> Default for MaybeTrackedTracker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Tracker> MaybeTrackedTracker<T> {
+    /// Returns a tracker with tracking disabled
+    #[inline]
+    pub fn new_disabled() -> Self {
+        Self {
+            enabled: false,
+            inner: T::new(),
+        }
+    }
+
+    /// Returns if tracking is enabled
+    #[inline]
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Enables or disables tracking. This should only be changed between
+    /// searches, `reset` keeps the setting.
+    #[inline]
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    /// Returns the inner tracker
+    #[inline]
+    pub fn inner(&self) -> &T {
+        &self.inner
+    }
+
+    #[inline]
+    pub(crate) fn inner_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+impl<T: Tracker> Tracker for MaybeTrackedTracker<T> {
+    /// Whether tracking is enabled and the inner snapshot
+    type Snapshot = (bool, T::Snapshot);
+
+    const IS_NOOP: bool = T::IS_NOOP;
+    const NEW: Self = Self {
+        enabled: true,
+        inner: T::NEW,
+    };
+
+    #[inline]
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        (self.enabled, self.inner.snapshot())
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        self.enabled = snapshot.0;
+        self.inner.restore(snapshot.1);
+    }
+
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        self.inner.proven_prefix()
+    }
+}
+
+/// A `Tracker` that keeps `T` on the heap, so that large nested trackers do not
+/// take up space in every search frame. The box is only allocated once the
+/// tracker is first used by a comparison, so searches that never compare do
+/// not allocate, and `reset` keeps the allocation for reuse. No-op trackers are
+/// never allocated. See [crate::TreeOrdBoxed].
+#[cfg(feature = "alloc")]
+pub struct BoxedTracker<T: Tracker>(Option<alloc::boxed::Box<T>>);
+
+#[cfg(feature = "alloc")]
+impl<T: Tracker + Clone> Clone for BoxedTracker<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Tracker + fmt::Debug> fmt::Debug for BoxedTracker<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("BoxedTracker").field(&self.0).finish()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Tracker> Default for BoxedTracker<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Tracker> BoxedTracker<T> {
+    /// Returns if the inner tracker has been allocated
+    #[inline]
+    pub fn is_allocated(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Returns the inner tracker if it has been allocated
+    #[inline]
+    pub fn get(&self) -> Option<&T> {
+        self.0.as_deref()
+    }
+
+    /// Returns the inner tracker, allocating it if needed
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut T {
+        self.0
+            .get_or_insert_with(|| alloc::boxed::Box::new(T::new()))
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T: Tracker> Tracker for BoxedTracker<T> {
+    /// The inner snapshot, or `None` if the inner tracker was not allocated
+    type Snapshot = Option<T::Snapshot>;
+
+    const IS_NOOP: bool = T::IS_NOOP;
+    const NEW: Self = Self(None);
+
+    #[inline]
+    fn reset(&mut self) {
+        if let Some(t) = self.0.as_mut() {
+            t.reset();
+        }
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        self.0.as_ref().map(|t| t.snapshot())
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        match snapshot {
+            Some(snapshot) => self.get_mut().restore(snapshot),
+            None => self.reset(),
+        }
+    }
+
+    #[inline]
+    fn proven_prefix(&self) -> Option<usize> {
+        match self.0.as_ref() {
+            Some(t) => t.proven_prefix(),
+            None => T::new().proven_prefix(),
+        }
+    }
+}
+
+/// Returns the `TypeId` of `T` even if it is not `'static`. Types that differ
+/// only in lifetimes get the same `TypeId`, so this can only be used for
+/// checking against types that have no lifetimes.
+#[cfg(not(feature = "nightly-specialization"))]
+#[inline]
+fn non_static_type_id<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn get_type_id(&self) -> TypeId
+        where
+            Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        #[inline]
+        fn get_type_id(&self) -> TypeId
+        where
+            Self: 'static,
+        {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+    // Safety: only the lifetime bound of the trait object is changed. The
+    // trait object is never stored, and `get_type_id` only uses the lifetimes
+    // to call `TypeId::of`, which never touches any data.
+    let phantom: &(dyn NonStaticAny + 'static) =
+        unsafe { core::mem::transmute::<&dyn NonStaticAny, _>(&phantom) };
+    phantom.get_type_id()
+}
+
+/// Returns the arguments of `[T]::tree_cmp` as their `[u8]` versions if `T` is
+/// `u8`. The compiler can fold the check into a constant.
+#[cfg(not(feature = "nightly-specialization"))]
+#[inline]
+#[allow(clippy::type_complexity)]
+pub(crate) fn as_bytes_tree_cmp<'a, T: TreeOrd>(
+    lhs: &'a [T],
+    rhs: &'a [T],
+    tracker: &'a mut LexicographicTracker<T>,
+) -> Option<(&'a [u8], &'a [u8], &'a mut LexicographicTracker<u8>)> {
+    if non_static_type_id::<T>() == TypeId::of::<u8>() {
+        // Safety: `u8` has no lifetimes, so `T` is exactly `u8` and these casts
+        // are all between identical types
+        unsafe {
+            Some((
+                &*(lhs as *const [T] as *const [u8]),
+                &*(rhs as *const [T] as *const [u8]),
+                &mut *(tracker as *mut LexicographicTracker<T> as *mut LexicographicTracker<u8>),
+            ))
+        }
+    } else {
+        None
+    }
+}
+
+/// The `TreeOrd` implementation of `[T]`, which is generic over the index type
+/// of the tracker. This can be used by `TreeOrd` impls for sequence types.
+#[inline]
+pub fn lexicographic_tree_cmp<T: TreeOrd, I: TrackerIndex>(
+    lhs: &[T],
+    rhs: &[T],
+    tracker: &mut LexicographicTracker<T, I>,
+) -> Ordering {
+    lexicographic_tree_cmp_impl::<T, I, false>(lhs, rhs, tracker)
+}
+
+/// The `TreeOrd::tree_cmp_hereditary` implementation of `[T]`
+#[inline]
+pub fn lexicographic_tree_cmp_hereditary<T: TreeOrd, I: TrackerIndex>(
+    lhs: &[T],
+    rhs: &[T],
+    tracker: &mut LexicographicTracker<T, I>,
+) -> Ordering {
+    lexicographic_tree_cmp_impl::<T, I, true>(lhs, rhs, tracker)
+}
+
+/// The `TreeEq` implementation of `[T]`. The lengths are checked first, and
+/// then only the elements after the proven prefix are compared.
+#[inline]
+pub fn lexicographic_tree_eq<T: TreeOrd, I: TrackerIndex>(
+    lhs: &[T],
+    rhs: &[T],
+    tracker: &LexicographicTracker<T, I>,
+) -> bool {
+    if lhs.len() != rhs.len() {
+        return false
+    }
+    let start = min(tracker.proven_prefix_len(), lhs.len());
+    stats::record(start);
+    lhs[start..] == rhs[start..]
+}
+
+/// Returns the index where the keys of a comparison that returned `res`
+/// diverged, given the length of the left hand side. Every lexicographic
+/// comparison records that index as the bound on the side of `res`, or the
+/// bound was already that index if the comparison diverged right at the proven
+/// prefix.
+#[inline]
+pub(crate) fn lexicographic_divergence<T: TreeOrd, I: TrackerIndex>(
+    res: Ordering,
+    lhs_len: usize,
+    tracker: &LexicographicTracker<T, I>,
+) -> usize {
+    match res {
+        Less => tracker.max_eq_len(),
+        Equal => lhs_len,
+        Greater => tracker.min_eq_len(),
+    }
+}
+
+/// Records that the keys diverged at element `i` with `res`
+#[inline]
+pub(crate) fn lexicographic_diverged<T: TreeOrd, I: TrackerIndex>(
+    i: usize,
+    res: Ordering,
+    tracker: &mut LexicographicTracker<T, I>,
+) -> Ordering {
+    if res == Less {
+        tracker.set_max_eq_len(i);
+    } else {
+        tracker.set_min_eq_len(i);
+    }
+    res
+}
+
+#[inline]
+fn lexicographic_tree_cmp_impl<T: TreeOrd, I: TrackerIndex, const HEREDITARY: bool>(
+    lhs: &[T],
+    rhs: &[T],
+    tracker: &mut LexicographicTracker<T, I>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    stats::record(start);
+    let end = min(lhs.len(), rhs.len());
+    if start >= end {
+        return lexicographic_len_cmp::<T, I, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+    }
+    let len = end.wrapping_sub(start);
+    // enable bound check elmination in the compiler
+    let x = &lhs[start..end];
+    let y = &rhs[start..end];
+    // unroll first iter, the subtracker may already have state for the `start`
+    // element
+    match x[0].tree_cmp(&y[0], tracker.subtracker()) {
+        Less => return Less,
+        Equal => (),
+        Greater => return Greater,
+    }
+    if <T as TreeOrd>::Tracker::IS_NOOP {
+        // the elements are compared in groups of 4 with known lengths, so that
+        // the index arithmetic and bounds checks are only done once per group
+        let mut j: usize = 1;
+        for (xs, ys) in x[1..].chunks_exact(4).zip(y[1..].chunks_exact(4)) {
+            for k in 0..4 {
+                let res = xs[k].cmp(&ys[k]);
+                if res != Equal {
+                    return lexicographic_diverged(
+                        start.wrapping_add(j).wrapping_add(k),
+                        res,
+                        tracker,
+                    )
+                }
+            }
+            j = j.wrapping_add(4);
+        }
+        for j in j..len {
+            let res = x[j].cmp(&y[j]);
+            if res != Equal {
+                return lexicographic_diverged(start.wrapping_add(j), res, tracker)
+            }
+        }
+        return lexicographic_len_cmp::<T, I, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+    }
+    // If an element diverges at the index where the next comparison will
+    // start, the subtracker is moved to it so that it starts warm. This is
+    // only possible up to the larger of the bounds, and uses a separate
+    // subtracker so that the current one is not lost if the element is equal.
+    let warm_end = if <T as TreeOrd>::Tracker::IS_NOOP {
+        start
+    } else {
+        max(tracker.min_eq_len(), tracker.max_eq_len())
+    };
+    for j in 1..len {
+        let i = j.wrapping_add(start);
+        if i <= warm_end {
+            let mut subtracker = <T as TreeOrd>::Tracker::new();
+            match x[j].tree_cmp(&y[j], &mut subtracker) {
+                Less => {
+                    tracker.set_max_eq_len(i);
+                    if tracker.min_eq_len() >= i {
+                        tracker.set_subtracker(subtracker);
+                    }
+                    return Less
+                }
+                Equal => (),
+                Greater => {
+                    tracker.set_min_eq_len(i);
+                    if tracker.max_eq_len() >= i {
+                        tracker.set_subtracker(subtracker);
+                    }
+                    return Greater
+                }
+            }
+            continue
+        }
+        match x[j].cmp(&y[j]) {
+            Less => {
+                tracker.set_max_eq_len(i);
+                return Less
+            }
+            Equal => (),
+            Greater => {
+                tracker.set_min_eq_len(i);
+                return Greater
+            }
+        }
+    }
+    lexicographic_len_cmp::<T, I, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+}
+
+/// The same as `lexicographic_tree_cmp_impl` but comparing from the ends of the
+/// slices, so that the tracker tracks the common suffix length
+#[inline]
+pub(crate) fn suffix_tree_cmp<T: TreeOrd, const HEREDITARY: bool>(
+    lhs: &[T],
+    rhs: &[T],
+    tracker: &mut LexicographicTracker<T>,
+) -> Ordering {
+    let start = tracker.proven_prefix_len();
+    stats::record(start);
+    let end = min(lhs.len(), rhs.len());
+    if start >= end {
+        return lexicographic_len_cmp::<T, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+    }
+    let x = &lhs[..lhs.len().wrapping_sub(start)];
+    let y = &rhs[..rhs.len().wrapping_sub(start)];
+    let mut pairs = x.iter().rev().zip(y.iter().rev());
+    // the subtracker may already have state for the `start` element
+    if let Some((a, b)) = pairs.next() {
+        match a.tree_cmp(b, tracker.subtracker()) {
+            Less => return Less,
+            Equal => (),
+            Greater => return Greater,
+        }
+    }
+    let warm_end = if <T as TreeOrd>::Tracker::IS_NOOP {
+        start
+    } else {
+        max(tracker.min_eq_len(), tracker.max_eq_len())
+    };
+    for (j, (a, b)) in pairs.enumerate() {
+        let i = start.wrapping_add(1).wrapping_add(j);
+        let res = if i <= warm_end {
+            let mut subtracker = <T as TreeOrd>::Tracker::new();
+            let res = a.tree_cmp(b, &mut subtracker);
+            if res != Equal {
+                lexicographic_diverged(i, res, tracker);
+                if min(tracker.min_eq_len(), tracker.max_eq_len()) == i {
+                    tracker.set_subtracker(subtracker);
+                }
+                return res
+            }
+            res
+        } else {
+            a.cmp(b)
+        };
+        if res != Equal {
+            return lexicographic_diverged(i, res, tracker)
+        }
+    }
+    lexicographic_len_cmp::<T, usize, HEREDITARY>(lhs.len(), rhs.len(), tracker)
+}
+
+/// Compares the lengths of sequences whose common prefixes are equal. The
+/// shorter sequence is a prefix of the longer one, so all of its elements are
+/// proven equal. In hereditary mode, `Equal` means that the whole sequences are
+/// equal and is recorded like a `Greater` with all elements equal, which is
+/// only sound with the key as `lhs` (see `TreeOrd::tree_cmp_hereditary`).
+#[inline]
+pub(crate) fn lexicographic_len_cmp<T: TreeOrd, I: TrackerIndex, const HEREDITARY: bool>(
+    lhs_len: usize,
+    rhs_len: usize,
+    tracker: &mut LexicographicTracker<T, I>,
+) -> Ordering {
+    let end = min(lhs_len, rhs_len);
+    match lhs_len.cmp(&rhs_len) {
+        Less => {
+            tracker.set_max_eq_len(end);
+            Less
+        }
+        Equal => {
+            if HEREDITARY {
+                tracker.set_min_eq_len(end);
+            }
+            Equal
+        }
+        Greater => {
+            tracker.set_min_eq_len(end);
+            Greater
+        }
+    }
+}
+
+/// The tracker of `Result<T, E>`. The subtrackers are materialized on first
+/// use. In a valid search the query is the same on every comparison, so only
+/// the subtracker of the side that the query is on can ever be used (`Ok` and
+/// `Err` values are ordered without comparing their contents). This means at
+/// most one of the subtrackers needs to be stored, and if the other side is
+/// ever used the tracker switches to a fresh subtracker of that side.
+pub enum ResultTracker<T: TreeOrd, E: TreeOrd> {
+    Neither,
+    Ok(<T as TreeOrd>::Tracker),
+    Err(<E as TreeOrd>::Tracker),
+}
+
+impl<T: TreeOrd, E: TreeOrd> ResultTracker<T, E> {
+    /// Returns the `Ok` subtracker, materializing it if needed
+    #[inline]
+    pub fn ok(&mut self) -> &mut <T as TreeOrd>::Tracker {
+        if !matches!(self, Self::Ok(_)) {
+            *self = Self::Ok(<T as TreeOrd>::Tracker::new());
+        }
+        match self {
+            Self::Ok(t) => t,
+            _ => tree_cmp_unreachable(),
+        }
+    }
+
+    /// Returns the `Err` subtracker, materializing it if needed
+    #[inline]
+    pub fn err(&mut self) -> &mut <E as TreeOrd>::Tracker {
+        if !matches!(self, Self::Err(_)) {
+            *self = Self::Err(<E as TreeOrd>::Tracker::new());
+        }
+        match self {
+            Self::Err(e) => e,
+            _ => tree_cmp_unreachable(),
+        }
+    }
+}
+
+impl<T: TreeOrd, E: TreeOrd> Clone for ResultTracker<T, E>
+where
+    T::Tracker: Clone,
+    E::Tracker: Clone,
+{
+    fn clone(&self) -> Self {
+        match self {
+            Self::Neither => Self::Neither,
+            Self::Ok(t) => Self::Ok(t.clone()),
+            Self::Err(e) => Self::Err(e.clone()),
+        }
+    }
+}
+
+impl<T: TreeOrd, E: TreeOrd> fmt::Debug for ResultTracker<T, E>
+where
+    T::Tracker: fmt::Debug,
+    E::Tracker: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Neither => write!(f, "Neither"),
+            Self::Ok(t) => f.debug_tuple("Ok").field(t).finish(),
+            Self::Err(e) => f.debug_tuple("Err").field(e).finish(),
+        }
+    }
+}
+
+impl<T: TreeOrd, E: TreeOrd> Default for ResultTracker<T, E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The snapshot of a [ResultTracker]
+pub enum ResultSnapshot<T: TreeOrd, E: TreeOrd> {
+    Neither,
+    Ok(<<T as TreeOrd>::Tracker as Tracker>::Snapshot),
+    Err(<<E as TreeOrd>::Tracker as Tracker>::Snapshot),
 }
 
 impl<T: TreeOrd, E: TreeOrd> Tracker for ResultTracker<T, E> {
+    type Snapshot = ResultSnapshot<T, E>;
+
     const IS_NOOP: bool = <T as TreeOrd>::Tracker::IS_NOOP && <E as TreeOrd>::Tracker::IS_NOOP;
+    const NEW: Self = Self::Neither;
 
-    fn new() -> Self {
-        Self {
-            t: <T as TreeOrd>::Tracker::new(),
-            e: <E as TreeOrd>::Tracker::new(),
+    #[inline]
+    fn reset(&mut self) {
+        *self = Self::Neither;
+    }
+
+    fn snapshot(&self) -> Self::Snapshot {
+        match self {
+            Self::Neither => ResultSnapshot::Neither,
+            Self::Ok(t) => ResultSnapshot::Ok(t.snapshot()),
+            Self::Err(e) => ResultSnapshot::Err(e.snapshot()),
+        }
+    }
+
+    fn restore(&mut self, snapshot: Self::Snapshot) {
+        match snapshot {
+            ResultSnapshot::Neither => *self = Self::Neither,
+            ResultSnapshot::Ok(t) => self.ok().restore(t),
+            ResultSnapshot::Err(e) => self.err().restore(e),
         }
     }
 }
 
+/// The maximum size of tuples whose fields all have no-op trackers that are
+/// compared with plain `Ord`
+const SMALL_NOOP_SIZE: usize = 8;
+
 macro_rules! tuple_recast {
     ($tuple_name:ident, $tracker_name:ident, $i_len:expr, $($i:tt $s:tt $t:tt),+) => {
         pub struct $tracker_name<$($t: TreeOrd,)+> {
@@ -61,13 +1269,95 @@ macro_rules! tuple_recast {
             $(pub $s: <$t as TreeOrd>::Tracker,)+
         }
 
+        impl<$($t: TreeOrd,)+> Clone for $tracker_name<$($t,)+>
+        where
+            $(<$t as TreeOrd>::Tracker: Clone,)+
+        {
+            fn clone(&self) -> Self {
+                Self {
+                    min_eq_len: self.min_eq_len,
+                    max_eq_len: self.max_eq_len,
+                    $($s: self.$s.clone(),)+
+                }
+            }
+        }
+
+        impl<$($t: TreeOrd,)+> fmt::Debug for $tracker_name<$($t,)+>
+        where
+            $(<$t as TreeOrd>::Tracker: fmt::Debug,)+
+        {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($tracker_name))
+                    .field("min_eq_len", &self.min_eq_len)
+                    .field("max_eq_len", &self.max_eq_len)
+                    $(.field(stringify!($s), &self.$s))+
+                    .finish()
+            }
+        }
+
+        impl<$($t: TreeOrd,)+> Default for $tracker_name<$($t,)+> {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        impl<$($t: TreeOrd,)+> $tracker_name<$($t,)+> {
+            /// If all the fields have no-op trackers and the whole tuple is small,
+            /// a plain `Ord` comparison is cheaper than the lock-in bookkeeping
+            /// and the tracker is unused
+            const SMALL_NOOP: bool = $(<$t as TreeOrd>::Tracker::IS_NOOP &&)+
+                (size_of::<($($t,)+)>() <= SMALL_NOOP_SIZE);
+        }
+
         impl<$($t: TreeOrd,)+> Tracker for $tracker_name<$($t,)+> {
-            const IS_NOOP: bool = false;
+            const IS_NOOP: bool = Self::SMALL_NOOP;
+
+            /// `min_eq_len`, `max_eq_len`, and the subtracker snapshots
+            type Snapshot = (u8, u8, ($(<<$t as TreeOrd>::Tracker as Tracker>::Snapshot,)+));
+
+            const NEW: Self = Self {
+                min_eq_len: 0,
+                max_eq_len: 0,
+                $($s: <$t as TreeOrd>::Tracker::NEW,)+
+            };
+
+            #[inline]
+            fn reset(&mut self) {
+                self.min_eq_len = 0;
+                self.max_eq_len = 0;
+                // only the first subtracker is used without being reinitialized
+                // first, see `tree_cmp`
+                self.a.reset();
+            }
+
+            fn snapshot(&self) -> Self::Snapshot {
+                (self.min_eq_len, self.max_eq_len, ($(self.$s.snapshot(),)+))
+            }
+
+            fn restore(&mut self, snapshot: Self::Snapshot) {
+                let (min_eq_len, max_eq_len, ($($s,)+)) = snapshot;
+                self.min_eq_len = min_eq_len;
+                self.max_eq_len = max_eq_len;
+                $(self.$s.restore($s);)+
+            }
+        }
 
-            fn new() -> Self {
+        impl<$($t: TreeOrd,)+> $tracker_name<$($t,)+> {
+            /// Returns a tracker that starts with the first `n` fields known to
+            /// be equal between the query and every key that will be compared.
+            /// Those fields are never compared.
+            ///
+            /// If the fields are not actually equal, the results of comparisons
+            /// are unspecified (but memory safe).
+            ///
+            /// # Panics
+            ///
+            /// If `n` is greater than the number of fields
+            pub fn with_locked_fields(n: u8) -> Self {
+                assert!(usize::from(n) <= $i_len);
                 Self {
-                    min_eq_len: 0,
-                    max_eq_len: 0,
+                    min_eq_len: n,
+                    max_eq_len: n,
                     $($s: <$t as TreeOrd>::Tracker::new(),)+
                 }
             }
@@ -76,12 +1366,44 @@ macro_rules! tuple_recast {
         impl<$($t: TreeOrd,)+> TreeOrd<Self> for ($($t,)+) {
             type Tracker = $tracker_name<$($t,)+>;
 
+            #[inline]
             fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                Self::Tracker::tree_cmp_impl::<false>(self, rhs, tracker)
+            }
+
+            #[inline]
+            fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+                Self::Tracker::tree_cmp_impl::<true>(self, rhs, tracker)
+            }
+        }
+
+        impl<$($t: TreeOrd,)+> $tracker_name<$($t,)+> {
+            /// The `TreeOrd` implementation of the tuple. If `HEREDITARY`, the
+            /// fields are compared with `tree_cmp_hereditary`, and a whole
+            /// `Equal` is recorded like a `Greater` with all fields equal.
+            fn tree_cmp_impl<const HEREDITARY: bool>(
+                lhs: &($($t,)+),
+                rhs: &($($t,)+),
+                tracker: &mut Self,
+            ) -> Ordering {
+                if Self::SMALL_NOOP {
+                    return lhs.cmp(rhs)
+                }
                 let mut start = min(tracker.min_eq_len, tracker.max_eq_len);
+                stats::record(usize::from(start));
                 match start {
                     $(
                         $i => {
-                            match self.$i.tree_cmp(&rhs.$i, &mut tracker.$s) {
+                            // no-op subtrackers are skipped so that the plain
+                            // `Ord` can be inlined without the tracker plumbing
+                            let res = if <$t as TreeOrd>::Tracker::IS_NOOP {
+                                lhs.$i.cmp(&rhs.$i)
+                            } else if HEREDITARY {
+                                lhs.$i.tree_cmp_hereditary(&rhs.$i, &mut tracker.$s)
+                            } else {
+                                lhs.$i.tree_cmp(&rhs.$i, &mut tracker.$s)
+                            };
+                            match res {
                                 Less => {
                                     return Less
                                 }
@@ -105,8 +1427,17 @@ macro_rules! tuple_recast {
                                 // will usually be locking in on the next `tree_cmp` call
                                 // or two (in contrast to using just `cmp` here which would
                                 // miss a bound improvement for the next initial `start`)
-                                tracker.$s = <$t as TreeOrd>::Tracker::new();
-                                match self.$i.tree_cmp(&rhs.$i, &mut tracker.$s) {
+                                let res = if <$t as TreeOrd>::Tracker::IS_NOOP {
+                                    lhs.$i.cmp(&rhs.$i)
+                                } else {
+                                    tracker.$s = <$t as TreeOrd>::Tracker::new();
+                                    if HEREDITARY {
+                                        lhs.$i.tree_cmp_hereditary(&rhs.$i, &mut tracker.$s)
+                                    } else {
+                                        lhs.$i.tree_cmp(&rhs.$i, &mut tracker.$s)
+                                    }
+                                };
+                                match res {
                                     Less => {
                                         tracker.max_eq_len = $i;
                                         return Less
@@ -119,7 +1450,12 @@ macro_rules! tuple_recast {
                                 }
                             }
                         )+
-                        $i_len => return Equal,
+                        $i_len => {
+                            if HEREDITARY {
+                                tracker.min_eq_len = $i_len;
+                            }
+                            return Equal
+                        }
                         _ => tree_cmp_unreachable(),
                     }
                 }
@@ -147,4 +1483,8 @@ impl<A: TreeOrd> TreeOrd<Self> for (A,) {
     fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
         self.0.tree_cmp(&rhs.0, tracker)
     }
+
+    fn tree_cmp_hereditary(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.0.tree_cmp_hereditary(&rhs.0, tracker)
+    }
 }
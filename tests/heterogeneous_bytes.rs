@@ -0,0 +1,120 @@
+//! Tests of searching `TreeOrdVec` and `TreeOrdBytes` spaces with queries of
+//! the other byte types. This has its own test binary because it counts the
+//! allocations of the current thread with a global allocator.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    cmp::Ordering,
+};
+
+use rand_xoshiro::{
+    rand_core::{RngCore, SeedableRng},
+    Xoshiro128StarStar,
+};
+use tree_ord::{search::*, TreeOrdBytes, TreeOrdVec};
+
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCS: Cell<u64> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCS.try_with(|c| c.set(c.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocs() -> u64 {
+    ALLOCS.with(|c| c.get())
+}
+
+/// Returns the sequence of probed indexes and their orderings of a binary
+/// search of `space` for `query`, which is the same for the homogeneous and
+/// heterogeneous comparisons if they compare the same bytes
+fn probes<K, Q>(space: &[K], query: &Q) -> Vec<(usize, Ordering)>
+where
+    K: tree_ord::TreeOrd<Q>,
+    Q: ?Sized,
+{
+    let mut probes = vec![];
+    let mut f = tree_search_fn(query);
+    tree_binary_search_by(space, |k: &K| {
+        let res = f(k);
+        // recover the index from the address
+        let i = (k as *const K as usize - space.as_ptr() as usize) / size_of::<K>();
+        probes.push((i, res));
+        res
+    })
+    .ok();
+    probes
+}
+
+#[test]
+fn heterogeneous_bytes() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<Vec<u8>> = vec![];
+    for pad in [0, 30, 31, 32, 33, 64, 100] {
+        for _ in 0..64 {
+            let mut v = vec![b'x'; pad];
+            for _ in 0..(rng.next_u32() % 12) {
+                v.push(b'a' + (rng.next_u32() % 4) as u8);
+            }
+            space.push(v);
+        }
+    }
+    space.sort();
+    space.dedup();
+    let mut queries = space.clone();
+    for v in &space {
+        let mut v = v.clone();
+        v.push(b'b');
+        queries.push(v.clone());
+        v.truncate(v.len() / 2);
+        queries.push(v);
+    }
+    let owned: Vec<TreeOrdVec> = space.iter().map(|v| TreeOrdVec(v.clone())).collect();
+    let borrowed: Vec<TreeOrdBytes> = space.iter().map(|v| TreeOrdBytes(v)).collect();
+
+    for query in &queries {
+        let expected = space.binary_search(query).is_ok();
+        let homogeneous = probes(&owned, &TreeOrdVec(query.clone()));
+        assert_eq!(probes(&owned, &TreeOrdBytes(query)), homogeneous);
+        assert_eq!(probes(&owned, query.as_slice()), homogeneous);
+        assert_eq!(probes(&borrowed, &TreeOrdBytes(query)), homogeneous);
+        assert_eq!(probes(&borrowed, &TreeOrdVec(query.clone())), homogeneous);
+        assert_eq!(probes(&borrowed, query.as_slice()), homogeneous);
+        assert_eq!(
+            homogeneous.last().map(|(_, res)| *res) == Some(Ordering::Equal),
+            expected
+        );
+    }
+
+    // a homogeneous query has to copy the bytes
+    let before = allocs();
+    let _ = tree_binary_search(&owned, &owned[owned.len() - 1].clone());
+    assert_eq!(allocs(), before + 1);
+
+    // searching an owned space with borrowed queries does not allocate
+    let before = allocs();
+    for query in &queries {
+        let expected = space.binary_search(query);
+        let lower = space.partition_point(|k| k < query);
+        let upper = space.partition_point(|k| k <= query);
+        assert_eq!(tree_binary_search(&owned, query.as_slice()), expected);
+        assert_eq!(tree_binary_search(&owned, &TreeOrdBytes(query)), expected);
+        assert_eq!(tree_lower_bound(&owned, query.as_slice()), lower);
+        assert_eq!(tree_upper_bound(&owned, &TreeOrdBytes(query)), upper);
+        assert_eq!(tree_equal_range(&owned, query.as_slice()), lower..upper);
+    }
+    assert_eq!(allocs(), before);
+}
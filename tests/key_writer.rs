@@ -0,0 +1,151 @@
+//! Tests of `KeyWriter` that also run with `--no-default-features`
+
+use core::cmp::Ordering;
+
+use rand_xoshiro::{
+    rand_core::{RngCore, SeedableRng},
+    Xoshiro128StarStar,
+};
+use tree_ord::key::{encode_f32, encode_f64, CapacityError, KeyWriter};
+
+type Push = fn(&mut KeyWriter) -> Result<usize, CapacityError>;
+
+/// Every field type along with its expected encoding
+#[allow(clippy::type_complexity)]
+const FIELDS: &[(Push, &[u8])] = &[
+    (|k| k.push_u8(0xab), &[0xab]),
+    (|k| k.push_i8(-1), &[0x7f]),
+    (|k| k.push_u16(0x1234), &[0x12, 0x34]),
+    (|k| k.push_i16(1), &[0x80, 0x01]),
+    (|k| k.push_u32(7), &[0, 0, 0, 7]),
+    (|k| k.push_i32(i32::MIN), &[0, 0, 0, 0]),
+    (|k| k.push_u64(u64::MAX), &[0xff; 8]),
+    (|k| k.push_i64(0), &[0x80, 0, 0, 0, 0, 0, 0, 0]),
+    (|k| k.push_u128(1), &[
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+    ]),
+    (|k| k.push_i128(-1), &[
+        0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff,
+    ]),
+    (|k| k.push_f32(-0.0), &[0x7f, 0xff, 0xff, 0xff]),
+    (|k| k.push_f64(1.0), &[0xbf, 0xf0, 0, 0, 0, 0, 0, 0]),
+    (|k| k.push_bool(true), &[1]),
+    (|k| k.push_bytes(&[]), &[0, 1]),
+    (|k| k.push_bytes(&[0, 5, 0]), &[0, 0xff, 5, 0, 0xff, 0, 1]),
+    (|k| k.push_str("ab"), &[b'a', b'b', 0, 1]),
+    (|k| k.push_option(None::<u8>, |k, x| k.push_u8(x)), &[0]),
+    (|k| k.push_option(Some("a"), |k, x| k.push_str(x)), &[
+        1, b'a', 0, 1,
+    ]),
+    (|k| k.push_desc(|k| k.push_u16(1)), &[0xff, 0xfe]),
+    (|k| k.push_desc(|k| k.push_str("a\0")), &[
+        !b'a', 0xff, 0, 0xff, 0xfe,
+    ]),
+];
+
+#[test]
+fn key_writer_capacity() {
+    let mut buf = [0u8; 32];
+    for (i, (push, expected)) in FIELDS.iter().enumerate() {
+        let n = expected.len();
+        // exact fit
+        let mut key = KeyWriter::new(&mut buf[..n]);
+        assert_eq!(push(&mut key), Ok(n), "{i}");
+        assert_eq!(key.as_bytes(), *expected, "{i}");
+        // overflow, nothing of the field is written
+        let mut key = KeyWriter::new(&mut buf[..(n - 1)]);
+        assert_eq!(push(&mut key), Err(CapacityError), "{i}");
+        assert!(key.is_empty());
+        // after other fields, at exact fit and overflow
+        let mut key = KeyWriter::new(&mut buf[..(n + 2)]);
+        key.push_u16(0x0102).unwrap();
+        assert_eq!(push(&mut key), Ok(n + 2), "{i}");
+        assert_eq!(&key.as_bytes()[2..], *expected, "{i}");
+        let mut key = KeyWriter::new(&mut buf[..(n + 1)]);
+        key.push_u16(0x0102).unwrap();
+        assert_eq!(push(&mut key), Err(CapacityError), "{i}");
+        assert_eq!(key.as_bytes(), &[1, 2]);
+        // the writer is still usable after an error
+        if n > 1 {
+            assert_eq!(key.push_bool(false), Ok(3));
+        }
+        key.clear();
+        assert!(key.is_empty());
+    }
+
+    // a `Some` whose value does not fit removes its tag
+    let mut buf = [0u8; 3];
+    let mut key = KeyWriter::new(&mut buf);
+    assert_eq!(
+        key.push_option(Some(1u32), |k, x| k.push_u32(x)),
+        Err(CapacityError)
+    );
+    assert_eq!(key.len(), 0);
+    assert_eq!(
+        key.push_desc(|k| {
+            k.push_u8(1)?;
+            k.push_u32(2)
+        }),
+        Err(CapacityError)
+    );
+    assert_eq!(key.len(), 0);
+    assert_eq!(key.push_desc(|k| k.push_u8(1)), Ok(1));
+    assert_eq!(key.into_bytes(), &[0xfe]);
+}
+
+#[test]
+fn key_writer_order() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let words: [&[u8]; 6] = [b"", b"a", b"ab", b"a\0", b"\0", b"\xff"];
+    let mut values = [(&b""[..], 0i16, 0.0f32, false); 64];
+    for v in values.iter_mut() {
+        *v = (
+            words[(rng.next_u32() % 6) as usize],
+            [i16::MIN, -1, 0, 1][(rng.next_u32() % 4) as usize],
+            [f32::NEG_INFINITY, -0.0, 0.0, f32::NAN][(rng.next_u32() % 4) as usize],
+            (rng.next_u32() & 1) == 0,
+        );
+    }
+    let mut bufs = [[0u8; 16]; 64];
+    let mut lens = [0usize; 64];
+    for (i, v) in values.iter().enumerate() {
+        let mut key = KeyWriter::new(&mut bufs[i]);
+        key.push_bytes(v.0).unwrap();
+        key.push_desc(|k| k.push_i16(v.1)).unwrap();
+        key.push_f32(v.2).unwrap();
+        lens[i] = key.push_bool(v.3).unwrap();
+    }
+    for i in 0..values.len() {
+        for j in 0..values.len() {
+            let (x, y) = (&values[i], &values[j]);
+            let expected =
+                x.0.cmp(y.0)
+                    .then(x.1.cmp(&y.1).reverse())
+                    .then(x.2.total_cmp(&y.2))
+                    .then(x.3.cmp(&y.3));
+            assert_eq!(bufs[i][..lens[i]].cmp(&bufs[j][..lens[j]]), expected);
+        }
+    }
+    assert_eq!(encode_f32(1.0).cmp(&encode_f32(f32::NAN)), Ordering::Less);
+    assert_eq!(encode_f64(-1.0).cmp(&encode_f64(-2.0)), Ordering::Greater);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn key_writer_matches_builder() {
+    use tree_ord::key::KeyBuilder;
+
+    let mut buf = [0u8; 64];
+    let mut key = KeyWriter::new(&mut buf);
+    key.push_str("a\0b").unwrap();
+    key.push_option(Some(-3i64), |k, x| k.push_desc(|k| k.push_i64(x)))
+        .unwrap();
+    key.push_f64(f64::NAN).unwrap();
+    let expected = KeyBuilder::new()
+        .push_str("a\0b")
+        .push_option(Some(-3i64), |k, x| k.push_desc(|k| k.push_i64(x)))
+        .push_f64(f64::NAN)
+        .finish();
+    assert_eq!(key.as_bytes(), expected.as_slice());
+}
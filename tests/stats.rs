@@ -0,0 +1,63 @@
+//! Tests of the process wide counters of the `stats` module. This has its own
+//! test binary so that no other tests change the counters concurrently.
+#![cfg(feature = "stats")]
+
+use tree_ord::{
+    corpus::{ClusteredBytes, NestedSlices},
+    search::*,
+    stats,
+    testing::CountingOrd as COrd,
+    TreeOrd, TreeOrdBytes,
+};
+
+#[test]
+fn stats() {
+    stats::reset();
+    let before = stats::snapshot();
+    assert_eq!(before, stats::Stats::default());
+
+    let space: Vec<Vec<Vec<COrd<u64>>>> = NestedSlices {
+        count: 1 << 12,
+        depth: 2,
+        widths: vec![4],
+        max_len: 1 << 5,
+        plateau_levels: vec![4, 8],
+    }
+    .generate(0);
+    for query in &space {
+        tree_binary_search(&space, query).unwrap();
+    }
+    let after_slices = stats::snapshot();
+    assert!(after_slices.comparisons > before.comparisons);
+    assert!(after_slices.skipped > before.skipped);
+
+    let space = ClusteredBytes {
+        count: 1 << 12,
+        max_len: 1 << 9,
+        plateau_levels: vec![128, 255],
+    }
+    .generate(0);
+    for query in &space {
+        let mut tracker = TreeOrdBytes::tracker_with_known_prefix(0);
+        tree_binary_search_by(&space, |k| {
+            TreeOrdBytes(k).tree_cmp(&TreeOrdBytes(query), &mut tracker)
+        })
+        .unwrap();
+    }
+    let after_bytes = stats::snapshot();
+    assert!(after_bytes.comparisons > after_slices.comparisons);
+    assert!(after_bytes.skipped > after_slices.skipped);
+
+    let space: Vec<(u64, Vec<u8>)> = (0..256u64).map(|i| (i / 16, vec![i as u8; 8])).collect();
+    for query in &space {
+        tree_binary_search(&space, query).unwrap();
+    }
+    let after_tuples = stats::snapshot();
+    assert!(after_tuples.comparisons > after_bytes.comparisons);
+    assert!(after_tuples.skipped > after_bytes.skipped);
+
+    // nothing else runs in this binary, so the counters are exactly zero
+    // after a reset
+    stats::reset();
+    assert_eq!(stats::snapshot(), stats::Stats::default());
+}
@@ -1,12 +1,22 @@
 #![allow(clippy::needless_range_loop)]
 
-use std::{cell::RefCell, cmp::Ordering};
+use std::{
+    cell::RefCell,
+    cmp::{min, Ordering},
+};
 
 use rand_xoshiro::{
     rand_core::{RngCore, SeedableRng},
     Xoshiro128StarStar,
 };
-use tree_ord::{Tracker, TreeOrd, TreeOrdBytes, TreeOrdVec};
+use tree_ord::{
+    corpus::{ClusteredBytes, NestedSlices},
+    merge::*,
+    search::*,
+    testing::{inc_cmp_count, CountScope, CountingOrd as COrd},
+    PartialTreeOrd, Tracker, TreeOrd, TreeOrdBytes, TreeOrdBytesAdaptive, TreeOrdI128, TreeOrdStr,
+    TreeOrdString, TreeOrdU128, TreeOrdU16s, TreeOrdU32s, TreeOrdU64s, TreeOrdVec,
+};
 use Ordering::*;
 
 const N: u64 = 1 << 15; //1 << 16;
@@ -15,227 +25,272 @@ const N1: u64 = 1 << 5;
 const M: u64 = 1 << 16;
 const M1: u64 = 1 << 9;
 
-thread_local! {
-    pub static CMP_COUNT: RefCell<u64> = RefCell::new(0);
-}
-
-pub fn get_cmp_count() -> u64 {
-    CMP_COUNT.with(|f| *f.borrow())
-}
-
-pub fn inc_cmp_count() {
-    CMP_COUNT.with(|f| {
-        let x = f.borrow().checked_add(1).unwrap();
-        *f.borrow_mut() = x;
-    })
-}
-
-#[derive(Debug, Clone, PartialEq, Eq)]
-pub struct COrd(u64);
-
-#[allow(clippy::incorrect_partial_ord_impl_on_ord_type)]
-impl PartialOrd for COrd {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        inc_cmp_count();
-        self.0.partial_cmp(&other.0)
-    }
-}
-
-impl Ord for COrd {
-    fn cmp(&self, other: &Self) -> Ordering {
-        inc_cmp_count();
-        self.0.cmp(&other.0)
-    }
-}
-
-impl TreeOrd for COrd {
-    type Tracker = ();
-
-    fn tree_cmp(&self, rhs: &Self, _: &mut Self::Tracker) -> Ordering {
-        self.cmp(rhs)
-    }
-}
-
 #[test]
 fn tuples() {
-    let init = get_cmp_count();
-    type T2 = (COrd, COrd);
+    let scope = CountScope::new();
+    type T2 = (COrd<u64>, COrd<u64>);
     let t2: T2 = (COrd(32), COrd(48));
     let mut tracker = <T2 as TreeOrd>::Tracker::new();
     assert_eq!(t2.tree_cmp(&(COrd(8), COrd(64)), &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 1);
+    assert_eq!(scope.count(), 1);
     assert_eq!(t2.tree_cmp(&(COrd(48), COrd(64)), &mut tracker), Less);
-    assert_eq!(get_cmp_count(), init + 2);
+    assert_eq!(scope.count(), 2);
     assert_eq!(t2.tree_cmp(&(COrd(32), COrd(64)), &mut tracker), Less);
-    assert_eq!(get_cmp_count(), init + 4);
+    assert_eq!(scope.count(), 4);
     assert_eq!(t2.tree_cmp(&(COrd(32), COrd(16)), &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 6);
+    assert_eq!(scope.count(), 6);
     // after being bounded on both sides with a prefix of 32, only 1 `Ord`
     // call should be incurred
     assert_eq!(t2.tree_cmp(&(COrd(32), COrd(24)), &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 7);
+    assert_eq!(scope.count(), 7);
     assert_eq!(t2.tree_cmp(&(COrd(32), COrd(50)), &mut tracker), Less);
-    assert_eq!(get_cmp_count(), init + 8);
+    assert_eq!(scope.count(), 8);
     assert_eq!(t2.tree_cmp(&(COrd(32), COrd(48)), &mut tracker), Equal);
-    assert_eq!(get_cmp_count(), init + 9);
+    assert_eq!(scope.count(), 9);
     // in nonhereditary tree settings we can still be going down a tree, and `Equal`
     // doesn't constrain bounds so we can't increase the known prefix length
     assert_eq!(t2.tree_cmp(&(COrd(32), COrd(47)), &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 10);
+    assert_eq!(scope.count(), 10);
 
-    let init = get_cmp_count();
-    type T3 = (COrd, COrd, COrd);
+    let scope = CountScope::new();
+    type T3 = (COrd<u64>, COrd<u64>, COrd<u64>);
     let t3: T3 = (COrd(32), COrd(48), COrd(40));
     let mut tracker = <T3 as TreeOrd>::Tracker::new();
     assert_eq!(
         t3.tree_cmp(&(COrd(32), COrd(48), COrd(40)), &mut tracker),
         Equal
     );
-    assert_eq!(get_cmp_count(), init + 3);
+    assert_eq!(scope.count(), 3);
     assert_eq!(
         t3.tree_cmp(&(COrd(32), COrd(48), COrd(99)), &mut tracker),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 6);
+    assert_eq!(scope.count(), 6);
     assert_eq!(
         t3.tree_cmp(&(COrd(32), COrd(48), COrd(16)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 9);
+    assert_eq!(scope.count(), 9);
     assert_eq!(
         t3.tree_cmp(&(COrd(32), COrd(48), COrd(35)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 10);
+    assert_eq!(scope.count(), 10);
     assert_eq!(
         t3.tree_cmp(&(COrd(32), COrd(48), COrd(45)), &mut tracker),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 11);
+    assert_eq!(scope.count(), 11);
     assert_eq!(
         t3.tree_cmp(&(COrd(32), COrd(48), COrd(40)), &mut tracker),
         Equal
     );
-    assert_eq!(get_cmp_count(), init + 12);
+    assert_eq!(scope.count(), 12);
 }
 
 #[test]
 fn result() {
     #[derive(PartialEq, Eq, PartialOrd, Ord)]
-    struct COrd2(COrd);
+    struct COrd2(COrd<u64>);
     impl TreeOrd for COrd2 {
-        type Tracker = <COrd as TreeOrd>::Tracker;
+        type Tracker = <COrd<u64> as TreeOrd>::Tracker;
 
         fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
             self.0.tree_cmp(&rhs.0, tracker)
         }
     }
-    let init = get_cmp_count();
-    type T = Result<COrd, COrd2>;
+    let scope = CountScope::new();
+    type T = Result<COrd<u64>, COrd2>;
     let t: T = Err(COrd2(COrd(32)));
     let mut tracker = <T as TreeOrd>::Tracker::new();
     assert_eq!(t.tree_cmp(&Ok(COrd(32)), &mut tracker), Greater);
     assert_eq!(Err(0u8).cmp(&Ok(0u8)), Greater);
-    assert_eq!(get_cmp_count(), init);
+    assert_eq!(scope.count(), 0);
     assert_eq!(t.tree_cmp(&Err(COrd2(COrd(32))), &mut tracker), Equal);
-    assert_eq!(get_cmp_count(), init + 1);
+    assert_eq!(scope.count(), 1);
     assert_eq!(t.tree_cmp(&Ok(COrd(48)), &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 1);
+    assert_eq!(scope.count(), 1);
     assert_eq!(t.tree_cmp(&Err(COrd2(COrd(16))), &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 2);
+    assert_eq!(scope.count(), 2);
+}
+
+#[test]
+fn result_tracker() {
+    use std::mem::size_of;
+
+    use tree_ord::utils::{LexicographicTracker, ResultTracker};
+
+    // the same as `result` but with subtrackers that track
+    type T = Result<Vec<COrd<u64>>, Vec<COrd<u64>>>;
+    let t: T = Err(vec![COrd(32), COrd(8)]);
+    let mut tracker = <T as TreeOrd>::Tracker::new();
+    assert!(matches!(tracker, ResultTracker::Neither));
+    let scope = CountScope::new();
+    assert_eq!(t.tree_cmp(&Ok(vec![COrd(32)]), &mut tracker), Greater);
+    assert_eq!(scope.count(), 0);
+    assert!(matches!(tracker, ResultTracker::Neither));
+    assert_eq!(
+        t.tree_cmp(&Err(vec![COrd(32), COrd(4)]), &mut tracker),
+        Greater
+    );
+    assert_eq!(scope.count(), 2);
+    assert!(matches!(tracker, ResultTracker::Err(_)));
+    assert_eq!(
+        t.tree_cmp(&Err(vec![COrd(32), COrd(16)]), &mut tracker),
+        Less
+    );
+    assert_eq!(scope.count(), 4);
+    // the common prefix is now skipped
+    assert_eq!(
+        t.tree_cmp(&Err(vec![COrd(32), COrd(8)]), &mut tracker),
+        Equal
+    );
+    assert_eq!(scope.count(), 5);
+    assert_eq!(t.tree_cmp(&Ok(vec![COrd(48)]), &mut tracker), Greater);
+    assert_eq!(scope.count(), 5);
+    tracker.reset();
+    assert!(matches!(tracker, ResultTracker::Neither));
+
+    // searches with both sides in the space
+    let mut space: Vec<T> = gen_nested_vecs()
+        .into_iter()
+        .step_by(8)
+        .map(|mut v| {
+            if (v[0].len() % 2) == 0 {
+                Ok(v.pop().unwrap())
+            } else {
+                Err(v.pop().unwrap())
+            }
+        })
+        .collect();
+    space.sort();
+    for query in &space {
+        let found = tree_binary_search(&space, query).unwrap();
+        assert_eq!(space[found], *query);
+    }
+
+    // only one of the subtrackers is stored
+    assert!(
+        size_of::<<Result<Vec<u8>, Vec<u8>> as TreeOrd>::Tracker>()
+            < size_of::<(LexicographicTracker<u8>, LexicographicTracker<u8>)>()
+    );
+}
+
+#[test]
+fn lexicographic_tracker_size() {
+    use std::mem::size_of;
+
+    use tree_ord::utils::LexicographicTracker;
+
+    // no-op subtrackers take no space, so these are just the two lengths
+    assert_eq!(
+        size_of::<LexicographicTracker<u8>>(),
+        2 * size_of::<usize>()
+    );
+    assert_eq!(size_of::<LexicographicTracker<u8, u8>>(), 2);
+    assert_eq!(size_of::<<TreeOrdU128 as TreeOrd>::Tracker>(), 2);
+    // nested trackers add no index of their own
+    assert_eq!(
+        size_of::<LexicographicTracker<Vec<u8>>>(),
+        4 * size_of::<usize>()
+    );
+    assert_eq!(
+        size_of::<LexicographicTracker<Vec<u8>, u32>>(),
+        2 * size_of::<usize>() + 2 * size_of::<u32>()
+    );
 }
 
 #[test]
 fn slices() {
-    type T = Vec<COrd>;
+    type T = Vec<COrd<u64>>;
     let t: T = vec![];
-    let init = get_cmp_count();
+    let scope = CountScope::new();
     let mut tracker = <T as TreeOrd>::Tracker::new();
     assert_eq!(t.tree_cmp(&vec![], &mut tracker), Equal);
-    assert_eq!(get_cmp_count(), init);
+    assert_eq!(scope.count(), 0);
     assert_eq!(t.tree_cmp(&vec![COrd(0)], &mut tracker), Less);
     assert_eq!(t.tree_cmp(&vec![], &mut tracker), Equal);
-    assert_eq!(get_cmp_count(), init);
+    assert_eq!(scope.count(), 0);
 
     let t: T = vec![COrd(32), COrd(48), COrd(35)];
-    let init = get_cmp_count();
+    let scope = CountScope::new();
     let mut tracker = <T as TreeOrd>::Tracker::new();
     assert_eq!(t.tree_cmp(&vec![COrd(32), COrd(0)], &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 2);
+    assert_eq!(scope.count(), 2);
     assert_eq!(
         t.tree_cmp(&vec![COrd(32), COrd(64), COrd(0), COrd(0)], &mut tracker),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 4);
+    assert_eq!(scope.count(), 4);
     assert_eq!(t.tree_cmp(&vec![COrd(32), COrd(16)], &mut tracker), Greater);
-    assert_eq!(get_cmp_count(), init + 5);
+    assert_eq!(scope.count(), 5);
     assert_eq!(t.tree_cmp(&vec![COrd(32), COrd(64)], &mut tracker), Less);
-    assert_eq!(get_cmp_count(), init + 6);
+    assert_eq!(scope.count(), 6);
     assert_eq!(t.tree_cmp(&vec![COrd(32), COrd(49)], &mut tracker), Less);
-    assert_eq!(get_cmp_count(), init + 7);
+    assert_eq!(scope.count(), 7);
     assert_eq!(
         t.tree_cmp(&vec![COrd(32), COrd(47), COrd(35)], &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 8);
+    assert_eq!(scope.count(), 8);
     assert_eq!(
         t.tree_cmp(&vec![COrd(32), COrd(48), COrd(35)], &mut tracker),
         Equal
     );
-    assert_eq!(get_cmp_count(), init + 10);
+    assert_eq!(scope.count(), 10);
     assert_eq!(
         t.tree_cmp(&vec![COrd(32), COrd(48), COrd(40)], &mut tracker),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 12);
+    assert_eq!(scope.count(), 12);
     assert_eq!(
         t.tree_cmp(&vec![COrd(32), COrd(48), COrd(30)], &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 14);
+    assert_eq!(scope.count(), 14);
 }
 
 #[test]
 fn nested_tuple() {
-    type T = (COrd, Vec<COrd>, COrd);
+    type T = (COrd<u64>, Vec<COrd<u64>>, COrd<u64>);
     let t: T = (COrd(32), vec![COrd(16), COrd(16)], COrd(64));
-    let init = get_cmp_count();
+    let scope = CountScope::new();
     let mut tracker = <T as TreeOrd>::Tracker::new();
     assert_eq!(
         t.tree_cmp(&(COrd(32), vec![COrd(32)], COrd(0)), &mut tracker),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 2);
+    assert_eq!(scope.count(), 2);
     assert_eq!(
         t.tree_cmp(&(COrd(16), vec![COrd(16)], COrd(0)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 3);
+    assert_eq!(scope.count(), 3);
     assert_eq!(
         t.tree_cmp(&(COrd(24), vec![COrd(16)], COrd(0)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 4);
+    assert_eq!(scope.count(), 4);
     assert_eq!(
         t.tree_cmp(&(COrd(32), vec![COrd(16)], COrd(0)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 6);
+    assert_eq!(scope.count(), 6);
     assert_eq!(
         t.tree_cmp(&(COrd(32), vec![COrd(16), COrd(20)], COrd(0)), &mut tracker),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 8);
+    assert_eq!(scope.count(), 8);
     assert_eq!(
         t.tree_cmp(&(COrd(32), vec![COrd(16), COrd(10)], COrd(0)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 10);
+    assert_eq!(scope.count(), 9);
     assert_eq!(
         t.tree_cmp(&(COrd(32), vec![COrd(16), COrd(11)], COrd(0)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 11);
+    assert_eq!(scope.count(), 10);
     assert_eq!(
         t.tree_cmp(
             &(COrd(32), vec![COrd(16), COrd(16)], COrd(99)),
@@ -243,7 +298,7 @@ fn nested_tuple() {
         ),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 13);
+    assert_eq!(scope.count(), 12);
     // t.1 is not locked in, need to keep B::Tracker until it is
     assert_eq!(
         t.tree_cmp(
@@ -252,12 +307,12 @@ fn nested_tuple() {
         ),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 14);
+    assert_eq!(scope.count(), 13);
     assert_eq!(
         t.tree_cmp(&(COrd(32), vec![COrd(16), COrd(16)], COrd(0)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 16);
+    assert_eq!(scope.count(), 15);
     assert_eq!(
         t.tree_cmp(
             &(COrd(32), vec![COrd(16), COrd(16)], COrd(60)),
@@ -265,7 +320,7 @@ fn nested_tuple() {
         ),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 17);
+    assert_eq!(scope.count(), 16);
     assert_eq!(
         t.tree_cmp(
             &(COrd(32), vec![COrd(16), COrd(16)], COrd(64)),
@@ -273,7 +328,7 @@ fn nested_tuple() {
         ),
         Equal
     );
-    assert_eq!(get_cmp_count(), init + 18);
+    assert_eq!(scope.count(), 17);
     assert_eq!(
         t.tree_cmp(
             &(COrd(32), vec![COrd(16), COrd(16)], COrd(63)),
@@ -281,7 +336,7 @@ fn nested_tuple() {
         ),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 19);
+    assert_eq!(scope.count(), 18);
     assert_eq!(
         t.tree_cmp(
             &(COrd(32), vec![COrd(16), COrd(16)], COrd(64)),
@@ -289,10 +344,10 @@ fn nested_tuple() {
         ),
         Equal
     );
-    assert_eq!(get_cmp_count(), init + 20);
+    assert_eq!(scope.count(), 19);
 
     // test multiple convergences at same time
-    let init = get_cmp_count();
+    let scope = CountScope::new();
     let mut tracker = <T as TreeOrd>::Tracker::new();
     assert_eq!(
         t.tree_cmp(
@@ -301,12 +356,12 @@ fn nested_tuple() {
         ),
         Less
     );
-    assert_eq!(get_cmp_count(), init + 4);
+    assert_eq!(scope.count(), 4);
     assert_eq!(
         t.tree_cmp(&(COrd(32), vec![COrd(16), COrd(16)], COrd(0)), &mut tracker),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 8);
+    assert_eq!(scope.count(), 8);
     assert_eq!(
         t.tree_cmp(
             &(COrd(32), vec![COrd(16), COrd(16)], COrd(50)),
@@ -314,83 +369,278 @@ fn nested_tuple() {
         ),
         Greater
     );
-    assert_eq!(get_cmp_count(), init + 9);
+    assert_eq!(scope.count(), 9);
 }
 
-fn gen_nested_vecs() -> Vec<Vec<Vec<COrd>>> {
-    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
-    type T = Vec<Vec<COrd>>;
-    let mut res: Vec<Vec<Vec<COrd>>> = vec![];
-    for _ in 0..N {
-        let mut t0: T = vec![];
-        for _ in 0..N0 {
-            let len = (rng.next_u64() % N1) as usize;
-            let mut t1 = vec![];
-            for _ in 0..len {
-                t1.push(COrd(0));
-            }
-            if len != 0 {
-                for i in 0..((rng.next_u64() as usize) % len) {
-                    t1[i] = COrd(4);
-                }
-                t1.rotate_left((rng.next_u64() as usize) % len);
-                for i in 0..((rng.next_u64() as usize) % len) {
-                    t1[i] = COrd(8);
-                }
-                t1.rotate_left((rng.next_u64() as usize) % len);
-            }
-            t0.push(t1);
-        }
-        res.push(t0);
+fn gen_nested_vecs() -> Vec<Vec<Vec<COrd<u64>>>> {
+    NestedSlices {
+        count: N as usize,
+        depth: 2,
+        widths: vec![N0 as usize],
+        max_len: N1 as usize,
+        plateau_levels: vec![4, 8],
     }
-    res.sort();
-    res
+    .generate(0)
+}
+
+#[test]
+fn corpus_generators() {
+    use tree_ord::corpus::{ClusteredInts, Nested};
+    let bytes = ClusteredBytes {
+        count: 1 << 10,
+        max_len: 64,
+        plateau_levels: vec![1, 2],
+    };
+    let space = bytes.generate(7);
+    assert_eq!(space.len(), 1 << 10);
+    assert!(space.windows(2).all(|w| w[0] <= w[1]));
+    assert!(space.iter().flatten().all(|x| [0, 1, 2].contains(x)));
+    assert_eq!(space, bytes.generate(7));
+    assert_ne!(space, bytes.generate(8));
+
+    let ints = ClusteredInts::default().generate(0);
+    assert!(ints.windows(2).all(|w| w[0] <= w[1]));
+    assert!(ints.iter().all(|v| v.len() < (1 << 7)));
+
+    let nested = NestedSlices {
+        count: 1 << 8,
+        depth: 3,
+        widths: vec![2, 3],
+        max_len: 8,
+        plateau_levels: vec![5],
+    };
+    let space: Vec<Vec<Vec<Vec<u8>>>> = nested.generate(0);
+    assert!(space.windows(2).all(|w| w[0] <= w[1]));
+    for key in &space {
+        assert_eq!(key.len(), 2);
+        assert!(key.iter().all(|v| v.len() == 3));
+        assert!(key.iter().flatten().all(|v| v.len() < 8));
+    }
+    let dynamic: Vec<Nested> = nested.generate_nested(0);
+    assert_eq!(dynamic.len(), space.len());
+    // the shape of the keys does not match
+    let res = std::panic::catch_unwind(|| nested.generate::<Vec<Vec<u8>>>(0));
+    assert!(res.is_err());
 }
 
 #[test]
 fn nested_slices() {
-    type T = Vec<Vec<COrd>>;
+    type T = Vec<Vec<COrd<u64>>>;
     let mut tree_comparisons = 0;
     let mut comparisons = 0;
     let space = gen_nested_vecs();
     let inxs = space.clone();
     for rhs in &inxs {
-        let init = get_cmp_count();
+        let scope = CountScope::new();
         let mut tracker = <T as TreeOrd>::Tracker::new();
         let found = space
             .binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker))
             .unwrap();
-        tree_comparisons += get_cmp_count() - init;
+        tree_comparisons += scope.count();
 
-        let init = get_cmp_count();
+        let scope = CountScope::new();
         let expected = space.binary_search_by(|lhs| lhs.cmp(rhs)).unwrap();
-        comparisons += get_cmp_count() - init;
+        comparisons += scope.count();
         assert_eq!(found, expected);
     }
-    assert_eq!((tree_comparisons, comparisons), (3396610, 5301800));
+    assert_eq!((tree_comparisons, comparisons), (4878167, 7775412));
 }
 
-fn gen_bytes() -> Vec<Vec<u8>> {
+#[test]
+fn boxed_tracker() {
+    use tree_ord::{utils::BoxedTracker, TreeOrdBoxed};
+
+    type T = Vec<Vec<COrd<u64>>>;
+    let space: Vec<T> = gen_nested_vecs().into_iter().step_by(8).collect();
+    let boxed_space: Vec<TreeOrdBoxed<T>> = space.iter().cloned().map(TreeOrdBoxed).collect();
+    let mut tree_comparisons = 0;
+    let mut boxed_comparisons = 0;
+    for (rhs, boxed_rhs) in space.iter().zip(&boxed_space) {
+        let scope = CountScope::new();
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        let expected =
+            tree_binary_search_by(&space, |lhs| lhs.tree_cmp(rhs, &mut tracker)).unwrap();
+        tree_comparisons += scope.count();
+
+        let scope = CountScope::new();
+        let mut boxed_tracker = BoxedTracker::new();
+        let found = tree_binary_search_by(&boxed_space, |lhs| {
+            lhs.tree_cmp(boxed_rhs, &mut boxed_tracker)
+        })
+        .unwrap();
+        boxed_comparisons += scope.count();
+        assert_eq!(found, expected);
+        assert_eq!(
+            boxed_tracker.get().unwrap().proven_prefix(),
+            tracker.proven_prefix()
+        );
+    }
+    assert_eq!(boxed_comparisons, tree_comparisons);
+
+    // nothing is allocated until a comparison uses the tracker
+    let mut tracker = <TreeOrdBoxed<T> as TreeOrd>::Tracker::new();
+    assert!(!tracker.is_allocated());
+    assert_eq!(tracker.proven_prefix(), Some(0));
+    let empty: &[TreeOrdBoxed<T>] = &[];
+    assert_eq!(
+        tree_binary_search_by(empty, |lhs| lhs.tree_cmp(&boxed_space[0], &mut tracker)),
+        Err(0)
+    );
+    let snapshot = tracker.snapshot();
+    tracker.reset();
+    tracker.restore(snapshot);
+    let _ = tracker.fork();
+    assert!(!tracker.is_allocated());
+    boxed_space[1].tree_cmp(&boxed_space[0], &mut tracker);
+    assert!(tracker.is_allocated());
+    // `reset` keeps the allocation
+    tracker.reset();
+    assert!(tracker.is_allocated());
+    assert_eq!(tracker.proven_prefix(), Some(0));
+
+    // no-op trackers are never allocated
+    let mut tracker = <TreeOrdBoxed<u64> as TreeOrd>::Tracker::new();
+    assert_eq!(
+        TreeOrdBoxed(1u64).tree_cmp(&TreeOrdBoxed(2), &mut tracker),
+        Less
+    );
+    assert!(!tracker.is_allocated());
+}
+
+#[test]
+fn tracker_pool() {
+    use tree_ord::pool::TrackerPool;
+
+    type T = Vec<Vec<COrd<u64>>>;
+    let space: Vec<T> = gen_nested_vecs().into_iter().step_by(8).collect();
+    let pool = TrackerPool::<<T as TreeOrd>::Tracker>::new();
+    let mut pooled_comparisons = 0;
+    let mut tree_comparisons = 0;
+    for rhs in &space {
+        let scope = CountScope::new();
+        let found = {
+            let mut tracker = pool.acquire();
+            assert_eq!(tracker.proven_prefix(), Some(0));
+            tree_binary_search_by(&space, |lhs| lhs.tree_cmp(rhs, &mut tracker))
+        };
+        pooled_comparisons += scope.count();
+
+        let scope = CountScope::new();
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        let expected = tree_binary_search_by(&space, |lhs| lhs.tree_cmp(rhs, &mut tracker));
+        tree_comparisons += scope.count();
+        assert_eq!(found, expected);
+    }
+    assert_eq!(pooled_comparisons, tree_comparisons);
+    // the one tracker is reused for every search
+    assert_eq!(pool.created(), 1);
+    assert_eq!(pool.acquired(), space.len());
+
+    // trackers can be acquired at the same time, and go back to the pool reset
+    let mut a = pool.acquire();
+    let mut b = pool.acquire();
+    assert_eq!(pool.available(), 0);
+    assert_eq!(pool.created(), 2);
+    let fresh = format!("{:?}", <T as TreeOrd>::Tracker::new());
+    let mid = space.len() / 2;
+    for tracker in [&mut a, &mut b] {
+        space[mid - 1].tree_cmp(&space[mid], tracker);
+        space[mid + 1].tree_cmp(&space[mid], tracker);
+        assert_ne!(format!("{:?}", **tracker), fresh);
+    }
+    drop((a, b));
+    assert_eq!(pool.available(), 2);
+    let a = pool.acquire();
+    let b = pool.acquire();
+    assert_eq!(pool.created(), 2);
+    assert_eq!(format!("{:?}", *a), fresh);
+    assert_eq!(format!("{:?}", *b), fresh);
+}
+
+thread_local! {
+    pub static TRACKER_WRITES: RefCell<u64> = const { RefCell::new(0) };
+}
+
+/// A tracker that counts how many times it is written to
+#[derive(Debug, Clone, Copy)]
+pub struct CountingTracker;
+
+impl Tracker for CountingTracker {
+    type Snapshot = ();
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = CountingTracker;
+
+    fn snapshot(&self) {}
+
+    fn restore(&mut self, _: ()) {}
+}
+
+impl CountingTracker {
+    fn write(&mut self) {
+        TRACKER_WRITES.with(|f| *f.borrow_mut() += 1);
+    }
+}
+
+/// A `u64` whose `tree_cmp` writes to a `CountingTracker`
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct WOrd(u64);
+
+impl TreeOrd for WOrd {
+    type Tracker = CountingTracker;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        tracker.write();
+        self.0.cmp(&rhs.0)
+    }
+}
+
+#[test]
+fn maybe_tracked() {
+    use tree_ord::{utils::MaybeTrackedTracker, MaybeTracked};
+
+    let writes = || TRACKER_WRITES.with(|f| *f.borrow());
     let mut rng = Xoshiro128StarStar::seed_from_u64(0);
-    type T = Vec<u8>;
-    let mut res: Vec<Vec<u8>> = vec![];
-    for _ in 0..M {
-        let len = (rng.next_u64() % M1) as usize;
-        let mut t0: T = vec![0; len];
-        if len != 0 {
-            for i in 0..((rng.next_u64() as usize) % len) {
-                t0[i] = 128;
-            }
-            t0.rotate_left((rng.next_u64() as usize) % len);
-            for i in 0..((rng.next_u64() as usize) % len) {
-                t0[i] = 255;
-            }
-            t0.rotate_left((rng.next_u64() as usize) % len);
-        }
-        res.push(t0);
+    let mut space: Vec<MaybeTracked<Vec<WOrd>>> = (0..1024)
+        .map(|_| {
+            let len = (rng.next_u64() % 8) as usize;
+            MaybeTracked((0..len).map(|_| WOrd(rng.next_u64() % 4)).collect())
+        })
+        .collect();
+    space.sort();
+    let mut tracked_writes = 0;
+    for query in &space {
+        let init = writes();
+        let mut tracker = MaybeTrackedTracker::new();
+        assert!(tracker.is_enabled());
+        let tracked = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker));
+        tracked_writes += writes() - init;
+
+        let init = writes();
+        let mut tracker = MaybeTrackedTracker::new_disabled();
+        let untracked = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker));
+        assert_eq!(writes(), init);
+        assert_eq!(tracker.inner().proven_prefix(), Some(0));
+        assert_eq!(space[tracked.unwrap()].0, space[untracked.unwrap()].0);
+        assert_eq!(space[tracked.unwrap()].0, query.0);
     }
-    res.sort();
-    res
+    assert!(tracked_writes > 0);
+
+    // the setting survives `reset` and `fork`
+    let mut tracker = MaybeTrackedTracker::<CountingTracker>::new_disabled();
+    tracker.reset();
+    assert!(!tracker.fork().is_enabled());
+    tracker.set_enabled(true);
+    assert!(tracker.fork().is_enabled());
+}
+
+fn gen_bytes() -> Vec<Vec<u8>> {
+    ClusteredBytes {
+        count: M as usize,
+        max_len: M1 as usize,
+        plateau_levels: vec![128, 255],
+    }
+    .generate(0)
 }
 
 #[test]
@@ -406,3 +656,7245 @@ fn bytes() {
         assert_eq!(found, expected);
     }
 }
+
+#[test]
+fn search_fn() {
+    let space = gen_bytes();
+    for (i, rhs) in space.iter().enumerate().step_by(7) {
+        let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+        let manual = space.binary_search_by(|lhs| lhs.tree_cmp(rhs, &mut tracker));
+        let found = space.binary_search_by(tree_search_fn(rhs));
+        assert_eq!(found, manual);
+        assert_eq!(space[found.unwrap()], space[i]);
+        // the corpus has duplicates, so different probing orders can find different
+        // matches
+        assert_eq!(space[tree_binary_search(&space, rhs).unwrap()], space[i]);
+
+        let mut f = tree_search_fn(rhs);
+        let lower = space.partition_point(|lhs: &Vec<u8>| f(lhs) == Less);
+        assert_eq!(lower, space.partition_point(|lhs| lhs < rhs));
+        let mut f = tree_search_fn(rhs);
+        let upper = space.partition_point(|lhs: &Vec<u8>| f(lhs) != Greater);
+        assert_eq!(upper, space.partition_point(|lhs| lhs <= rhs));
+    }
+    // queries that may not be in the space
+    for rhs in [vec![], vec![1], vec![128, 0], vec![255; 600]] {
+        let expected = space.binary_search(&rhs);
+        for found in [
+            space.binary_search_by(tree_search_fn(&rhs)),
+            tree_binary_search(&space, &rhs),
+        ] {
+            match (found, expected) {
+                (Ok(found), Ok(_)) => assert_eq!(space[found], rhs),
+                (found, expected) => assert_eq!(found, expected),
+            }
+        }
+    }
+}
+
+#[test]
+fn gallop_search() {
+    let space = gen_bytes();
+    let len = space.len();
+    for (i, rhs) in space.iter().enumerate().step_by(13) {
+        for hint in [
+            0,
+            i / 2,
+            i.saturating_sub(3),
+            i,
+            i + 1,
+            i + 100,
+            len,
+            len + 7,
+        ] {
+            let found = tree_gallop_search(&space, hint, rhs).unwrap();
+            assert_eq!(space[found], space[i]);
+        }
+    }
+    for rhs in [vec![], vec![1], vec![128, 0], vec![255; 600]] {
+        let expected = space.binary_search(&rhs);
+        for hint in [0, 1, len / 3, len - 1, len] {
+            match (tree_gallop_search(&space, hint, &rhs), expected) {
+                (Ok(found), Ok(_)) => assert_eq!(space[found], rhs),
+                (found, expected) => assert_eq!(found, expected),
+            }
+        }
+    }
+
+    // empty and single element spaces
+    let empty: &[u64] = &[];
+    assert_eq!(tree_gallop_search(empty, 0, &5), Err(0));
+    assert_eq!(tree_gallop_search(empty, 3, &5), Err(0));
+    assert_eq!(tree_gallop_search(&[5u64], 0, &5), Ok(0));
+    assert_eq!(tree_gallop_search(&[5u64], 1, &5), Ok(0));
+    assert_eq!(tree_gallop_search(&[5u64], 0, &6), Err(1));
+    assert_eq!(tree_gallop_search(&[5u64], 1, &4), Err(0));
+
+    // probe counts when the hint is close
+    let space: Vec<COrd<u64>> = (0..(1 << 12)).map(|i| COrd(i * 2)).collect();
+    let mut gallop_comparisons = 0;
+    let mut binary_comparisons = 0;
+    for i in (0..space.len()).step_by(5) {
+        for (delta, query) in [(0, 2 * i as u64), (3, 2 * i as u64 + 7)] {
+            let hint = i.saturating_sub(delta);
+            let scope = CountScope::new();
+            let found = tree_gallop_search(&space, hint, &COrd(query));
+            gallop_comparisons += scope.count();
+            let scope = CountScope::new();
+            let expected = tree_binary_search(&space, &COrd(query));
+            binary_comparisons += scope.count();
+            assert_eq!(found, expected);
+        }
+    }
+    assert_eq!((gallop_comparisons, binary_comparisons), (5737, 18868));
+}
+
+#[test]
+fn resumable_search() {
+    // a "paged" structure where keys can only be fetched by page
+    const PAGE_LEN: usize = 100;
+    let space = gen_bytes();
+    let pages: Vec<Vec<Vec<u8>>> = space.chunks(PAGE_LEN).map(|c| c.to_vec()).collect();
+    let fetch = |i: usize| -> &Vec<u8> { &pages[i / PAGE_LEN][i % PAGE_LEN] };
+
+    let mut queries: Vec<Vec<u8>> = space.iter().step_by(97).cloned().collect();
+    queries.extend([vec![], vec![1], vec![128, 0], vec![255; 600]]);
+    // drive many searches at once, yielding to the next search after every
+    // comparison
+    let mut searches: Vec<TreeSearch<Vec<u8>, Vec<u8>>> = queries
+        .iter()
+        .map(|q| TreeSearch::new(q, space.len()))
+        .collect();
+    let mut results = vec![None; searches.len()];
+    let mut steps = 0;
+    while results.iter().any(|res| res.is_none()) {
+        for (search, result) in searches.iter_mut().zip(results.iter_mut()) {
+            match search.next_probe() {
+                ProbeRequest::Probe(i) => {
+                    steps += 1;
+                    match search.feed(fetch(i)) {
+                        SearchStep::Left | SearchStep::Right => (),
+                        SearchStep::Found(j) => {
+                            assert_eq!(i, j);
+                            *result = Some(Ok(j));
+                        }
+                        SearchStep::NotFound(j) => *result = Some(Err(j)),
+                    }
+                }
+                ProbeRequest::Done(res) => assert_eq!(Some(res), *result),
+            }
+        }
+    }
+    assert!(steps > queries.len());
+    for (query, result) in queries.iter().zip(results) {
+        // the probing order is the same
+        assert_eq!(result.unwrap(), tree_binary_search(&space, query));
+    }
+
+    let mut search = TreeSearch::<Vec<u8>, Vec<u8>>::new(&queries[0], 0);
+    assert_eq!(search.next_probe(), ProbeRequest::Done(Err(0)));
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        search.feed(&vec![]);
+    }));
+    assert!(res.is_err());
+}
+
+#[test]
+fn equal_range() {
+    use tree_ord::checked::Checked;
+
+    // large groups of long keys that share long prefixes
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut keys = gen_nested_vecs();
+    keys.dedup();
+    let mut space: Vec<Vec<Vec<COrd<u64>>>> = vec![];
+    for key in keys.iter().step_by(3) {
+        for _ in 0..(rng.next_u64() % 64) {
+            space.push(key.clone());
+        }
+    }
+    let mut single = 0;
+    let mut separate = 0;
+    for query in keys.iter().step_by(5) {
+        let lower = space.partition_point(|k| k < query);
+        let upper = space.partition_point(|k| k <= query);
+        let scope = CountScope::new();
+        assert_eq!(tree_equal_range(space.as_slice(), query), lower..upper);
+        single += scope.count();
+        // a separate tracker for each bound
+        let scope = CountScope::new();
+        assert_eq!(tree_lower_bound(&space, query), lower);
+        assert_eq!(tree_upper_bound(&space, query), upper);
+        separate += scope.count();
+    }
+    assert_eq!((single, separate), (1075380, 1453771));
+
+    // the upper bound search stays within the bounds of the tracker
+    let checked: Vec<Checked<Vec<Vec<u64>>>> = space
+        .iter()
+        .map(|k| Checked(k.iter().map(|v| v.iter().map(|x| x.0).collect()).collect()))
+        .collect();
+    for query in checked.iter().step_by(7) {
+        let lower = checked.partition_point(|k| k < query);
+        let upper = checked.partition_point(|k| k <= query);
+        assert_eq!(tree_equal_range(checked.as_slice(), query), lower..upper);
+    }
+    assert_eq!(tree_equal_range(space.as_slice(), &vec![]), 0..0);
+    assert_eq!(tree_equal_range(&space[..0], &keys[0]), 0..0);
+}
+
+#[test]
+fn segmented_search() {
+    let space = gen_bytes();
+    // irregular segment lengths including empty segments
+    let mut segments: Vec<&[Vec<u8>]> = vec![&[]];
+    let mut rest = space.as_slice();
+    let mut seg_len = 1;
+    while !rest.is_empty() {
+        let (seg, tail) = rest.split_at(seg_len.min(rest.len()));
+        segments.push(seg);
+        segments.push(&[]);
+        rest = tail;
+        seg_len = (seg_len * 3) % 1000 + 1;
+    }
+    let segmented = Segmented::new(&segments);
+    assert_eq!(TreeIndexable::len(&segmented), space.len());
+    for i in (0..space.len()).step_by(11) {
+        assert_eq!(segmented.get(i), &space[i]);
+    }
+
+    let mut queries: Vec<Vec<u8>> = space.iter().step_by(61).cloned().collect();
+    queries.extend([vec![], vec![1], vec![128, 0], vec![255; 600]]);
+    for query in &queries {
+        assert_eq!(
+            tree_binary_search(&segmented, query),
+            tree_binary_search(&space, query)
+        );
+        let lower = tree_lower_bound(&segmented, query);
+        let upper = tree_upper_bound(&segmented, query);
+        assert_eq!(lower, space.partition_point(|k| k < query));
+        assert_eq!(upper, space.partition_point(|k| k <= query));
+        assert_eq!(tree_equal_range(&segmented, query), lower..upper);
+        assert_eq!(tree_equal_range(space.as_slice(), query), lower..upper);
+        let found = tree_gallop_search(&segmented, lower, query);
+        assert_eq!(found, tree_gallop_search(&space, lower, query));
+    }
+
+    let empty = Segmented::<u8>::new(&[]);
+    assert_eq!(tree_binary_search(&empty, &0), Err(0));
+    assert_eq!(tree_equal_range(&empty, &0), 0..0);
+}
+
+#[test]
+fn fixed_stride() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    for stride in [16, 64] {
+        // clustered records that share long prefixes
+        let mut records: Vec<Vec<u8>> = (0..2000)
+            .map(|_| {
+                let mut record = vec![0u8; stride];
+                let split = (rng.next_u64() as usize) % stride;
+                for byte in &mut record[split..] {
+                    *byte = (rng.next_u64() % 4) as u8;
+                }
+                record
+            })
+            .collect();
+        records.sort();
+        let mut buf: Vec<u8> = records.concat();
+        for query in records.iter().step_by(7) {
+            let found = fixed_stride_tree_search(&buf, stride, query).unwrap();
+            assert_eq!(&records[found], query);
+        }
+        for query in [
+            vec![],
+            vec![0; stride - 1],
+            vec![0; stride + 1],
+            vec![1; stride],
+            vec![255; stride * 2],
+        ] {
+            let expected = records.binary_search(&query);
+            match (fixed_stride_tree_search(&buf, stride, &query), expected) {
+                (Ok(found), Ok(_)) => assert_eq!(records[found], query),
+                (found, expected) => assert_eq!(found, expected),
+            }
+        }
+        // trailing partial record is ignored
+        buf.extend([255; 5]);
+        assert_eq!(FixedStride::new(&buf, stride).remainder(), &[255; 5]);
+        assert_eq!(
+            fixed_stride_tree_search(&buf, stride, &[255; 5]),
+            Err(records.len())
+        );
+    }
+    assert!(std::panic::catch_unwind(|| fixed_stride_tree_search(&[0; 4], 0, &[0])).is_err());
+}
+
+#[test]
+fn merge_join() {
+    let space = gen_nested_vecs();
+    // sorted queries with duplicates and nonmembers
+    let mut queries: Vec<Vec<Vec<COrd<u64>>>> = space.iter().step_by(3).cloned().collect();
+    queries.extend(space.iter().step_by(301).cloned());
+    for i in (0..space.len()).step_by(1001) {
+        let mut nonmember = space[i].clone();
+        nonmember.push(vec![COrd(99)]);
+        queries.push(nonmember);
+    }
+    queries.sort();
+
+    let scope = CountScope::new();
+    let joined: Vec<(Vec<Vec<_>>, Option<usize>)> =
+        tree_merge_join(&space, queries.iter().cloned()).collect();
+    let join_comparisons = scope.count();
+
+    let scope = CountScope::new();
+    let mut expected = vec![];
+    for query in &queries {
+        let lower = tree_lower_bound(&space, query);
+        expected.push((
+            query.clone(),
+            (space.as_slice().get(lower) == Some(query)).then_some(lower),
+        ));
+    }
+    let naive_comparisons = scope.count();
+    assert_eq!(joined, expected);
+    for (query, res) in &joined {
+        let lower = space.partition_point(|k| k < query);
+        assert_eq!(
+            *res,
+            (space.as_slice().get(lower) == Some(query)).then_some(lower)
+        );
+    }
+    assert_eq!((join_comparisons, naive_comparisons), (1131577, 1089283));
+
+    // clustered short keys
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<(COrd<u64>, COrd<u64>)> = (0..(1 << 14))
+        .map(|_| (COrd(rng.next_u64() % 64), COrd(rng.next_u64() % 1024)))
+        .collect();
+    space.sort();
+    let mut queries: Vec<(COrd<u64>, COrd<u64>)> = (0..(1 << 12))
+        .map(|_| (COrd(rng.next_u64() % 8), COrd(rng.next_u64() % 1024)))
+        .collect();
+    queries.sort();
+    let scope = CountScope::new();
+    let joined: Vec<Option<usize>> = tree_merge_join(&space, queries.iter().cloned())
+        .map(|(_, res)| res)
+        .collect();
+    let join_comparisons = scope.count();
+    let scope = CountScope::new();
+    let mut expected = vec![];
+    for query in &queries {
+        let lower = tree_lower_bound(&space, query);
+        expected.push((space.as_slice().get(lower) == Some(query)).then_some(lower));
+    }
+    let naive_comparisons = scope.count();
+    assert_eq!(joined, expected);
+    assert_eq!((join_comparisons, naive_comparisons), (12399, 68582));
+
+    let empty: &[u64] = &[];
+    assert_eq!(tree_merge_join(empty, [1, 2]).collect::<Vec<_>>(), vec![
+        (1, None),
+        (2, None)
+    ]);
+    assert_eq!(
+        tree_merge_join(&[1u64, 1, 3], [0, 1, 1, 2, 3, 4]).collect::<Vec<_>>(),
+        vec![
+            (0, None),
+            (1, Some(0)),
+            (1, Some(0)),
+            (2, None),
+            (3, Some(2)),
+            (4, None)
+        ]
+    );
+}
+
+fn reference_merge<K: Ord>(a: &[K], b: &[K]) -> Vec<(MergeSide, usize)> {
+    let mut res = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if a[i] <= b[j] {
+            res.push((MergeSide::A, i));
+            i += 1;
+        } else {
+            res.push((MergeSide::B, j));
+            j += 1;
+        }
+    }
+    res.extend((i..a.len()).map(|i| (MergeSide::A, i)));
+    res.extend((j..b.len()).map(|j| (MergeSide::B, j)));
+    res
+}
+
+fn tracked_merge<K: TreeOrd>(a: &[K], b: &[K]) -> Vec<(MergeSide, usize)> {
+    let mut res = vec![];
+    tree_merge_ranges(a, b, |side, range| {
+        assert!(!range.is_empty());
+        res.extend(range.map(|i| (side, i)))
+    });
+    res
+}
+
+#[test]
+fn merge() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let space = gen_bytes();
+    // splits with different run length characteristics
+    for max_run in [1, 2, 8, 64, 1000] {
+        let mut a = vec![];
+        let mut b = vec![];
+        let mut i = 0;
+        while i < space.len() {
+            let run = 1 + (rng.next_u64() as usize) % max_run;
+            let end = (i + run).min(space.len());
+            if rng.next_u64() % 2 == 0 {
+                a.extend_from_slice(&space[i..end]);
+            } else {
+                b.extend_from_slice(&space[i..end]);
+            }
+            i = end;
+        }
+        let expected = reference_merge(&a, &b);
+        assert_eq!(tracked_merge(&a, &b), expected);
+        assert_eq!(tracked_merge(&b, &a), reference_merge(&b, &a));
+        let mut out = vec![];
+        tree_merge(&a, &b, &mut out);
+        assert_eq!(out, space);
+    }
+    // ties and one sided exhaustion
+    let cases: &[(&[u64], &[u64])] = &[
+        (&[], &[]),
+        (&[1, 2, 3], &[]),
+        (&[], &[1, 2, 3]),
+        (&[1, 1, 1], &[1, 1]),
+        (&[5; 20], &[5; 30]),
+        (&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12], &[12, 13]),
+        (&[0, 12], &[1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12]),
+        (&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 20], &[
+            9, 10, 10, 11, 30,
+        ]),
+    ];
+    for (a, b) in cases {
+        assert_eq!(tracked_merge(a, b), reference_merge(a, b));
+        assert_eq!(tracked_merge(b, a), reference_merge(b, a));
+    }
+
+    // comparison counts on clustered runs
+    let space = gen_nested_vecs();
+    let mut a = vec![];
+    let mut b = vec![];
+    for (i, chunk) in space.chunks(40).enumerate() {
+        if i % 2 == 0 {
+            a.extend_from_slice(chunk);
+        } else {
+            b.extend_from_slice(chunk);
+        }
+    }
+    let scope = CountScope::new();
+    let tracked = tracked_merge(&a, &b);
+    let tracked_comparisons = scope.count();
+    let scope = CountScope::new();
+    let expected = reference_merge(&a, &b);
+    let comparisons = scope.count();
+    assert_eq!(tracked, expected);
+    assert_eq!((tracked_comparisons, comparisons), (69394, 330260));
+}
+
+fn check_set_ops<K: TreeOrd + Clone + std::fmt::Debug>(a: &[K], b: &[K]) {
+    use std::collections::BTreeSet;
+    let set_a: BTreeSet<&K> = a.iter().collect();
+    let set_b: BTreeSet<&K> = b.iter().collect();
+    let mut out = vec![];
+    tree_union(a, b, &mut out);
+    assert!(out.iter().eq(set_a.union(&set_b).copied()));
+    out.clear();
+    tree_intersection(a, b, &mut out);
+    assert!(out.iter().eq(set_a.intersection(&set_b).copied()));
+    out.clear();
+    tree_difference(a, b, &mut out);
+    assert!(out.iter().eq(set_a.difference(&set_b).copied()));
+    out.clear();
+    tree_difference(b, a, &mut out);
+    assert!(out.iter().eq(set_b.difference(&set_a).copied()));
+}
+
+#[test]
+fn set_ops() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space = gen_bytes();
+    space.dedup();
+    for (max_run, overlap) in [(1, 2), (4, 3), (64, 4), (1000, 2)] {
+        let mut a = vec![];
+        let mut b = vec![];
+        let mut i = 0;
+        while i < space.len() {
+            let run = 1 + (rng.next_u64() as usize) % max_run;
+            let end = (i + run).min(space.len());
+            let r = rng.next_u64() % overlap;
+            if r != 1 {
+                a.extend_from_slice(&space[i..end]);
+            }
+            if r != 0 {
+                b.extend_from_slice(&space[i..end]);
+            }
+            i = end;
+        }
+        check_set_ops(&a, &b);
+    }
+    check_set_ops::<u64>(&[], &[]);
+    check_set_ops::<u64>(&[1, 2, 3], &[]);
+    check_set_ops(&space, &space);
+    check_set_ops(&space[..1000], &space[500..]);
+
+    // comparison counts on clustered runs
+    let mut space = gen_nested_vecs();
+    space.dedup();
+    let mut a = vec![];
+    let mut b = vec![];
+    for (i, chunk) in space.chunks(40).enumerate() {
+        if i % 3 != 1 {
+            a.extend_from_slice(chunk);
+        }
+        if i % 3 != 0 {
+            b.extend_from_slice(chunk);
+        }
+    }
+    let scope = CountScope::new();
+    let mut out = vec![];
+    tree_union(&a, &b, &mut out);
+    let tracked_comparisons = scope.count();
+    let scope = CountScope::new();
+    let mut expected = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Less => {
+                expected.push(a[i].clone());
+                i += 1;
+            }
+            Equal => {
+                expected.push(a[i].clone());
+                i += 1;
+                j += 1;
+            }
+            Greater => {
+                expected.push(b[j].clone());
+                j += 1;
+            }
+        }
+    }
+    let comparisons = scope.count();
+    expected.extend_from_slice(&a[i..]);
+    expected.extend_from_slice(&b[j..]);
+    assert_eq!(out, expected);
+    assert_eq!((tracked_comparisons, comparisons), (736149, 897275));
+}
+
+#[test]
+fn k_way_merge() {
+    use std::{cmp::Reverse, collections::BinaryHeap};
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let space = gen_nested_vecs();
+    for (k, max_run) in [(1, 1), (3, 1), (8, 1), (8, 50), (17, 4), (64, 200)] {
+        let mut runs: Vec<Vec<Vec<Vec<COrd<u64>>>>> = vec![vec![]; k];
+        let mut i = 0;
+        while i < space.len() {
+            let run = 1 + (rng.next_u64() as usize) % max_run;
+            let end = (i + run).min(space.len());
+            runs[(rng.next_u64() as usize) % k].extend_from_slice(&space[i..end]);
+            i = end;
+        }
+        let slices: Vec<&[Vec<Vec<COrd<u64>>>]> = runs.iter().map(|run| run.as_slice()).collect();
+
+        let scope = CountScope::new();
+        let merge = TreeKWayMerge::new(slices.clone());
+        assert_eq!(merge.size_hint(), (space.len(), Some(space.len())));
+        let merged: Vec<&Vec<Vec<COrd<u64>>>> = merge.collect();
+        let tracked_comparisons = scope.count();
+        assert!(merged.iter().copied().eq(space.iter()));
+
+        let scope = CountScope::new();
+        let mut heap = BinaryHeap::new();
+        for (r, run) in slices.iter().enumerate() {
+            if let Some(key) = run.first() {
+                heap.push(Reverse((key, r, 0)));
+            }
+        }
+        let mut heap_merged = vec![];
+        while let Some(Reverse((key, r, i))) = heap.pop() {
+            heap_merged.push(key);
+            if let Some(key) = slices[r].get(i + 1) {
+                heap.push(Reverse((key, r, i + 1)));
+            }
+        }
+        let heap_comparisons = scope.count();
+        assert_eq!(merged, heap_merged);
+        if k >= 8 {
+            assert!(
+                tracked_comparisons < heap_comparisons,
+                "{tracked_comparisons} {heap_comparisons}"
+            );
+        }
+    }
+
+    // stability and exhaustion
+    let a = [1u64, 2, 2];
+    let b = [1u64, 3];
+    let c = [2u64];
+    let runs: Vec<&[u64]> = vec![&a, &[], &b, &c];
+    let merged: Vec<*const u64> = TreeKWayMerge::new(runs).map(|x| x as *const u64).collect();
+    assert_eq!(merged, vec![
+        &a[0] as *const u64,
+        &b[0],
+        &a[1],
+        &a[2],
+        &c[0],
+        &b[1]
+    ]);
+    assert_eq!(TreeKWayMerge::<u64>::new(vec![]).next(), None);
+    assert_eq!(TreeKWayMerge::<u64>::new(vec![&[], &[]]).next(), None);
+}
+
+#[test]
+fn k_way_merge_clustered_bytes() {
+    use std::{cmp::Reverse, collections::BinaryHeap};
+
+    // long keys that share long prefixes, in runs that interleave closely
+    let space: Vec<Vec<COrd<u8>>> = ClusteredBytes {
+        count: 1 << 12,
+        max_len: 1 << 9,
+        plateau_levels: vec![128, 255],
+    }
+    .generate(0)
+    .into_iter()
+    .map(|v| v.into_iter().map(COrd).collect())
+    .collect();
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut counts = vec![];
+    for k in [8, 16, 32, 64] {
+        for max_run in [1, 16] {
+            let mut runs: Vec<Vec<Vec<COrd<u8>>>> = vec![vec![]; k];
+            let mut i = 0;
+            while i < space.len() {
+                let run = 1 + (rng.next_u64() as usize) % max_run;
+                let end = (i + run).min(space.len());
+                runs[(rng.next_u64() as usize) % k].extend_from_slice(&space[i..end]);
+                i = end;
+            }
+            let slices: Vec<&[Vec<COrd<u8>>]> = runs.iter().map(|run| run.as_slice()).collect();
+
+            let scope = CountScope::new();
+            let merged: Vec<&Vec<COrd<u8>>> = TreeKWayMerge::new(slices.clone()).collect();
+            let tracked_comparisons = scope.count();
+            assert!(merged.iter().copied().eq(space.iter()));
+
+            let scope = CountScope::new();
+            let mut heap = BinaryHeap::new();
+            for (r, run) in slices.iter().enumerate() {
+                if let Some(key) = run.first() {
+                    heap.push(Reverse((key, r, 0)));
+                }
+            }
+            while let Some(Reverse((_, r, i))) = heap.pop() {
+                if let Some(key) = slices[r].get(i + 1) {
+                    heap.push(Reverse((key, r, i + 1)));
+                }
+            }
+            let heap_comparisons = scope.count();
+            // the loser tree replays one key comparison per level, while the
+            // heap needs about two per level when sifting down, and the
+            // gallops over longer runs output most keys without comparisons
+            assert!(tracked_comparisons < heap_comparisons);
+            counts.push((tracked_comparisons, heap_comparisons));
+        }
+    }
+    assert_eq!(counts, vec![
+        (1023111, 1613878),
+        (610313, 1469077),
+        (1307554, 1936564),
+        (772843, 1921592),
+        (1579195, 2214357),
+        (892726, 2159600),
+        (1823507, 2417463),
+        (1005803, 2404997)
+    ]);
+}
+
+#[test]
+fn sort() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let sorted = gen_nested_vecs();
+    // partially shuffled and fully shuffled
+    for swaps in [0, 100, sorted.len()] {
+        let mut v = sorted.clone();
+        for _ in 0..swaps {
+            let i = (rng.next_u64() as usize) % v.len();
+            let j = (rng.next_u64() as usize) % v.len();
+            v.swap(i, j);
+        }
+        let mut w = v.clone();
+        let scope = CountScope::new();
+        tree_sort(&mut v);
+        let tree_comparisons = scope.count();
+        let scope = CountScope::new();
+        w.sort();
+        let comparisons = scope.count();
+        assert_eq!(v, sorted);
+        assert_eq!(w, sorted);
+        if swaps == 0 {
+            // only the single run needs to be found
+            assert!(tree_comparisons <= comparisons);
+        } else {
+            assert!(tree_comparisons < comparisons);
+        }
+    }
+
+    // stability
+    #[derive(Debug, Clone, Copy)]
+    struct Tagged(u8, usize);
+    impl PartialEq for Tagged {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 == other.0
+        }
+    }
+    impl Eq for Tagged {}
+    impl PartialOrd for Tagged {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for Tagged {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+    impl TreeOrd for Tagged {
+        type Tracker = ();
+
+        fn tree_cmp(&self, rhs: &Self, _: &mut ()) -> Ordering {
+            self.cmp(rhs)
+        }
+    }
+    for len in [0, 1, 2, 15, 16, 17, 100, 1000] {
+        let mut v: Vec<Tagged> = (0..len)
+            .map(|i| Tagged((rng.next_u64() % 8) as u8, i))
+            .collect();
+        tree_sort(&mut v);
+        for w in v.windows(2) {
+            assert!((w[0].0 < w[1].0) || ((w[0].0 == w[1].0) && (w[0].1 < w[1].1)));
+        }
+    }
+}
+
+#[test]
+fn dedup() {
+    use tree_ord::checked::Checked;
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    // large groups of long keys
+    let mut keys = gen_nested_vecs();
+    keys.dedup();
+    let mut v: Vec<Vec<Vec<COrd<u64>>>> = vec![];
+    for key in keys.iter().step_by(7) {
+        let n = 1 + if rng.next_u64() % 4 == 0 {
+            (rng.next_u64() % 200) as usize
+        } else {
+            (rng.next_u64() % 3) as usize
+        };
+        for _ in 0..n {
+            v.push(key.clone());
+        }
+    }
+    let mut w = v.clone();
+    let scope = CountScope::new();
+    tree_dedup(&mut v);
+    let tree_comparisons = scope.count();
+    let scope = CountScope::new();
+    // `COrd<u64>` only counts `Ord` calls
+    w.dedup_by(|a, b| a.cmp(&b) == Equal);
+    let comparisons = scope.count();
+    assert_eq!(v, w);
+    assert_eq!((tree_comparisons, comparisons), (1392779, 7444325));
+
+    // the comparisons of the gallops are valid searches
+    let mut v: Vec<_> = w
+        .iter()
+        .flat_map(|k| (0..(k.len() % 40)).map(move |_| Checked(k.clone())))
+        .collect();
+    let mut w = v.clone();
+    tree_dedup(&mut v);
+    w.dedup();
+    assert_eq!(v, w);
+
+    // the first of each group is kept
+    for len in [0, 1, 2, 10, 1000] {
+        let mut v: Vec<(Vec<u8>, usize)> = (0..len)
+            .map(|i| (vec![7; 40 + (rng.next_u64() % 3) as usize], i))
+            .collect();
+        v.sort();
+        let mut w = v.clone();
+        tree_dedup_by_key(&mut v, |x| x.0.clone());
+        w.dedup_by_key(|x| x.0.clone());
+        assert_eq!(v, w);
+    }
+}
+
+#[test]
+fn common_prefix() {
+    use tree_ord::prefix::*;
+    let empty: &[u8] = &[];
+    assert_eq!(common_prefix_len(empty, empty), 0);
+    assert_eq!(common_prefix_len(empty, &[1]), 0);
+    assert_eq!(common_prefix_len(&[1, 2, 3], &[1, 2, 3]), 3);
+    assert_eq!(common_prefix_len(&[1, 2, 3], &[1, 2]), 2);
+    assert_eq!(common_prefix_len(&[1, 2, 3], &[4, 5, 6]), 0);
+    assert_eq!(bytes_common_prefix_len(empty, empty), 0);
+    assert_eq!(bytes_common_prefix_len(&[0; 100], &[0; 100]), 100);
+    assert_eq!(bytes_common_prefix_len(&[0; 100], &[1; 100]), 0);
+    let empty_range: &[Vec<u8>] = &[];
+    assert_eq!(range_common_prefix_len(empty_range), 0);
+    assert_eq!(bytes_range_common_prefix_len(empty_range), 0);
+    assert_eq!(range_common_prefix_len(&[vec![1, 2, 3]]), 3);
+    assert_eq!(
+        range_common_prefix_len(&[vec![1, 2, 3], vec![1, 2, 5], vec![1, 3]]),
+        1
+    );
+
+    let space = gen_bytes();
+    for w in space.windows(2).step_by(5) {
+        let expected = w[0].iter().zip(&w[1]).take_while(|(x, y)| x == y).count();
+        assert_eq!(common_prefix_len(&w[0], &w[1]), expected);
+        assert_eq!(bytes_common_prefix_len(&w[0], &w[1]), expected);
+    }
+    for range in space.chunks(1000) {
+        let expected = range
+            .iter()
+            .map(|key| common_prefix_len(&range[0], key))
+            .min()
+            .unwrap();
+        assert_eq!(range_common_prefix_len(range), expected);
+        assert_eq!(bytes_range_common_prefix_len(range), expected);
+    }
+
+    // the proven prefix of a tracker is a common prefix of the search range
+    for rhs in space.iter().step_by(101) {
+        let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+        let mut lo = 0;
+        let mut hi = space.len();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match space[mid].tree_cmp(rhs, &mut tracker) {
+                Less => lo = mid + 1,
+                Equal => break,
+                Greater => hi = mid,
+            }
+            if lo < hi {
+                // the keys are sorted, so checking the ends covers the whole range
+                let proven = tracker.proven_prefix_len();
+                assert!(common_prefix_len(&space[lo], rhs) >= proven);
+                assert!(common_prefix_len(&space[hi - 1], rhs) >= proven);
+            }
+        }
+    }
+}
+
+#[test]
+fn range() {
+    use std::ops::{Bound, Bound::*, RangeBounds};
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space = vec![];
+    for (i, key) in gen_bytes().into_iter().take(1 << 12).enumerate() {
+        // duplicate keys to test boundaries
+        if (i % 7) == 0 {
+            space.push(key.clone());
+            space.push(key.clone());
+        }
+        space.push(key);
+    }
+    let bound = |rng: &mut Xoshiro128StarStar| -> Bound<Vec<u8>> {
+        let key = space[(rng.next_u64() as usize) % space.len()].clone();
+        match rng.next_u64() % 4 {
+            0 => Included(key),
+            1 => Excluded(key),
+            2 => Unbounded,
+            _ => {
+                // a key that is probably not in the space
+                let mut key = key;
+                key.push(rng.next_u64() as u8);
+                Included(key)
+            }
+        }
+    };
+    for _ in 0..2000 {
+        let start = bound(&mut rng);
+        let end = bound(&mut rng);
+        let range = (start.as_ref(), end.as_ref());
+        let res = tree_range(&space, range);
+        let expected: Vec<usize> = (0..space.len())
+            .filter(|i| range.contains(&space[*i]))
+            .collect();
+        if let (Some(first), Some(last)) = (expected.first(), expected.last()) {
+            assert_eq!(res, *first..(last + 1));
+        } else {
+            assert!(res.is_empty());
+        }
+    }
+    let empty: &[u64] = &[];
+    assert_eq!(tree_range(empty, ..), 0..0);
+    assert_eq!(tree_range(&[1u64, 2, 2, 3][..], 2..), 1..4);
+    assert_eq!(tree_range(&[1u64, 2, 2, 3][..], ..=2), 0..3);
+    assert_eq!(tree_range(&[1u64, 2, 2, 3][..], 2..2), 1..1);
+    // inverted
+    assert!(tree_range(&[1u64, 2, 2, 3][..], (Excluded(&2), Excluded(&2))).is_empty());
+    assert!(tree_range(&[1u64, 2, 2, 3][..], (Included(&3), Included(&1))).is_empty());
+}
+
+#[test]
+fn prefix_cache() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+
+    // correctness with random walks, jumps, and changing spaces
+    let bytes = gen_bytes();
+    let mut cache = PrefixCache::default();
+    let mut i = 0;
+    for step in 0..20000 {
+        let space = if (step / 5000) % 2 == 0 {
+            &bytes[..]
+        } else {
+            &bytes[(bytes.len() / 3)..]
+        };
+        i = if (rng.next_u64() % 50) == 0 {
+            (rng.next_u64() as usize) % bytes.len()
+        } else {
+            (i + (rng.next_u64() as usize) % 8).saturating_sub(4) % bytes.len()
+        };
+        let mut query = bytes[i].clone();
+        if (rng.next_u64() % 2) == 0 {
+            query.push(rng.next_u64() as u8);
+        }
+        match cache.search(space, &query) {
+            Ok(j) => assert_eq!(space[j], query),
+            Err(j) => assert_eq!(j, space.partition_point(|k| *k < query)),
+        }
+    }
+
+    // queries outside of the cached window on both sides
+    let space: Vec<u64> = (0..1000).map(|x| x * 2).collect();
+    let mut cache = PrefixCache::new(8);
+    assert_eq!(cache.search(&space, &500), Ok(250));
+    let window = cache.window().unwrap();
+    assert!(window.len() >= 8 && window.contains(&250));
+    assert_eq!(cache.search(&space, &3), Err(2));
+    assert_eq!(cache.search(&space, &1997), Err(999));
+    assert_eq!(cache.search(&space, &2000), Err(1000));
+    assert_eq!(cache.search(&space, &0), Ok(0));
+    assert_eq!(cache.search(&space[..10], &1998), Err(10));
+    cache.clear();
+    assert!(cache.window().is_none());
+    assert_eq!(cache.search(&space, &1998), Ok(999));
+
+    // comparison counts for temporally local queries
+    let space = gen_nested_vecs();
+    let mut queries = vec![];
+    let mut i = 0;
+    for _ in 0..2000 {
+        i = (i + (rng.next_u64() as usize) % 16) % space.len();
+        queries.push(space[i].clone());
+    }
+    let scope = CountScope::new();
+    for query in &queries {
+        tree_binary_search(&space, query).unwrap();
+    }
+    let fresh = scope.count();
+    let scope = CountScope::new();
+    let mut cache = PrefixCache::default();
+    for query in &queries {
+        let j = cache.search(&space, query).unwrap();
+        assert_eq!(space[j], *query);
+    }
+    let cached = scope.count();
+    assert!(cached < fresh, "{cached} {fresh}");
+}
+
+#[test]
+fn insert_cursor() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    // nearly sorted stream of byte keys with duplicates
+    let mut stream: Vec<Vec<COrd<u64>>> = gen_bytes()
+        .iter()
+        .step_by(8)
+        .map(|k| k.iter().map(|b| COrd(u64::from(*b))).collect())
+        .collect();
+    for i in 0..stream.len() {
+        if (rng.next_u64() % 8) == 0 {
+            let j = (i + (rng.next_u64() as usize) % 16).min(stream.len() - 1);
+            stream.swap(i, j);
+        }
+    }
+    let mut expected = stream.clone();
+    expected.sort();
+
+    let scope = CountScope::new();
+    let mut cursor = InsertCursor::new();
+    for key in stream.iter().cloned() {
+        let i = cursor.insert(key);
+        assert!(i < cursor.as_slice().len());
+    }
+    let cursor_comparisons = scope.count();
+    assert_eq!(cursor.into_inner(), expected);
+
+    let scope = CountScope::new();
+    let mut v: Vec<Vec<COrd<u64>>> = vec![];
+    for key in stream.iter().cloned() {
+        let i = tree_upper_bound(&v, &key);
+        v.insert(i, key);
+    }
+    let search_comparisons = scope.count();
+    assert_eq!(v, expected);
+    assert!(
+        cursor_comparisons * 3 < search_comparisons,
+        "{cursor_comparisons} {search_comparisons}"
+    );
+
+    // random order, duplicates, and stability
+    let mut cursor = InsertCursor::from_sorted(vec![(1u8, 0u8), (5, 0)]);
+    for x in [3u8, 1, 5, 0, 3, 9, 1] {
+        cursor.insert((x, 1));
+    }
+    for x in [3u8, 1] {
+        cursor.insert((x, 2));
+    }
+    let mut expected = vec![(1u8, 0u8), (5, 0)];
+    for x in [3u8, 1, 5, 0, 3, 9, 1] {
+        expected.push((x, 1));
+    }
+    expected.extend([(3, 2), (1, 2)]);
+    expected.sort_by_key(|k| k.0);
+    let v = cursor.into_inner();
+    assert_eq!(
+        v.iter().map(|k| k.0).collect::<Vec<_>>(),
+        expected.iter().map(|k| k.0).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn scoped() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let bytes: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(16).collect();
+    let mut pairs: Vec<(u64, String)> = (0..4096)
+        .map(|_| {
+            let x = rng.next_u64() % 64;
+            let s = format!("{:x}", rng.next_u64() % 4096);
+            (x, s)
+        })
+        .collect();
+    pairs.sort();
+    for i in 0..4096 {
+        let b = &bytes[(rng.next_u64() as usize) % bytes.len()];
+        assert_eq!(
+            tree_ord::scoped::tree_binary_search(&bytes, b),
+            tree_binary_search(&bytes, b)
+        );
+        let p = &pairs[i];
+        assert_eq!(
+            tree_ord::scoped::tree_lower_bound(&pairs, p),
+            tree_lower_bound(&pairs, p)
+        );
+        assert_eq!(
+            tree_ord::scoped::tree_upper_bound(&bytes, b),
+            tree_upper_bound(&bytes, b)
+        );
+        let p = (p.0, format!("{}0", p.1));
+        assert_eq!(
+            tree_ord::scoped::tree_binary_search(&pairs, &p),
+            tree_binary_search(&pairs, &p)
+        );
+    }
+    // nested use of the same tracker type
+    let res = tree_ord::scoped::with_tracker(|_: &mut <Vec<u8> as TreeOrd>::Tracker| {
+        tree_ord::scoped::tree_binary_search(&bytes, &bytes[7])
+    });
+    assert_eq!(bytes[res.unwrap()], bytes[7]);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_batch() {
+    use tree_ord::par::par_tree_binary_search_batch;
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let space = gen_bytes();
+    let mut queries: Vec<Vec<u8>> = (0..20000)
+        .map(|_| space[(rng.next_u64() as usize) % space.len()].clone())
+        .collect();
+    for query in queries.iter_mut().step_by(3) {
+        query.push(rng.next_u64() as u8);
+    }
+    let expected: Vec<_> = queries
+        .iter()
+        .map(|query| tree_binary_search(&space, query))
+        .collect();
+    assert_eq!(par_tree_binary_search_batch(&space, &queries), expected);
+    assert_eq!(
+        par_tree_binary_search_batch(&space, &queries[..1]),
+        &expected[..1]
+    );
+    assert!(par_tree_binary_search_batch::<Vec<u8>, Vec<u8>>(&space, &[]).is_empty());
+    assert_eq!(
+        par_tree_binary_search_batch::<Vec<u8>, Vec<u8>>(&[], &queries[..3]),
+        vec![Err(0); 3]
+    );
+}
+
+#[test]
+fn key_value() {
+    use tree_ord::KeyValue;
+    let keys: Vec<Vec<COrd<u64>>> = gen_bytes()
+        .iter()
+        .step_by(4)
+        .map(|k| k.iter().map(|b| COrd(u64::from(*b))).collect())
+        .collect();
+    let space: Vec<KeyValue<Vec<COrd<u64>>, u64>> = keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| KeyValue::from((k.clone(), i as u64)))
+        .collect();
+    let mut kv_comparisons = 0;
+    let mut key_comparisons = 0;
+    for query in keys.iter().step_by(7) {
+        let scope = CountScope::new();
+        let kv = tree_binary_search(&space, query);
+        kv_comparisons += scope.count();
+        let scope = CountScope::new();
+        let k = tree_binary_search(&keys, query);
+        key_comparisons += scope.count();
+        assert_eq!(kv, k);
+        assert_eq!(space[kv.unwrap()].key(), query);
+    }
+    assert_eq!(kv_comparisons, key_comparisons);
+
+    // only keys are compared
+    let a = KeyValue::new(vec![1u8, 2], 0u64);
+    let b = KeyValue::new(vec![1u8, 2], 1u64);
+    assert_eq!(a, b);
+    assert_eq!(a.tree_cmp(&b, &mut Tracker::new()), Equal);
+    assert_eq!(a.tree_cmp(&vec![1u8, 3], &mut Tracker::new()), Less);
+    assert_eq!(b.into_pair(), (vec![1, 2], 1));
+}
+
+#[test]
+fn by_key() {
+    use tree_ord::{utils::LexicographicTracker, ByKey, TreeOrdKey};
+
+    #[derive(Debug, Clone)]
+    struct Entry {
+        size: u64,
+        path: String,
+    }
+
+    impl TreeOrdKey for Entry {
+        type Key = String;
+
+        fn tree_key(&self) -> &String {
+            &self.path
+        }
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut entries: Vec<Entry> = (0..4096)
+        .map(|i| Entry {
+            size: 4096 - i,
+            path: format!(
+                "/usr/lib/{}/{:x}",
+                rng.next_u64() % 4,
+                rng.next_u64() % (1 << 20)
+            ),
+        })
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    let space = ByKey::from_slice(&entries);
+    let mut skipped = 0;
+    let mut searches = 0;
+    for entry in entries.iter().step_by(13) {
+        let query = ByKey::from_ref(entry);
+        let mut tracker = LexicographicTracker::<u8>::new();
+        let i = tree_binary_search_by(space, |k| k.tree_cmp(query, &mut tracker)).unwrap();
+        assert_eq!(entries[i].path, entry.path);
+        // searches that do not end early skip over the common "/usr/lib/x/" prefix
+        if tracker.proven_prefix_len() >= "/usr/lib/x/".len() {
+            skipped += 1;
+        }
+        searches += 1;
+    }
+    assert!(skipped * 10 > searches * 9, "{skipped} {searches}");
+    assert_eq!(ByKey::from_ref(&entries[0]).0.size, entries[0].size);
+}
+
+#[test]
+fn tracker_reset() {
+    type T = (
+        Vec<COrd<u64>>,
+        (COrd<u64>, Vec<(COrd<u64>, Vec<COrd<u64>>)>),
+        Result<Vec<COrd<u64>>, COrd<u64>>,
+    );
+    let mut space: Vec<T> = gen_nested_vecs()
+        .into_iter()
+        .step_by(8)
+        .map(|mut v| {
+            let c = v.pop().unwrap();
+            let b = v.pop().unwrap();
+            let a = v.pop().unwrap();
+            let d = v.pop().unwrap();
+            let len = COrd(b.len() as u64 % 2);
+            let r = if (c.len() % 4) == 0 {
+                Err(COrd(0))
+            } else {
+                Ok(c.clone())
+            };
+            (a, (len, vec![(COrd(0), b), (COrd(1), d)]), r)
+        })
+        .collect();
+    space.sort();
+    let mut tracker = <T as TreeOrd>::Tracker::new();
+    for (i, rhs) in space.iter().enumerate().step_by(3) {
+        let scope = CountScope::new();
+        let mut fresh = <T as TreeOrd>::Tracker::new();
+        let expected = tree_binary_search_by(&space, |lhs| lhs.tree_cmp(rhs, &mut fresh));
+        let fresh_comparisons = scope.count();
+
+        // run a search for a different key first so that there is stale state
+        let other = &space[(i * 7) % space.len()];
+        tree_binary_search_by(&space, |lhs| lhs.tree_cmp(other, &mut tracker)).unwrap();
+        tracker.reset();
+        let scope = CountScope::new();
+        let found = tree_binary_search_by(&space, |lhs| lhs.tree_cmp(rhs, &mut tracker));
+        assert_eq!(scope.count(), fresh_comparisons);
+        assert_eq!(found, expected);
+        tracker.reset();
+    }
+}
+
+#[test]
+fn tracker_clone() {
+    type T = (u64, Vec<u8>, Result<String, u8>);
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<T> = gen_bytes()
+        .into_iter()
+        .step_by(16)
+        .map(|v| {
+            let r = if (v.len() % 3) == 0 {
+                Err(v.len() as u8)
+            } else {
+                Ok(format!("{:x}", rng.next_u64() % 64))
+            };
+            (rng.next_u64() % 2, v, r)
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    for (i, rhs) in space.iter().enumerate().step_by(11) {
+        let mut tracker = <T as TreeOrd>::Tracker::default();
+        let mut lo = 0;
+        let mut hi = space.len();
+        // narrow down the search partially
+        for _ in 0..4 {
+            let mid = lo + (hi - lo) / 2;
+            match space[mid].tree_cmp(rhs, &mut tracker) {
+                Less => lo = mid + 1,
+                Equal => break,
+                Greater => hi = mid,
+            }
+        }
+        let mut fork = tracker.clone();
+        // continue with a binary search on the original
+        let found = tree_binary_search_by(&space[lo..hi], |lhs| lhs.tree_cmp(rhs, &mut tracker));
+        assert_eq!(found, Ok(i - lo));
+        // and a linear scan on the fork
+        let found = space[lo..hi]
+            .iter()
+            .position(|lhs| lhs.tree_cmp(rhs, &mut fork) == Equal);
+        assert_eq!(found, Some(i - lo));
+        let _ = format!("{fork:?}");
+    }
+}
+
+#[test]
+fn known_prefix() {
+    use tree_ord::{utils::*, TreeOrdVec};
+    // keys that all start with the same 4 element table id
+    let table_id = [COrd(7), COrd(7), COrd(0), COrd(3)];
+    let space: Vec<Vec<COrd<u64>>> = (0..64u64)
+        .map(|i| {
+            let mut v = table_id.to_vec();
+            v.extend([COrd(i / 8), COrd(i % 8)]);
+            v
+        })
+        .collect();
+    let query = &space[37];
+    // the first comparison only compares the elements after the table id
+    let scope = CountScope::new();
+    let mut tracker = LexicographicTracker::<COrd<u64>>::with_known_prefix(4);
+    assert_eq!(space[32].tree_cmp(query, &mut tracker), Less);
+    assert_eq!(scope.count(), 2);
+    let scope = CountScope::new();
+    let mut tracker = LexicographicTracker::<COrd<u64>>::new();
+    assert_eq!(space[32].tree_cmp(query, &mut tracker), Less);
+    assert_eq!(scope.count(), 6);
+    // whole searches
+    for query in &space {
+        let scope = CountScope::new();
+        let mut tracker = LexicographicTracker::<COrd<u64>>::with_known_prefix(4);
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker));
+        let seeded = scope.count();
+        let scope = CountScope::new();
+        assert_eq!(found, tree_binary_search(&space, query));
+        assert!(seeded < scope.count());
+        assert_eq!(space[found.unwrap()], *query);
+    }
+
+    // tuples
+    type T = (COrd<u64>, Vec<COrd<u64>>);
+    let space: Vec<T> = (0..64u64)
+        .map(|i| (COrd(42), vec![COrd(i / 8), COrd(i % 8)]))
+        .collect();
+    for query in &space {
+        let scope = CountScope::new();
+        let mut tracker = TupleTracker2::<COrd<u64>, Vec<COrd<u64>>>::with_locked_fields(1);
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker));
+        let seeded = scope.count();
+        let scope = CountScope::new();
+        assert_eq!(found, tree_binary_search(&space, query));
+        assert!(seeded < scope.count());
+    }
+    let mut tracker = TupleTracker2::<COrd<u64>, Vec<COrd<u64>>>::with_locked_fields(2);
+    assert_eq!(space[0].tree_cmp(&space[1], &mut tracker), Equal);
+
+    // bytes
+    let space: Vec<TreeOrdVec> = (0..64u8)
+        .map(|i| {
+            let mut v = vec![9u8; 70];
+            v.push(i);
+            TreeOrdVec(v)
+        })
+        .collect();
+    for query in &space {
+        let mut tracker = TreeOrdBytes::tracker_with_known_prefix(70);
+        assert_eq!(tracker.proven_prefix_len(), 70);
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker));
+        assert_eq!(space[found.unwrap()], *query);
+    }
+}
+
+#[test]
+fn bounds_seeded() {
+    // keys with a long common prefix that are inserted in mostly ascending order
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let prefix: Vec<COrd<u64>> = (0..16).map(|_| COrd(0)).collect();
+    let key = |x: u64| {
+        let mut v = prefix.clone();
+        v.push(COrd(x));
+        v
+    };
+    let mut space: Vec<Vec<COrd<u64>>> = vec![];
+    let mut seeded = 0;
+    let mut unseeded = 0;
+    for i in 0..4096u64 {
+        let x = if (rng.next_u64() % 16) == 0 {
+            rng.next_u64() % (i * 4 + 1)
+        } else {
+            i * 4
+        };
+        let query = key(x);
+        let scope = CountScope::new();
+        let res = tree_binary_search_with_bounds(&space, &query);
+        seeded += scope.count();
+        let scope = CountScope::new();
+        let expected = tree_binary_search(&space, &query);
+        unseeded += scope.count();
+        match (res, expected) {
+            (Ok(i), Ok(_)) => assert_eq!(space[i], query),
+            (res, expected) => assert_eq!(res, expected),
+        }
+        let i = match res {
+            Ok(i) | Err(i) => i,
+        };
+        space.insert(i, query);
+    }
+    assert!(seeded * 4 < unseeded, "{seeded} {unseeded}");
+
+    // the last key is not compared if the first key is equal
+    let space: Vec<COrd<u64>> = (0..8).map(COrd).collect();
+    let scope = CountScope::new();
+    assert_eq!(tree_binary_search_with_bounds(&space, &COrd(0)), Ok(0));
+    assert_eq!(scope.count(), 1);
+
+    // degenerate cases
+    let space = [1u64, 3, 5];
+    assert_eq!(tree_binary_search_with_bounds(&space[..0], &0), Err(0));
+    assert_eq!(tree_binary_search_with_bounds(&space[..1], &1), Ok(0));
+    assert_eq!(tree_binary_search_with_bounds(&space[..1], &2), Err(1));
+    for x in 0..7 {
+        assert_eq!(
+            tree_binary_search_with_bounds(&space, &x),
+            space.binary_search(&x)
+        );
+    }
+    let (_, min_cmp, max_cmp) = tracker_with_bounds(&0u64, &1, &5);
+    assert_eq!((min_cmp, max_cmp), (Greater, Greater));
+    let (_, min_cmp, max_cmp) = tracker_with_bounds(&6u64, &1, &5);
+    assert_eq!((min_cmp, max_cmp), (Less, Less));
+}
+
+#[test]
+fn tracker_snapshot() {
+    let query = vec![1u8, 5];
+    let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+    // descend a level and take a snapshot
+    assert_eq!(vec![2, 0].tree_cmp(&query, &mut tracker), Greater);
+    let snapshot = tracker.snapshot();
+    // speculatively descend into a subtree bounded by `[1, 9]` and `[1, 0]`
+    assert_eq!(vec![1, 9].tree_cmp(&query, &mut tracker), Greater);
+    assert_eq!(vec![1, 0].tree_cmp(&query, &mut tracker), Less);
+    assert_eq!(vec![1, 5].tree_cmp(&query, &mut tracker), Equal);
+    // back up and compare with a key outside of the subtree bounds, the stale
+    // tracker skips the first element and gets the wrong answer
+    let key = vec![0, 7];
+    let mut stale = tracker.clone();
+    assert_eq!(key.tree_cmp(&query, &mut stale), Greater);
+    tracker.restore(snapshot);
+    assert_eq!(key.tree_cmp(&query, &mut tracker), Less);
+    assert_eq!(key.cmp(&query), Less);
+
+    // the same with tuples, where the first field gets locked
+    type T = (u8, u8, Vec<u8>);
+    let query: T = (0, 1, vec![5]);
+    let mut tracker = <T as TreeOrd>::Tracker::new();
+    let snapshot = tracker.snapshot();
+    assert_eq!((0, 2, vec![]).tree_cmp(&query, &mut tracker), Greater);
+    assert_eq!((0, 0, vec![]).tree_cmp(&query, &mut tracker), Less);
+    let key: T = (1, 0, vec![]);
+    let mut stale = tracker.clone();
+    assert_eq!(key.tree_cmp(&query, &mut stale), Less);
+    tracker.restore(snapshot);
+    assert_eq!(key.tree_cmp(&query, &mut tracker), Greater);
+    assert_eq!(key.cmp(&query), Greater);
+
+    let mut tracker = <Result<Vec<u8>, u8> as TreeOrd>::Tracker::new();
+    let snapshot = tracker.snapshot();
+    assert_eq!(
+        Ok::<Vec<u8>, u8>(vec![3, 3]).tree_cmp(&Ok(vec![3, 4]), &mut tracker),
+        Less
+    );
+    tracker.restore(snapshot);
+    assert_eq!(
+        Ok::<Vec<u8>, u8>(vec![3, 3]).tree_cmp(&Ok(vec![3, 4]), &mut tracker),
+        Less
+    );
+}
+
+#[test]
+fn tracker_fork() {
+    type T = Vec<u8>;
+    // collects all keys equal to `query` in the implicit tree over the sorted
+    // `space`
+    fn collect_equal(
+        space: &[T],
+        offset: usize,
+        query: &T,
+        tracker: &mut <T as TreeOrd>::Tracker,
+        res: &mut Vec<usize>,
+    ) {
+        if space.is_empty() {
+            return
+        }
+        let mid = space.len() / 2;
+        match space[mid].tree_cmp(query, tracker) {
+            Less => collect_equal(&space[(mid + 1)..], offset + mid + 1, query, tracker, res),
+            Equal => {
+                res.push(offset + mid);
+                let mut fork = tracker.fork();
+                collect_equal(&space[..mid], offset, query, &mut fork, res);
+                collect_equal(&space[(mid + 1)..], offset + mid + 1, query, tracker, res);
+            }
+            Greater => collect_equal(&space[..mid], offset, query, tracker, res),
+        }
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space = vec![];
+    for key in gen_bytes().into_iter().step_by(4) {
+        for _ in 0..(1 + rng.next_u64() % 4) {
+            space.push(key.clone());
+        }
+    }
+    for query in space.iter().step_by(7) {
+        let mut res = vec![];
+        collect_equal(
+            &space,
+            0,
+            query,
+            &mut <T as TreeOrd>::Tracker::new(),
+            &mut res,
+        );
+        res.sort();
+        let expected: Vec<usize> = (0..space.len()).filter(|i| space[*i] == *query).collect();
+        assert_eq!(res, expected);
+    }
+}
+
+#[test]
+fn instrumented() {
+    use tree_ord::instrument::*;
+    type T = Instrumented<Vec<Vec<InstrumentedElement<u64>>>>;
+    let space: Vec<T> = gen_nested_vecs()
+        .into_iter()
+        .map(|v| {
+            Instrumented(
+                v.into_iter()
+                    .map(|v| v.into_iter().map(|c| InstrumentedElement(c.0)).collect())
+                    .collect(),
+            )
+        })
+        .collect();
+    let mut stats = TrackerStats::default();
+    for rhs in &space {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        tree_binary_search_by(&space, |lhs| lhs.tree_cmp(rhs, &mut tracker)).unwrap();
+        let s = tracker.stats();
+        stats.tree_cmps += s.tree_cmps;
+        stats.element_cmps += s.element_cmps;
+        stats.baseline_element_cmps += s.baseline_element_cmps;
+    }
+    // the same numbers as in `nested_slices`
+    assert_eq!(
+        (stats.element_cmps, stats.baseline_element_cmps),
+        (3094085, 5301800)
+    );
+    assert_eq!(stats.skipped_element_cmps(), 5301800 - 3094085);
+    assert_eq!(stats.tree_cmps, 458769);
+}
+
+#[test]
+fn checked() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use tree_ord::checked::*;
+    type T = Checked<Vec<u8>>;
+    let space: Vec<T> = gen_bytes().into_iter().step_by(16).map(Checked).collect();
+
+    // valid searches in both orientations
+    for query in space.iter().step_by(5) {
+        // a lower bound search continues after `Equal`s
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        let mut last = None;
+        let i = space.partition_point(|k| {
+            last = Some(k.clone());
+            k.tree_cmp(query, &mut tracker) == Less
+        });
+        assert_eq!(space[i], *query);
+        // repeated comparisons
+        let last = last.unwrap();
+        last.tree_cmp(query, &mut tracker);
+        last.tree_cmp(query, &mut tracker);
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        let j = tree_binary_search_by(&space, |k| query.tree_cmp(k, &mut tracker).reverse());
+        assert_eq!(space[j.unwrap()], *query);
+        tracker.reset();
+        tree_binary_search_by(&space, |k| query.tree_cmp(k, &mut tracker).reverse()).unwrap();
+    }
+
+    let panics = |f: &dyn Fn()| catch_unwind(AssertUnwindSafe(f)).is_err();
+    let key = |v: &[u8]| Checked(v.to_vec());
+    let query = key(&[1, 5]);
+    // compares with a key outside of the bounds
+    assert!(panics(&|| {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        key(&[1, 9]).tree_cmp(&query, &mut tracker);
+        key(&[1, 0]).tree_cmp(&query, &mut tracker);
+        key(&[0, 7]).tree_cmp(&query, &mut tracker);
+    }));
+    assert!(panics(&|| {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        query.tree_cmp(&key(&[1, 0]), &mut tracker);
+        query.tree_cmp(&key(&[1, 9]), &mut tracker);
+        query.tree_cmp(&key(&[2]), &mut tracker);
+    }));
+    // reuses a tracker for a different query
+    assert!(panics(&|| {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        key(&[1, 9]).tree_cmp(&query, &mut tracker);
+        key(&[1, 0]).tree_cmp(&query, &mut tracker);
+        key(&[1, 4]).tree_cmp(&key(&[1, 6]), &mut tracker);
+    }));
+    assert!(panics(&|| {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        key(&[1, 9]).tree_cmp(&query, &mut tracker);
+        key(&[1, 0]).tree_cmp(&key(&[1, 6]), &mut tracker);
+    }));
+    // the same sequences are fine after resets
+    let mut tracker = <T as TreeOrd>::Tracker::new();
+    key(&[1, 9]).tree_cmp(&query, &mut tracker);
+    key(&[1, 0]).tree_cmp(&query, &mut tracker);
+    tracker.reset();
+    key(&[0, 7]).tree_cmp(&query, &mut tracker);
+    tracker.reset();
+    key(&[1, 4]).tree_cmp(&key(&[1, 6]), &mut tracker);
+}
+
+#[test]
+fn checked_tree_ord() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use tree_ord::checked::CheckedTreeOrd;
+
+    // the crate's own impls
+    type T = CheckedTreeOrd<(Vec<u8>, Option<Vec<u16>>)>;
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<T> = gen_bytes()
+        .into_iter()
+        .step_by(8)
+        .map(|v| {
+            let o = if (v.len() % 4) == 0 {
+                None
+            } else {
+                Some(
+                    (0..(rng.next_u64() % 4))
+                        .map(|_| (rng.next_u64() % 3) as u16)
+                        .collect(),
+                )
+            };
+            CheckedTreeOrd((v, o))
+        })
+        .collect();
+    space.sort();
+    for query in &space {
+        let found = tree_binary_search(&space, query).unwrap();
+        assert_eq!(space[found], *query);
+    }
+
+    // an impl that forgets to compare lengths when one slice is a prefix of the
+    // other
+    #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+    struct Broken(Vec<u8>);
+
+    impl TreeOrd for Broken {
+        type Tracker = ();
+
+        fn tree_cmp(&self, rhs: &Self, _: &mut ()) -> Ordering {
+            for (x, y) in self.0.iter().zip(rhs.0.iter()) {
+                match x.cmp(y) {
+                    Equal => (),
+                    o => return o,
+                }
+            }
+            Equal
+        }
+    }
+
+    let lhs = CheckedTreeOrd(Broken(vec![1, 2]));
+    assert_eq!(
+        lhs.tree_cmp(&CheckedTreeOrd(Broken(vec![1, 3])), &mut ()),
+        Less
+    );
+    assert!(catch_unwind(AssertUnwindSafe(|| {
+        lhs.tree_cmp(&CheckedTreeOrd(Broken(vec![1, 2, 3])), &mut ())
+    }))
+    .is_err());
+}
+
+#[test]
+fn narrow_index() {
+    use tree_ord::{utils::*, NarrowVec};
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    struct NarrowU8(Vec<COrd<u64>>);
+
+    impl TreeOrd for NarrowU8 {
+        type Tracker = LexicographicTracker<COrd<u64>, u8>;
+
+        fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+            lexicographic_tree_cmp(&self.0, &rhs.0, tracker)
+        }
+    }
+
+    // keys with common prefixes longer than the `u8` index maximum
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<Vec<COrd<u64>>> = (0..2048)
+        .map(|_| {
+            let len = 240 + (rng.next_u64() % 40) as usize;
+            let mut v: Vec<COrd<u64>> = (0..len).map(|_| COrd(0)).collect();
+            for _ in 0..(rng.next_u64() % 3) {
+                let i = (rng.next_u64() as usize) % len;
+                v[i] = COrd(rng.next_u64() % 2);
+            }
+            v
+        })
+        .collect();
+    space.sort();
+    let narrow: Vec<NarrowU8> = space.iter().cloned().map(NarrowU8).collect();
+    let narrow32: Vec<NarrowVec<COrd<u64>>> = space.iter().cloned().map(NarrowVec).collect();
+    let mut wide_comparisons = 0;
+    let mut narrow_comparisons = 0;
+    let mut saturated = false;
+    for (i, query) in space.iter().enumerate() {
+        let scope = CountScope::new();
+        let found = tree_binary_search(&space, query).unwrap();
+        wide_comparisons += scope.count();
+        assert_eq!(space[found], *query);
+
+        let scope = CountScope::new();
+        let mut tracker = LexicographicTracker::<COrd<u64>, u8>::new();
+        let found =
+            tree_binary_search_by(&narrow, |k| k.tree_cmp(&narrow[i], &mut tracker)).unwrap();
+        narrow_comparisons += scope.count();
+        assert_eq!(space[found], *query);
+        assert!(tracker.proven_prefix_len() <= 254);
+        saturated |= tracker.proven_prefix_len() == 254;
+
+        let found = tree_binary_search(&narrow32, &narrow32[i]).unwrap();
+        assert_eq!(space[found], *query);
+    }
+    assert!(saturated);
+    assert!(wide_comparisons < narrow_comparisons);
+
+    // saturation of seeded prefixes
+    let tracker = LexicographicTracker::<COrd<u64>, u8>::with_known_prefix(1000);
+    assert_eq!(tracker.proven_prefix_len(), 254);
+}
+
+#[test]
+fn const_tracker() {
+    type T = (u64, Vec<u8>, Result<Vec<u8>, u8>);
+
+    struct SearchContext {
+        tracker: <T as TreeOrd>::Tracker,
+        name: &'static str,
+    }
+
+    impl SearchContext {
+        const fn new(name: &'static str) -> Self {
+            Self {
+                tracker: <T as TreeOrd>::Tracker::NEW,
+                name,
+            }
+        }
+    }
+
+    static CONTEXT: SearchContext = SearchContext::new("tuples");
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<T> = gen_bytes()
+        .into_iter()
+        .step_by(16)
+        .map(|v| {
+            let r = if (v.len() % 4) == 0 {
+                Err(v.len() as u8)
+            } else {
+                Ok(v.clone())
+            };
+            (rng.next_u64() % 4, v, r)
+        })
+        .collect();
+    space.sort();
+    assert_eq!(CONTEXT.name, "tuples");
+    for query in space.iter().step_by(3) {
+        let mut tracker = CONTEXT.tracker.clone();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker)).unwrap();
+        assert_eq!(space[found], *query);
+    }
+}
+
+#[test]
+fn small_noop_tuples() {
+    type T = Vec<(u32, u16)>;
+    const {
+        assert!(<(u32, u16) as TreeOrd>::Tracker::IS_NOOP);
+        // too large, or with a field that has a tracker
+        assert!(!<(u64, u64) as TreeOrd>::Tracker::IS_NOOP);
+        assert!(!<(COrd<u64>, COrd<u64>) as TreeOrd>::Tracker::IS_NOOP);
+        assert!(!<(u8, Vec<u8>) as TreeOrd>::Tracker::IS_NOOP);
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<T> = (0..(1 << 12))
+        .map(|_| {
+            let len = (rng.next_u32() % 32) as usize;
+            (0..len)
+                .map(|_| (rng.next_u32() % 2, (rng.next_u32() % 2) as u16))
+                .collect()
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    for query in &space {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker));
+        assert_eq!(found, space.binary_search(query));
+        for k in space.iter().step_by(61) {
+            let mut tracker = <(u32, u16) as TreeOrd>::Tracker::new();
+            if let (Some(a), Some(b)) = (k.first(), query.first()) {
+                assert_eq!(a.tree_cmp(b, &mut tracker), a.cmp(b));
+            }
+        }
+    }
+}
+
+#[test]
+fn hereditary() {
+    // a multimap with long runs of equal keys that share long prefixes
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<Vec<COrd<u64>>> = vec![];
+    for _ in 0..(1 << 8) {
+        let mut key: Vec<COrd<u64>> = (0..16).map(|_| COrd(0)).collect();
+        for _ in 0..4 {
+            key.push(COrd(rng.next_u64() % 4));
+        }
+        for _ in 0..(1 + (rng.next_u64() % 32)) {
+            space.push(key.clone());
+        }
+    }
+    space.sort();
+    let mut queries = space.clone();
+    queries.dedup();
+
+    let mut normal = 0;
+    let mut hereditary = 0;
+    for query in &queries {
+        let expected = space.partition_point(|k| k < query);
+        let scope = CountScope::new();
+        assert_eq!(tree_lower_bound(&space, query), expected);
+        normal += scope.count();
+        let scope = CountScope::new();
+        let lower = tree_lower_bound_hereditary(&space, query);
+        hereditary += scope.count();
+        assert_eq!(lower, expected);
+
+        // the equal range scan continues past the run with a separate tracker
+        let upper = lower + tree_upper_bound(&space[lower..], query);
+        assert_eq!(upper, space.partition_point(|k| k <= query));
+        assert!(space[lower..upper].iter().all(|k| k == query));
+    }
+    assert_eq!((normal, hereditary), (12540, 11086));
+
+    // wrappers forward to the hereditary comparisons of their fields
+    fn counts<K: TreeOrd>(space: &[K], queries: &[K]) -> (u64, u64) {
+        let mut normal = 0;
+        let mut hereditary = 0;
+        for query in queries {
+            let scope = CountScope::new();
+            let lower = tree_lower_bound(space, query);
+            normal += scope.count();
+            let scope = CountScope::new();
+            assert_eq!(tree_lower_bound_hereditary(space, query), lower);
+            hereditary += scope.count();
+        }
+        (normal, hereditary)
+    }
+    let wrap = |v: &Vec<Vec<COrd<u64>>>| -> Vec<_> { v.iter().map(|k| Some(k.clone())).collect() };
+    assert_eq!(counts(&wrap(&space), &wrap(&queries)), (12540, 11086));
+    let wrap = |v: &Vec<Vec<COrd<u64>>>| -> Vec<Result<_, ()>> {
+        v.iter().map(|k| Ok(k.clone())).collect()
+    };
+    assert_eq!(counts(&wrap(&space), &wrap(&queries)), (12540, 11086));
+    let wrap =
+        |v: &Vec<Vec<COrd<u64>>>| -> Vec<_> { v.iter().map(|k| RefCell::new(k.clone())).collect() };
+    assert_eq!(counts(&wrap(&space), &wrap(&queries)), (12540, 11086));
+    let wrap =
+        |v: &Vec<Vec<COrd<u64>>>| -> Vec<_> { v.iter().map(|k| (0u8, k.clone(), 0u8)).collect() };
+    // the tuple resets the subtracker of the middle field until both of its
+    // bounds are on that field
+    assert_eq!(counts(&wrap(&space), &wrap(&queries)), (19569, 16287));
+
+    // small tuples of untracked fields use plain `Ord`
+    let mut tracker = <(u8, u8) as TreeOrd>::Tracker::new();
+    assert_eq!((1, 2).tree_cmp_hereditary(&(1, 2), &mut tracker), Equal);
+}
+
+#[test]
+fn hereditary_misuse() {
+    // Scanning forwards past a run of equal keys with a hereditary tracker is
+    // not allowed. The `Equal` is recorded as if it were a `Greater`, so the
+    // tracker claims that the first byte is shared with every key up to the
+    // query, and a key after the run gets compared only by its second byte.
+    let query: &[u8] = &[1, 5];
+    let mut tracker = <[u8] as TreeOrd>::Tracker::new();
+    assert_eq!(
+        [1u8, 4].as_slice().tree_cmp_hereditary(query, &mut tracker),
+        Less
+    );
+    assert_eq!(
+        [1u8, 5].as_slice().tree_cmp_hereditary(query, &mut tracker),
+        Equal
+    );
+    assert_eq!(tracker.proven_prefix_len(), 1);
+    // wrong, `[2, 0]` is greater than `[1, 5]`
+    assert_eq!(
+        [2u8, 0].as_slice().tree_cmp_hereditary(query, &mut tracker),
+        Less
+    );
+
+    // with `tree_cmp` the `Equal` does not strengthen anything
+    let mut tracker = <[u8] as TreeOrd>::Tracker::new();
+    assert_eq!([1u8, 4].as_slice().tree_cmp(query, &mut tracker), Less);
+    assert_eq!([1u8, 5].as_slice().tree_cmp(query, &mut tracker), Equal);
+    assert_eq!(tracker.proven_prefix_len(), 0);
+    assert_eq!([2u8, 0].as_slice().tree_cmp(query, &mut tracker), Greater);
+
+    // the same happens with `TreeOrdBytes` once whole chunks are shared
+    let mut lhs = vec![7u8; 64];
+    let mut query = lhs.clone();
+    query[40] = 8;
+    let mut tracker = <TreeOrdBytes as TreeOrd>::Tracker::new();
+    assert_eq!(
+        TreeOrdBytes(&lhs).tree_cmp_hereditary(&TreeOrdBytes(&query), &mut tracker),
+        Less
+    );
+    assert_eq!(
+        TreeOrdBytes(&query).tree_cmp_hereditary(&TreeOrdBytes(&query), &mut tracker),
+        Equal
+    );
+    lhs[0] = 8;
+    lhs[40] = 0;
+    assert_eq!(
+        TreeOrdBytes(&lhs).tree_cmp_hereditary(&TreeOrdBytes(&query), &mut tracker),
+        Less
+    );
+    assert_eq!(lhs.cmp(&query), Greater);
+
+    // The `Equal` is recorded for the key being on the left hand side. With the
+    // query on the left, the keys greater than the query return `Less` and the
+    // recorded `Equal` lands on their side of the tracker.
+    let query: Vec<u64> = vec![1, 5, 5];
+    let mut tracker = <Vec<u64> as TreeOrd>::Tracker::new();
+    assert_eq!(
+        query.tree_cmp_hereditary(&vec![1, 5, 9], &mut tracker),
+        Less
+    );
+    assert_eq!(
+        query.tree_cmp_hereditary(&vec![1, 0, 0], &mut tracker),
+        Greater
+    );
+    assert_eq!(
+        query.tree_cmp_hereditary(&vec![1, 5, 5], &mut tracker),
+        Equal
+    );
+    // wrong, `[1, 5, 5]` is greater than `[1, 3, 5]`
+    assert_eq!(
+        query.tree_cmp_hereditary(&vec![1, 3, 5], &mut tracker),
+        Equal
+    );
+    let query: &[u8] = &[1, 5, 5];
+    let mut tracker = <[u8] as TreeOrd>::Tracker::new();
+    assert_eq!(query.tree_cmp_hereditary(&[1, 5, 9], &mut tracker), Less);
+    assert_eq!(query.tree_cmp_hereditary(&[1, 0, 0], &mut tracker), Greater);
+    assert_eq!(query.tree_cmp_hereditary(&[1, 5, 5], &mut tracker), Equal);
+    assert_eq!(query.tree_cmp_hereditary(&[1, 3, 5], &mut tracker), Equal);
+    // the same search with the key on the left hand side is correct
+    let mut tracker = <[u8] as TreeOrd>::Tracker::new();
+    assert_eq!(
+        [1u8, 5, 9]
+            .as_slice()
+            .tree_cmp_hereditary(query, &mut tracker),
+        Greater
+    );
+    assert_eq!(
+        [1u8, 0, 0]
+            .as_slice()
+            .tree_cmp_hereditary(query, &mut tracker),
+        Less
+    );
+    assert_eq!(
+        [1u8, 5, 5]
+            .as_slice()
+            .tree_cmp_hereditary(query, &mut tracker),
+        Equal
+    );
+    assert_eq!(
+        [1u8, 3, 5]
+            .as_slice()
+            .tree_cmp_hereditary(query, &mut tracker),
+        Less
+    );
+}
+
+#[test]
+fn checked_hereditary() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use tree_ord::checked::*;
+    type T = Checked<Vec<u64>>;
+    let panics = |f: &dyn Fn()| catch_unwind(AssertUnwindSafe(f)).is_err();
+    let key = |v: &[u64]| Checked(v.to_vec());
+    let query = key(&[1, 5]);
+    // the query on the left hand side is rejected before the wrong result
+    assert!(panics(&|| {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        query.tree_cmp_hereditary(&key(&[1, 5, 9]), &mut tracker);
+        query.tree_cmp_hereditary(&key(&[1, 0]), &mut tracker);
+    }));
+    assert!(panics(&|| {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        query.tree_cmp(&key(&[1, 5, 9]), &mut tracker);
+        query.tree_cmp(&key(&[1, 0]), &mut tracker);
+        query.tree_cmp_hereditary(&key(&[1, 5]), &mut tracker);
+    }));
+    // continuing above a hereditary `Equal` is rejected
+    assert!(panics(&|| {
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        key(&[1, 4]).tree_cmp_hereditary(&query, &mut tracker);
+        key(&[1, 5]).tree_cmp_hereditary(&query, &mut tracker);
+        key(&[2, 0]).tree_cmp_hereditary(&query, &mut tracker);
+    }));
+    // with the key on the left hand side
+    let mut tracker = <T as TreeOrd>::Tracker::new();
+    assert_eq!(
+        key(&[1, 5, 9]).tree_cmp_hereditary(&query, &mut tracker),
+        Greater
+    );
+    assert_eq!(key(&[1, 0]).tree_cmp_hereditary(&query, &mut tracker), Less);
+    assert_eq!(
+        key(&[1, 5]).tree_cmp_hereditary(&query, &mut tracker),
+        Equal
+    );
+    assert_eq!(key(&[1, 3]).tree_cmp_hereditary(&query, &mut tracker), Less);
+    // hereditary lower bound searches
+    let space: Vec<T> = gen_bytes()
+        .into_iter()
+        .step_by(16)
+        .map(|v| Checked(v.into_iter().map(u64::from).collect()))
+        .collect();
+    for query in space.iter().step_by(5) {
+        assert_eq!(
+            tree_lower_bound_hereditary(&space, query),
+            space.partition_point(|k| k < query)
+        );
+    }
+}
+
+#[test]
+fn proven_prefix() {
+    use tree_ord::prefix::*;
+
+    assert_eq!(().proven_prefix(), None);
+    assert_eq!(
+        <(u8, Vec<u8>) as TreeOrd>::Tracker::new().proven_prefix(),
+        None
+    );
+
+    // A toy radix tree descent, where every node is a range of the sorted keys
+    // that knows the prefix shared by all of its keys. Checking if the query
+    // matches the node prefix only needs to start from the proven prefix.
+    fn descend<K: TreeOrd>(space: &[K], query: &K, bytes: fn(&K) -> &[u8]) -> usize {
+        let mut tracker = K::Tracker::new();
+        let mut skipped = 0;
+        let (mut lo, mut hi) = (0, space.len());
+        while lo < hi {
+            let proven = tracker.proven_prefix().unwrap();
+            let node: Vec<&[u8]> = space[lo..hi].iter().map(bytes).collect();
+            let node_prefix = bytes_range_common_prefix_len(&node);
+            let q = bytes(query);
+            // recomputed from scratch
+            let shared = min(
+                common_prefix_len(node[0], q),
+                common_prefix_len(node[node.len() - 1], q),
+            );
+            assert!(proven <= shared);
+            let matches = q.len() >= node_prefix && q[..node_prefix] == node[0][..node_prefix];
+            if proven <= node_prefix {
+                let matches_from_proven = q.len() >= node_prefix
+                    && q[proven..node_prefix] == node[0][proven..node_prefix];
+                assert_eq!(matches_from_proven, matches);
+                skipped += proven;
+            }
+            let mid = lo + (hi - lo) / 2;
+            match space[mid].tree_cmp(query, &mut tracker) {
+                Less => lo = mid + 1,
+                Equal => return skipped,
+                Greater => hi = mid,
+            }
+        }
+        skipped
+    }
+
+    let space: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(16).collect();
+    let mut skipped = 0;
+    for query in space.iter().step_by(7) {
+        skipped += descend(&space, query, |k| k);
+    }
+    assert!(skipped > 0);
+
+    // the proven prefix of `TreeOrdVec` is in bytes
+    let space: Vec<TreeOrdVec> = space.into_iter().map(TreeOrdVec).collect();
+    let mut skipped_bytes = 0;
+    for query in space.iter().step_by(7) {
+        skipped_bytes += descend(&space, query, |k| &k.0);
+    }
+    assert!(skipped_bytes > 0);
+}
+
+#[test]
+fn length_resolved() {
+    // all the keys less than a query are strict prefixes of it, so the `Less`
+    // comparisons are all resolved by length
+    let base: Vec<COrd<u64>> = (0..256).map(|_| COrd(1)).collect();
+    let mut space: Vec<Vec<COrd<u64>>> = vec![];
+    for len in 0..base.len() {
+        space.push(base[..len].to_vec());
+        let mut diverging = base[..len].to_vec();
+        diverging.push(COrd(2));
+        space.push(diverging);
+    }
+    space.sort();
+    let queries: Vec<Vec<COrd<u64>>> = (0..base.len())
+        .map(|len| {
+            let mut q = base[..len].to_vec();
+            q.push(COrd(0));
+            q
+        })
+        .collect();
+
+    let scope = CountScope::new();
+    for query in &queries {
+        space.binary_search(query).unwrap_err();
+    }
+    let ord = scope.count();
+    let scope = CountScope::new();
+    for query in &queries {
+        let res = tree_binary_search(&space, query);
+        assert_eq!(res, space.binary_search(query));
+    }
+    let tree = scope.count() - ord;
+    assert_eq!((ord, tree), (311872, 82368));
+
+    // the same with bytes
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let base: Vec<u8> = (0..1024).map(|_| 1 + (rng.next_u32() % 2) as u8).collect();
+    let mut space: Vec<TreeOrdVec> = vec![];
+    for len in 0..base.len() {
+        space.push(TreeOrdVec(base[..len].to_vec()));
+        let mut diverging = base[..len].to_vec();
+        diverging.push(4);
+        space.push(TreeOrdVec(diverging));
+    }
+    space.sort();
+    for len in (0..base.len()).step_by(3) {
+        let mut query = base[..len].to_vec();
+        query.push(0);
+        let query = TreeOrdVec(query);
+        let mut tracker = <TreeOrdVec as TreeOrd>::Tracker::new();
+        let res = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        assert_eq!(res, space.binary_search(&query));
+        // the last `Less` comparison was with the longest strict prefix
+        assert_eq!(tracker.max_eq_len(), len);
+    }
+}
+
+#[test]
+fn bytes_chunk_index() {
+    // keys that share many leading chunks, so that the proven prefix starts
+    // past the first chunk for most comparisons
+    const CHUNK_LEN: usize = TreeOrdBytes::CHUNK_LEN;
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<TreeOrdVec> = (0..(1 << 12))
+        .map(|_| {
+            let mut v = vec![7u8; 8 * CHUNK_LEN];
+            for _ in 0..(rng.next_u32() % (8 * CHUNK_LEN as u32)) {
+                v.push((rng.next_u32() % 2) as u8);
+            }
+            TreeOrdVec(v)
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+
+    // the number of bytes that get compared, which is everything after the
+    // proven prefix
+    let mut scanned = 0;
+    for query in &space {
+        let mut tracker = <TreeOrdVec as TreeOrd>::Tracker::new();
+        let mut proven = 0;
+        let found = tree_binary_search_by(&space, |k| {
+            let end = min(k.0.len(), query.0.len());
+            scanned += end - min(tracker.proven_prefix_len(), end);
+            let res = k.tree_cmp(query, &mut tracker);
+            // the proven prefix never decreases during a binary search
+            assert!(tracker.proven_prefix_len() >= proven);
+            proven = tracker.proven_prefix_len();
+            res
+        });
+        assert_eq!(space[found.unwrap()], *query);
+    }
+    assert_eq!(scanned, 6669380);
+}
+
+#[test]
+fn bytes_tail_tracking() {
+    // medium length keys with a header that is not a multiple of the chunk
+    // length, that mostly diverge in the middle of chunks and in the partial
+    // chunks at their ends
+    let header = [9u8; 45];
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<TreeOrdVec> = (0..(1 << 12))
+        .map(|_| {
+            let len = (rng.next_u32() % 160) as usize;
+            let mut v = vec![0u8; len];
+            for _ in 0..4 {
+                if len != 0 {
+                    let i = (rng.next_u32() as usize) % len;
+                    v[i] = (rng.next_u32() % 3) as u8;
+                }
+            }
+            TreeOrdVec([&header[..], &v].concat())
+        })
+        .collect();
+    space.sort();
+    let mut nonmembers = vec![];
+    for k in space.iter().step_by(5) {
+        let mut v = k.0.clone();
+        let i = (rng.next_u32() as usize) % v.len();
+        v[i] = v[i].wrapping_add(1);
+        v.truncate(v.len() - (rng.next_u32() as usize % 3));
+        nonmembers.push(TreeOrdVec(v));
+    }
+    for query in space.iter().chain(nonmembers.iter()) {
+        // every comparison of the search must agree with `Ord`
+        let mut tracker = <TreeOrdVec as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        });
+        let lower = space.partition_point(|k| k < query);
+        match found {
+            Ok(i) => assert_eq!(space[i], *query),
+            Err(i) => assert_eq!(i, lower),
+        }
+        assert_eq!(tree_lower_bound_hereditary(&space, query), lower);
+
+        if query.0.starts_with(&header) {
+            let mut tracker = TreeOrdBytes::tracker_with_known_prefix(header.len());
+            let res = space.partition_point(|k| {
+                let res = k.tree_cmp(query, &mut tracker);
+                assert_eq!(res, k.cmp(query));
+                res == Less
+            });
+            assert_eq!(res, lower);
+        }
+    }
+}
+
+#[test]
+fn bytes_divergence() {
+    // the `simd` feature must find the same diverging bytes as the scalar path
+    fn check(lhs: &[u8], rhs: &[u8], known: usize) {
+        let mut tracker = TreeOrdBytes::tracker_with_known_prefix(known);
+        let res = TreeOrdBytes(lhs).tree_cmp(&TreeOrdBytes(rhs), &mut tracker);
+        assert_eq!(res, lhs.cmp(rhs));
+        let diverge = tree_ord::prefix::common_prefix_len(lhs, rhs);
+        if diverge < min(lhs.len(), rhs.len()) {
+            match res {
+                Less => assert_eq!(tracker.max_eq_len(), diverge),
+                Equal => unreachable!(),
+                Greater => assert_eq!(tracker.min_eq_len(), diverge),
+            }
+        }
+    }
+
+    // adversarial, diverging at every position around chunk boundaries with
+    // unaligned known prefixes
+    let len = 3 * TreeOrdBytes::CHUNK_LEN + 5;
+    for diverge in 0..len {
+        for known in [0, diverge / 2, diverge.saturating_sub(1), diverge] {
+            let lhs = vec![0x55u8; len];
+            let mut rhs = lhs.clone();
+            rhs[diverge] = 0x56;
+            check(&lhs, &rhs, known);
+            check(&rhs, &lhs, known);
+            // differences in the high and low bits of the bytes
+            rhs[diverge] = 0xd5;
+            check(&lhs, &rhs, known);
+            check(&rhs, &lhs, known);
+            // only the first difference matters
+            for i in (diverge + 1)..len {
+                rhs[i] = 0;
+            }
+            check(&lhs, &rhs, known);
+            check(&rhs, &lhs, known);
+            check(&lhs[..(len - 3)], &rhs, known);
+        }
+    }
+
+    // random
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    for _ in 0..(1 << 14) {
+        let len = (rng.next_u32() % 200) as usize;
+        let lhs: Vec<u8> = (0..len).map(|_| (rng.next_u32() % 2) as u8).collect();
+        let mut rhs = lhs.clone();
+        if len != 0 {
+            let i = (rng.next_u32() as usize) % len;
+            rhs[i] = rng.next_u32() as u8;
+            rhs.truncate(len - (rng.next_u32() as usize % (len - i)));
+        }
+        check(&lhs, &rhs, 0);
+    }
+}
+
+#[test]
+fn u8_fast_path() {
+    // `Vec<u8>` uses the same comparison as `TreeOrdVec`, so searches on long
+    // keys make the same comparisons and leave the trackers in the same states
+    let space: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(4).collect();
+    let bytes_space: Vec<TreeOrdVec> = space.iter().cloned().map(TreeOrdVec).collect();
+    for (query, bytes_query) in space.iter().zip(bytes_space.iter()).step_by(3) {
+        let mut states = vec![];
+        let mut tracker = <TreeOrdVec as TreeOrd>::Tracker::new();
+        let expected = tree_binary_search_by(&bytes_space, |k| {
+            let res = k.tree_cmp(bytes_query, &mut tracker);
+            states.push((res, tracker.min_eq_len(), tracker.max_eq_len()));
+            res
+        });
+        let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+        let mut i = 0;
+        let found = tree_binary_search_by(&space, |k| {
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!((res, tracker.min_eq_len(), tracker.max_eq_len()), states[i]);
+            i += 1;
+            res
+        });
+        assert_eq!(i, states.len());
+        assert_eq!(found, expected);
+    }
+
+    // `i8` has the same layout as `u8` but is ordered differently
+    let mut tracker = <[i8] as TreeOrd>::Tracker::new();
+    assert_eq!([-1i8, 0].as_slice().tree_cmp(&[1, 0], &mut tracker), Less);
+}
+
+#[test]
+fn divergence_subtracker() {
+    // keys whose last element is a long vector, so that keys diverge deep in
+    // the outer vector after some equal elements
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<Vec<Vec<COrd<u64>>>> = (0..(1 << 12))
+        .map(|_| {
+            let mut key = vec![vec![COrd(0)]; 1 + (rng.next_u64() % 2) as usize];
+            let mut last: Vec<COrd<u64>> = (0..64).map(|_| COrd(0)).collect();
+            for _ in 0..3 {
+                let i = 32 + (rng.next_u64() as usize) % 32;
+                last[i] = COrd(rng.next_u64() % 2);
+            }
+            key.push(last);
+            key
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+
+    let mut tree_comparisons = 0;
+    let mut comparisons = 0;
+    for query in &space {
+        let scope = CountScope::new();
+        let found = tree_binary_search(&space, query);
+        tree_comparisons += scope.count();
+        let scope = CountScope::new();
+        let expected = tree_binary_search_by(&space, |k| k.cmp(query));
+        comparisons += scope.count();
+        assert_eq!(found, expected);
+    }
+    // without moving the subtracker to the diverging element this was 358832
+    assert_eq!((tree_comparisons, comparisons), (313348, 557214));
+}
+
+#[test]
+fn u128_byte_prefix() {
+    // the bytes after the proven prefix are the comparison proxy, a plain
+    // comparison always looks at all 16 bytes
+    fn search<K: TreeOrd<Tracker = tree_ord::utils::LexicographicTracker<u8, u8>> + Ord>(
+        space: &[K],
+        query: &K,
+        compared: &mut (usize, usize),
+    ) -> Result<usize, usize> {
+        let mut tracker = K::Tracker::new();
+        tree_binary_search_by(space, |k| {
+            compared.0 += 16 - tracker.proven_prefix_len();
+            compared.1 += 16;
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        })
+    }
+
+    // clusters of keys sharing their high bytes, like IPv6 addresses in a few
+    // subnets
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let clusters: Vec<u128> = (0..4)
+        .map(|_| (u128::from(rng.next_u64()) << 64) | (u128::from(rng.next_u32()) << 32))
+        .collect();
+    let mut space: Vec<u128> = (0..(1 << 12))
+        .map(|i| clusters[i % clusters.len()] | u128::from(rng.next_u32() >> (i % 32)))
+        .collect();
+    space.sort();
+    space.dedup();
+    let tree_space: Vec<TreeOrdU128> = space.iter().map(|x| TreeOrdU128(*x)).collect();
+    let mut compared = (0, 0);
+    for x in &space {
+        for query in [x.wrapping_sub(1), *x, x.wrapping_add(1)] {
+            let found = search(&tree_space, &TreeOrdU128(query), &mut compared);
+            assert_eq!(found, space.binary_search(&query));
+        }
+    }
+    assert_eq!(compared, (871421, 1807184));
+
+    // clusters on both sides of zero and at the extremes, where the sign
+    // adjustment matters
+    let clusters: [i128; 5] = [i128::MIN, -(1 << 100), -(1 << 20), 0, i128::MAX - (1 << 33)];
+    let mut space: Vec<i128> = (0..(1 << 12))
+        .map(|i| {
+            let low = i128::from(rng.next_u32() >> (i % 32));
+            clusters[i % clusters.len()].saturating_add(low - (1 << 31))
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    let tree_space: Vec<TreeOrdI128> = space.iter().map(|x| TreeOrdI128(*x)).collect();
+    let mut compared = (0, 0);
+    for x in &space {
+        for query in [x.wrapping_sub(1), *x, x.wrapping_add(1)] {
+            let found = search(&tree_space, &TreeOrdI128(query), &mut compared);
+            assert_eq!(found, space.binary_search(&query));
+        }
+    }
+    assert_eq!(compared, (718613, 1453424));
+
+    // hereditary lower bounds
+    for x in &space {
+        assert_eq!(
+            tree_lower_bound_hereditary(&tree_space, &TreeOrdI128(*x)),
+            space.partition_point(|k| k < x)
+        );
+    }
+}
+
+#[test]
+fn tree_ord_str() {
+    // multibyte chars straddling chunk boundaries, diverging in their first and
+    // continuation bytes
+    let chars = ['a', 'é', 'ê', 'ß', '€', '中', '😀', '😁'];
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<String> = vec![];
+    for pad in (TreeOrdBytes::CHUNK_LEN - 4)..=(TreeOrdBytes::CHUNK_LEN + 1) {
+        for c0 in chars {
+            for _ in 0..8 {
+                let mut s = "x".repeat(pad);
+                s.push(c0);
+                for _ in 0..(rng.next_u32() % 40) {
+                    s.push(chars[(rng.next_u32() as usize) % chars.len()]);
+                }
+                space.push(s);
+            }
+        }
+    }
+    space.sort();
+    space.dedup();
+    let owned: Vec<TreeOrdString> = space
+        .iter()
+        .map(|s| TreeOrdString::from(s.as_str()))
+        .collect();
+    let borrowed: Vec<TreeOrdStr> = space.iter().map(|s| TreeOrdStr(s)).collect();
+    for lhs in &space {
+        for rhs in &space {
+            let mut tracker = <TreeOrdStr as TreeOrd>::Tracker::new();
+            let res = TreeOrdStr(lhs).tree_cmp(&TreeOrdStr(rhs), &mut tracker);
+            assert_eq!(res, lhs.cmp(rhs));
+            let diverge = tree_ord::prefix::common_prefix_len(lhs.as_bytes(), rhs.as_bytes());
+            match res {
+                Less => assert_eq!(tracker.max_eq_len(), diverge),
+                Equal => (),
+                Greater => assert_eq!(tracker.min_eq_len(), diverge),
+            }
+        }
+    }
+    for (i, query) in space.iter().enumerate() {
+        // probed by a plain `&str`
+        assert_eq!(tree_binary_search(&owned, query.as_str()), Ok(i));
+        assert_eq!(tree_binary_search(&borrowed, query.as_str()), Ok(i));
+        assert_eq!(&*borrowed[i], &*owned[i]);
+        let mut prefix = query.clone();
+        prefix.pop();
+        assert_eq!(
+            tree_lower_bound_hereditary(&borrowed, prefix.as_str()),
+            space.partition_point(|k| *k < prefix)
+        );
+    }
+}
+
+#[test]
+fn chunked_ints() {
+    use tree_ord::utils::LexicographicTracker;
+
+    fn check<T: TreeOrd + Ord + Copy>(
+        lhs: &[T],
+        rhs: &[T],
+        known: usize,
+        f: impl Fn(&[T], &[T], &mut LexicographicTracker<T>) -> Ordering,
+    ) {
+        let mut tracker = LexicographicTracker::with_known_prefix(known);
+        let res = f(lhs, rhs, &mut tracker);
+        assert_eq!(res, lhs.cmp(rhs));
+        let diverge = lhs.iter().zip(rhs).take_while(|(a, b)| a == b).count();
+        if diverge < min(lhs.len(), rhs.len()) {
+            match res {
+                Less => assert_eq!(tracker.max_eq_len(), diverge),
+                Equal => unreachable!(),
+                Greater => assert_eq!(tracker.min_eq_len(), diverge),
+            }
+        }
+    }
+
+    // diverging at every position around chunk boundaries, with values whose
+    // native byte order differs from their numeric order
+    fn adversarial<T: TreeOrd + Ord + Copy>(
+        chunk_len: usize,
+        lo: T,
+        hi: T,
+        f: impl Fn(&[T], &[T], &mut LexicographicTracker<T>) -> Ordering + Copy,
+    ) {
+        let len = 3 * chunk_len + 1;
+        for diverge in 0..len {
+            for known in [0, diverge / 2, diverge] {
+                let lhs = vec![lo; len];
+                let mut rhs = lhs.clone();
+                rhs[diverge] = hi;
+                check(&lhs, &rhs, known, f);
+                check(&rhs, &lhs, known, f);
+                check(&lhs[..diverge], &rhs, known, f);
+                check(&rhs, &lhs[..(len - 1)], known, f);
+            }
+        }
+    }
+    adversarial(TreeOrdU16s::CHUNK_LEN, 0x00ffu16, 0x0100, |x, y, t| {
+        TreeOrdU16s(x).tree_cmp(&TreeOrdU16s(y), t)
+    });
+    adversarial(
+        TreeOrdU32s::CHUNK_LEN,
+        0x0000_00ffu32,
+        0x0000_0100,
+        |x, y, t| TreeOrdU32s(x).tree_cmp(&TreeOrdU32s(y), t),
+    );
+    adversarial(TreeOrdU64s::CHUNK_LEN, 0xffu64, 1 << 56, |x, y, t| {
+        TreeOrdU64s(x).tree_cmp(&TreeOrdU64s(y), t)
+    });
+
+    // searches make the same comparisons as the `[u64]` impl
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<Vec<u64>> = (0..(1 << 12))
+        .map(|_| {
+            let len = (rng.next_u64() % 64) as usize;
+            let mut v = vec![u64::MAX / 2; len];
+            for _ in 0..3 {
+                if len != 0 {
+                    v[(rng.next_u64() as usize) % len] = rng.next_u64() % 3;
+                }
+            }
+            v
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    let chunked: Vec<TreeOrdU64s> = space.iter().map(|v| TreeOrdU64s(v)).collect();
+    for (query, chunked_query) in space.iter().zip(chunked.iter()).step_by(7) {
+        let mut states = vec![];
+        let mut tracker = <[u64] as TreeOrd>::Tracker::new();
+        let expected = tree_binary_search_by(&space, |k| {
+            let res = k.as_slice().tree_cmp(query, &mut tracker);
+            states.push((res, tracker.min_eq_len(), tracker.max_eq_len()));
+            res
+        });
+        let mut tracker = <TreeOrdU64s as TreeOrd>::Tracker::new();
+        let mut i = 0;
+        let found = tree_binary_search_by(&chunked, |k| {
+            let res = k.tree_cmp(chunked_query, &mut tracker);
+            assert_eq!((res, tracker.min_eq_len(), tracker.max_eq_len()), states[i]);
+            i += 1;
+            res
+        });
+        assert_eq!(found, expected);
+        assert_eq!(
+            tree_lower_bound_hereditary(&chunked, chunked_query),
+            space.partition_point(|k| k < query)
+        );
+    }
+}
+
+/// Keys from 16 bytes to 4 KiB that share long prefixes
+fn gen_mixed_len_bytes(n: usize) -> Vec<Vec<u8>> {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<Vec<u8>> = (0..n)
+        .map(|_| {
+            let len = 16 << (rng.next_u32() % 9);
+            let mut key = vec![0u8; len];
+            for _ in 0..2 {
+                let i = (rng.next_u32() as usize) % len;
+                key[i] = (rng.next_u32() % 4) as u8;
+            }
+            key
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    space
+}
+
+#[test]
+fn bytes_adaptive() {
+    use tree_ord::utils::AdaptiveBytesTracker;
+
+    // the adaptive tracker makes the same comparisons as `TreeOrdBytes` and
+    // leaves its byte tracker in the same states
+    let space = gen_mixed_len_bytes(1 << 12);
+    let bytes: Vec<TreeOrdBytes> = space.iter().map(|k| TreeOrdBytes(k)).collect();
+    let adaptive: Vec<TreeOrdBytesAdaptive> =
+        space.iter().map(|k| TreeOrdBytesAdaptive(k)).collect();
+    let mut adapted = 0;
+    for (i, query) in space.iter().enumerate() {
+        let mut states = vec![];
+        let mut tracker = <TreeOrdBytes as TreeOrd>::Tracker::new();
+        let expected = tree_binary_search_by(&bytes, |k| {
+            let res = k.tree_cmp(&TreeOrdBytes(query), &mut tracker);
+            states.push((res, tracker.min_eq_len(), tracker.max_eq_len()));
+            res
+        });
+        assert_eq!(expected, Ok(i));
+        let mut tracker = AdaptiveBytesTracker::new();
+        let mut j = 0;
+        let found = tree_binary_search_by(&adaptive, |k| {
+            let res = k.tree_cmp(&TreeOrdBytesAdaptive(query), &mut tracker);
+            let inner = tracker.inner();
+            assert_eq!((res, inner.min_eq_len(), inner.max_eq_len()), states[j]);
+            assert_eq!(tracker.proven_prefix(), Some(inner.proven_prefix_len()));
+            j += 1;
+            res
+        });
+        assert_eq!(found, expected);
+        if tracker.stride() == AdaptiveBytesTracker::LARGE_STRIDE {
+            adapted += 1;
+        }
+        assert_eq!(
+            tree_lower_bound_hereditary(
+                &adaptive,
+                &TreeOrdBytesAdaptive(&query[..(query.len() - 1)])
+            ),
+            space.partition_point(|k| k.as_slice() < &query[..(query.len() - 1)])
+        );
+    }
+    // only searches that proved a long prefix switched strides
+    assert!(adapted > 0 && adapted < space.len());
+
+    // snapshots include the stride
+    let mut tracker = AdaptiveBytesTracker::with_known_prefix(AdaptiveBytesTracker::ADAPT_LEN);
+    let snapshot = tracker.snapshot();
+    let long = vec![0u8; 4096];
+    TreeOrdBytesAdaptive(&long).tree_cmp(&TreeOrdBytesAdaptive(&long[..4000]), &mut tracker);
+    assert_eq!(tracker.stride(), AdaptiveBytesTracker::LARGE_STRIDE);
+    tracker.restore(snapshot);
+    assert_eq!(tracker.stride(), AdaptiveBytesTracker::SMALL_STRIDE);
+}
+
+#[test]
+fn bytes_misaligned() {
+    // the same orderings and diverging bytes no matter how the keys are aligned
+    // in memory
+    let len = 4 * TreeOrdBytes::CHUNK_LEN;
+    let mut buf0 = vec![0x33u8; len + 8];
+    let mut buf1 = buf0.clone();
+    for diverge in 0..len {
+        for (off0, off1) in [(0, 0), (1, 1), (3, 3), (1, 3), (3, 0), (7, 5)] {
+            buf0.fill(0x33);
+            buf1.fill(0x33);
+            buf1[off1 + diverge] = 0x34;
+            let lhs = &buf0[off0..(off0 + len)];
+            let rhs = &buf1[off1..(off1 + len)];
+            for known in [0, diverge / 3, diverge] {
+                for (x, y) in [(lhs, rhs), (rhs, lhs), (lhs, &rhs[..diverge])] {
+                    let mut tracker = TreeOrdBytes::tracker_with_known_prefix(known);
+                    let res = TreeOrdBytes(x).tree_cmp(&TreeOrdBytes(y), &mut tracker);
+                    assert_eq!(res, x.cmp(y));
+                    match res {
+                        Less => assert_eq!(tracker.max_eq_len(), diverge),
+                        Equal => unreachable!(),
+                        Greater => assert_eq!(tracker.min_eq_len(), diverge),
+                    }
+                }
+            }
+        }
+    }
+
+    // searches over keys at every alignment
+    let space = gen_bytes();
+    let bufs: Vec<Vec<u8>> = space
+        .iter()
+        .enumerate()
+        .map(|(i, k)| {
+            let mut buf = vec![0u8; i % 8];
+            buf.extend_from_slice(k);
+            buf
+        })
+        .collect();
+    let keys: Vec<TreeOrdBytes> = bufs
+        .iter()
+        .enumerate()
+        .map(|(i, buf)| TreeOrdBytes(&buf[(i % 8)..]))
+        .collect();
+    for (i, query) in space.iter().enumerate().step_by(5) {
+        let mut buf = vec![0u8; 3];
+        buf.extend_from_slice(query);
+        let found = tree_binary_search(&keys, &TreeOrdBytes(&buf[3..]));
+        // `gen_bytes` has duplicates
+        assert_eq!(space[found.unwrap()], space[i]);
+    }
+}
+
+#[test]
+fn bytes_mid_chunk_prefix() {
+    // keys that diverge at byte 37, in the middle of the second chunk
+    let key = |diverge: u8, later: u8| {
+        let mut v = vec![5u8; 3 * TreeOrdBytes::CHUNK_LEN];
+        v[37] = diverge;
+        v[80] = later;
+        v
+    };
+    let query = key(2, 2);
+    let mut tracker = <TreeOrdBytes as TreeOrd>::Tracker::new();
+    assert_eq!(
+        TreeOrdBytes(&key(1, 9)).tree_cmp(&TreeOrdBytes(&query), &mut tracker),
+        Less
+    );
+    assert_eq!(
+        TreeOrdBytes(&key(3, 0)).tree_cmp(&TreeOrdBytes(&query), &mut tracker),
+        Greater
+    );
+    // the proven prefix is byte exact instead of rounded down to a chunk, so
+    // later comparisons do not rescan bytes 32..37
+    assert_eq!(tracker.proven_prefix_len(), 37);
+    assert_eq!(
+        TreeOrdBytes(&key(2, 1)).tree_cmp(&TreeOrdBytes(&query), &mut tracker),
+        Less
+    );
+    assert_eq!(
+        TreeOrdBytes(&key(2, 3)).tree_cmp(&TreeOrdBytes(&query), &mut tracker),
+        Greater
+    );
+    assert_eq!(tracker.proven_prefix_len(), 80);
+}
+
+#[test]
+fn bytes_short_words() {
+    // keys shorter than `SHORT_LEN` are compared as big-endian words, the later
+    // bytes are ordered opposite to the diverging byte so that a little-endian
+    // comparison would get the wrong result
+    fn check(lhs: &[u8], rhs: &[u8], known: usize, diverge: usize) {
+        let mut tracker = TreeOrdBytes::tracker_with_known_prefix(known);
+        let res = TreeOrdBytes(lhs).tree_cmp(&TreeOrdBytes(rhs), &mut tracker);
+        assert_eq!(res, lhs.cmp(rhs));
+        match res {
+            Less => assert_eq!(tracker.max_eq_len(), diverge),
+            Equal => unreachable!(),
+            Greater => assert_eq!(tracker.min_eq_len(), diverge),
+        }
+    }
+
+    for len in 1..=TreeOrdBytes::SHORT_LEN {
+        for diverge in 0..len {
+            let mut lhs: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let mut rhs = lhs.clone();
+            lhs[diverge] = 1;
+            rhs[diverge] = 2;
+            for i in (diverge + 1)..len {
+                lhs[i] = 255;
+                rhs[i] = 0;
+            }
+            for known in [0, diverge / 2, diverge] {
+                check(&lhs, &rhs, known, diverge);
+                check(&rhs, &lhs, known, diverge);
+                // only the first difference matters
+                check(&lhs[..(diverge + 1)], &rhs, known, diverge);
+            }
+        }
+        // differing only in length
+        let key: Vec<u8> = (0..len).map(|i| i as u8).collect();
+        for short in 0..len {
+            let mut tracker = TreeOrdBytes::tracker_with_known_prefix(short);
+            assert_eq!(
+                TreeOrdBytes(&key[..short]).tree_cmp(&TreeOrdBytes(&key), &mut tracker),
+                Less
+            );
+            assert_eq!(tracker.max_eq_len(), short);
+        }
+    }
+
+    // the proven prefix is never compared, even when the last word overlaps it
+    for len in [3, 10, 13] {
+        let lhs = vec![9u8; len];
+        let mut rhs = vec![0u8; len];
+        rhs[len - 1] = 10;
+        let mut tracker = TreeOrdBytes::tracker_with_known_prefix(len - 2);
+        assert_eq!(
+            TreeOrdBytes(&lhs).tree_cmp(&TreeOrdBytes(&rhs), &mut tracker),
+            Greater
+        );
+        assert_eq!(tracker.min_eq_len(), len - 2);
+    }
+
+    // keys differing only in byte 7 or byte 8, on either side of the first word
+    let base = [0x80u8; 16];
+    for i in [7, 8] {
+        let mut lhs = base;
+        lhs[i] = 0x7f;
+        check(&lhs, &base, 0, i);
+        check(&base, &lhs, 0, i);
+        check(&lhs[..9], &base[..9], 0, i);
+        check(&lhs[..12], &base[..10], 5, i);
+    }
+}
+
+#[test]
+fn ascii_case_insensitive() {
+    use std::hash::{BuildHasher, RandomState};
+
+    use tree_ord::{utils::LexicographicTracker, AsciiCaseInsensitive, AsciiCaseInsensitiveString};
+
+    let recase = |s: &str, rng: &mut Xoshiro128StarStar| -> String {
+        s.chars()
+            .map(|c| {
+                if (rng.next_u32() & 1) == 0 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c.to_ascii_lowercase()
+                }
+            })
+            .collect()
+    };
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let alphabet = b"aBc-_Z";
+    let mut space: Vec<String> = vec![];
+    for _ in 0..2000 {
+        let mut s = "X-Forwarded-".to_owned();
+        for _ in 0..(rng.next_u32() % 12) {
+            let c = alphabet[(rng.next_u32() as usize) % alphabet.len()];
+            s.push(c as char);
+        }
+        if (rng.next_u32() % 8) == 0 {
+            s.push('é');
+        }
+        space.push(s);
+    }
+    let mut space: Vec<AsciiCaseInsensitiveString> =
+        space.into_iter().map(AsciiCaseInsensitiveString).collect();
+    space.sort();
+    space.dedup();
+    let borrowed: Vec<AsciiCaseInsensitive> = space.iter().map(|s| s.as_borrowed()).collect();
+    let mut skipping = 0;
+    for (i, key) in space.iter().enumerate() {
+        let query = AsciiCaseInsensitiveString(recase(&key.0, &mut rng));
+        assert_eq!(query, *key);
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        assert_eq!(found, Ok(i));
+        if tracker.proven_prefix_len() >= 12 {
+            skipping += 1;
+        }
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&borrowed, |k| {
+            k.tree_cmp(&query.as_borrowed(), &mut tracker)
+        });
+        assert_eq!(found, Ok(i));
+        assert_eq!(
+            tree_binary_search_by(&borrowed, |k| k.cmp(&query.as_borrowed())),
+            Ok(i)
+        );
+    }
+    // the shared "x-forwarded-" ends up proven and skipped in most searches
+    assert!(skipping > (space.len() * 3 / 4));
+    for lhs in borrowed.iter().step_by(7) {
+        for rhs in borrowed.iter().step_by(5) {
+            let mut tracker = LexicographicTracker::new();
+            assert_eq!(lhs.tree_cmp(rhs, &mut tracker), lhs.cmp(rhs));
+            assert_eq!(
+                lhs.cmp(rhs),
+                lhs.0.to_ascii_lowercase().cmp(&rhs.0.to_ascii_lowercase())
+            );
+        }
+    }
+
+    // the known prefix is never compared
+    let mut tracker = LexicographicTracker::with_known_prefix(3);
+    assert_eq!(
+        AsciiCaseInsensitive("abcX").tree_cmp(&AsciiCaseInsensitive("zzzx"), &mut tracker),
+        Equal
+    );
+    // equal keys hash the same
+    let hasher = RandomState::new();
+    assert_eq!(
+        hasher.hash_one(AsciiCaseInsensitive("Content-Type")),
+        hasher.hash_one(AsciiCaseInsensitive("content-type"))
+    );
+}
+
+#[test]
+fn utf16_ordered() {
+    use tree_ord::{
+        testing::check_tree_ord, utils::LexicographicTracker, Utf16Ordered, Utf16Units,
+    };
+
+    let utf16_cmp = |a: &str, b: &str| a.encode_utf16().cmp(b.encode_utf16());
+    // pairs on either side of the surrogate range, which flip relative to `str`
+    let flipped = [
+        ("\u{10000}", "\u{E000}"),
+        ("\u{10000}", "\u{FFFF}"),
+        ("\u{10FFFF}", "\u{E000}"),
+        ("\u{1F600}", "\u{FF5E}"),
+        ("prefix/\u{1F600}/a", "prefix/\u{FFFD}"),
+        ("prefix/\u{10000}", "prefix/\u{E000}z"),
+    ];
+    for (a, b) in flipped {
+        assert_eq!(a.cmp(b), Greater);
+        assert_eq!(utf16_cmp(a, b), Less);
+        assert_eq!(Utf16Ordered(a).cmp(&Utf16Ordered(b)), Less);
+        let mut tracker = LexicographicTracker::new();
+        assert_eq!(
+            Utf16Ordered(a).tree_cmp(&Utf16Ordered(b), &mut tracker),
+            Less
+        );
+        let mut tracker = LexicographicTracker::new();
+        assert_eq!(
+            Utf16Ordered(b).tree_cmp(&Utf16Ordered(a), &mut tracker),
+            Greater
+        );
+    }
+    // pairs that keep their order
+    let kept = [
+        ("\u{D7FF}", "\u{10000}"),
+        ("\u{D7FF}", "\u{E000}"),
+        ("\u{E000}", "\u{FFFF}"),
+        ("\u{10000}", "\u{10FFFF}"),
+        ("\u{1F600}", "\u{1F600}a"),
+        ("", "\u{10000}"),
+    ];
+    for (a, b) in kept {
+        assert_eq!(a.cmp(b), Less);
+        assert_eq!(utf16_cmp(a, b), Less);
+        let mut tracker = LexicographicTracker::new();
+        assert_eq!(
+            Utf16Ordered(a).tree_cmp(&Utf16Ordered(b), &mut tracker),
+            Less
+        );
+    }
+
+    // strings with shared prefixes that differ around the surrogate range, and
+    // within the UTF-8 encodings of `char`s with equal leading bytes
+    let alphabet = [
+        'a',
+        'é',
+        '\u{D7FF}',
+        '\u{E000}',
+        '\u{FFFF}',
+        '\u{10000}',
+        '\u{1F600}',
+        '\u{1F601}',
+        '\u{10FFFF}',
+    ];
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<String> = vec![];
+    for _ in 0..1000 {
+        let mut s = String::from("shared/\u{1F600}/");
+        for _ in 0..(rng.next_u32() % 8) {
+            s.push(alphabet[(rng.next_u32() as usize) % alphabet.len()]);
+        }
+        space.push(s);
+    }
+    space.sort_by(|a, b| utf16_cmp(a, b));
+    space.dedup();
+    let units: Vec<Vec<u16>> = space.iter().map(|s| s.encode_utf16().collect()).collect();
+    let wrapped: Vec<Utf16Ordered> = space.iter().map(|s| Utf16Ordered(s)).collect();
+    let wrapped_units: Vec<Utf16Units> = units.iter().map(|u| Utf16Units(u)).collect();
+    assert!(wrapped.windows(2).all(|w| w[0] < w[1]));
+    assert!(wrapped_units.windows(2).all(|w| w[0] < w[1]));
+    // the order really differs from `str` somewhere
+    assert!(space.windows(2).any(|w| w[0] > w[1]));
+    check_tree_ord(&wrapped, &wrapped, 0);
+    check_tree_ord(&wrapped_units, &wrapped_units, 0);
+    let mut skipping = 0;
+    for (i, query) in wrapped.iter().enumerate() {
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&wrapped, |k| k.tree_cmp(query, &mut tracker));
+        assert_eq!(found, Ok(i));
+        // the proven prefix is always on a `char` boundary
+        assert!(query.is_char_boundary(tracker.proven_prefix_len()));
+        if tracker.proven_prefix_len() >= "shared/\u{1F600}/".len() {
+            skipping += 1;
+        }
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&wrapped_units, |k| {
+            k.tree_cmp(&wrapped_units[i], &mut tracker)
+        });
+        assert_eq!(found, Ok(i));
+    }
+    assert!(skipping > (space.len() * 3 / 4));
+    for lhs in wrapped.iter().step_by(7) {
+        for rhs in wrapped.iter().step_by(5) {
+            let mut tracker = LexicographicTracker::new();
+            assert_eq!(lhs.tree_cmp(rhs, &mut tracker), utf16_cmp(lhs, rhs));
+        }
+    }
+
+    // unpaired surrogates are plain code units
+    assert!(Utf16Units(&[0xd800]) < Utf16Units(&[0xd800, 0xdc00]));
+    assert!(Utf16Units(&[0xdfff]) < Utf16Units(&[0xe000]));
+}
+
+#[cfg(feature = "unicode-case")]
+#[test]
+fn case_folded() {
+    use tree_ord::{utils::LexicographicTracker, CaseFolded};
+
+    let eq = |a: &str, b: &str| CaseFolded(a) == CaseFolded(b);
+    // not locale aware, 'İ' and 'ı' only fold to themselves
+    assert!(eq("KIR", "kir"));
+    assert!(!eq("kır", "kir"));
+    assert!(!eq("İstanbul", "istanbul"));
+    assert!(!eq("İstanbul", "i\u{307}STANBUL"));
+    assert!(eq("İSTANBUL", "İstanbul"));
+    // simple folding does not expand 'ß', and 'ẞ' folds to it
+    assert!(!eq("straße", "STRASSE"));
+    assert!(eq("STRAẞE", "straße"));
+    assert!(!eq("ß", "ss"));
+    assert!(CaseFolded("ß") > CaseFolded("st"));
+    assert!(CaseFolded("ß") > CaseFolded("SS"));
+    assert!(eq("ПРИВЕТ мир", "привет МИР"));
+    assert!(eq("Ёлка", "ёЛКА"));
+    assert!(eq("ΣΊΣΥΦΟΣ", "σίσυφος"));
+    assert!(eq("ǅemal", "ǆEMAL"));
+    assert!(eq("\u{212a}elvin", "kelvin"));
+
+    // the prefix is in `char`s, and 'ß' and 'ẞ' are one `char` each
+    let mut tracker = LexicographicTracker::new();
+    assert_eq!(
+        CaseFolded("aßx").tree_cmp(&CaseFolded("AẞY"), &mut tracker),
+        Less
+    );
+    assert_eq!(tracker.max_eq_len(), 2);
+    assert_eq!(
+        CaseFolded("aẞz").tree_cmp(&CaseFolded("AẞY"), &mut tracker),
+        Greater
+    );
+    assert_eq!(tracker.min_eq_len(), 2);
+    assert_eq!(tracker.proven_prefix_len(), 2);
+    let mut tracker = LexicographicTracker::with_known_prefix(2);
+    // the first two folded `char`s are never compared
+    assert_eq!(
+        CaseFolded("bßx").tree_cmp(&CaseFolded("ZẞX"), &mut tracker),
+        Equal
+    );
+    assert_eq!(
+        CaseFolded("bßx").tree_cmp(&CaseFolded("Zßy"), &mut tracker),
+        Less
+    );
+    assert_eq!(tracker.max_eq_len(), 2);
+
+    let words = [
+        "straße",
+        "STRAẞE",
+        "strasse",
+        "stras",
+        "strat",
+        "ıI",
+        "iİ",
+        "ii",
+        "İi",
+        "привет",
+        "ПРИВЕТ",
+        "пРиВет",
+        "Ёлка",
+        "ёлкА",
+        "елка",
+        "ǅemal",
+        "ǆemal",
+        "DŽEMAL",
+        "ﬃ",
+        "FFI",
+        "ffj",
+    ];
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<String> = vec![];
+    for _ in 0..3000 {
+        let mut s = String::new();
+        for _ in 0..(1 + rng.next_u32() % 3) {
+            s.push_str(words[(rng.next_u32() as usize) % words.len()]);
+        }
+        space.push(s);
+    }
+    let mut space: Vec<CaseFolded<String>> = space.into_iter().map(CaseFolded).collect();
+    space.sort();
+    space.dedup();
+    for lhs in space.iter().step_by(13) {
+        for rhs in space.iter().step_by(11) {
+            let mut tracker = LexicographicTracker::new();
+            assert_eq!(lhs.tree_cmp(rhs, &mut tracker), lhs.cmp(rhs));
+        }
+    }
+    for (i, key) in space.iter().enumerate() {
+        let query = CaseFolded(key.0.to_uppercase());
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        // uppercasing can change the fold of some `char`s
+        if query == *key {
+            assert_eq!(found, Ok(i));
+        }
+        let query = CaseFolded(key.0.to_lowercase());
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        assert_eq!(found, tree_binary_search_by(&space, |k| k.cmp(&query)));
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn accent_insensitive() {
+    use tree_ord::{utils::LexicographicTracker, AccentInsensitive};
+
+    let eq = |a: &str, b: &str| AccentInsensitive(a) == AccentInsensitive(b);
+    // precomposed and decomposed
+    assert!(eq("résumé", "re\u{301}sume\u{301}"));
+    assert!(eq("résumé", "resume"));
+    assert!(!eq("résumé", "Resume"));
+    assert!(eq("Ångström", "A\u{30a}ngstro\u{308}m"));
+    assert!(eq("ṩ", "s\u{323}\u{307}"));
+    assert!(eq("Ελληνικά", "Ελληνικα"));
+    assert!(eq("йод", "иод"));
+    // Hangul syllables and their jamo
+    assert!(eq(
+        "한국어",
+        "\u{1112}\u{1161}\u{11ab}\u{1100}\u{116e}\u{11a8}\u{110b}\u{1165}"
+    ));
+    assert!(AccentInsensitive("é") < AccentInsensitive("f"));
+
+    // the prefix is in transformed `char`s, and "한" is three of them
+    let mut tracker = LexicographicTracker::new();
+    assert_eq!(
+        AccentInsensitive("한x").tree_cmp(&AccentInsensitive("\u{1112}\u{1161}y"), &mut tracker),
+        Greater
+    );
+    assert_eq!(tracker.min_eq_len(), 2);
+    let mut tracker = LexicographicTracker::with_known_prefix(2);
+    // the first two transformed `char`s are never compared
+    assert_eq!(
+        AccentInsensitive("ébc").tree_cmp(&AccentInsensitive("xy\u{300}c"), &mut tracker),
+        Equal
+    );
+
+    let words = [
+        "café",
+        "cafe\u{301}",
+        "cafe",
+        "Café",
+        "naïve",
+        "naive",
+        "über",
+        "u\u{308}ber",
+        "uber",
+        "Ελληνικά",
+        "ελληνικα",
+        "Москва",
+        "йогурт",
+        "иогурт",
+        "한국",
+        "\u{1112}\u{1161}\u{11ab}",
+        "日本",
+        "ǅ",
+        "ﬁ",
+        "x",
+    ];
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<String> = vec![];
+    for _ in 0..3000 {
+        let mut s = String::new();
+        for _ in 0..(1 + rng.next_u32() % 3) {
+            s.push_str(words[(rng.next_u32() as usize) % words.len()]);
+        }
+        space.push(s);
+    }
+    let mut space: Vec<AccentInsensitive<String>> =
+        space.into_iter().map(AccentInsensitive).collect();
+    space.sort();
+    space.dedup();
+    for lhs in space.iter().step_by(13) {
+        for rhs in space.iter().step_by(11) {
+            let mut tracker = LexicographicTracker::new();
+            assert_eq!(lhs.tree_cmp(rhs, &mut tracker), lhs.cmp(rhs));
+        }
+    }
+    for (i, key) in space.iter().enumerate() {
+        // the same key with every accent decomposed or removed
+        let decomposed: String = key
+            .0
+            .replace("é", "e\u{301}")
+            .replace("ï", "i\u{308}")
+            .replace('ü', "u");
+        let query = AccentInsensitive(decomposed);
+        assert_eq!(query, *key);
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        assert_eq!(found, Ok(i));
+    }
+}
+
+#[cfg(feature = "unicode-normalization")]
+#[test]
+fn nfc() {
+    use tree_ord::{utils::LexicographicTracker, Nfc};
+
+    let eq = |a: &str, b: &str| Nfc(a) == Nfc(b);
+    assert!(eq("e\u{301}", "\u{e9}"));
+    assert!(!eq("e\u{301}", "e"));
+    // normalization changes the length, "\u{1e69}" is one `char` in NFC
+    assert!(eq("s\u{323}\u{307}", "\u{1e69}"));
+    assert!(eq("s\u{307}\u{323}", "\u{1e69}"));
+    // combining marks are reordered by combining class, the cedilla (202) goes
+    // before the acute (230) and then only the cedilla composes
+    assert!(eq("c\u{301}\u{327}", "\u{e7}\u{301}"));
+    assert!(eq("a\u{301}\u{302}", "\u{e1}\u{302}"));
+    assert!(!eq("a\u{301}\u{302}", "\u{e2}\u{301}"));
+    // singletons and Hangul
+    assert!(eq("\u{212b}", "\u{c5}"));
+    assert!(eq("\u{1112}\u{1161}\u{11ab}", "\u{d55c}"));
+    assert!(eq("\u{d558}\u{11ab}", "\u{d55c}"));
+    assert!(eq("か\u{3099}", "が"));
+    assert!(Nfc("e\u{301}") > Nfc("ez"));
+
+    // the prefix is in normalized `char`s, "s\u{323}\u{307}" is one of them
+    let mut tracker = LexicographicTracker::new();
+    assert_eq!(
+        Nfc("s\u{323}\u{307}x").tree_cmp(&Nfc("\u{1e69}y"), &mut tracker),
+        Less
+    );
+    assert_eq!(tracker.max_eq_len(), 1);
+    let mut tracker = LexicographicTracker::with_known_prefix(2);
+    // the first two normalized `char`s are never compared
+    assert_eq!(Nfc("ae\u{301}c").tree_cmp(&Nfc("xyc"), &mut tracker), Equal);
+
+    let parts = [
+        "e", "\u{301}", "\u{327}", "\u{323}", "\u{307}", "c", "s", "\u{e9}", "\u{1e69}",
+        "\u{1112}", "\u{1161}", "\u{11ab}", "\u{d55c}", "か", "\u{3099}", "ab",
+    ];
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<String> = vec![];
+    for _ in 0..3000 {
+        let mut s = String::new();
+        for _ in 0..(rng.next_u32() % 8) {
+            s.push_str(parts[(rng.next_u32() as usize) % parts.len()]);
+        }
+        space.push(s);
+    }
+    let mut space: Vec<Nfc<String>> = space.into_iter().map(Nfc).collect();
+    space.sort();
+    space.dedup();
+    for lhs in space.iter().step_by(13) {
+        for rhs in space.iter().step_by(11) {
+            let mut tracker = LexicographicTracker::new();
+            assert_eq!(lhs.tree_cmp(rhs, &mut tracker), lhs.cmp(rhs));
+        }
+    }
+    for (i, key) in space.iter().enumerate() {
+        // a canonically equivalent but differently composed query
+        let query = Nfc(key
+            .0
+            .replace('\u{e9}', "e\u{301}")
+            .replace("\u{d55c}", "\u{1112}\u{1161}\u{11ab}"));
+        assert_eq!(query, *key);
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        assert_eq!(found, Ok(i));
+    }
+}
+
+#[test]
+fn suffix_lexicographic() {
+    use tree_ord::{
+        utils::LexicographicTracker, SuffixLexicographic, SuffixLexicographicBytes,
+        SuffixLexicographicStr,
+    };
+
+    assert!(SuffixLexicographic(&[1, 2, 3]) > SuffixLexicographic(&[3, 2, 2]));
+    // a suffix of a longer slice is less
+    assert!(SuffixLexicographic(&[2, 3]) < SuffixLexicographic(&[1, 2, 3]));
+    assert!(SuffixLexicographicStr("gz") < SuffixLexicographicStr("tar.gz"));
+    assert!(SuffixLexicographicStr("a.tar.gz") < SuffixLexicographicStr("b.tgz"));
+
+    // element keys with long shared suffixes, the suffix is skipped
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let suffixes: Vec<Vec<u64>> = (0..4)
+        .map(|_| (0..24).map(|_| rng.next_u64() % 4).collect())
+        .collect();
+    let mut keys: Vec<Vec<COrd<u64>>> = vec![];
+    for _ in 0..2000 {
+        let mut key: Vec<COrd<u64>> = (0..(rng.next_u32() % 6))
+            .map(|_| COrd(rng.next_u64() % 4))
+            .collect();
+        let suffix = &suffixes[(rng.next_u32() as usize) % suffixes.len()];
+        key.extend(suffix.iter().map(|x| COrd(*x)));
+        keys.push(key);
+    }
+    let mut space: Vec<SuffixLexicographic<COrd<u64>>> = keys
+        .iter()
+        .map(|k| SuffixLexicographic(k.as_slice()))
+        .collect();
+    space.sort();
+    space.dedup();
+    let mut tree_comparisons = 0;
+    let mut comparisons = 0;
+    for query in &space {
+        let scope = CountScope::new();
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker)).unwrap();
+        tree_comparisons += scope.count();
+        let scope = CountScope::new();
+        let expected = tree_binary_search_by(&space, |k| k.cmp(query)).unwrap();
+        comparisons += scope.count();
+        assert_eq!(found, expected);
+    }
+    assert!(tree_comparisons * 2 < comparisons);
+
+    // bytes straddling the word boundaries
+    let mut space: Vec<Vec<u8>> = vec![];
+    for _ in 0..3000 {
+        let mut key: Vec<u8> = (0..(rng.next_u32() % 20))
+            .map(|_| b"ab."[(rng.next_u32() % 3) as usize])
+            .collect();
+        key.extend_from_slice(
+            [
+                &b".example.com"[..],
+                b".example.org",
+                b"service.example.com",
+            ][(rng.next_u32() % 3) as usize],
+        );
+        space.push(key);
+    }
+    space.sort_by(|a, b| a.iter().rev().cmp(b.iter().rev()));
+    space.dedup();
+    for lhs in space.iter().step_by(13) {
+        for rhs in space.iter().step_by(11) {
+            let expected = lhs.iter().rev().cmp(rhs.iter().rev());
+            let mut tracker = LexicographicTracker::new();
+            let res = SuffixLexicographicBytes(lhs)
+                .tree_cmp(&SuffixLexicographicBytes(rhs), &mut tracker);
+            assert_eq!(res, expected);
+            let suffix = lhs
+                .iter()
+                .rev()
+                .zip(rhs.iter().rev())
+                .take_while(|(a, b)| a == b)
+                .count();
+            match res {
+                Less => assert_eq!(tracker.max_eq_len(), suffix),
+                Equal => (),
+                Greater => assert_eq!(tracker.min_eq_len(), suffix),
+            }
+            let mut tracker = LexicographicTracker::new();
+            assert_eq!(
+                SuffixLexicographic(lhs.as_slice())
+                    .tree_cmp(&SuffixLexicographic(rhs), &mut tracker),
+                expected
+            );
+        }
+    }
+    let strs: Vec<&str> = space
+        .iter()
+        .map(|k| std::str::from_utf8(k).unwrap())
+        .collect();
+    for (i, query) in strs.iter().enumerate() {
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&strs, |k| {
+            SuffixLexicographicStr(k).tree_cmp(&SuffixLexicographicStr(query), &mut tracker)
+        });
+        assert_eq!(found, Ok(i));
+    }
+}
+
+#[test]
+fn natural_ord_str() {
+    use tree_ord::{utils::NaturalTracker, NaturalOrdStr};
+
+    let n = NaturalOrdStr;
+    assert!(n("file2") < n("file10"));
+    assert!(n("file10.txt") > n("file9.txt"));
+    assert!(n("v1.2.10") > n("v1.2.9"));
+    // leading zeros only break ties
+    assert!(n("img007") < n("img10"));
+    assert!(n("img1") < n("img01"));
+    assert!(n("img01") < n("img001"));
+    assert!(n("img01b") > n("img1z"));
+    assert!(n("x") < n("x0"));
+    assert!(n("a") < n("ab1"));
+    assert!(n("99999999999999999999999") < n("100000000000000000000000"));
+    assert_eq!(n("älpha1").cmp(&n("älpha1")), Equal);
+
+    // reference implementation in terms of segment keys
+    fn segments(s: &str) -> Vec<&[u8]> {
+        let b = s.as_bytes();
+        let mut res = vec![];
+        let mut i = 0;
+        while i < b.len() {
+            let mut j = i;
+            while j < b.len() && (b[j].is_ascii_digit() == b[i].is_ascii_digit()) {
+                j += 1;
+            }
+            res.push(&b[i..j]);
+            i = j;
+        }
+        res
+    }
+    fn reference(x: &str, y: &str) -> Ordering {
+        let (x, y) = (segments(x), segments(y));
+        for (a, b) in x.iter().zip(y.iter()) {
+            let res = if a[0].is_ascii_digit() && b[0].is_ascii_digit() {
+                let key = |s: &[u8]| {
+                    let zeros = s.iter().take_while(|c| **c == b'0').count();
+                    (s.len() - zeros, s[zeros..].to_vec(), zeros)
+                };
+                key(a).cmp(&key(b))
+            } else {
+                a.cmp(b)
+            };
+            if res != Equal {
+                return res
+            }
+        }
+        x.len().cmp(&y.len())
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let parts = [
+        "file", "File", "_", ".", "-", "0", "00", "1", "01", "007", "2", "9", "10", "123", "v",
+        "rc", "ä",
+    ];
+    let mut corpus: Vec<String> = vec![];
+    for _ in 0..3000 {
+        let mut s = String::new();
+        for _ in 0..(rng.next_u32() % 8) {
+            s.push_str(parts[(rng.next_u32() as usize) % parts.len()]);
+        }
+        corpus.push(s);
+    }
+    for lhs in corpus.iter().step_by(7) {
+        for rhs in corpus.iter().step_by(5) {
+            assert_eq!(n(lhs).cmp(&n(rhs)), reference(lhs, rhs), "{lhs:?} {rhs:?}");
+            assert_eq!(n(lhs) == n(rhs), lhs == rhs);
+        }
+    }
+
+    // versioned filenames, the shared leading segments are skipped
+    let mut names: Vec<String> = vec![];
+    for project in ["alpha", "beta"] {
+        for major in 0..4 {
+            for minor in 0..12 {
+                for patch in 0..12 {
+                    names.push(format!("{project}-{major}.{minor}.{patch}.tar.gz"));
+                    names.push(format!("{project}-{major}.{minor:02}.{patch:03}.zip"));
+                }
+            }
+        }
+    }
+    let mut space: Vec<NaturalOrdStr> = names.iter().map(|s| NaturalOrdStr(s)).collect();
+    space.sort();
+    space.dedup();
+    for w in space.windows(2) {
+        assert_eq!(reference(w[0].0, w[1].0), Less);
+    }
+    let mut skipped3 = 0;
+    let mut skipped5 = 0;
+    for (i, query) in space.iter().enumerate() {
+        let mut tracker = NaturalTracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            let proven = tracker.proven_bytes();
+            // the proven segments are shared by the key and the query
+            assert_eq!(k.0.as_bytes()[..proven], query.0.as_bytes()[..proven]);
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        });
+        assert_eq!(found, Ok(i));
+        // the project, "-", and major version are usually proven, and often
+        // the "." and minor version as well
+        if tracker.proven_segments() >= 3 {
+            skipped3 += 1;
+        }
+        if tracker.proven_segments() >= 5 {
+            skipped5 += 1;
+        }
+    }
+    assert!(skipped3 * 10 > space.len() * 9);
+    assert!(skipped5 * 2 > space.len());
+}
+
+#[test]
+fn version_str() {
+    use tree_ord::{utils::NaturalTracker, VersionStr};
+
+    let v = VersionStr;
+    assert!(v("1.9.0") < v("1.10.0"));
+    assert!(v("1.10.0") < v("2.0"));
+    assert!(v("1.0") < v("1.0.0"));
+    assert!(v("1.0.0") < v("1.0.0.0"));
+    assert!(v("1.2.3.4") < v("1.2.3.5"));
+    assert!(v("") < v("0"));
+    // numeric segments before other segments
+    assert!(v("1.2") < v("1.2a"));
+    assert!(v("1.2a") < v("1.2b"));
+    assert!(v("1.99") < v("1.-"));
+    assert!(v("1.0.0") < v("1.0.0-rc.2+build5"));
+    assert!(v("1.10.0-rc.2+build5") < v("1.10.0-rc.2+build6"));
+    assert!(v("1.10.0-rc.2") < v("1.10.0-rc.10"));
+    assert!(v("1..2") < v("1.0.2"));
+    assert!(v("1.") < v("1.0"));
+    // leading zeros only break ties
+    assert!(v("1.01") < v("1.2"));
+    assert!(v("1.1") < v("1.01"));
+    assert!(v("1.01") < v("1.001"));
+    assert!(v("1.01.9") > v("1.1.10"));
+    assert!(v("99999999999999999999999") < v("100000000000000000000000"));
+    assert_eq!(v("1.ä.2").cmp(&v("1.ä.2")), Equal);
+
+    // reference implementation in terms of segment keys
+    fn reference(x: &str, y: &str) -> Ordering {
+        let key = |s: &str| -> Vec<(u8, usize, Vec<u8>, usize)> {
+            s.split('.')
+                .map(|seg| {
+                    let b = seg.as_bytes();
+                    if b.is_empty() {
+                        (0, 0, vec![], 0)
+                    } else if b.iter().all(u8::is_ascii_digit) {
+                        let zeros = b.iter().take_while(|c| **c == b'0').count();
+                        (1, b.len() - zeros, b[zeros..].to_vec(), zeros)
+                    } else {
+                        (2, 0, b.to_vec(), 0)
+                    }
+                })
+                .collect()
+        };
+        key(x).cmp(&key(y))
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let parts = [
+        ".", ".", ".", "0", "00", "1", "01", "2", "9", "10", "123", "-rc", "a", "+b", "ä",
+    ];
+    let mut corpus: Vec<String> = vec![];
+    for _ in 0..3000 {
+        let mut s = String::new();
+        for _ in 0..(rng.next_u32() % 8) {
+            s.push_str(parts[(rng.next_u32() as usize) % parts.len()]);
+        }
+        corpus.push(s);
+    }
+    for lhs in corpus.iter().step_by(7) {
+        for rhs in corpus.iter().step_by(5) {
+            assert_eq!(v(lhs).cmp(&v(rhs)), reference(lhs, rhs), "{lhs:?} {rhs:?}");
+            assert_eq!(v(lhs) == v(rhs), lhs == rhs);
+        }
+    }
+    let mut space: Vec<VersionStr> = corpus.iter().map(|s| VersionStr(s)).collect();
+    space.sort();
+    space.dedup();
+    for query in corpus.iter().step_by(3) {
+        let query = VersionStr(query);
+        let mut tracker = NaturalTracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            let res = k.tree_cmp(&query, &mut tracker);
+            assert_eq!(res, k.cmp(&query));
+            res
+        });
+        assert_eq!(found, space.binary_search(&query));
+        assert_eq!(
+            tree_lower_bound_hereditary(&space, &query),
+            space.partition_point(|k| k < &query)
+        );
+    }
+
+    // release histories of a few packages with 4 part versions and
+    // prereleases, the shared leading segments are skipped
+    let mut versions: Vec<String> = vec![];
+    for major in [1, 10, 2024] {
+        for minor in 0..12 {
+            for patch in 0..8 {
+                versions.push(format!("{major}.{minor}.{patch}"));
+                versions.push(format!("{major}.{minor}.{patch}.{}", patch * 7));
+                versions.push(format!("{major}.{minor}.{patch}-rc.{}", minor % 3));
+            }
+        }
+    }
+    let mut space: Vec<VersionStr> = versions.iter().map(|s| VersionStr(s)).collect();
+    space.sort();
+    space.dedup();
+    for w in space.windows(2) {
+        assert_eq!(reference(w[0].0, w[1].0), Less);
+    }
+    let mut skipped1 = 0;
+    let mut skipped2 = 0;
+    for (i, query) in space.iter().enumerate() {
+        let mut tracker = NaturalTracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            let proven = tracker.proven_bytes();
+            // the proven segments and their dots are shared by the key and the
+            // query
+            assert_eq!(k.0.as_bytes()[..proven], query.0.as_bytes()[..proven]);
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        });
+        assert_eq!(found, Ok(i));
+        if tracker.proven_segments() >= 1 {
+            skipped1 += 1;
+        }
+        if tracker.proven_segments() >= 2 {
+            skipped2 += 1;
+        }
+    }
+    // the major version is almost always proven, and usually the minor version
+    // as well
+    assert!(skipped1 * 10 > space.len() * 9);
+    assert!(skipped2 * 3 > space.len() * 2);
+}
+
+#[test]
+fn dns_name() {
+    use std::hash::{BuildHasher, RandomState};
+
+    use tree_ord::{testing::check_tree_ord, utils::NaturalTracker, DnsName};
+
+    let d = |s: &'static str| DnsName(s.as_bytes());
+    assert!(d("example.com") < d("a.example.com"));
+    assert!(d("a.example.com") < d("b.example.com"));
+    assert!(d("z.example.com") < d("a.example.net"));
+    assert!(d("com") < d("example.com"));
+    assert!(d("ab.com") > d("a.com"));
+    assert!(d("zz.a.com") < d("b.com"));
+    // trailing dots and case are ignored
+    assert_eq!(d("Example.COM."), d("example.com"));
+    assert_eq!(d("."), d(""));
+    assert!(d(".") < d("com"));
+    assert!(d("") < d("-"));
+    // empty labels sort before nonempty labels
+    assert!(d(".com") > d("com"));
+    assert!(d(".com") < d("a.com"));
+    assert!(d("a..com") < d("a.b.com"));
+    // only one trailing dot is ignored, the rightmost label of "com.." is empty
+    assert!(d("com..") < d("com."));
+    // bytes within a label are compared after lowercasing, so '_' (0x5f) is
+    // after 'Z' (0x5a) but before 'a' (0x61), which 'Z' lowercases past
+    assert!(d("_tcp.example.com") < d("Z.example.com"));
+    assert!(d("_tcp.example.com") > d("9.example.com"));
+    let hasher = RandomState::new();
+    assert_eq!(
+        hasher.hash_one(d("WWW.Example.com.")),
+        hasher.hash_one(d("www.example.COM"))
+    );
+
+    // reference implementation in terms of reversed lowercase labels
+    fn reference(x: &[u8], y: &[u8]) -> Ordering {
+        let key = |s: &[u8]| -> Vec<Vec<u8>> {
+            let s = s.strip_suffix(b".").unwrap_or(s);
+            if s.is_empty() {
+                return vec![]
+            }
+            s.split(|b| *b == b'.')
+                .rev()
+                .map(|label| label.to_ascii_lowercase())
+                .collect()
+        };
+        key(x).cmp(&key(y))
+    }
+
+    // a zone file worth of names under a few zones, with mixed case, service
+    // labels, wildcards, and optional trailing dots
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let zones = [
+        "example.com",
+        "Example.NET",
+        "corp.example.com",
+        "eu-west-1.compute.internal",
+        "svc.cluster.local",
+        "xn--bcher-kva.example",
+        "co.uk",
+    ];
+    let hosts = [
+        "www",
+        "mail",
+        "MX1",
+        "mx2",
+        "ns1",
+        "ns2",
+        "api",
+        "_dmarc",
+        "_sip._tcp",
+        "*",
+        "db-01",
+        "db-10",
+        "a",
+        "b",
+        "xn--nxasmq6b",
+        "",
+    ];
+    let mut corpus: Vec<String> = vec![];
+    for _ in 0..2000 {
+        let mut name = String::new();
+        for _ in 0..(rng.next_u32() % 4) {
+            name.push_str(hosts[(rng.next_u32() as usize) % hosts.len()]);
+            name.push('.');
+        }
+        name.push_str(zones[(rng.next_u32() as usize) % zones.len()]);
+        if (rng.next_u32() % 2) == 0 {
+            name.push('.');
+        }
+        if (rng.next_u32() % 4) == 0 {
+            name = name.to_ascii_uppercase();
+        }
+        corpus.push(name);
+    }
+    corpus.extend([".", "", "com", "local", "uk."].map(String::from));
+    for lhs in corpus.iter().step_by(7) {
+        for rhs in corpus.iter().step_by(5) {
+            let (x, y) = (DnsName::from(lhs.as_str()), DnsName::from(rhs.as_str()));
+            let expected = reference(lhs.as_bytes(), rhs.as_bytes());
+            assert_eq!(x.cmp(&y), expected, "{lhs:?} {rhs:?}");
+            assert_eq!(x == y, expected == Equal);
+            let mut tracker = NaturalTracker::new();
+            assert_eq!(x.tree_cmp(&y, &mut tracker), expected);
+        }
+    }
+    let names: Vec<DnsName> = corpus.iter().map(|s| DnsName::from(s.as_str())).collect();
+    check_tree_ord(&names, &names, 0);
+    let mut space = names.clone();
+    space.sort();
+    space.dedup();
+    let mut skipped2 = 0;
+    for query in &names {
+        let mut tracker = NaturalTracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            // the proven labels and their dots are shared by the key and the
+            // query, ignoring the trailing dot and case
+            let proven = tracker.proven_bytes();
+            let trim = |s: &[u8]| s.strip_suffix(b".").unwrap_or(s).to_ascii_lowercase();
+            let (k_trim, q_trim) = (trim(k.0), trim(query.0));
+            assert_eq!(
+                k_trim[(k_trim.len() - proven.min(k_trim.len()))..],
+                q_trim[(q_trim.len() - proven.min(q_trim.len()))..]
+            );
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        });
+        assert_eq!(space[found.unwrap()], *query);
+        assert_eq!(
+            tree_lower_bound_hereditary(&space, query),
+            space.partition_point(|k| k < query)
+        );
+        if tracker.proven_segments() >= 2 {
+            skipped2 += 1;
+        }
+    }
+    // the zone is usually proven and skipped
+    assert!(skipped2 * 2 > names.len(), "{skipped2}");
+}
+
+#[test]
+fn component_ordered_path() {
+    use std::{
+        hash::{BuildHasher, RandomState},
+        path::Path,
+    };
+
+    use tree_ord::{testing::check_tree_ord, utils::LexicographicTracker, ComponentOrderedPath};
+
+    let p = ComponentOrderedPath;
+    // directories are grouped together
+    assert!(p("foo/a") < p("foo/z"));
+    assert!(p("foo/z") < p("foo-bar/x"));
+    assert!(p("foo/z") < p("foo.txt"));
+    assert!(p("foo") < p("foo/a"));
+    // repeated and trailing slashes are ignored
+    assert_eq!(p("foo//bar/"), p("foo/bar"));
+    assert_eq!(p("//"), p("/"));
+    assert_eq!(p("").components().count(), 0);
+    assert!(p("") < p("/"));
+    // absolute paths sort before relative paths
+    assert!(p("/") < p("/a"));
+    assert!(p("/zzz") < p("a"));
+    assert!(p("/zzz") < p(".."));
+    // "." and ".." are ordinary components
+    assert_ne!(p("a/./b"), p("a/b"));
+    assert_ne!(p("a/../b"), p("b"));
+    assert!(p("a/./b") < p("a/b"));
+    assert!(p("a/../b") < p("a/b"));
+    let hasher = RandomState::new();
+    assert_eq!(hasher.hash_one(p("/a//b/")), hasher.hash_one(p("/a/b")));
+    assert_ne!(hasher.hash_one(p("/a/b")), hasher.hash_one(p("a/b")));
+    let path = Path::new("/usr/lib");
+    let converted = ComponentOrderedPath::from_path(path).unwrap();
+    assert_eq!(converted, p("/usr/lib/"));
+    assert_eq!(converted.as_path(), path);
+
+    // reference implementation that sorts the component vectors directly
+    fn reference(x: &str, y: &str) -> Ordering {
+        let key = |s: &str| -> Vec<Vec<u8>> {
+            let mut v = vec![];
+            if s.starts_with('/') {
+                v.push(vec![]);
+            }
+            v.extend(
+                s.split('/')
+                    .filter(|c| !c.is_empty())
+                    .map(|c| c.as_bytes().to_vec()),
+            );
+            v
+        };
+        key(x).cmp(&key(y))
+    }
+
+    // a source tree with similar names, mixed separators, and dot components
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let parts = [
+        "src", "src-old", "src.bak", "foo", "foo-bar", "foo_bar", "Foo", "lib.rs", "mod.rs", "a",
+        "a.b", ".", "..", ".git", "ä",
+    ];
+    let seps = ["/", "/", "/", "/", "//"];
+    let mut corpus: Vec<String> = vec![];
+    for _ in 0..2000 {
+        let mut s = String::new();
+        if (rng.next_u32() % 3) == 0 {
+            s.push('/');
+        }
+        s.push_str("project/crates");
+        for _ in 0..(rng.next_u32() % 5) {
+            s.push_str(seps[(rng.next_u32() as usize) % seps.len()]);
+            s.push_str(parts[(rng.next_u32() as usize) % parts.len()]);
+        }
+        if (rng.next_u32() % 4) == 0 {
+            s.push('/');
+        }
+        corpus.push(s);
+    }
+    corpus.extend(["", "/", "//", "project", "project-x", "/project"].map(String::from));
+    for lhs in corpus.iter().step_by(7) {
+        for rhs in corpus.iter().step_by(5) {
+            let expected = reference(lhs, rhs);
+            assert_eq!(p(lhs).cmp(&p(rhs)), expected, "{lhs:?} {rhs:?}");
+            assert_eq!(p(lhs) == p(rhs), expected == Equal);
+            let mut tracker = LexicographicTracker::new();
+            assert_eq!(p(lhs).tree_cmp(&p(rhs), &mut tracker), expected);
+        }
+    }
+    let paths: Vec<ComponentOrderedPath> = corpus.iter().map(|s| p(s)).collect();
+    check_tree_ord(&paths, &paths, 0);
+    let mut space = paths.clone();
+    space.sort();
+    space.dedup();
+    for w in space.windows(2) {
+        assert_eq!(reference(w[0].0, w[1].0), Less);
+    }
+    let mut skipped = 0;
+    for query in &paths {
+        let mut tracker = LexicographicTracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            // the proven components are shared by the key and the query
+            let proven = tracker.proven_prefix_len();
+            assert!(k
+                .components()
+                .take(proven)
+                .eq(query.components().take(proven)));
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        });
+        assert_eq!(space[found.unwrap()], *query);
+        assert_eq!(
+            tree_lower_bound_hereditary(&space, query),
+            space.partition_point(|k| k < query)
+        );
+        // "project/crates" is usually proven and skipped
+        if tracker.proven_prefix_len() >= 2 {
+            skipped += 1;
+        }
+    }
+    assert!(skipped * 2 > paths.len(), "{skipped}");
+}
+
+#[test]
+fn shortlex() {
+    use tree_ord::{utils::ShortlexTracker, Shortlex};
+
+    assert!(Shortlex("b") < Shortlex("aa"));
+    assert!(Shortlex("ab") < Shortlex("b0"));
+    assert!(Shortlex([3, 4].as_slice()) < Shortlex([1, 2, 3].as_slice()));
+    assert!(Shortlex(vec![1, 2]) < Shortlex(vec![1, 3]));
+    assert!(Shortlex(String::new()) < Shortlex("\0".to_owned()));
+
+    // the order only differs from plain lexicographic order between different
+    // lengths
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut corpus: Vec<Vec<u8>> = vec![];
+    for _ in 0..3000 {
+        let len = (rng.next_u32() % 12) as usize;
+        corpus.push(
+            (0..len)
+                .map(|_| b"abc"[(rng.next_u32() % 3) as usize])
+                .collect(),
+        );
+    }
+    for lhs in corpus.iter().step_by(7) {
+        for rhs in corpus.iter().step_by(5) {
+            let res = Shortlex(lhs.as_slice()).cmp(&Shortlex(rhs.as_slice()));
+            if lhs.len() == rhs.len() {
+                assert_eq!(res, lhs.cmp(rhs));
+            } else {
+                assert_eq!(res, lhs.len().cmp(&rhs.len()));
+            }
+            let mut tracker = ShortlexTracker::new();
+            assert_eq!(
+                Shortlex(lhs.as_slice()).tree_cmp(&Shortlex(rhs.as_slice()), &mut tracker),
+                res
+            );
+        }
+    }
+
+    // once the length locks in, only the content is compared and its prefix is
+    // tracked
+    let mut space: Vec<Vec<COrd<u64>>> = corpus
+        .iter()
+        .map(|k| k.iter().map(|x| COrd(u64::from(*x))).collect())
+        .collect();
+    space.sort_by(|a, b| a.len().cmp(&b.len()).then_with(|| a.cmp(b)));
+    space.dedup();
+    let space: Vec<Shortlex<&[COrd<u64>]>> = space.iter().map(|k| Shortlex(k.as_slice())).collect();
+    let mut locked = 0;
+    let mut tree_comparisons = 0;
+    let mut comparisons = 0;
+    for (i, query) in space.iter().enumerate() {
+        let mut tracker = <Shortlex<&[COrd<u64>]> as TreeOrd>::Tracker::new();
+        let scope = CountScope::new();
+        let found = tree_binary_search_by(&space, |k| {
+            if tracker.len_locked() {
+                let proven = tracker.content().proven_prefix_len();
+                assert_eq!(k.0.len(), query.0.len());
+                assert_eq!(k.0[..proven], query.0[..proven]);
+            }
+            k.tree_cmp(query, &mut tracker)
+        });
+        tree_comparisons += scope.count();
+        assert_eq!(found, Ok(i));
+        let scope = CountScope::new();
+        tree_binary_search_by(&space, |k| k.cmp(query)).unwrap();
+        comparisons += scope.count();
+        if tracker.len_locked() {
+            locked += 1;
+        }
+    }
+    assert!(locked * 10 > space.len() * 9);
+    assert!(tree_comparisons * 5 < comparisons * 4);
+
+    let mut tracker = ShortlexTracker::new();
+    assert_eq!(
+        Shortlex("abc").tree_cmp(&Shortlex("abd"), &mut tracker),
+        Less
+    );
+    assert!(!tracker.len_locked());
+    assert_eq!(
+        Shortlex("abz").tree_cmp(&Shortlex("abd"), &mut tracker),
+        Greater
+    );
+    assert!(tracker.len_locked());
+    assert_eq!(tracker.proven_prefix(), Some(2));
+}
+
+#[test]
+fn case_tiebreak() {
+    use tree_ord::{utils::CaseTiebreakTracker, CaseTiebreak};
+
+    let c = CaseTiebreak;
+    assert!(c("apple") < c("Apricot"));
+    assert!(c("Apple") < c("apple"));
+    assert!(c("APPLE") < c("Apple"));
+    assert!(c("apple") < c("APPLEs"));
+    assert!(c("Apple") != c("apple"));
+    assert_eq!(c("Apple").cmp(&c("Apple")), Equal);
+
+    // words with many case only duplicates
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let words = [
+        "apple", "apricot", "app", "banana", "band", "b", "ärger", "a-b", "a_b",
+    ];
+    let mut corpus: Vec<String> = vec![];
+    for _ in 0..3000 {
+        let word = words[(rng.next_u32() as usize) % words.len()];
+        let s: String = word
+            .chars()
+            .map(|ch| {
+                if (rng.next_u32() & 3) == 0 {
+                    ch.to_ascii_uppercase()
+                } else {
+                    ch
+                }
+            })
+            .collect();
+        corpus.push(s);
+    }
+    let reference = |x: &str, y: &str| {
+        x.to_ascii_lowercase()
+            .cmp(&y.to_ascii_lowercase())
+            .then_with(|| x.cmp(y))
+    };
+    for lhs in corpus.iter().step_by(7) {
+        for rhs in corpus.iter().step_by(5) {
+            assert_eq!(c(lhs).cmp(&c(rhs)), reference(lhs, rhs));
+            assert_eq!(c(lhs) == c(rhs), lhs == rhs);
+        }
+    }
+
+    let mut space: Vec<CaseTiebreak> = corpus.iter().map(|s| CaseTiebreak(s)).collect();
+    space.sort();
+    space.dedup();
+    let mut in_tiebreak = 0;
+    for (i, query) in space.iter().enumerate() {
+        let mut tracker = CaseTiebreakTracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            let proven = tracker.proven_prefix().unwrap();
+            assert!(k.0.as_bytes()[..proven].eq_ignore_ascii_case(&query.0.as_bytes()[..proven]));
+            if tracker.in_tiebreak() {
+                assert!(k.0.eq_ignore_ascii_case(query.0));
+                let proven = tracker.tiebreak().proven_prefix_len();
+                assert_eq!(k.0.as_bytes()[..proven], query.0.as_bytes()[..proven]);
+            }
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        });
+        assert_eq!(found, Ok(i));
+        if tracker.in_tiebreak() {
+            in_tiebreak += 1;
+        }
+    }
+    assert!(in_tiebreak * 2 > space.len());
+}
+
+#[test]
+fn key_builder() {
+    use tree_ord::key::KeyBuilder;
+
+    fn check<T: Ord + std::fmt::Debug>(values: &[T], encode: impl Fn(&T, &mut KeyBuilder)) {
+        let mut builder = KeyBuilder::new();
+        let keys: Vec<Vec<u8>> = values
+            .iter()
+            .map(|v| {
+                encode(v, &mut builder);
+                builder.finish()
+            })
+            .collect();
+        for i in (0..values.len()).step_by(3) {
+            for j in (0..values.len()).step_by(2) {
+                assert_eq!(
+                    keys[i].cmp(&keys[j]),
+                    values[i].cmp(&values[j]),
+                    "{:?} {:?}",
+                    values[i],
+                    values[j]
+                );
+            }
+        }
+        // the encodings also search the same with `TreeOrdBytes`
+        let mut sorted: Vec<&[u8]> = keys.iter().map(|k| k.as_slice()).collect();
+        sorted.sort();
+        sorted.dedup();
+        for (i, query) in sorted.iter().enumerate() {
+            let mut tracker = <TreeOrdBytes as TreeOrd>::Tracker::new();
+            let found = tree_binary_search_by(&sorted, |k| {
+                TreeOrdBytes(k).tree_cmp(&TreeOrdBytes(query), &mut tracker)
+            });
+            assert_eq!(found, Ok(i));
+        }
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    // values from small domains so that there are many ties and shared
+    // prefixes, along with the extremes
+    let int = |rng: &mut Xoshiro128StarStar| -> u128 {
+        match rng.next_u32() % 4 {
+            0 => u128::from(rng.next_u32() % 4),
+            1 => u128::MAX - u128::from(rng.next_u32() % 4),
+            2 => (u128::MAX >> 1) + u128::from(rng.next_u32() % 4) - 2,
+            _ => (u128::from(rng.next_u64()) << 64) | u128::from(rng.next_u64()),
+        }
+    };
+    let bytes = |rng: &mut Xoshiro128StarStar| -> Vec<u8> {
+        (0..(rng.next_u32() % 5))
+            .map(|_| [0, 1, 0xfe, 0xff, b'a'][(rng.next_u32() % 5) as usize])
+            .collect()
+    };
+
+    // a byte string followed by bytes that could be mistaken for an escape
+    let values: Vec<(Vec<u8>, u8, Vec<u8>)> = (0..1000)
+        .map(|_| {
+            (
+                bytes(&mut rng),
+                [0, 1, 0xff][(rng.next_u32() % 3) as usize],
+                bytes(&mut rng),
+            )
+        })
+        .collect();
+    check(&values, |v, key| {
+        key.push_bytes(&v.0).push_u8(v.1).push_bytes(&v.2);
+    });
+
+    let words = ["", "a", "ab", "a\0", "a\0b", "b", "\0", "\u{ff}"];
+    let values: Vec<(String, i32, bool, Option<String>)> = (0..1000)
+        .map(|_| {
+            let word = |rng: &mut Xoshiro128StarStar| words[(rng.next_u32() % 8) as usize];
+            (
+                word(&mut rng).to_owned(),
+                int(&mut rng) as i32,
+                (rng.next_u32() & 1) == 0,
+                ((rng.next_u32() & 1) == 0).then(|| word(&mut rng).to_owned()),
+            )
+        })
+        .collect();
+    check(&values, |v, key| {
+        key.push_str(&v.0)
+            .push_i32(v.1)
+            .push_bool(v.2)
+            .push_option(v.3.as_deref(), |key, x| key.push_str(x));
+    });
+
+    #[allow(clippy::type_complexity)]
+    let values: Vec<(
+        u8,
+        i8,
+        u16,
+        i16,
+        u32,
+        u64,
+        i64,
+        u128,
+        i128,
+        Option<Option<u64>>,
+    )> = (0..1000)
+        .map(|_| {
+            (
+                int(&mut rng) as u8,
+                int(&mut rng) as i8,
+                int(&mut rng) as u16,
+                int(&mut rng) as i16,
+                int(&mut rng) as u32,
+                int(&mut rng) as u64,
+                int(&mut rng) as i64,
+                int(&mut rng),
+                int(&mut rng) as i128,
+                match rng.next_u32() % 3 {
+                    0 => None,
+                    1 => Some(None),
+                    _ => Some(Some(int(&mut rng) as u64)),
+                },
+            )
+        })
+        .collect();
+    check(&values, |v, key| {
+        key.push_u8(v.0)
+            .push_i8(v.1)
+            .push_u16(v.2)
+            .push_i16(v.3)
+            .push_u32(v.4)
+            .push_u64(v.5)
+            .push_i64(v.6)
+            .push_u128(v.7)
+            .push_i128(v.8)
+            .push_option(v.9, |key, x| key.push_option(x, |key, x| key.push_u64(x)));
+    });
+
+    let mut key = KeyBuilder::new();
+    key.push_str("a\0").push_i8(-1).push_bool(true);
+    assert_eq!(key.as_bytes(), &[b'a', 0, 0xff, 0, 1, 0x7f, 1]);
+}
+
+#[test]
+fn key_floats() {
+    use tree_ord::key::{decode_f32, decode_f64, encode_f32, encode_f64, KeyBuilder};
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    // bit patterns from the whole space, with extra weight on the exponent and
+    // mantissa extremes (zeros, subnormals, infinities, and NaN payloads)
+    let mut f64s: Vec<f64> = vec![
+        0.0,
+        -0.0,
+        f64::MIN_POSITIVE,
+        -f64::MIN_POSITIVE,
+        f64::EPSILON,
+        f64::MAX,
+        f64::MIN,
+        f64::INFINITY,
+        f64::NEG_INFINITY,
+        f64::NAN,
+        -f64::NAN,
+        f64::from_bits(1),
+        f64::from_bits(1 | (1 << 63)),
+    ];
+    let mut f32s: Vec<f32> = f64s.iter().map(|x| *x as f32).collect();
+    for _ in 0..4000 {
+        let sign = u64::from(rng.next_u32() & 1);
+        let exponent = match rng.next_u32() % 4 {
+            0 => 0,
+            1 => 0x7ff,
+            2 => u64::from(rng.next_u32() % 4),
+            _ => u64::from(rng.next_u32()) & 0x7ff,
+        };
+        let mantissa = match rng.next_u32() % 3 {
+            0 => u64::from(rng.next_u32() % 4),
+            1 => (1 << 52) - 1 - u64::from(rng.next_u32() % 4),
+            _ => rng.next_u64() & ((1 << 52) - 1),
+        };
+        f64s.push(f64::from_bits((sign << 63) | (exponent << 52) | mantissa));
+        let exponent = exponent as u32 & 0xff;
+        let mantissa = mantissa as u32 & ((1 << 23) - 1);
+        f32s.push(f32::from_bits(
+            ((sign as u32) << 31) | (exponent << 23) | mantissa,
+        ));
+        f32s.push(f32::from_bits(rng.next_u32()));
+    }
+
+    for x in &f64s {
+        assert_eq!(decode_f64(encode_f64(*x)).to_bits(), x.to_bits());
+    }
+    for x in &f32s {
+        assert_eq!(decode_f32(encode_f32(*x)).to_bits(), x.to_bits());
+    }
+    for x in f64s.iter().step_by(3) {
+        for y in f64s.iter().step_by(5) {
+            assert_eq!(encode_f64(*x).cmp(&encode_f64(*y)), x.total_cmp(y));
+        }
+    }
+    for x in f32s.iter().step_by(3) {
+        for y in f32s.iter().step_by(5) {
+            assert_eq!(encode_f32(*x).cmp(&encode_f32(*y)), x.total_cmp(y));
+        }
+    }
+
+    // the zeros are distinct but adjacent
+    let mut sorted = f64s.clone();
+    sorted.sort_by(f64::total_cmp);
+    sorted.dedup_by(|x, y| x.to_bits() == y.to_bits());
+    let i = sorted
+        .iter()
+        .position(|x| x.to_bits() == (-0.0f64).to_bits())
+        .unwrap();
+    assert_eq!(sorted[i + 1].to_bits(), 0.0f64.to_bits());
+    assert!(encode_f64(-0.0) < encode_f64(0.0));
+    assert_eq!(encode_f64(f64::from_bits(1)), [0x80, 0, 0, 0, 0, 0, 0, 1]);
+
+    // floats within composite keys
+    let mut key = KeyBuilder::new();
+    let a = key.push_f32(-1.5).push_f64(2.0).finish();
+    let b = key.push_f32(-1.5).push_f64(f64::NAN).finish();
+    let c = key.push_f32(-0.0).push_f64(f64::NEG_INFINITY).finish();
+    assert!(a < b);
+    assert!(b < c);
+    assert_eq!(&a[..4], &encode_f32(-1.5));
+}
+
+#[test]
+fn key_builder_desc() {
+    use tree_ord::key::KeyBuilder;
+
+    type Value = (String, u64, Option<Vec<u8>>, i16, bool);
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let words = ["", "a", "ab", "a\0", "a\0b", "b", "\0", "\u{ff}"];
+    let bytes = |rng: &mut Xoshiro128StarStar| -> Vec<u8> {
+        (0..(rng.next_u32() % 4))
+            .map(|_| [0, 1, 0xfe, 0xff][(rng.next_u32() % 4) as usize])
+            .collect()
+    };
+    let values: Vec<Value> = (0..800)
+        .map(|_| {
+            (
+                words[(rng.next_u32() % 8) as usize].to_owned(),
+                [0, 1, u64::MAX][(rng.next_u32() % 3) as usize],
+                ((rng.next_u32() & 1) == 0).then(|| bytes(&mut rng)),
+                [i16::MIN, -1, 0, 1, i16::MAX][(rng.next_u32() % 5) as usize],
+                (rng.next_u32() & 1) == 0,
+            )
+        })
+        .collect();
+
+    // every combination of directions
+    for desc in 0..32u32 {
+        let dir = |i: u32, res: Ordering| {
+            if (desc & (1 << i)) != 0 {
+                res.reverse()
+            } else {
+                res
+            }
+        };
+        let reference = |x: &Value, y: &Value| {
+            dir(0, x.0.cmp(&y.0))
+                .then(dir(1, x.1.cmp(&y.1)))
+                .then(dir(2, x.2.cmp(&y.2)))
+                .then(dir(3, x.3.cmp(&y.3)))
+                .then(dir(4, x.4.cmp(&y.4)))
+        };
+        let mut key = KeyBuilder::new();
+        let keys: Vec<Vec<u8>> = values
+            .iter()
+            .map(|v| {
+                let field = |key: &mut KeyBuilder, i: u32, f: &dyn Fn(&mut KeyBuilder)| {
+                    if (desc & (1 << i)) != 0 {
+                        key.push_desc(|key| {
+                            f(key);
+                            key
+                        });
+                    } else {
+                        f(key);
+                    }
+                };
+                field(&mut key, 0, &|key| {
+                    key.push_str(&v.0);
+                });
+                field(&mut key, 1, &|key| {
+                    key.push_u64(v.1);
+                });
+                field(&mut key, 2, &|key| {
+                    key.push_option(v.2.as_deref(), |key, x| key.push_bytes(x));
+                });
+                field(&mut key, 3, &|key| {
+                    key.push_i16(v.3);
+                });
+                field(&mut key, 4, &|key| {
+                    key.push_bool(v.4);
+                });
+                key.finish()
+            })
+            .collect();
+        for i in (0..values.len()).step_by(7) {
+            for j in (0..values.len()).step_by(3) {
+                assert_eq!(
+                    keys[i].cmp(&keys[j]),
+                    reference(&values[i], &values[j]),
+                    "{desc} {:?} {:?}",
+                    values[i],
+                    values[j]
+                );
+            }
+        }
+    }
+
+    // nested directions cancel out
+    let mut key = KeyBuilder::new();
+    let a = key
+        .push_desc(|key| key.push_desc(|key| key.push_str("ab")))
+        .finish();
+    assert_eq!(a, key.push_str("ab").finish());
+    let a = key.push_desc(|key| key.push_str("a\0")).finish();
+    assert_eq!(a, [!b'a', 0xff, 0, 0xff, 0xfe]);
+}
+
+#[test]
+fn key_reader() {
+    use tree_ord::key::{KeyBuilder, KeyError, KeyErrorKind, KeyReader};
+
+    #[derive(Debug, Clone)]
+    enum Schema {
+        U8,
+        I8,
+        U16,
+        I16,
+        U32,
+        I32,
+        U64,
+        I64,
+        U128,
+        I128,
+        F32,
+        F64,
+        Bool,
+        Bytes,
+        Str,
+        Opt(Box<Schema>),
+        Desc(Vec<Schema>),
+    }
+
+    #[derive(Debug, Clone)]
+    enum Value {
+        U8(u8),
+        I8(i8),
+        U16(u16),
+        I16(i16),
+        U32(u32),
+        I32(i32),
+        U64(u64),
+        I64(i64),
+        U128(u128),
+        I128(i128),
+        F32(f32),
+        F64(f64),
+        Bool(bool),
+        Bytes(Vec<u8>),
+        Str(String),
+        Opt(Option<Box<Value>>),
+        Desc(Vec<Value>),
+    }
+
+    fn gen_schema(rng: &mut Xoshiro128StarStar, depth: u32) -> Schema {
+        let n = if depth < 3 { 17 } else { 15 };
+        match rng.next_u32() % n {
+            0 => Schema::U8,
+            1 => Schema::I8,
+            2 => Schema::U16,
+            3 => Schema::I16,
+            4 => Schema::U32,
+            5 => Schema::I32,
+            6 => Schema::U64,
+            7 => Schema::I64,
+            8 => Schema::U128,
+            9 => Schema::I128,
+            10 => Schema::F32,
+            11 => Schema::F64,
+            12 => Schema::Bool,
+            13 => Schema::Bytes,
+            14 => Schema::Str,
+            15 => Schema::Opt(Box::new(gen_schema(rng, depth + 1))),
+            _ => Schema::Desc(
+                (0..(1 + rng.next_u32() % 3))
+                    .map(|_| gen_schema(rng, depth + 1))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn gen_value(rng: &mut Xoshiro128StarStar, schema: &Schema) -> Value {
+        let x = (u128::from(rng.next_u64()) << 64) | u128::from(rng.next_u64());
+        match schema {
+            Schema::U8 => Value::U8(x as u8),
+            Schema::I8 => Value::I8(x as i8),
+            Schema::U16 => Value::U16(x as u16),
+            Schema::I16 => Value::I16(x as i16),
+            Schema::U32 => Value::U32(x as u32),
+            Schema::I32 => Value::I32(x as i32),
+            Schema::U64 => Value::U64(x as u64),
+            Schema::I64 => Value::I64(x as i64),
+            Schema::U128 => Value::U128(x),
+            Schema::I128 => Value::I128(x as i128),
+            Schema::F32 => Value::F32(f32::from_bits(x as u32)),
+            Schema::F64 => Value::F64(f64::from_bits(x as u64)),
+            Schema::Bool => Value::Bool((x & 1) == 0),
+            Schema::Bytes => Value::Bytes(
+                (0..(rng.next_u32() % 80))
+                    .map(|_| [0, 1, 0xfe, 0xff, b'a'][(rng.next_u32() % 5) as usize])
+                    .collect(),
+            ),
+            Schema::Str => Value::Str(
+                (0..(rng.next_u32() % 80))
+                    .map(|_| {
+                        ['\0', 'a', 'é', '\u{ffff}', '\u{10ffff}'][(rng.next_u32() % 5) as usize]
+                    })
+                    .collect(),
+            ),
+            Schema::Opt(schema) => {
+                Value::Opt(((x & 1) == 0).then(|| Box::new(gen_value(rng, schema))))
+            }
+            Schema::Desc(schemas) => {
+                Value::Desc(schemas.iter().map(|s| gen_value(rng, s)).collect())
+            }
+        }
+    }
+
+    fn encode<'a>(key: &'a mut KeyBuilder, value: &Value) -> &'a mut KeyBuilder {
+        match value {
+            Value::U8(x) => key.push_u8(*x),
+            Value::I8(x) => key.push_i8(*x),
+            Value::U16(x) => key.push_u16(*x),
+            Value::I16(x) => key.push_i16(*x),
+            Value::U32(x) => key.push_u32(*x),
+            Value::I32(x) => key.push_i32(*x),
+            Value::U64(x) => key.push_u64(*x),
+            Value::I64(x) => key.push_i64(*x),
+            Value::U128(x) => key.push_u128(*x),
+            Value::I128(x) => key.push_i128(*x),
+            Value::F32(x) => key.push_f32(*x),
+            Value::F64(x) => key.push_f64(*x),
+            Value::Bool(x) => key.push_bool(*x),
+            Value::Bytes(x) => key.push_bytes(x),
+            Value::Str(x) => key.push_str(x),
+            Value::Opt(x) => key.push_option(x.as_deref(), encode),
+            Value::Desc(values) => key.push_desc(|key| {
+                for value in values {
+                    encode(key, value);
+                }
+                key
+            }),
+        }
+    }
+
+    fn decode(reader: &mut KeyReader, schema: &Schema) -> Result<Value, KeyError> {
+        Ok(match schema {
+            Schema::U8 => Value::U8(reader.read_u8()?),
+            Schema::I8 => Value::I8(reader.read_i8()?),
+            Schema::U16 => Value::U16(reader.read_u16()?),
+            Schema::I16 => Value::I16(reader.read_i16()?),
+            Schema::U32 => Value::U32(reader.read_u32()?),
+            Schema::I32 => Value::I32(reader.read_i32()?),
+            Schema::U64 => Value::U64(reader.read_u64()?),
+            Schema::I64 => Value::I64(reader.read_i64()?),
+            Schema::U128 => Value::U128(reader.read_u128()?),
+            Schema::I128 => Value::I128(reader.read_i128()?),
+            Schema::F32 => Value::F32(reader.read_f32()?),
+            Schema::F64 => Value::F64(reader.read_f64()?),
+            Schema::Bool => Value::Bool(reader.read_bool()?),
+            Schema::Bytes => {
+                let bytes = reader.read_bytes()?;
+                let res = bytes.to_vec();
+                assert_eq!(bytes.len(), res.len());
+                assert_eq!(bytes, *res.as_slice());
+                if let Some(slice) = bytes.as_slice() {
+                    assert_eq!(slice, res.as_slice());
+                }
+                Value::Bytes(res)
+            }
+            Schema::Str => {
+                let s = reader.read_str()?;
+                let res = String::from(s);
+                assert_eq!(s.to_string(), res);
+                assert_eq!(s, *res.as_str());
+                if let Some(slice) = s.as_str() {
+                    assert_eq!(slice, res);
+                }
+                Value::Str(res)
+            }
+            Schema::Opt(schema) => {
+                Value::Opt(reader.read_option(|r| decode(r, schema))?.map(Box::new))
+            }
+            Schema::Desc(schemas) => {
+                Value::Desc(reader.read_desc(|r| schemas.iter().map(|s| decode(r, s)).collect())?)
+            }
+        })
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut key = KeyBuilder::new();
+    for _ in 0..2000 {
+        let schema: Vec<Schema> = (0..(1 + rng.next_u32() % 5))
+            .map(|_| gen_schema(&mut rng, 0))
+            .collect();
+        let values: Vec<Value> = schema.iter().map(|s| gen_value(&mut rng, s)).collect();
+        for value in &values {
+            encode(&mut key, value);
+        }
+        let encoded = key.finish();
+
+        // encode -> decode -> encode is byte identical
+        let mut reader = KeyReader::new(&encoded);
+        let decoded: Vec<Value> = schema
+            .iter()
+            .map(|s| decode(&mut reader, s).unwrap())
+            .collect();
+        reader.finish().unwrap();
+        for value in &decoded {
+            encode(&mut key, value);
+        }
+        assert_eq!(key.finish(), encoded);
+
+        // truncated keys are errors
+        let len = (rng.next_u32() as usize) % encoded.len();
+        let mut reader = KeyReader::new(&encoded[..len]);
+        let res: Result<Vec<Value>, KeyError> =
+            schema.iter().map(|s| decode(&mut reader, s)).collect();
+        assert!(res.is_err() || reader.finish().is_err());
+
+        // random bytes never panic
+        let garbage: Vec<u8> = (0..(rng.next_u32() % 40))
+            .map(|_| match rng.next_u32() % 3 {
+                0 => [0, 1, 0xfe, 0xff][(rng.next_u32() % 4) as usize],
+                _ => rng.next_u32() as u8,
+            })
+            .collect();
+        let mut reader = KeyReader::new(&garbage);
+        for s in &schema {
+            if decode(&mut reader, s).is_err() {
+                break
+            }
+        }
+    }
+
+    let err = |kind, offset| KeyError { kind, offset };
+    let mut reader = KeyReader::new(&[5, b'a', 0, 5]);
+    reader.read_u8().unwrap();
+    assert_eq!(
+        reader.read_bytes().unwrap_err(),
+        err(KeyErrorKind::InvalidEscape, 1)
+    );
+    assert_eq!(
+        KeyReader::new(&[b'a', 0]).read_bytes().unwrap_err(),
+        err(KeyErrorKind::UnexpectedEnd, 0)
+    );
+    assert_eq!(
+        KeyReader::new(&[2]).read_bool(),
+        Err(err(KeyErrorKind::InvalidBool, 0))
+    );
+    assert_eq!(
+        KeyReader::new(&[2]).read_option(|r| r.read_u8()),
+        Err(err(KeyErrorKind::InvalidTag, 0))
+    );
+    assert_eq!(
+        KeyReader::new(&[0xc3, 0, 1]).read_str().unwrap_err(),
+        err(KeyErrorKind::InvalidUtf8, 0)
+    );
+    assert_eq!(
+        KeyReader::new(&[0, 0, 0]).read_u32(),
+        Err(err(KeyErrorKind::UnexpectedEnd, 0))
+    );
+    // a failed descending read does not consume the fields read before the
+    // error
+    let mut reader = KeyReader::new(&[!0, !1, !2]);
+    assert_eq!(
+        reader.read_desc(|r| Ok((r.read_bool()?, r.read_bool()?, r.read_bool()?))),
+        Err(err(KeyErrorKind::InvalidBool, 2))
+    );
+    assert_eq!(reader.offset(), 0);
+    let mut reader = KeyReader::new(&[1, 2]);
+    reader.read_u8().unwrap();
+    assert_eq!(reader.remaining(), &[2]);
+    assert_eq!(reader.finish(), Err(err(KeyErrorKind::TrailingBytes, 1)));
+}
+
+#[test]
+fn fdb_tuple() {
+    use tree_ord::key::{
+        fdb::{TupleBuilder, TupleReader},
+        KeyErrorKind,
+    };
+
+    // known encodings from the tuple layer specification and its reference
+    // implementations
+    let mut key = TupleBuilder::new();
+    let vectors: Vec<(Vec<u8>, &[u8])> = vec![
+        (key.push_null().finish(), b"\x00"),
+        (
+            key.push_bytes(b"foo\x00bar").finish(),
+            b"\x01foo\x00\xffbar\x00",
+        ),
+        (
+            key.push_str("F\u{d4}O\u{0}bar").finish(),
+            b"\x02F\xc3\x94O\x00\xffbar\x00",
+        ),
+        (
+            key.push_nested(|k| k.push_bytes(b"foo\x00bar").push_null().push_nested(|k| k))
+                .finish(),
+            b"\x05\x01foo\x00\xffbar\x00\x00\xff\x05\x00\x00",
+        ),
+        (key.push_i64(-5551212).finish(), b"\x11\xab\x4b\x93"),
+        (key.push_u8(0).finish(), b"\x14"),
+        (key.push_i8(1).finish(), b"\x15\x01"),
+        (key.push_i8(-1).finish(), b"\x13\xfe"),
+        (key.push_u16(255).finish(), b"\x15\xff"),
+        (key.push_i16(-255).finish(), b"\x13\x00"),
+        (key.push_u32(256).finish(), b"\x16\x01\x00"),
+        (key.push_i32(-256).finish(), b"\x12\xfe\xff"),
+        (
+            key.push_u64(u64::MAX).finish(),
+            b"\x1c\xff\xff\xff\xff\xff\xff\xff\xff",
+        ),
+        (
+            key.push_i64(i64::MIN).finish(),
+            b"\x0c\x7f\xff\xff\xff\xff\xff\xff\xff",
+        ),
+        (
+            key.push_u128(1 << 64).finish(),
+            b"\x1d\x09\x01\x00\x00\x00\x00\x00\x00\x00\x00",
+        ),
+        (
+            key.push_i128(-(1 << 64)).finish(),
+            b"\x0b\xf6\xfe\xff\xff\xff\xff\xff\xff\xff\xff",
+        ),
+        (key.push_f32(1.0).finish(), b"\x20\xbf\x80\x00\x00"),
+        (key.push_f32(-42.0).finish(), b"\x20\x3d\xd7\xff\xff"),
+        (
+            key.push_f64(-42.0).finish(),
+            b"\x21\x3f\xba\xff\xff\xff\xff\xff\xff",
+        ),
+        (key.push_bool(false).finish(), b"\x26"),
+        (key.push_bool(true).finish(), b"\x27"),
+        (
+            key.push_option(None::<u8>, |k, x| k.push_u8(x))
+                .push_nested(|k| k.push_option(None::<u8>, |k, x| k.push_u8(x)))
+                .finish(),
+            b"\x00\x05\x00\xff\x00",
+        ),
+    ];
+    for (i, (encoded, expected)) in vectors.iter().enumerate() {
+        assert_eq!(encoded.as_slice(), *expected, "{i}");
+    }
+
+    let mut reader = TupleReader::new(b"\x05\x01foo\x00\xffbar\x00\x00\xff\x05\x00\x00");
+    reader
+        .read_nested(|r| {
+            assert_eq!(r.read_bytes()?.to_vec(), b"foo\0bar");
+            r.read_null()?;
+            r.read_nested(|_| Ok(()))
+        })
+        .unwrap();
+    reader.finish().unwrap();
+    let mut reader = TupleReader::new(b"\x11\xab\x4b\x93\x1c\xff\xff\xff\xff\xff\xff\xff\xff");
+    assert_eq!(reader.read_i32(), Ok(-5551212));
+    assert_eq!(
+        reader.clone().read_i64().unwrap_err().kind,
+        KeyErrorKind::OutOfRange
+    );
+    assert_eq!(reader.read_u64(), Ok(u64::MAX));
+    assert_eq!(
+        TupleReader::new(b"\x15\x01").read_str().unwrap_err().kind,
+        KeyErrorKind::InvalidTypeCode
+    );
+    assert_eq!(
+        TupleReader::new(b"\x13\xfe").read_u8().unwrap_err().kind,
+        KeyErrorKind::OutOfRange
+    );
+
+    // order preservation against tuple comparisons
+    type Value = (i128, Option<String>, (u64, Option<i16>), f64, Vec<u8>, bool);
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let words = ["", "a", "ab", "a\0", "\0", "\u{ff}"];
+    let int = |rng: &mut Xoshiro128StarStar| -> i128 {
+        let x = (i128::from(rng.next_u64()) << 64) | i128::from(rng.next_u64());
+        // all the magnitudes in bytes, around the powers of 256
+        let shift = rng.next_u32() % 128;
+        match rng.next_u32() % 3 {
+            0 => x >> shift,
+            1 => (1i128 << (shift & !7)).wrapping_sub(1 + i128::from(rng.next_u32() % 3)),
+            _ => (-1i128 << (shift & !7)).wrapping_add(i128::from(rng.next_u32() % 3)),
+        }
+    };
+    let values: Vec<Value> = (0..1500)
+        .map(|_| {
+            let word = |rng: &mut Xoshiro128StarStar| words[(rng.next_u32() % 6) as usize];
+            (
+                int(&mut rng),
+                ((rng.next_u32() & 1) == 0).then(|| word(&mut rng).to_owned()),
+                (
+                    int(&mut rng) as u64 & 0x1ffff,
+                    ((rng.next_u32() & 1) == 0).then(|| int(&mut rng) as i16),
+                ),
+                [f64::NEG_INFINITY, -1.5, -0.0, 0.0, 2.5, f64::NAN][(rng.next_u32() % 6) as usize],
+                word(&mut rng).as_bytes().to_vec(),
+                (rng.next_u32() & 1) == 0,
+            )
+        })
+        .collect();
+    let keys: Vec<Vec<u8>> = values
+        .iter()
+        .map(|v| {
+            key.push_i128(v.0)
+                .push_option(v.1.as_deref(), |k, x| k.push_str(x))
+                .push_nested(|k| k.push_u64(v.2 .0).push_option(v.2 .1, |k, x| k.push_i16(x)))
+                .push_f64(v.3)
+                .push_bytes(&v.4)
+                .push_bool(v.5)
+                .finish()
+        })
+        .collect();
+    for i in (0..values.len()).step_by(3) {
+        for j in (0..values.len()).step_by(2) {
+            let (x, y) = (&values[i], &values[j]);
+            let expected =
+                x.0.cmp(&y.0)
+                    .then(x.1.cmp(&y.1))
+                    .then(x.2.cmp(&y.2))
+                    .then(x.3.total_cmp(&y.3))
+                    .then(x.4.cmp(&y.4))
+                    .then(x.5.cmp(&y.5));
+            assert_eq!(keys[i].cmp(&keys[j]), expected, "{x:?} {y:?}");
+        }
+    }
+
+    // round trips
+    for (v, encoded) in values.iter().zip(keys.iter()) {
+        let mut reader = TupleReader::new(encoded);
+        assert_eq!(reader.read_i128(), Ok(v.0));
+        let s = reader
+            .read_option(|r| r.read_str().map(String::from))
+            .unwrap();
+        assert_eq!(s, v.1);
+        let nested = reader
+            .read_nested(|r| Ok((r.read_u64()?, r.read_option(|r| r.read_i16())?)))
+            .unwrap();
+        assert_eq!(nested, v.2);
+        assert_eq!(reader.read_f64().unwrap().to_bits(), v.3.to_bits());
+        assert_eq!(reader.read_bytes().unwrap().to_vec(), v.4);
+        assert_eq!(reader.read_bool(), Ok(v.5));
+        reader.finish().unwrap();
+        // random bytes and truncations never panic
+        let len = (rng.next_u32() as usize) % encoded.len();
+        let mut reader = TupleReader::new(&encoded[..len]);
+        let _ = reader.read_i128();
+        let _ = reader.read_option(|r| r.read_str().map(|_| ()));
+        let _ = reader.read_nested(|r| r.read_u64());
+        let garbage: Vec<u8> = (0..(rng.next_u32() % 24))
+            .map(|_| rng.next_u32() as u8)
+            .collect();
+        let mut reader = TupleReader::new(&garbage);
+        let _ = reader.read_u128();
+        let _ = reader.read_nested(|r| r.read_option(|r| r.read_bytes().map(|_| ())));
+        let _ = reader.read_f32();
+    }
+}
+
+#[test]
+fn memcomparable() {
+    use tree_ord::key::{
+        memcomparable::{MemcomparableBuilder, MemcomparableReader},
+        KeyErrorKind,
+    };
+
+    // known encodings from the TiKV implementation
+    let mut key = MemcomparableBuilder::new();
+    let vectors: Vec<(&[u8], &[u8])> = vec![
+        (&[], &[0, 0, 0, 0, 0, 0, 0, 0, 247]),
+        (&[0], &[0, 0, 0, 0, 0, 0, 0, 0, 248]),
+        (&[1, 2, 3], &[1, 2, 3, 0, 0, 0, 0, 0, 250]),
+        (&[1, 2, 3, 0], &[1, 2, 3, 0, 0, 0, 0, 0, 251]),
+        (&[1, 2, 3, 4, 5, 6, 7], &[1, 2, 3, 4, 5, 6, 7, 0, 254]),
+        (&[0, 0, 0, 0, 0, 0, 0, 0], &[
+            0, 0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 0, 247,
+        ]),
+        (&[1, 2, 3, 4, 5, 6, 7, 8], &[
+            1, 2, 3, 4, 5, 6, 7, 8, 255, 0, 0, 0, 0, 0, 0, 0, 0, 247,
+        ]),
+        (&[1, 2, 3, 4, 5, 6, 7, 8, 9], &[
+            1, 2, 3, 4, 5, 6, 7, 8, 255, 9, 0, 0, 0, 0, 0, 0, 0, 248,
+        ]),
+    ];
+    for (i, (bytes, expected)) in vectors.iter().enumerate() {
+        assert_eq!(key.push_bytes(bytes).finish(), *expected, "{i}");
+        let desc: Vec<u8> = expected.iter().map(|b| !b).collect();
+        assert_eq!(key.push_desc(|k| k.push_bytes(bytes)).finish(), desc, "{i}");
+        let mut reader = MemcomparableReader::new(expected);
+        assert_eq!(reader.read_bytes().unwrap(), **bytes);
+        reader.finish().unwrap();
+        let mut reader = MemcomparableReader::new(&desc);
+        assert_eq!(reader.read_desc(|r| r.read_bytes()).unwrap(), **bytes);
+        reader.finish().unwrap();
+    }
+    assert_eq!(key.push_i64(-1).finish(), [
+        0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff
+    ]);
+    assert_eq!(key.push_i64(i64::MIN).finish(), [0; 8]);
+    assert_eq!(key.push_u64(1).finish(), [0, 0, 0, 0, 0, 0, 0, 1]);
+    assert_eq!(key.push_f64(0.0).finish(), [0x80, 0, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(key.push_f64(-1.0).finish(), [
+        0x40, 0x0f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff
+    ]);
+
+    // invalid markers and padding
+    let err = |bytes: &[u8]| MemcomparableReader::new(bytes).read_bytes().unwrap_err();
+    assert_eq!(
+        err(&[1, 2, 3, 0, 0, 0, 0, 0, 246]).kind,
+        KeyErrorKind::InvalidGroup
+    );
+    assert_eq!(
+        err(&[1, 2, 3, 0, 0, 0, 0, 1, 250]).kind,
+        KeyErrorKind::InvalidGroup
+    );
+    assert_eq!(
+        err(&[1, 2, 3, 4, 0, 0, 0, 0, 255]).kind,
+        KeyErrorKind::UnexpectedEnd
+    );
+    assert_eq!(
+        err(&[1, 2, 3, 0, 0, 0, 0, 0]).kind,
+        KeyErrorKind::UnexpectedEnd
+    );
+    let mut reader = MemcomparableReader::new(&[0xff, 0, 0, 0, 0, 0, 0, 0, 249]);
+    assert_eq!(
+        reader.read_str().unwrap_err().kind,
+        KeyErrorKind::InvalidUtf8
+    );
+    assert_eq!(reader.offset(), 0);
+
+    // order preservation against plain byte string comparison, with lengths
+    // around the multiples of 8
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let lens = [0, 1, 7, 8, 9, 15, 16, 17, 24];
+    let values: Vec<(Vec<u8>, i64, Vec<u8>)> = (0..600)
+        .map(|_| {
+            let bytes = |rng: &mut Xoshiro128StarStar| -> Vec<u8> {
+                let len = lens[(rng.next_u32() as usize) % lens.len()];
+                // small alphabet including 0 and 0xff so that there are many
+                // common prefixes and padding lookalikes
+                (0..len)
+                    .map(|_| [0, 1, 0xfe, 0xff][(rng.next_u32() % 4) as usize])
+                    .collect()
+            };
+            let a = bytes(&mut rng);
+            let x = (rng.next_u64() as i64) >> (rng.next_u32() % 64);
+            (a, x, bytes(&mut rng))
+        })
+        .collect();
+    let keys: Vec<Vec<u8>> = values
+        .iter()
+        .map(|v| {
+            key.push_bytes(&v.0)
+                .push_desc(|k| k.push_i64(v.1).push_bytes(&v.2))
+                .finish()
+        })
+        .collect();
+    for i in 0..values.len() {
+        for j in (0..values.len()).step_by(3) {
+            let (x, y) = (&values[i], &values[j]);
+            let expected = x.0.cmp(&y.0).then(y.1.cmp(&x.1)).then(y.2.cmp(&x.2));
+            assert_eq!(keys[i].cmp(&keys[j]), expected, "{x:?} {y:?}");
+        }
+    }
+
+    // round trips
+    for (v, encoded) in values.iter().zip(keys.iter()) {
+        let mut reader = MemcomparableReader::new(encoded);
+        let bytes = reader.read_bytes().unwrap();
+        assert_eq!(bytes.len(), v.0.len());
+        assert_eq!(bytes.iter().collect::<Vec<u8>>(), v.0);
+        let (x, rest) = reader
+            .read_desc(|r| Ok((r.read_i64()?, r.read_bytes()?.to_vec())))
+            .unwrap();
+        assert_eq!((x, rest), (v.1, v.2.clone()));
+        reader.finish().unwrap();
+        // truncations and random bytes never panic
+        let len = (rng.next_u32() as usize) % encoded.len();
+        let mut reader = MemcomparableReader::new(&encoded[..len]);
+        let _ = reader.read_bytes();
+        let _ = reader.read_desc(|r| r.read_i64());
+        let garbage: Vec<u8> = (0..(rng.next_u32() % 40))
+            .map(|_| [0, 0xf7, 0xfa, 0xff][(rng.next_u32() % 4) as usize])
+            .collect();
+        let mut reader = MemcomparableReader::new(&garbage);
+        if let Ok(bytes) = reader.read_bytes() {
+            assert_eq!(bytes.iter().count(), bytes.len());
+        }
+        let _ = reader.read_desc(|r| r.read_str().map(|s| s.as_bytes().len()));
+        let _ = reader.read_f64();
+    }
+}
+
+#[test]
+fn morton() {
+    use tree_ord::{utils::LexicographicTracker, Morton2, Morton3};
+
+    // interleaves bit by bit, with the first coordinate in the low bit of each
+    // group
+    fn reference(coords: &[u32]) -> u128 {
+        let mut z = 0u128;
+        for bit in 0..32 {
+            for (i, c) in coords.iter().enumerate() {
+                z |= u128::from((c >> bit) & 1) << (bit * coords.len() + i);
+            }
+        }
+        z
+    }
+
+    fn search<K: TreeOrd<Tracker = LexicographicTracker<u8, u8>> + Ord>(
+        space: &[K],
+        query: &K,
+        compared: &mut (usize, usize),
+    ) -> Result<usize, usize> {
+        let mut tracker = K::Tracker::new();
+        tree_binary_search_by(space, |k| {
+            compared.0 += 32 - tracker.proven_prefix_len();
+            compared.1 += 32;
+            let res = k.tree_cmp(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            res
+        })
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    // points in a few small regions, like the objects of a sparse map
+    let coord = |rng: &mut Xoshiro128StarStar, i: usize| {
+        let base = [0, 0x8000_0000, u32::MAX - 0xffff, 0x1234_5678][i % 4];
+        base.wrapping_add(rng.next_u32() >> (16 + (i % 16)))
+    };
+    let mut space2: Vec<Morton2> = vec![];
+    let mut space3: Vec<Morton3> = vec![];
+    for i in 0..(1 << 11) {
+        let c = [coord(&mut rng, i), coord(&mut rng, i), coord(&mut rng, i)];
+        let p2 = Morton2(c[0], c[1]);
+        let p3 = Morton3(c[0], c[1], c[2]);
+        assert_eq!(u128::from(p2.interleaved()), reference(&c[..2]));
+        assert_eq!(p3.interleaved(), reference(&c));
+        assert_eq!(Morton2::from_interleaved(p2.interleaved()), p2);
+        assert_eq!(Morton3::from_interleaved(p3.interleaved()), p3);
+        space2.push(p2);
+        space3.push(p3);
+    }
+    for i in 0..space3.len() {
+        for j in (0..space3.len()).step_by(7) {
+            let (x, y) = (space3[i], space3[j]);
+            assert_eq!(
+                x.cmp(&y),
+                reference(&[x.0, x.1, x.2]).cmp(&reference(&[y.0, y.1, y.2]))
+            );
+            let (x, y) = (space2[i], space2[j]);
+            assert_eq!(
+                x.cmp(&y),
+                reference(&[x.0, x.1]).cmp(&reference(&[y.0, y.1]))
+            );
+        }
+    }
+    space2.sort();
+    space2.dedup();
+    space3.sort();
+    space3.dedup();
+
+    let mut compared = (0, 0);
+    for p in &space2 {
+        for query in [Morton2(p.0.wrapping_sub(1), p.1), *p, Morton2(p.0, p.1 ^ 1)] {
+            let found = search(&space2, &query, &mut compared);
+            assert_eq!(found, space2.binary_search(&query));
+        }
+    }
+    // the high bit groups shared within a region are skipped
+    assert_eq!(compared, (1002434, 1695808));
+    let mut compared = (0, 0);
+    for p in &space3 {
+        for query in [
+            Morton3(p.0, p.1, p.2 ^ 1),
+            *p,
+            Morton3(p.0, p.1.wrapping_add(1), p.2),
+        ] {
+            let found = search(&space3, &query, &mut compared);
+            assert_eq!(found, space3.binary_search(&query));
+        }
+    }
+    assert_eq!(compared, (1057097, 1855232));
+
+    // hereditary lower bounds
+    for p in &space3 {
+        assert_eq!(
+            tree_lower_bound_hereditary(&space3, p),
+            space3.partition_point(|k| k < p)
+        );
+    }
+    assert_eq!(
+        tree_lower_bound_hereditary(&space2, &Morton2(u32::MAX, u32::MAX)),
+        space2.partition_point(|k| k < &Morton2(u32::MAX, u32::MAX))
+    );
+}
+
+#[test]
+fn nulls_last() {
+    use tree_ord::NullsLast;
+
+    assert!(NullsLast(Some(u64::MAX)) < NullsLast(None));
+    assert!(NullsLast(Some(1)) < NullsLast(Some(2)));
+    assert_eq!(NullsLast::<u8>(None).cmp(&NullsLast(None)), Equal);
+    assert_eq!(Option::from(NullsLast(Some(3))), Some(3));
+    assert_eq!(NullsLast::from(None::<u8>), NullsLast(None));
+
+    // reference comparator with `None` mapped above every `Some`
+    fn reference(x: &(u8, Option<Vec<u8>>), y: &(u8, Option<Vec<u8>>)) -> Ordering {
+        let key = |v: &(u8, Option<Vec<u8>>)| (v.0, v.1.is_none(), v.1.clone());
+        key(x).cmp(&key(y))
+    }
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let values: Vec<(u8, Option<Vec<u8>>)> = (0..2000)
+        .map(|_| {
+            let len = rng.next_u32() % 4;
+            let bytes = (0..len).map(|_| (rng.next_u32() % 3) as u8).collect();
+            (
+                (rng.next_u32() % 4) as u8,
+                (rng.next_u32() % 4 != 0).then_some(bytes),
+            )
+        })
+        .collect();
+    let wrap = |v: &(u8, Option<Vec<u8>>)| (v.0, NullsLast(v.1.clone()));
+    for x in values.iter().step_by(7) {
+        for y in values.iter().step_by(3) {
+            assert_eq!(wrap(x).cmp(&wrap(y)), reference(x, y), "{x:?} {y:?}");
+        }
+    }
+    let mut space: Vec<(u8, NullsLast<Vec<u8>>)> = values.iter().map(wrap).collect();
+    space.sort();
+    space.dedup();
+    assert!(space.last().unwrap().1 .0.is_none());
+    for v in &values {
+        let query = wrap(v);
+        let mut tracker = <(u8, NullsLast<Vec<u8>>) as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            let res = k.tree_cmp(&query, &mut tracker);
+            assert_eq!(res, k.cmp(&query));
+            res
+        });
+        assert_eq!(found, space.binary_search(&query));
+        assert_eq!(
+            tree_lower_bound_hereditary(&space, &query),
+            space.partition_point(|k| k < &query)
+        );
+    }
+
+    // once the user id is locked in, only the timestamps are compared, with
+    // nulls on the `Greater` side
+    type T = (COrd<u64>, NullsLast<Vec<u8>>);
+    let query: T = (COrd(5), NullsLast(Some(vec![1, 2])));
+    let mut tracker = <T as TreeOrd>::Tracker::new();
+    let scope = CountScope::new();
+    assert_eq!(
+        (COrd(5), NullsLast(None)).tree_cmp(&query, &mut tracker),
+        Greater
+    );
+    assert_eq!(
+        (COrd(5), NullsLast(Some(vec![0, 9]))).tree_cmp(&query, &mut tracker),
+        Less
+    );
+    assert_eq!(scope.count(), 2);
+    assert_eq!(
+        (COrd(5), NullsLast(None)).tree_cmp(&query, &mut tracker),
+        Greater
+    );
+    assert_eq!(
+        (COrd(5), NullsLast(Some(vec![1, 3]))).tree_cmp(&query, &mut tracker),
+        Greater
+    );
+    assert_eq!(
+        (COrd(5), NullsLast(Some(vec![1, 2]))).tree_cmp(&query, &mut tracker),
+        Equal
+    );
+    assert_eq!(scope.count(), 2);
+}
+
+/// A lexicographic float sequence for testing `PartialTreeOrd` trackers
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct FloatVec(Vec<f64>);
+
+/// The lengths of the prefixes shared with the query by the strongest `Less`
+/// and `Greater` keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FloatVecTracker {
+    less_eq_len: usize,
+    greater_eq_len: usize,
+}
+
+impl Tracker for FloatVecTracker {
+    type Snapshot = Self;
+
+    const IS_NOOP: bool = false;
+    const NEW: Self = FloatVecTracker {
+        less_eq_len: 0,
+        greater_eq_len: 0,
+    };
+
+    fn snapshot(&self) -> Self {
+        *self
+    }
+
+    fn restore(&mut self, snapshot: Self) {
+        *self = snapshot;
+    }
+}
+
+impl PartialTreeOrd for FloatVec {
+    type Tracker = FloatVecTracker;
+
+    fn partial_tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Option<Ordering> {
+        let start = min(tracker.less_eq_len, tracker.greater_eq_len);
+        let mut i = start;
+        let res = loop {
+            match (self.0[..].get(i), rhs.0[..].get(i)) {
+                (Some(lhs), Some(rhs)) => match lhs.partial_cmp(rhs)? {
+                    Equal => i += 1,
+                    res => break res,
+                },
+                (lhs, rhs) => break lhs.is_some().cmp(&rhs.is_some()),
+            }
+        };
+        match res {
+            Less => tracker.less_eq_len = i,
+            Greater => tracker.greater_eq_len = i,
+            Equal => (),
+        }
+        Some(res)
+    }
+}
+
+#[test]
+fn partial_tree_ord() {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let partial_cmp = |lhs: &f64, rhs: &f64| lhs.partial_cmp(rhs).unwrap();
+
+    // floats without NaNs
+    let mut space: Vec<f64> = (0..512)
+        .map(|_| f64::from(rng.next_u32() % 1024) / 4.0 - 128.0)
+        .collect();
+    space.sort_by(partial_cmp);
+    space.dedup();
+    for i in 0..1024 {
+        let query = f64::from(i) / 4.0 - 128.5;
+        assert_eq!(
+            partial_tree_binary_search(&space[..], &query),
+            Ok(space.binary_search_by(|k| partial_cmp(k, &query)))
+        );
+    }
+    assert_eq!(
+        partial_tree_binary_search(&[f32::NEG_INFINITY, -0.0, 1.0][..], &0.0),
+        Ok(Ok(1))
+    );
+
+    // a NaN query is unordered with the first probe
+    let mid = space.len() / 2;
+    assert_eq!(
+        partial_tree_binary_search(&space[..], &f64::NAN),
+        Err(Unordered { index: mid })
+    );
+    // a NaN in the space only errors if the search probes it
+    let query = space[mid + 1];
+    space[mid + 1] = f64::NAN;
+    assert_eq!(
+        partial_tree_binary_search(&space[..], &query),
+        Err(Unordered { index: mid + 1 })
+    );
+    assert_eq!(partial_tree_binary_search(&space[..], &space[0]), Ok(Ok(0)));
+    assert_eq!(
+        Unordered { index: 3 }.to_string(),
+        "the key at index 3 is unordered with the query"
+    );
+
+    // `TreeOrd` types through the blanket impl
+    let keys: Vec<Vec<u8>> = (0..64u8).map(|i| vec![i / 8, i % 8]).collect();
+    for (i, key) in keys.iter().enumerate() {
+        assert_eq!(partial_tree_binary_search(&keys[..], key), Ok(Ok(i)));
+    }
+
+    // float sequences with a tracker
+    let mut space: Vec<FloatVec> = (0..256)
+        .map(|_| FloatVec((0..3).map(|_| f64::from(rng.next_u32() % 4)).collect()))
+        .collect();
+    space.sort_by(|lhs, rhs| lhs.partial_cmp(rhs).unwrap());
+    space.dedup();
+    for query in (0..64u32).map(|i| FloatVec(vec![f64::from(i / 16), f64::from(i / 4 % 4), 1.5])) {
+        assert_eq!(
+            partial_tree_binary_search(&space[..], &query),
+            Ok(space.binary_search_by(|k| k.partial_cmp(&query).unwrap()))
+        );
+    }
+    for key in &space {
+        let mut query = key.clone();
+        query.0[2] = f64::NAN;
+        let mut tracker = FloatVecTracker::new();
+        let mut unordered = 0;
+        let res = partial_tree_binary_search_by(&space[..], |k| {
+            let snapshot = tracker.snapshot();
+            let res = k.partial_tree_cmp(&query, &mut tracker);
+            if res.is_none() {
+                // the unordered comparison leaves the tracker as it was
+                assert_eq!(tracker, snapshot);
+                unordered += 1;
+            }
+            res
+        });
+        let Err(Unordered { index }) = res else {
+            panic!("{res:?}")
+        };
+        assert_eq!(unordered, 1);
+        // only a key with the same ordered prefix reaches the NaN
+        assert_eq!(space[index].0[..2], key.0[..2]);
+    }
+}
+
+/// A `u64` that counts equality checks instead of `Ord` comparisons
+#[derive(Debug, Clone, PartialOrd, Ord)]
+pub struct EqCount(u64);
+
+impl PartialEq for EqCount {
+    fn eq(&self, other: &Self) -> bool {
+        inc_cmp_count();
+        self.0 == other.0
+    }
+}
+
+impl Eq for EqCount {}
+
+impl TreeOrd for EqCount {
+    type Tracker = ();
+
+    fn tree_cmp(&self, rhs: &Self, _: &mut Self::Tracker) -> Ordering {
+        self.0.cmp(&rhs.0)
+    }
+}
+
+#[test]
+fn tree_eq() {
+    use tree_ord::TreeEq;
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<Vec<EqCount>> = (0..1024)
+        .map(|_| {
+            let len = 6 + rng.next_u32() % 3;
+            (0..len)
+                .map(|_| EqCount(u64::from(rng.next_u32() % 3)))
+                .collect()
+        })
+        .collect();
+    space.sort();
+    space.dedup_by(|a, b| a.iter().map(|x| x.0).eq(b.iter().map(|x| x.0)));
+    let mut queries = space.clone();
+    queries.extend((0..256).map(|_| {
+        let len = 6 + rng.next_u32() % 3;
+        (0..len)
+            .map(|_| EqCount(u64::from(rng.next_u32() % 3)))
+            .collect()
+    }));
+
+    let mut tree_eq_count = 0;
+    let mut eq_count = 0;
+    for query in &queries {
+        let mut tracker = <Vec<EqCount> as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&space, |k| {
+            // checks against `==` at every step of the search
+            let scope = CountScope::new();
+            let eq = k.tree_eq(query, &tracker);
+            tree_eq_count += scope.count();
+            let scope = CountScope::new();
+            assert_eq!(eq, k == query);
+            eq_count += scope.count();
+            k.tree_cmp(query, &mut tracker)
+        });
+        assert_eq!(found, space.binary_search(query));
+    }
+    assert!(tree_eq_count * 3 < eq_count * 2);
+    assert_eq!((tree_eq_count, eq_count), (10739, 18222));
+
+    // strings and the default implementation
+    let query = "hello world";
+    let mut tracker = <str as TreeOrd>::Tracker::new();
+    assert_eq!("hello there".tree_cmp(query, &mut tracker), Less);
+    assert_eq!("hello zzz".tree_cmp(query, &mut tracker), Greater);
+    assert_eq!(tracker.proven_prefix_len(), 6);
+    assert!("hello world".tree_eq(query, &tracker));
+    assert!(!"hello worle".tree_eq(query, &tracker));
+    assert!(!"hello world!".tree_eq(query, &tracker));
+    assert_eq!(tracker.proven_prefix_len(), 6);
+    let mut tracker = <(u8, String) as TreeOrd>::Tracker::new();
+    assert_eq!(
+        (1u8, "a".to_owned()).tree_cmp(&(1, "b".to_owned()), &mut tracker),
+        Less
+    );
+    assert!((1u8, "b".to_owned()).tree_eq(&(1, "b".to_owned()), &tracker));
+    assert!(!(1u8, "c".to_owned()).tree_eq(&(1, "b".to_owned()), &tracker));
+}
+
+/// The index of the first differing element, or the shorter length
+fn naive_divergence<T: PartialEq>(lhs: &[T], rhs: &[T]) -> usize {
+    lhs.iter()
+        .zip(rhs)
+        .position(|(a, b)| a != b)
+        .unwrap_or(min(lhs.len(), rhs.len()))
+}
+
+#[test]
+fn tree_cmp_detail() {
+    // fresh trackers
+    let detail = |lhs: &[u16], rhs: &[u16]| {
+        lhs.tree_cmp_detail(rhs, &mut <[u16] as TreeOrd>::Tracker::new())
+    };
+    assert_eq!(detail(&[1, 2, 3], &[1, 2, 4]), (Less, 2));
+    assert_eq!(detail(&[1, 2], &[1, 2, 3]), (Less, 2));
+    assert_eq!(detail(&[1, 2, 3, 4], &[1, 2, 3]), (Greater, 3));
+    assert_eq!(detail(&[1, 2, 3], &[1, 2, 3]), (Equal, 3));
+    assert_eq!(detail(&[], &[0]), (Less, 0));
+    assert_eq!(detail(&[7], &[0]), (Greater, 0));
+    // the default implementation
+    assert_eq!(5u32.tree_cmp_detail(&6, &mut ()), (Less, usize::MAX));
+
+    // positions are absolute after the tracker skips a prefix
+    let query = [5u16, 5, 5, 5, 5, 5, 5, 5, 1];
+    let mut tracker = <[u16] as TreeOrd>::Tracker::new();
+    assert_eq!(
+        [5u16, 5, 5, 5, 5, 5, 5, 5, 0][..].tree_cmp_detail(&query[..], &mut tracker),
+        (Less, 8)
+    );
+    assert_eq!(
+        [5u16, 5, 5, 5, 5, 5, 5, 5, 2][..].tree_cmp_detail(&query[..], &mut tracker),
+        (Greater, 8)
+    );
+    assert_eq!(tracker.proven_prefix_len(), 8);
+    assert_eq!(
+        [5u16, 5, 5, 5, 5, 5, 5, 5, 1, 0][..].tree_cmp_detail(&query[..], &mut tracker),
+        (Greater, 9)
+    );
+    assert_eq!(
+        [5u16, 5, 5, 5, 5, 5, 5, 5, 1][..].tree_cmp_detail(&query[..], &mut tracker),
+        (Equal, 9)
+    );
+
+    // the chunked byte comparison, diverging past the skipped prefix
+    let query = [3u8; 100];
+    let mut lhs = query;
+    lhs[64] = 2;
+    let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+    assert_eq!(
+        lhs.to_vec().tree_cmp_detail(&query.to_vec(), &mut tracker),
+        (Less, 64)
+    );
+    lhs[64] = 3;
+    lhs[70] = 4;
+    assert_eq!(lhs.tree_cmp_detail(&query, &mut tracker), (Greater, 70));
+    assert_eq!(tracker.proven_prefix_len(), 64);
+    lhs[70] = 3;
+    lhs[67] = 4;
+    assert_eq!(
+        TreeOrdBytes(&lhs).tree_cmp_detail(&TreeOrdBytes(&query), &mut tracker),
+        (Greater, 67)
+    );
+    assert_eq!(
+        TreeOrdBytes(&lhs[..66]).tree_cmp_detail(&TreeOrdBytes(&query), &mut tracker),
+        (Less, 66)
+    );
+
+    // `str` and `String` count bytes
+    let mut tracker = <str as TreeOrd>::Tracker::new();
+    assert_eq!("abc".tree_cmp_detail("abd", &mut tracker), (Less, 2));
+    assert_eq!(
+        "abe"
+            .to_owned()
+            .tree_cmp_detail(&"abd".to_owned(), &mut tracker),
+        (Greater, 2)
+    );
+    assert_eq!(
+        "abd\u{e9}".tree_cmp_detail("abd", &mut tracker),
+        (Greater, 3)
+    );
+
+    // every comparison of random searches
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut bytes: Vec<Vec<u8>> = (0..512)
+        .map(|_| {
+            let len = rng.next_u32() % 40;
+            (0..len).map(|_| (rng.next_u32() % 2) as u8).collect()
+        })
+        .collect();
+    bytes.sort();
+    let mut words: Vec<Vec<u16>> = (0..512)
+        .map(|_| {
+            let len = rng.next_u32() % 12;
+            (0..len).map(|_| (rng.next_u32() % 2) as u16).collect()
+        })
+        .collect();
+    words.sort();
+    for i in 0..512 {
+        let query = &bytes[i];
+        let mut tracker = <Vec<u8> as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&bytes, |k| {
+            let (res, pos) = k.tree_cmp_detail(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            assert_eq!(pos, naive_divergence(k, query));
+            res
+        });
+        assert!(found.is_ok());
+        let query = &words[i];
+        let mut tracker = <Vec<u16> as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&words, |k| {
+            let (res, pos) = k.tree_cmp_detail(query, &mut tracker);
+            assert_eq!(res, k.cmp(query));
+            assert_eq!(pos, naive_divergence(k, query));
+            res
+        });
+        assert!(found.is_ok());
+    }
+}
+
+#[test]
+fn ptr_eq_fast() {
+    use std::{rc::Rc, sync::Arc};
+
+    use tree_ord::PtrEqFast;
+
+    let a = Arc::new(vec![COrd(1), COrd(2), COrd(3)]);
+    let same = PtrEqFast(a.clone());
+    let copy = PtrEqFast(Arc::new(vec![COrd(1), COrd(2), COrd(3)]));
+    let mut tracker = <PtrEqFast<Arc<Vec<COrd<u64>>>> as TreeOrd>::Tracker::new();
+    let scope = CountScope::new();
+    assert_eq!(PtrEqFast(a.clone()).tree_cmp(&same, &mut tracker), Equal);
+    assert_eq!(
+        PtrEqFast(a.clone()).tree_cmp_hereditary(&same, &mut tracker),
+        Equal
+    );
+    assert_eq!(PtrEqFast(a.clone()).cmp(&same), Equal);
+    // identical pointers skip the content comparison
+    assert_eq!(scope.count(), 0);
+    // equal contents in a different allocation are compared
+    assert_eq!(copy.tree_cmp(&same, &mut tracker), Equal);
+    assert_eq!(scope.count(), 3);
+    assert_eq!(
+        PtrEqFast(Rc::new(COrd(2))).cmp(&PtrEqFast(Rc::new(COrd(1)))),
+        Greater
+    );
+
+    // deduplicated keys searched with the same allocations as in the tree
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<PtrEqFast<Rc<Vec<COrd<u64>>>>> = (0..256)
+        .map(|_| {
+            let len = 1 + rng.next_u32() % 4;
+            PtrEqFast(Rc::new(
+                (0..len)
+                    .map(|_| COrd(u64::from(rng.next_u32() % 4)))
+                    .collect(),
+            ))
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    let mut fast = 0;
+    let mut plain = 0;
+    for query in &space {
+        let scope = CountScope::new();
+        let mut tracker = <PtrEqFast<Rc<Vec<COrd<u64>>>> as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(query, &mut tracker));
+        fast += scope.count();
+        let scope = CountScope::new();
+        let copy = Rc::new((*query.0).clone());
+        let mut tracker = <Rc<Vec<COrd<u64>>> as TreeOrd>::Tracker::new();
+        let copy_found = tree_binary_search_by(&space, |k| k.0.tree_cmp(&copy, &mut tracker));
+        plain += scope.count();
+        assert_eq!(found, copy_found);
+        assert_eq!(found, space.binary_search(query));
+    }
+    // the final comparison with the identical key is always skipped
+    assert!(fast < plain, "{fast} {plain}");
+}
+
+#[test]
+fn tree_ord_bounded() {
+    use tree_ord::{utils::LexicographicTracker, TreeOrdBounded};
+
+    assert_eq!(<(u8, bool, Option<i8>)>::MIN, (0, false, None));
+    assert_eq!(<(u8, bool, Option<i8>)>::MAX, (255, true, Some(127)));
+    assert_eq!(char::MAX, <char as TreeOrdBounded>::MAX);
+
+    // ascending inserts of entries under a reserved maximum prefix, like a
+    // system keyspace. Every search ends up past the last key, so without the
+    // extremes there is never a `Greater` result and nothing is proven.
+    type T = (COrd<u64>, COrd<u64>, u32);
+    let mut space: Vec<T> = vec![];
+    let mut plain = 0;
+    let mut extremes = 0;
+    for seq in 0..1000 {
+        let query: T = (COrd(u64::MAX), COrd(u64::MAX), seq);
+        let scope = CountScope::new();
+        let mut tracker = <T as TreeOrd>::Tracker::new();
+        let found = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        plain += scope.count();
+        let scope = CountScope::new();
+        let mut tracker = tracker_with_extremes(&query);
+        let found_extremes = tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker));
+        extremes += scope.count();
+        assert_eq!(found, Err(space.len()));
+        assert_eq!(found_extremes, found);
+        space.push(query);
+    }
+    // the two prefix fields are compared on every probe without the extremes,
+    // and only until the first probe with them
+    assert_eq!((plain, extremes), (15974, 4998));
+
+    // the same with byte keys, where only the maximum side is known
+    let mut space: Vec<Vec<u8>> = vec![];
+    for seq in 0u32..1000 {
+        let mut query = vec![0xff, 0xff];
+        query.extend_from_slice(&seq.to_be_bytes());
+        let mut tracker = LexicographicTracker::<u8>::with_extremes(&query);
+        assert_eq!((tracker.min_eq_len(), tracker.max_eq_len()), (2, 0));
+        let mut probes = 0;
+        let found = tree_binary_search_by(&space, |k| {
+            if probes > 0 {
+                assert!(tracker.proven_prefix_len() >= 2);
+            }
+            probes += 1;
+            k.tree_cmp(&query, &mut tracker)
+        });
+        assert_eq!(found, Err(space.len()));
+        space.push(query);
+    }
+    let query = [0xff, 0xfe, 0xff];
+    let tracker = LexicographicTracker::<u8>::with_extremes(&query);
+    assert_eq!((tracker.min_eq_len(), tracker.max_eq_len()), (1, 0));
+
+    // The seeded prefix is on the side of `Greater` results, which are the keys
+    // greater than the query only if the key is on the left hand side. With the
+    // query on the left, the keys less than the query skip the prefix.
+    let query: &[u8] = &[255, 255, 3];
+    let mut tracker = LexicographicTracker::<u8>::with_extremes(query);
+    assert_eq!(query.tree_cmp(&[255, 255, 5], &mut tracker), Less);
+    // wrong, `[255, 255, 3]` is greater than `[0, 0, 9]`
+    assert_eq!(query.tree_cmp(&[0, 0, 9], &mut tracker), Less);
+    let mut tracker = LexicographicTracker::<u8>::with_extremes(query);
+    assert_eq!(
+        [255u8, 255, 5].as_slice().tree_cmp(query, &mut tracker),
+        Greater
+    );
+    assert_eq!([0u8, 0, 9].as_slice().tree_cmp(query, &mut tracker), Less);
+    // `Checked` catches this for `tracker_with_extremes`
+    {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        use tree_ord::checked::Checked;
+        type C = Checked<(u8, u8, u32)>;
+        let query: C = Checked((255, 255, 3));
+        let res = catch_unwind(AssertUnwindSafe(|| {
+            let mut tracker = tracker_with_extremes(&query);
+            query.tree_cmp(&Checked((255, 255, 5)), &mut tracker);
+        }));
+        assert!(res.is_err());
+        let space: Vec<C> = [(0, 0, 9), (255, 255, 1), (255, 255, 3), (255, 255, 5)]
+            .into_iter()
+            .map(Checked)
+            .collect();
+        let mut tracker = tracker_with_extremes(&query);
+        assert_eq!(
+            tree_binary_search_by(&space, |k| k.tree_cmp(&query, &mut tracker)),
+            Ok(2)
+        );
+    }
+
+    // any queries
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let field = |rng: &mut Xoshiro128StarStar| [0, 1, 254, 255][(rng.next_u32() % 4) as usize];
+    type K = (u8, Option<u8>, (bool, u8), u16);
+    let mut space: Vec<K> = (0..2000)
+        .map(|_| {
+            (
+                field(&mut rng),
+                (rng.next_u32() % 3 != 0).then(|| field(&mut rng)),
+                ((rng.next_u32() & 1) == 0, field(&mut rng)),
+                rng.next_u32() as u16,
+            )
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    for i in 0..2000 {
+        let query: K = if i % 2 == 0 {
+            space[(rng.next_u32() as usize) % space.len()]
+        } else {
+            (
+                field(&mut rng),
+                Some(field(&mut rng)),
+                (true, field(&mut rng)),
+                [0, u16::MAX][i % 4 / 2],
+            )
+        };
+        let mut tracker = tracker_with_extremes(&query);
+        let found = tree_binary_search_by(&space, |k| {
+            let res = k.tree_cmp(&query, &mut tracker);
+            assert_eq!(res, k.cmp(&query));
+            res
+        });
+        assert_eq!(found, space.binary_search(&query));
+    }
+}
+
+#[test]
+fn dyn_tree_ord() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use tree_ord::dynamic::{DynKey, DynKeyTracker, DynTreeOrd};
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut space: Vec<(u16, Vec<u8>)> = (0..1000)
+        .map(|_| {
+            let len = rng.next_u32() % 6;
+            (
+                (rng.next_u32() % 4) as u16,
+                (0..len).map(|_| (rng.next_u32() % 3) as u8).collect(),
+            )
+        })
+        .collect();
+    space.sort();
+    space.dedup();
+    let boxed: Vec<Box<dyn DynTreeOrd>> = space
+        .iter()
+        .map(|k| Box::new(k.clone()) as Box<dyn DynTreeOrd>)
+        .collect();
+    let dyn_space: Vec<DynKey> = space.iter().map(|k| DynKey::new(k.clone())).collect();
+    for i in 0..1000 {
+        let query = if i % 2 == 0 {
+            space[(rng.next_u32() as usize) % space.len()].clone()
+        } else {
+            let len = rng.next_u32() % 6;
+            (
+                (rng.next_u32() % 4) as u16,
+                (0..len).map(|_| (rng.next_u32() % 3) as u8).collect(),
+            )
+        };
+        // static dispatch, recording the comparison results
+        let mut tracker = <(u16, Vec<u8>) as TreeOrd>::Tracker::new();
+        let mut results = vec![];
+        let found = tree_binary_search_by(&space, |k| {
+            let res = k.tree_cmp(&query, &mut tracker);
+            results.push(res);
+            res
+        });
+        assert_eq!(found, space.binary_search(&query));
+
+        // trait objects
+        let dyn_query: Box<dyn DynTreeOrd> = Box::new(query.clone());
+        let mut dyn_tracker = dyn_query.new_tracker();
+        let mut dyn_results = vec![];
+        let dyn_found = tree_binary_search_by(&boxed, |k| {
+            let res = k.dyn_tree_cmp(&*dyn_query, &mut *dyn_tracker);
+            dyn_results.push(res);
+            res
+        });
+        assert_eq!(dyn_found, found);
+        assert_eq!(dyn_results, results);
+
+        // `TreeOrd` through `DynKey`
+        let dyn_query = DynKey::new(query.clone());
+        assert_eq!(tree_binary_search(&dyn_space, &dyn_query), found);
+        assert_eq!(
+            tree_lower_bound_hereditary(&dyn_space, &dyn_query),
+            tree_lower_bound_hereditary(&space, &query)
+        );
+    }
+
+    // the tracker of `DynKey` is created lazily and keeps its state through
+    // snapshots and forks
+    let query = DynKey::new(vec![1u8, 2, 3]);
+    let mut tracker = DynKeyTracker::new();
+    assert!(tracker.inner().is_none());
+    let empty = tracker.snapshot();
+    assert_eq!(
+        DynKey::new(vec![1u8, 2, 4]).tree_cmp(&query, &mut tracker),
+        Greater
+    );
+    assert_eq!(
+        DynKey::new(vec![1u8, 0]).tree_cmp(&query, &mut tracker),
+        Less
+    );
+    assert_eq!(tracker.proven_prefix(), Some(1));
+    let fork = tracker.fork();
+    let snapshot = tracker.snapshot();
+    assert_eq!(
+        DynKey::new(vec![1u8, 2, 0]).tree_cmp(&query, &mut tracker),
+        Less
+    );
+    assert_eq!(tracker.proven_prefix(), Some(2));
+    assert_eq!(fork.proven_prefix(), Some(1));
+    tracker.restore(snapshot);
+    assert_eq!(tracker.proven_prefix(), Some(1));
+    tracker.restore(empty);
+    assert_eq!(tracker.proven_prefix(), Some(0));
+    assert!(tracker
+        .inner()
+        .unwrap()
+        .tracker_type_name()
+        .contains("LexicographicTracker"));
+
+    // mismatched types panic with the type names
+    let panic_message = |f: &dyn Fn()| -> String {
+        let payload = catch_unwind(AssertUnwindSafe(f)).unwrap_err();
+        payload.downcast_ref::<String>().unwrap().clone()
+    };
+    let msg = panic_message(&|| {
+        let _ = DynKey::new(1u32).cmp(&DynKey::new(1u64));
+    });
+    assert!(msg.contains("`u32`") && msg.contains("`u64`"), "{msg}");
+    let msg = panic_message(&|| {
+        let mut tracker = 0u8.new_tracker();
+        String::new().dyn_tree_cmp(&String::new(), &mut *tracker);
+    });
+    assert!(msg.contains("`()`") && msg.contains("String"), "{msg}");
+    let msg = panic_message(&|| {
+        let mut tracker = vec![0u8].new_tracker();
+        let snapshot = 0u8.new_tracker().dyn_snapshot();
+        tracker.dyn_restore(snapshot);
+    });
+    assert!(msg.contains("LexicographicTracker"), "{msg}");
+}
+
+/// Keys over a small alphabet, so that they share many prefixes
+fn gen_small_alphabet(n: usize, max_len: u32, alphabet: &[u8], seed: u64) -> Vec<Vec<u8>> {
+    let mut rng = Xoshiro128StarStar::seed_from_u64(seed);
+    (0..n)
+        .map(|_| {
+            let len = rng.next_u32() % (max_len + 1);
+            (0..len)
+                .map(|_| alphabet[(rng.next_u32() as usize) % alphabet.len()])
+                .collect()
+        })
+        .collect()
+}
+
+fn check_mapped<'a, S, T: TreeOrd>(space: &'a [S], queries: &'a [S], f: impl Fn(&'a S) -> T) {
+    let mapped_space: Vec<T> = space.iter().map(&f).collect();
+    let mapped_queries: Vec<T> = queries.iter().map(&f).collect();
+    tree_ord::testing::check_tree_ord(&mapped_space, &mapped_queries, 0);
+}
+
+#[test]
+fn check_tree_ord_builtin() {
+    use std::{
+        cell::{Cell, RefCell},
+        pin::Pin,
+        rc::Rc,
+        sync::Arc,
+    };
+
+    use tree_ord::{
+        checked::{Checked, CheckedTreeOrd},
+        dynamic::DynKey,
+        instrument::Instrumented,
+        AsciiCaseInsensitive, AsciiCaseInsensitiveString, CaseTiebreak, KeyValue, MaybeTracked,
+        Morton2, Morton3, NarrowVec, NaturalOrdStr, NullsLast, OrdToTreeOrd, PtrEqFast, Shortlex,
+        SuffixLexicographic, SuffixLexicographicBytes, SuffixLexicographicStr, TreeOrdBoxed,
+        TreeOrdReverse, VersionStr,
+    };
+
+    let bytes = gen_small_alphabet(256, 12, &[0, 1, 2], 0);
+    let byte_queries = gen_small_alphabet(64, 12, &[0, 1, 2, 3], 1);
+    // long runs of zeros that cross the chunks of the chunked comparisons
+    let long = gen_small_alphabet(256, 80, &[0, 0, 0, 0, 0, 0, 0, 0, 0, 1], 2);
+    let long_queries = gen_small_alphabet(64, 80, &[0, 0, 0, 0, 0, 0, 0, 0, 0, 2], 3);
+
+    // the `Checked` wrapper panics if the generated sequences are invalid
+    check_mapped(&bytes, &byte_queries, |b| Checked(b.clone()));
+    check_mapped(&long, &long_queries, |b| CheckedTreeOrd(b.clone()));
+
+    // primitives and small types
+    check_mapped(&bytes, &byte_queries, |b| b.len() as u8);
+    check_mapped(&bytes, &byte_queries, |b| (b.len() as i32) - 6);
+    check_mapped(&bytes, &byte_queries, |b| b.len() as u64);
+    check_mapped(&bytes, &byte_queries, |b| char::from(b'a' + b.len() as u8));
+    check_mapped(&bytes, &byte_queries, |b| b.len() % 2 == 0);
+    check_mapped(&bytes, &byte_queries, |_| ());
+    check_mapped(&bytes, &byte_queries, |b| Cell::new(b.len() as u8));
+    check_mapped(&bytes, &byte_queries, |b| COrd(b.clone()));
+    check_mapped(&bytes, &byte_queries, |b| OrdToTreeOrd(b.clone()));
+    let u128_of = |b: &Vec<u8>| {
+        let mut buf = [0u8; 16];
+        for (i, x) in b.iter().take(16).enumerate() {
+            buf[i] = *x;
+        }
+        u128::from_be_bytes(buf)
+    };
+    check_mapped(&bytes, &byte_queries, |b| TreeOrdU128(u128_of(b)));
+    check_mapped(&bytes, &byte_queries, |b| {
+        TreeOrdI128((u128_of(b) as i128) ^ i128::MIN.wrapping_mul((b.len() % 2) as i128))
+    });
+    check_mapped(&bytes, &byte_queries, |b| {
+        let x = u128_of(b);
+        Morton2(x as u32, (x >> 96) as u32)
+    });
+    check_mapped(&bytes, &byte_queries, |b| {
+        let x = u128_of(b);
+        Morton3(x as u32, (x >> 64) as u32, (x >> 96) as u32)
+    });
+
+    // sequences
+    for (space, queries) in [(&bytes, &byte_queries), (&long, &long_queries)] {
+        check_mapped(space, queries, |b| b.clone());
+        check_mapped(space, queries, |b| {
+            let mut a = [0u8; 4];
+            for (i, x) in b.iter().take(4).enumerate() {
+                a[i] = *x;
+            }
+            a
+        });
+        check_mapped(space, queries, |b| {
+            b.chunks(3).map(|c| c.to_vec()).collect::<Vec<_>>()
+        });
+        check_mapped(space, queries, |b| {
+            b.iter().map(|x| *x as u16).collect::<Vec<_>>()
+        });
+        check_mapped(space, queries, |b| Box::<[u8]>::from(&b[..]));
+        check_mapped(space, queries, |b| Rc::new(b.clone()));
+        check_mapped(space, queries, |b| Arc::<[u8]>::from(&b[..]));
+        check_mapped(space, queries, |b| RefCell::new(b.clone()));
+        check_mapped(space, queries, |b| Pin::new(Box::new(b.clone())));
+        check_mapped(space, queries, |b| TreeOrdBytes(b));
+        check_mapped(space, queries, |b| TreeOrdVec(b.clone()));
+        check_mapped(space, queries, |b| TreeOrdBytesAdaptive(b));
+        check_mapped(space, queries, |b| NarrowVec(b.clone()));
+        check_mapped(space, queries, |b| TreeOrdReverse(b.clone()));
+        check_mapped(space, queries, |b| {
+            NullsLast((!b.is_empty()).then(|| b.clone()))
+        });
+        check_mapped(space, queries, |b| MaybeTracked(b.clone()));
+        check_mapped(space, queries, |b| TreeOrdBoxed(b.clone()));
+        check_mapped(space, queries, |b| KeyValue::new(b.clone(), b.len()));
+        check_mapped(space, queries, |b| PtrEqFast(Rc::new(b.clone())));
+        check_mapped(space, queries, |b| Instrumented(b.clone()));
+        check_mapped(space, queries, |b| DynKey::new(b.clone()));
+        check_mapped(space, queries, |b| SuffixLexicographic(&b[..]));
+        check_mapped(space, queries, |b| SuffixLexicographicBytes(b));
+        check_mapped(space, queries, |b| Shortlex(b.clone()));
+        check_mapped(space, queries, |b| Shortlex(&b[..]));
+        check_mapped(space, queries, |b| (!b.is_empty()).then(|| b.clone()));
+        check_mapped(space, queries, |b| -> Result<Vec<u8>, usize> {
+            if b.len() % 2 == 0 {
+                Ok(b.clone())
+            } else {
+                Err(b.len())
+            }
+        });
+        check_mapped(space, queries, |b| {
+            (b.len() % 3, b.clone(), b.last().copied())
+        });
+        check_mapped(space, queries, |b| {
+            let (lhs, rhs) = b.split_at(b.len() / 2);
+            (
+                lhs.to_vec(),
+                rhs.iter().map(|x| *x as u32).collect::<Vec<_>>(),
+            )
+        });
+        check_mapped(space, queries, |b| {
+            let x = |i: usize| b.as_slice().get(i).copied();
+            (x(0), x(1), x(2), x(3), b.clone())
+        });
+    }
+    let words: Vec<Vec<u16>> = long
+        .iter()
+        .map(|b| b.iter().map(|x| *x as u16).collect())
+        .collect();
+    let word_queries: Vec<Vec<u16>> = long_queries
+        .iter()
+        .map(|b| b.iter().map(|x| *x as u16).collect())
+        .collect();
+    check_mapped(&words, &word_queries, |w| TreeOrdU16s(w));
+    let words: Vec<Vec<u32>> = long
+        .iter()
+        .map(|b| b.iter().map(|x| *x as u32).collect())
+        .collect();
+    let word_queries: Vec<Vec<u32>> = long_queries
+        .iter()
+        .map(|b| b.iter().map(|x| *x as u32).collect())
+        .collect();
+    check_mapped(&words, &word_queries, |w| TreeOrdU32s(w));
+    let words: Vec<Vec<u64>> = long
+        .iter()
+        .map(|b| b.iter().map(|x| *x as u64).collect())
+        .collect();
+    let word_queries: Vec<Vec<u64>> = long_queries
+        .iter()
+        .map(|b| b.iter().map(|x| *x as u64).collect())
+        .collect();
+    check_mapped(&words, &word_queries, |w| TreeOrdU64s(w));
+
+    // strings, from pieces that exercise the case, digit, and accent handling
+    let pieces = [
+        "a", "A", "b", "B", "0", "1", "9", "00", ".", "-", " ", "é", "E\u{301}", "ß", "SS",
+    ];
+    let to_strings = |bytes: &[Vec<u8>]| -> Vec<String> {
+        bytes
+            .iter()
+            .map(|b| b.iter().map(|x| pieces[*x as usize]).collect())
+            .collect()
+    };
+    let strings = to_strings(&gen_small_alphabet(256, 8, &[0, 1, 2, 4, 5, 8, 11], 4));
+    let string_queries = to_strings(&gen_small_alphabet(
+        64,
+        8,
+        &(0..(pieces.len() as u8)).collect::<Vec<_>>(),
+        5,
+    ));
+    check_mapped(&strings, &string_queries, |s| s.clone());
+    check_mapped(&strings, &string_queries, |s| Rc::<str>::from(s.as_str()));
+    check_mapped(&strings, &string_queries, |s| TreeOrdStr(s));
+    check_mapped(&strings, &string_queries, |s| TreeOrdString(s.clone()));
+    check_mapped(&strings, &string_queries, |s| AsciiCaseInsensitive(s));
+    check_mapped(&strings, &string_queries, |s| {
+        AsciiCaseInsensitiveString(s.clone())
+    });
+    check_mapped(&strings, &string_queries, |s| SuffixLexicographicStr(s));
+    check_mapped(&strings, &string_queries, |s| NaturalOrdStr(s));
+    check_mapped(&strings, &string_queries, |s| VersionStr(s));
+    check_mapped(&strings, &string_queries, |s| Shortlex(s.clone()));
+    check_mapped(&strings, &string_queries, |s| Shortlex(s.as_str()));
+    check_mapped(&strings, &string_queries, |s| CaseTiebreak(s));
+    #[cfg(feature = "unicode-case")]
+    check_mapped(&strings, &string_queries, |s| {
+        tree_ord::CaseFolded(s.clone())
+    });
+    #[cfg(feature = "unicode-normalization")]
+    {
+        check_mapped(&strings, &string_queries, |s| {
+            tree_ord::AccentInsensitive(s.clone())
+        });
+        check_mapped(&strings, &string_queries, |s| tree_ord::Nfc(s.clone()));
+    }
+}
+
+/// A `Vec<u8>` whose `tree_cmp` wrongly treats `Equal` as proving the whole
+/// key on both sides
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct EqualProves(Vec<u8>);
+
+impl TreeOrd for EqualProves {
+    type Tracker = tree_ord::utils::LexicographicTracker<u8>;
+
+    fn tree_cmp(&self, rhs: &Self, tracker: &mut Self::Tracker) -> Ordering {
+        self.0.tree_cmp_hereditary(&rhs.0, tracker)
+    }
+}
+
+#[test]
+fn wrapper_std_impls() {
+    use std::{
+        cmp::Reverse,
+        collections::{BTreeSet, HashMap, HashSet},
+        hash::{BuildHasher, RandomState},
+        ops::Bound,
+    };
+
+    use tree_ord::{OrdToTreeOrd, TreeOrdReverse};
+
+    let hasher = RandomState::new();
+    let strings = ["", "a", "ab", "b", "shared/prefix/a", "shared/prefix/b"].map(String::from);
+
+    // `Eq`, `Ord`, and `Hash` agree with the inner types
+    for x in &strings {
+        for y in &strings {
+            let (bx, by) = (TreeOrdBytes(x.as_bytes()), TreeOrdBytes(y.as_bytes()));
+            let (vx, vy) = (
+                TreeOrdVec(x.clone().into_bytes()),
+                TreeOrdVec(y.clone().into_bytes()),
+            );
+            let (ox, oy) = (OrdToTreeOrd(x.clone()), OrdToTreeOrd(y.clone()));
+            let (rx, ry) = (TreeOrdReverse(x.clone()), TreeOrdReverse(y.clone()));
+            assert_eq!(bx == by, x == y);
+            assert_eq!(vx == vy, x == y);
+            assert_eq!(ox == oy, x == y);
+            assert_eq!(rx == ry, x == y);
+            assert_eq!(bx.cmp(&by), x.cmp(y));
+            assert_eq!(vx.cmp(&vy), x.cmp(y));
+            assert_eq!(ox.cmp(&oy), x.cmp(y));
+            assert_eq!(rx.cmp(&ry), y.cmp(x));
+        }
+        assert_eq!(
+            hasher.hash_one(TreeOrdBytes(x.as_bytes())),
+            hasher.hash_one(x.as_bytes())
+        );
+        assert_eq!(
+            hasher.hash_one(TreeOrdVec(x.clone().into_bytes())),
+            hasher.hash_one(x.as_bytes())
+        );
+        assert_eq!(hasher.hash_one(OrdToTreeOrd(x.clone())), hasher.hash_one(x));
+        assert_eq!(
+            hasher.hash_one(TreeOrdReverse(x.clone())),
+            hasher.hash_one(x)
+        );
+    }
+
+    // lookups through `Borrow`
+    let set: HashSet<TreeOrdVec> = strings
+        .iter()
+        .map(|s| TreeOrdVec::from(s.as_bytes()))
+        .collect();
+    assert!(set.contains(&b"shared/prefix/a"[..]));
+    assert!(!set.contains(&b"shared/prefix/c"[..]));
+    let set: BTreeSet<TreeOrdBytes> = strings
+        .iter()
+        .map(|s| TreeOrdBytes::from(s.as_str()))
+        .collect();
+    assert!(set.contains(&b"ab"[..]));
+    assert_eq!(
+        set.range::<[u8], _>((Bound::Included(&b"b"[..]), Bound::Unbounded))
+            .count(),
+        3
+    );
+    let map: HashMap<OrdToTreeOrd<String>, usize> = strings
+        .iter()
+        .cloned()
+        .map(OrdToTreeOrd::from)
+        .zip(0..)
+        .collect();
+    assert_eq!(map.get(&strings[2]), Some(&2));
+
+    // conversions and accessors
+    let v = TreeOrdVec::from(b"abc".to_vec());
+    assert_eq!(v.as_inner(), &b"abc".to_vec());
+    assert_eq!(v.as_borrowed(), TreeOrdBytes(b"abc"));
+    assert_eq!(&*v, b"abc");
+    assert_eq!(v.as_ref(), b"abc");
+    assert_eq!(TreeOrdVec::from(v.as_borrowed()), v);
+    assert_eq!(Vec::<u8>::from(v.clone()), b"abc");
+    assert_eq!(v.into_inner(), b"abc");
+    let b = TreeOrdBytes::from(&b"xyz"[..]);
+    let copy = b;
+    assert_eq!(<&[u8]>::from(copy), b"xyz");
+    assert_eq!(b.into_inner(), b.as_inner());
+    assert_eq!(b.len(), 3);
+    assert_eq!(TreeOrdBytes::default(), TreeOrdBytes(&[]));
+    let o = OrdToTreeOrd::from(7u64);
+    let copy = o;
+    assert_eq!(*copy, 7);
+    assert_eq!(o.as_inner(), &7);
+    assert_eq!(o.into_inner(), 7);
+    let r = TreeOrdReverse::from(Reverse(5u64));
+    assert_eq!(*r, 5);
+    assert_eq!(r.as_inner(), &5);
+    assert_eq!(Reverse::from(r), Reverse(5));
+    assert_eq!(r.into_inner(), 5);
+
+    // debug formatting
+    assert_eq!(format!("{:?}", OrdToTreeOrd(1u8)), "OrdToTreeOrd(1)");
+    assert_eq!(format!("{:?}", TreeOrdReverse(1u8)), "TreeOrdReverse(1)");
+    assert_eq!(
+        format!("{:?}", TreeOrdBytes(&[1, 2])),
+        "TreeOrdBytes([1, 2])"
+    );
+    assert_eq!(
+        format!("{:?}", TreeOrdVec(vec![1, 2])),
+        "TreeOrdVec([1, 2])"
+    );
+}
+
+#[test]
+fn tree_ord_ext() {
+    use std::cmp::Reverse;
+
+    use tree_ord::{TreeOrdExt, TreeOrdReverse};
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut raw: Vec<(u8, Vec<u8>, u16)> = vec![];
+    for _ in 0..512 {
+        let mut v = vec![7u8; (rng.next_u32() % 40) as usize];
+        for _ in 0..(rng.next_u32() % 3) {
+            v.push((rng.next_u32() % 3) as u8);
+        }
+        raw.push(((rng.next_u32() % 4) as u8, v, (rng.next_u32() % 8) as u16));
+    }
+
+    // the std ordering that the combinators should reproduce
+    let mut expected = raw.clone();
+    expected.sort_by(|a, b| (a.0, Reverse(&a.1), &a.2).cmp(&(b.0, Reverse(&b.1), &b.2)));
+    expected.dedup();
+
+    // owned combinators
+    let mut owned: Vec<_> = raw
+        .iter()
+        .cloned()
+        .map(|(a, b, c)| (a.untracked(), b.tree_reversed(), c))
+        .collect();
+    tree_sort(&mut owned);
+    tree_dedup(&mut owned);
+    let unwrapped: Vec<_> = owned
+        .iter()
+        .map(|(a, b, c)| (*a.as_inner(), b.as_inner().clone(), *c))
+        .collect();
+    assert_eq!(unwrapped, expected);
+
+    // reference combinators over the same fields
+    let borrowed: Vec<_> = expected
+        .iter()
+        .map(|(a, b, c)| (a.untracked_ref(), b.tree_reversed_ref(), c))
+        .collect();
+    assert!(borrowed.windows(2).all(|w| w[0] < w[1]));
+    for (i, (a, b, c)) in expected.iter().cloned().enumerate() {
+        assert!(std::ptr::eq(borrowed[i].1.as_inner(), &expected[i].1));
+        let query = (a.untracked_ref(), b.tree_reversed_ref(), &c);
+        assert_eq!(tree_binary_search(&borrowed, &query), Ok(i));
+        let query = (a.untracked(), b.clone().tree_reversed(), c);
+        assert_eq!(tree_binary_search(&owned, &query), Ok(i));
+        // a missing last field lands between the keys
+        let query = (
+            a.untracked(),
+            b.clone().tree_reversed(),
+            c.wrapping_add(u16::MAX / 2),
+        );
+        let probe = (a, Reverse(&b), c.wrapping_add(u16::MAX / 2));
+        let lower = expected.partition_point(|k| (k.0, Reverse(&k.1), k.2) < probe);
+        assert_eq!(tree_lower_bound(&owned, &query), lower);
+    }
+
+    // trackers compose as with the unwrapped fields
+    for x in &expected {
+        for y in &expected {
+            let lhs = (x.0.untracked(), x.1.clone().tree_reversed(), x.2);
+            let rhs = (y.0.untracked(), y.1.clone().tree_reversed(), y.2);
+            let mut tracker = Tracker::new();
+            assert_eq!(
+                lhs.tree_cmp(&rhs, &mut tracker),
+                (x.0, Reverse(&x.1), x.2).cmp(&(y.0, Reverse(&y.1), y.2))
+            );
+            let lhs = x.1.tree_reversed_ref();
+            let rhs = y.1.tree_reversed_ref();
+            let mut tracker = <TreeOrdReverse<Vec<u8>> as TreeOrd>::Tracker::new();
+            assert_eq!(lhs.tree_cmp(rhs, &mut tracker), y.1.cmp(&x.1));
+            let diverge = tree_ord::prefix::common_prefix_len(&x.1, &y.1);
+            match lhs.cmp(rhs) {
+                Less => assert_eq!(tracker.min_eq_len(), diverge),
+                Equal => (),
+                Greater => assert_eq!(tracker.max_eq_len(), diverge),
+            }
+        }
+    }
+}
+
+#[test]
+fn big_endian_key() {
+    use tree_ord::BigEndianKey;
+
+    macro_rules! check {
+        ($($t:ident),*) => {
+            $(
+                // the extremes are all 0 and all 1 bytes at compile time
+                const _: () = {
+                    let min: [u8; BigEndianKey::<$t>::LEN] = BigEndianKey($t::MIN).as_bytes();
+                    let max: [u8; BigEndianKey::<$t>::LEN] = BigEndianKey($t::MAX).as_bytes();
+                    let mut i = 0;
+                    while i < BigEndianKey::<$t>::LEN {
+                        assert!(min[i] == 0 && max[i] == u8::MAX);
+                        i += 1;
+                    }
+                    assert!(BigEndianKey::<$t>::from_bytes(min).0 == $t::MIN);
+                    assert!(BigEndianKey::<$t>::from_bytes(max).0 == $t::MAX);
+                };
+
+                let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+                let mut xs: Vec<$t> = vec![$t::MIN, $t::MIN + 1, 0, 1, $t::MAX - 1, $t::MAX];
+                #[allow(unused_comparisons)]
+                if $t::MIN < 0 {
+                    xs.push((0 as $t).wrapping_sub(1));
+                    xs.push((0 as $t).wrapping_sub(2));
+                }
+                for _ in 0..64 {
+                    let x = ((rng.next_u64() as u128) << 64) | (rng.next_u64() as u128);
+                    // keep a few high bytes shared
+                    xs.push((x as $t) >> (rng.next_u32() % $t::BITS));
+                    xs.push(x as $t);
+                }
+                for &x in &xs {
+                    let key = BigEndianKey(x);
+                    assert_eq!(BigEndianKey::<$t>::from_bytes(key.as_bytes()), key);
+                    for &y in &xs {
+                        let rhs = BigEndianKey(y);
+                        assert_eq!(key.as_bytes().cmp(&rhs.as_bytes()), x.cmp(&y));
+                        assert_eq!(key.cmp(&rhs), x.cmp(&y));
+                        let mut tracker = <BigEndianKey<$t> as TreeOrd>::Tracker::new();
+                        let res = key.tree_cmp(&rhs, &mut tracker);
+                        assert_eq!(res, x.cmp(&y));
+                        let diverge =
+                            tree_ord::prefix::common_prefix_len(&key.as_bytes(), &rhs.as_bytes());
+                        match res {
+                            Less => assert_eq!(tracker.max_eq_len(), diverge),
+                            Equal => (),
+                            Greater => assert_eq!(tracker.min_eq_len(), diverge),
+                        }
+                    }
+                }
+                xs.sort();
+                xs.dedup();
+                let keys: Vec<_> = xs.iter().map(|x| BigEndianKey(*x)).collect();
+                let bytes: Vec<_> = keys.iter().map(|k| k.as_bytes()).collect();
+                for (i, x) in xs.iter().enumerate() {
+                    assert_eq!(tree_binary_search(&keys, &BigEndianKey(*x)), Ok(i));
+                    let query = TreeOrdBytes(&bytes[i]);
+                    let space: Vec<_> = bytes.iter().map(|b| TreeOrdBytes(b)).collect();
+                    assert_eq!(tree_binary_search(&space, &query), Ok(i));
+                }
+            )*
+        };
+    }
+    check!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+    // signed edge cases
+    let bytes = |x: i32| BigEndianKey(x).as_bytes();
+    assert_eq!(bytes(i32::MIN), [0, 0, 0, 0]);
+    assert_eq!(bytes(-1), [0x7f, 0xff, 0xff, 0xff]);
+    assert_eq!(bytes(0), [0x80, 0, 0, 0]);
+    assert_eq!(bytes(i32::MAX), [0xff; 4]);
+    assert_eq!(
+        BigEndianKey(-2i64).as_bytes(),
+        tree_ord::key::KeyBuilder::new().push_i64(-2).finish()[..]
+    );
+}
+
+#[test]
+fn prefix_key() {
+    use std::hash::{BuildHasher, RandomState};
+
+    use tree_ord::{utils::LexicographicTracker, PrefixKey};
+
+    const N: usize = 4;
+
+    fn truncated<E>(x: &[E]) -> &[E] {
+        &x[..min(N, x.len())]
+    }
+
+    fn check<T, E>(keys: &[T])
+    where
+        T: std::ops::Deref<Target = [E]> + Clone + std::fmt::Debug,
+        E: TreeOrd<Tracker = ()> + Ord + std::hash::Hash + std::fmt::Debug,
+    {
+        let hasher = RandomState::new();
+        for x in keys {
+            for y in keys {
+                let (px, py) = (PrefixKey::<N, T>(x.clone()), PrefixKey::<N, T>(y.clone()));
+                let expected = truncated(x).cmp(truncated(y));
+                assert_eq!(px.cmp(&py), expected);
+                assert_eq!(px == py, expected == Equal);
+                if px == py {
+                    assert_eq!(hasher.hash_one(&px), hasher.hash_one(&py));
+                }
+                assert_eq!(px.prefix(), truncated(x));
+                let mut tracker = LexicographicTracker::<E>::new();
+                assert_eq!(px.tree_cmp(&py, &mut tracker), expected);
+                let mut tracker = LexicographicTracker::<E>::new();
+                assert_eq!(px.tree_cmp(&**y, &mut tracker), expected);
+                assert!(tracker.min_eq_len() <= N && tracker.max_eq_len() <= N);
+            }
+        }
+
+        // the buckets can be looked up with untruncated keys
+        let mut buckets: Vec<PrefixKey<N, T>> = keys.iter().cloned().map(PrefixKey).collect();
+        tree_sort(&mut buckets);
+        tree_dedup(&mut buckets);
+        assert!(buckets.windows(2).all(|w| w[0] < w[1]));
+        for x in keys {
+            let i = tree_binary_search(&buckets, &**x).unwrap();
+            assert_eq!(buckets[i].prefix(), truncated(x));
+            assert_eq!(tree_lower_bound(&buckets, &**x), i);
+            assert_eq!(tree_upper_bound(&buckets, &**x), i + 1);
+            let mut tracker = LexicographicTracker::<E>::new();
+            for bucket in &buckets {
+                // a valid search of a sorted space with one tracker
+                if bucket.tree_cmp(&**x, &mut tracker) != Less {
+                    break
+                }
+            }
+            assert!(tracker.proven_prefix_len() <= N);
+        }
+    }
+
+    // lengths around `N`, sharing prefixes so that many keys fall in the same
+    // bucket
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut bytes: Vec<Vec<u8>> = vec![];
+    for len in 0..=(N + 2) {
+        for _ in 0..24 {
+            bytes.push((0..len).map(|_| (rng.next_u32() % 2) as u8).collect());
+        }
+    }
+    check::<Vec<u8>, u8>(&bytes);
+    let borrowed: Vec<&[u8]> = bytes.iter().map(|v| v.as_slice()).collect();
+    check::<&[u8], u8>(&borrowed);
+    let owned: Vec<TreeOrdVec> = bytes.iter().cloned().map(TreeOrdVec).collect();
+    check::<TreeOrdVec, u8>(&owned);
+    let wide: Vec<Vec<u16>> = bytes
+        .iter()
+        .map(|v| v.iter().map(|b| u16::from(*b) << 8).collect())
+        .collect();
+    check::<Vec<u16>, u16>(&wide);
+
+    // the exact `N` boundary
+    let p = |x: &'static [u8]| PrefixKey::<N, &[u8]>(x);
+    assert_eq!(p(b"abcd"), p(b"abcd"));
+    assert_eq!(p(b"abcd"), p(b"abcde"));
+    assert_eq!(p(b"abcdx"), p(b"abcdy"));
+    assert!(p(b"abc") < p(b"abcd"));
+    assert!(p(b"abce") > p(b"abcdz"));
+    // keys shorter than `N` compare by their elements and then by length
+    assert!(p(b"") < p(b"a"));
+    assert!(p(b"ab") < p(b"abc"));
+    assert!(p(b"b") > p(b"abcd"));
+    assert_ne!(p(b"ab"), p(b"ab\0"));
+    assert_eq!(PrefixKey::<0, &[u8]>(b"a"), PrefixKey::<0, &[u8]>(b"b"));
+}
+
+#[test]
+fn check_tree_ord_failure() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    use tree_ord::testing::{check_tree_cmp_sequence, check_tree_ord};
+
+    let space: Vec<EqualProves> = gen_small_alphabet(64, 6, &[0, 1], 0)
+        .into_iter()
+        .map(EqualProves)
+        .collect();
+    let payload = catch_unwind(AssertUnwindSafe(|| check_tree_ord(&space, &[], 0))).unwrap_err();
+    let msg = payload.downcast_ref::<String>().unwrap();
+    assert!(msg.contains("`tree_cmp` returned"), "{msg}");
+    // replay the reported sequence
+    let replay = msg.split("check_tree_cmp_sequence(&space[").nth(1).unwrap();
+    let (query, rest) = replay.split_once("], &[").unwrap();
+    let indices: Vec<usize> = rest
+        .split_once(']')
+        .unwrap()
+        .0
+        .split(", ")
+        .map(|i| i.parse().unwrap())
+        .collect();
+    let keys: Vec<&EqualProves> = indices.iter().map(|i| &space[*i]).collect();
+    let query = &space[query.parse::<usize>().unwrap()];
+    assert!(catch_unwind(|| check_tree_cmp_sequence(query, &keys)).is_err());
+
+    // a valid impl passes
+    let space: Vec<Vec<u8>> = space.into_iter().map(|k| k.0).collect();
+    check_tree_ord(&space, &[], 0);
+}
+
+/// Insertion orders of the sorted `0..n` that produce adversarial shapes
+fn bst_insertion_orders(n: usize, rng: &mut Xoshiro128StarStar) -> Vec<(&'static str, Vec<usize>)> {
+    let ascending: Vec<usize> = (0..n).collect();
+    let descending: Vec<usize> = (0..n).rev().collect();
+    // alternates between the ends, so that the path to the middle zig-zags
+    let mut zig_zag = vec![];
+    let (mut lo, mut hi) = (0, n);
+    while lo < hi {
+        zig_zag.push(lo);
+        lo += 1;
+        if lo < hi {
+            hi -= 1;
+            zig_zag.push(hi);
+        }
+    }
+    let mut random = ascending.clone();
+    for i in (1..n).rev() {
+        random.swap(i, (rng.next_u32() as usize) % (i + 1));
+    }
+    vec![
+        ("ascending", ascending),
+        ("descending", descending),
+        ("zig-zag", zig_zag),
+        ("random", random),
+    ]
+}
+
+#[test]
+fn reference_bst() {
+    use tree_ord::testing::ReferenceBst;
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    // few distinct keys so that there are many duplicates
+    let mut keys = gen_small_alphabet(96, 4, &[0, 1], 0);
+    keys.sort();
+    let queries = gen_small_alphabet(64, 5, &[0, 1, 2], 1);
+    for (_, order) in bst_insertion_orders(keys.len(), &mut rng) {
+        for policy in 0..3 {
+            let mut bytes = ReferenceBst::new();
+            let mut strings = ReferenceBst::new();
+            let mut tuples = ReferenceBst::new();
+            for (j, i) in order.iter().enumerate() {
+                let equal_right = match policy {
+                    0 => j % 2 == 0,
+                    1 => rng.next_u32() % 2 == 0,
+                    _ => i % 3 == 0,
+                };
+                let key = &keys[*i];
+                bytes.insert(key.clone(), equal_right);
+                strings.insert(
+                    key.iter().map(|b| char::from(b'a' + b)).collect::<String>(),
+                    equal_right,
+                );
+                tuples.insert((key.first().copied(), key.clone()), equal_right);
+            }
+            for query in keys.iter().chain(&queries) {
+                bytes.check(query);
+                strings.check(&query.iter().map(|b| char::from(b'a' + b)).collect());
+                tuples.check(&(query.first().copied(), query.clone()));
+            }
+        }
+    }
+
+    // the shapes without duplicates
+    for (shape, order) in bst_insertion_orders(64, &mut rng) {
+        let mut bst = ReferenceBst::new();
+        for i in order {
+            bst.insert(i as u64, false);
+        }
+        let sorted: Vec<u64> = bst.in_order().iter().map(|i| *bst.get(*i)).collect();
+        assert_eq!(sorted, (0..64).collect::<Vec<_>>());
+        match shape {
+            "random" => assert!(bst.height() < 32),
+            _ => assert_eq!(bst.height(), 64),
+        }
+    }
+
+    // trackers that are strengthened on `Equal` are caught. When the second
+    // `[0, 1]` is inserted, the first `[0, 1]` is found to be equal after a
+    // `Less` from `[0, 0]`, and the insertion continues into its right subtree
+    // with the `[1]` that does not share the prefix of `[0, 0]`.
+    let keys = [
+        (vec![1], false),
+        (vec![0, 0], false),
+        (vec![0, 1], true),
+        (vec![1], false),
+        (vec![0, 1], true),
+    ];
+    let mut bst = ReferenceBst::new();
+    let mut broken = ReferenceBst::new();
+    for (key, equal_right) in keys {
+        bst.insert(key.clone(), equal_right);
+        broken.insert(EqualProves(key), equal_right);
+    }
+    assert_eq!(bst.find_all(&vec![0, 1]), vec![2, 4]);
+    bst.check(&vec![0, 1]);
+    assert!(std::panic::catch_unwind(|| broken.check(&EqualProves(vec![0, 1]))).is_err());
+}
+
+#[test]
+fn check_tree_ord_exhaustive() {
+    use tree_ord::testing::{all_sequences, check_tree_ord_exhaustive};
+
+    // the number of sequences is about `keys^depth`, so the longer keys get
+    // less depth. The bytes use the chunked comparison and the `u16`s use the
+    // generic one.
+    let alphabet = [0u8, 1, 2];
+    let to_words = |domain: &[Vec<u8>]| -> Vec<Vec<u16>> {
+        domain
+            .iter()
+            .map(|s| s.iter().map(|x| *x as u16).collect())
+            .collect()
+    };
+    let short = all_sequences(&alphabet, 2);
+    check_tree_ord_exhaustive(&short, 5);
+    check_tree_ord_exhaustive(&to_words(&short), 5);
+    let strings: Vec<String> = short
+        .iter()
+        .map(|s| s.iter().map(|x| char::from(b'a' + x)).collect())
+        .collect();
+    check_tree_ord_exhaustive(&strings, 5);
+    let domain = all_sequences(&alphabet, 3);
+    check_tree_ord_exhaustive(&domain, 3);
+    check_tree_ord_exhaustive(&to_words(&domain), 3);
+    check_tree_ord_exhaustive(&all_sequences(&alphabet, 4), 2);
+    // diverging and ending around the boundary of the first 32 byte chunk
+    let padded: Vec<Vec<u8>> = domain.iter().map(|s| [&[0; 30][..], s].concat()).collect();
+    check_tree_ord_exhaustive(&padded, 3);
+    let arrays: Vec<[u8; 3]> = domain
+        .iter()
+        .filter(|s| s.len() == 3)
+        .map(|s| [s[0], s[1], s[2]])
+        .collect();
+    check_tree_ord_exhaustive(&arrays, 3);
+    let nested: Vec<Vec<Vec<u8>>> = all_sequences(&[vec![], vec![0], vec![0, 1]], 2);
+    check_tree_ord_exhaustive(&nested, 4);
+
+    // tuples up to arity 3
+    let singles: Vec<(u8,)> = alphabet.iter().map(|a| (*a,)).collect();
+    check_tree_ord_exhaustive(&singles, 4);
+    let mut pairs = vec![];
+    let mut triples = vec![];
+    for a in alphabet {
+        for b in alphabet {
+            pairs.push((a, b));
+            for c in alphabet {
+                triples.push((a, b, c));
+            }
+        }
+    }
+    check_tree_ord_exhaustive(&pairs, 5);
+    check_tree_ord_exhaustive(&triples, 3);
+    // tuples of sequences, where whole fields are skipped
+    let mut seq_pairs = vec![];
+    for a in all_sequences(&alphabet, 1) {
+        for b in all_sequences(&alphabet, 1) {
+            seq_pairs.push((a.clone(), b));
+        }
+    }
+    check_tree_ord_exhaustive(&seq_pairs, 4);
+
+    // `Option` and `Result`
+    let options: Vec<Option<Vec<u8>>> = short
+        .iter()
+        .map(|s| Some(s.clone()))
+        .chain([None])
+        .collect();
+    check_tree_ord_exhaustive(&options, 4);
+    let results: Vec<Result<Vec<u8>, (u8, u8)>> = short
+        .iter()
+        .map(|s| Ok(s.clone()))
+        .chain(pairs.iter().map(|p| Err(*p)))
+        .collect();
+    check_tree_ord_exhaustive(&results, 3);
+    let option_seqs = all_sequences(&[None, Some(0u8), Some(1)], 2);
+    check_tree_ord_exhaustive(&option_seqs, 4);
+
+    // trackers that are strengthened on `Equal` are caught
+    let broken: Vec<EqualProves> = short.into_iter().map(EqualProves).collect();
+    assert!(std::panic::catch_unwind(|| check_tree_ord_exhaustive(&broken, 3)).is_err());
+}
+
+#[test]
+fn tree_map() {
+    use std::{
+        collections::BTreeMap,
+        ops::{Bound, Bound::*},
+    };
+
+    use tree_ord::collections::TreeMap;
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let keys: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(16).collect();
+    let mut map = TreeMap::new();
+    let mut expected = BTreeMap::new();
+    for _ in 0..20000 {
+        let key = &keys[(rng.next_u64() as usize) % keys.len()];
+        let value = rng.next_u64();
+        match rng.next_u64() % 4 {
+            0 | 1 => assert_eq!(
+                map.insert(key.clone(), value),
+                expected.insert(key.clone(), value)
+            ),
+            2 => assert_eq!(map.remove(key), expected.remove(key)),
+            _ => {
+                if let Some(v) = map.get_mut(key) {
+                    *v = value;
+                }
+                if let Some(v) = expected.get_mut(key) {
+                    *v = value;
+                }
+            }
+        }
+        assert_eq!(map.get(key), expected.get(key));
+        assert_eq!(map.len(), expected.len());
+    }
+    assert!(map.iter().eq(expected.iter()));
+    assert_eq!(map.first_key_value(), expected.first_key_value());
+    assert_eq!(map.last_key_value(), expected.last_key_value());
+    for key in &keys {
+        assert_eq!(map.get_key_value(key), expected.get_key_value(key));
+    }
+    let bound = |rng: &mut Xoshiro128StarStar| -> Bound<Vec<u8>> {
+        let key = keys[(rng.next_u64() as usize) % keys.len()].clone();
+        match rng.next_u64() % 3 {
+            0 => Included(key),
+            1 => Excluded(key),
+            _ => Unbounded,
+        }
+    };
+    for _ in 0..2000 {
+        let range = (bound(&mut rng), bound(&mut rng));
+        let res: Vec<_> = map.range::<Vec<u8>, _>(range.clone()).collect();
+        let inverted = match (&range.0, &range.1) {
+            (Included(s), Included(e)) => s > e,
+            (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s >= e,
+            _ => false,
+        };
+        if inverted {
+            assert!(res.is_empty());
+        } else {
+            assert_eq!(res, expected.range(range).collect::<Vec<_>>());
+        }
+    }
+
+    // ascending and descending inserts and removes go along the edges
+    let mut sorted: Vec<Vec<u8>> = keys.clone();
+    sorted.dedup();
+    sorted.truncate(1000);
+    let mut map: TreeMap<Vec<u8>, usize> = sorted.iter().cloned().zip(0..).collect();
+    let mut rev: TreeMap<Vec<u8>, usize> = sorted.iter().cloned().zip(0..1000).rev().collect();
+    assert!(map.iter().eq(rev.iter()));
+    for (i, key) in sorted.iter().enumerate() {
+        assert_eq!(map.get(key), Some(&i));
+        assert_eq!(map.remove(key), Some(i));
+        assert_eq!(
+            rev.remove(sorted.iter().rev().nth(i).unwrap()),
+            Some(999 - i)
+        );
+    }
+    assert!(map.is_empty() && rev.is_empty());
+    assert_eq!(map.range::<Vec<u8>, _>(..).next(), None);
+
+    // every descent is a valid comparison sequence
+    use tree_ord::checked::Checked;
+    let mut map = TreeMap::new();
+    for _ in 0..5000 {
+        let key = Checked(keys[(rng.next_u64() as usize) % keys.len()].clone());
+        let end = Checked(keys[(rng.next_u64() as usize) % keys.len()].clone());
+        match rng.next_u64() % 4 {
+            0 | 1 => {
+                map.insert(key, ());
+            }
+            2 => {
+                map.remove(&key);
+            }
+            _ => {
+                map.get(&key);
+                map.range(&key..=&end).count();
+                map.range((Excluded(&end), Unbounded)).count();
+            }
+        }
+    }
+}
+
+#[test]
+fn tree_map_comparisons() {
+    use std::collections::BTreeMap;
+
+    use tree_ord::collections::TreeMap;
+    // long keys that share long prefixes
+    let keys: Vec<Vec<COrd<u8>>> = ClusteredBytes {
+        count: 1 << 12,
+        max_len: 1 << 10,
+        plateau_levels: vec![128, 255],
+    }
+    .generate(0)
+    .into_iter()
+    .map(|v| v.into_iter().map(COrd).collect())
+    .collect();
+    let map: TreeMap<Vec<COrd<u8>>, ()> = keys.iter().map(|k| (k.clone(), ())).collect();
+    let btree: BTreeMap<Vec<COrd<u8>>, ()> = keys.iter().map(|k| (k.clone(), ())).collect();
+    let scope = CountScope::new();
+    for key in &keys {
+        assert!(map.contains_key(key));
+    }
+    let tree_count = scope.finish();
+    let scope = CountScope::new();
+    for key in &keys {
+        assert!(btree.contains_key(key));
+    }
+    let btree_count = scope.finish();
+    assert!((tree_count * 2) < btree_count, "{tree_count} {btree_count}");
+}
+
+#[test]
+fn sorted_vec_set() {
+    use std::{
+        collections::BTreeSet,
+        ops::{Bound, Bound::*},
+    };
+
+    use tree_ord::collections::SortedVecSet;
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let keys: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(16).collect();
+    let mut set = SortedVecSet::new();
+    let mut expected = BTreeSet::new();
+    for _ in 0..20000 {
+        let key = &keys[(rng.next_u64() as usize) % keys.len()];
+        match rng.next_u64() % 3 {
+            0 | 1 => assert_eq!(set.insert(key.clone()), expected.insert(key.clone())),
+            _ => assert_eq!(set.remove(key), expected.remove(key)),
+        }
+        assert_eq!(set.contains(key), expected.contains(key));
+        assert_eq!(set.get(key), expected.get(key));
+        assert_eq!(set.len(), expected.len());
+    }
+    assert!(set.iter().eq(expected.iter()));
+    assert_eq!(set.first(), expected.first());
+    assert_eq!(set.last(), expected.last());
+    let bound = |rng: &mut Xoshiro128StarStar| -> Bound<Vec<u8>> {
+        let key = keys[(rng.next_u64() as usize) % keys.len()].clone();
+        match rng.next_u64() % 3 {
+            0 => Included(key),
+            1 => Excluded(key),
+            _ => Unbounded,
+        }
+    };
+    for _ in 0..2000 {
+        let range = (bound(&mut rng), bound(&mut rng));
+        let res: Vec<_> = set.range::<Vec<u8>, _>(range.clone()).collect();
+        let inverted = match (&range.0, &range.1) {
+            (Included(s), Included(e)) => s > e,
+            (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s >= e,
+            _ => false,
+        };
+        if inverted {
+            assert!(res.is_empty());
+        } else {
+            assert_eq!(res, expected.range(range).collect::<Vec<_>>());
+        }
+    }
+
+    // construction keeps the last of equal keys and extending keeps the keys in
+    // the set, like `BTreeSet`
+    let pairs: Vec<(u8, u64)> = (0..1000u64)
+        .map(|i| ((rng.next_u64() % 64) as u8, i))
+        .collect();
+    #[derive(Debug, Clone, Copy)]
+    struct FirstField((u8, u64));
+    impl PartialEq for FirstField {
+        fn eq(&self, other: &Self) -> bool {
+            self.0 .0 == other.0 .0
+        }
+    }
+    impl Eq for FirstField {}
+    impl PartialOrd for FirstField {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl Ord for FirstField {
+        fn cmp(&self, other: &Self) -> Ordering {
+            self.0 .0.cmp(&other.0 .0)
+        }
+    }
+    impl TreeOrd for FirstField {
+        type Tracker = ();
+
+        fn tree_cmp(&self, rhs: &Self, _: &mut ()) -> Ordering {
+            self.cmp(rhs)
+        }
+    }
+    let set: SortedVecSet<FirstField> = pairs.iter().copied().map(FirstField).collect();
+    let expected: BTreeSet<FirstField> = pairs.iter().copied().map(FirstField).collect();
+    assert!(set.iter().eq(expected.iter()));
+    assert!(set.iter().all(|k| k.0 .1 == expected.get(k).unwrap().0 .1));
+    let mut extended = SortedVecSet::from_sorted_unchecked(set.as_slice()[..10].to_vec());
+    extended.extend(pairs.iter().copied().map(FirstField));
+    let mut expected_extended: BTreeSet<FirstField> = set.iter().copied().take(10).collect();
+    expected_extended.extend(pairs.iter().copied().map(FirstField));
+    assert!(extended.iter().eq(expected_extended.iter()));
+    assert!(extended
+        .iter()
+        .all(|k| k.0 .1 == expected_extended.get(k).unwrap().0 .1));
+
+    let mut set = SortedVecSet::from_unsorted(keys.clone());
+    let mut sorted = keys.clone();
+    sorted.sort();
+    sorted.dedup();
+    assert_eq!(set.as_slice(), &sorted[..]);
+    assert_eq!(set.replace(sorted[3].clone()), Some(sorted[3].clone()));
+    assert_eq!(set.take(&sorted[3]), Some(sorted[3].clone()));
+    assert_eq!(set.search(&sorted[3]), Err(3));
+    assert_eq!(set.into_vec().len(), sorted.len() - 1);
+}
+
+#[test]
+fn sorted_vec_set_comparisons() {
+    use tree_ord::collections::SortedVecSet;
+    let keys: Vec<Vec<COrd<u8>>> = ClusteredBytes {
+        count: 1 << 12,
+        max_len: 1 << 10,
+        plateau_levels: vec![128, 255],
+    }
+    .generate(0)
+    .into_iter()
+    .map(|v| v.into_iter().map(COrd).collect())
+    .collect();
+    let set = SortedVecSet::from_unsorted(keys.clone());
+    let mut sorted = keys.clone();
+    sorted.dedup();
+    assert_eq!(set.as_slice(), &sorted[..]);
+    let scope = CountScope::new();
+    for key in &keys {
+        assert!(set.contains(key));
+    }
+    let tree_count = scope.finish();
+    let scope = CountScope::new();
+    for key in &keys {
+        assert!(sorted.binary_search(key).is_ok());
+    }
+    let ord_count = scope.finish();
+    assert!((tree_count * 2) < ord_count, "{tree_count} {ord_count}");
+
+    // bulk construction from shuffled keys
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut shuffled = keys.clone();
+    for i in (1..shuffled.len()).rev() {
+        shuffled.swap(i, (rng.next_u64() as usize) % (i + 1));
+    }
+    let scope = CountScope::new();
+    let set = SortedVecSet::from_unsorted(shuffled.clone());
+    let tree_count = scope.finish();
+    let scope = CountScope::new();
+    shuffled.sort();
+    shuffled.dedup();
+    let ord_count = scope.finish();
+    assert_eq!(set.as_slice(), &shuffled[..]);
+    assert!(tree_count < ord_count, "{tree_count} {ord_count}");
+}
+
+#[test]
+fn sorted_vec_multimap() {
+    use std::{
+        collections::BTreeMap,
+        ops::{Bound, Bound::*},
+    };
+
+    use tree_ord::{checked::Checked, collections::SortedVecMultiMap};
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut keys: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(64).collect();
+    keys.dedup();
+    let mut map = SortedVecMultiMap::new();
+    let mut expected: BTreeMap<Vec<u8>, Vec<u64>> = BTreeMap::new();
+    for i in 0..20000 {
+        // biased towards a few keys so that there are large groups
+        let key = if (rng.next_u64() % 2) == 0 {
+            &keys[(rng.next_u64() as usize) % 4]
+        } else {
+            &keys[(rng.next_u64() as usize) % keys.len()]
+        };
+        match rng.next_u64() % 16 {
+            0 => {
+                let removed = expected.remove(key).unwrap_or_default();
+                assert_eq!(map.remove_key(key), removed);
+            }
+            1 => {
+                if let Some(v) = map.get_all_mut(key).first_mut() {
+                    *v = i;
+                }
+                if let Some(v) = expected.get_mut(key).and_then(|v| v.first_mut()) {
+                    *v = i;
+                }
+            }
+            _ => {
+                map.insert(key.clone(), i);
+                expected.entry(key.clone()).or_default().push(i);
+            }
+        }
+        let expected_values = expected.get(key).map(|v| &v[..]).unwrap_or_default();
+        assert_eq!(map.get_all(key), expected_values);
+        assert_eq!(map.contains_key(key), !expected_values.is_empty());
+        assert_eq!(map.len(), expected.values().map(|v| v.len()).sum::<usize>());
+    }
+    assert!(map.groups().eq(expected.iter().map(|(k, v)| (k, &v[..]))));
+    assert!(map.iter().eq(expected
+        .iter()
+        .flat_map(|(k, v)| v.iter().map(move |v| (k, v)))));
+    for key in &keys {
+        let range = map.equal_range(key);
+        assert_eq!(range.start, map.keys().partition_point(|k| k < key));
+        assert_eq!(range.end, map.keys().partition_point(|k| k <= key));
+    }
+    let bound = |rng: &mut Xoshiro128StarStar| -> Bound<Vec<u8>> {
+        let key = keys[(rng.next_u64() as usize) % keys.len()].clone();
+        match rng.next_u64() % 3 {
+            0 => Included(key),
+            1 => Excluded(key),
+            _ => Unbounded,
+        }
+    };
+    for _ in 0..2000 {
+        let range = (bound(&mut rng), bound(&mut rng));
+        let res: Vec<_> = map.range::<Vec<u8>, _>(range.clone()).collect();
+        let inverted = match (&range.0, &range.1) {
+            (Included(s), Included(e)) => s > e,
+            (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s >= e,
+            _ => false,
+        };
+        if inverted {
+            assert!(res.is_empty());
+        } else {
+            let expected: Vec<_> = expected
+                .range(range)
+                .flat_map(|(k, v)| v.iter().map(move |v| (k, v)))
+                .collect();
+            assert_eq!(res, expected);
+        }
+    }
+
+    // bulk construction and extending keep the insertion order within groups
+    let pairs: Vec<(u8, u64)> = (0..1000u64)
+        .map(|i| ((rng.next_u64() % 64) as u8, i))
+        .collect();
+    let mut map: SortedVecMultiMap<u8, u64> = pairs[..500].iter().copied().collect();
+    map.extend(pairs[500..].iter().copied());
+    let mut expected: BTreeMap<u8, Vec<u64>> = BTreeMap::new();
+    for (k, v) in &pairs {
+        expected.entry(*k).or_default().push(*v);
+    }
+    assert!(map.groups().eq(expected.iter().map(|(k, v)| (k, &v[..]))));
+    let mut inserted = SortedVecMultiMap::new();
+    for (k, v) in &pairs {
+        inserted.insert(*k, *v);
+    }
+    assert_eq!(inserted, map);
+
+    // a map of only duplicates, and groups at the ends
+    let mut map = SortedVecMultiMap::from_unsorted((0..100u64).map(|i| (7u8, i)).collect());
+    assert_eq!(map.equal_range(&7), 0..100);
+    assert_eq!(map.equal_range(&6), 0..0);
+    assert_eq!(map.equal_range(&8), 100..100);
+    assert_eq!(map.groups().count(), 1);
+    map.insert(0, 100);
+    map.insert(255, 101);
+    map.insert(0, 102);
+    assert_eq!(map.get_all(&0), &[100, 102]);
+    assert_eq!(map.get_all(&255), &[101]);
+    assert_eq!(map.get_all(&7).len(), 100);
+    let groups: Vec<_> = map.groups().map(|(k, v)| (*k, v.len())).collect();
+    assert_eq!(groups, [(0, 2), (7, 100), (255, 1)]);
+    assert_eq!(map.remove_key(&7), (0..100).collect::<Vec<_>>());
+    assert_eq!(map.remove_key(&7), []);
+    assert_eq!(map.keys(), &[0, 0, 255]);
+    assert_eq!(map.values(), &[100, 102, 101]);
+    map.clear();
+    assert!(map.is_empty());
+    assert_eq!(map.groups().next(), None);
+    assert_eq!(map.equal_range(&0), 0..0);
+
+    // the equal range search and the grouping only compare keys within the
+    // bounds of their trackers
+    let checked: Vec<Checked<Vec<u8>>> = keys
+        .iter()
+        .flat_map(|k| (0..(k.len() % 5)).map(move |_| Checked(k.clone())))
+        .collect();
+    let map = SortedVecMultiMap::from_unsorted(checked.into_iter().map(|k| (k, ())).collect());
+    for key in &keys {
+        let range = map.equal_range(&Checked(key.clone()));
+        assert_eq!(range.len(), key.len() % 5);
+    }
+    assert_eq!(
+        map.groups().count(),
+        keys.iter().filter(|k| (k.len() % 5) != 0).count()
+    );
+}
+
+#[test]
+fn sorted_vec_multimap_comparisons() {
+    use tree_ord::collections::SortedVecMultiMap;
+    // groups of long keys that share most of their prefix with each other
+    let keys: Vec<Vec<COrd<u8>>> = ClusteredBytes {
+        count: 1 << 8,
+        max_len: 1 << 10,
+        plateau_levels: vec![128, 255],
+    }
+    .generate(0)
+    .into_iter()
+    .map(|v| v.into_iter().map(COrd).collect())
+    .collect();
+    let mut entries = vec![];
+    for (i, key) in keys.iter().enumerate() {
+        for j in 0..(1 << (i % 8)) {
+            entries.push((key.clone(), j));
+        }
+    }
+    let map = SortedVecMultiMap::from_unsorted(entries);
+    let scope = CountScope::new();
+    for key in &keys {
+        assert!(!map.get_all(key).is_empty());
+    }
+    let tree_count = scope.finish();
+    let scope = CountScope::new();
+    for key in &keys {
+        let lower = map.keys().partition_point(|k| k < key);
+        let upper = map.keys().partition_point(|k| k <= key);
+        assert!(lower < upper);
+    }
+    let ord_count = scope.finish();
+    assert!((tree_count * 2) < ord_count, "{tree_count} {ord_count}");
+
+    let scope = CountScope::new();
+    assert!(map.groups().count() <= keys.len());
+    let tree_count = scope.finish();
+    let scope = CountScope::new();
+    assert!(map.keys().chunk_by(|a, b| a.cmp(b) == Equal).count() <= keys.len());
+    let ord_count = scope.finish();
+    assert!(tree_count < ord_count, "{tree_count} {ord_count}");
+}
+
+#[test]
+fn skip_list_map() {
+    use std::{
+        collections::BTreeMap,
+        ops::{Bound, Bound::*},
+    };
+
+    use tree_ord::{checked::Checked, collections::SkipListMap};
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let keys: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(16).collect();
+    let mut map = SkipListMap::with_seed(1);
+    let mut expected = BTreeMap::new();
+    for _ in 0..20000 {
+        let key = &keys[(rng.next_u64() as usize) % keys.len()];
+        let value = rng.next_u64();
+        match rng.next_u64() % 4 {
+            0 | 1 => assert_eq!(
+                map.insert(key.clone(), value),
+                expected.insert(key.clone(), value)
+            ),
+            2 => assert_eq!(map.remove(key), expected.remove(key)),
+            _ => {
+                if let Some(v) = map.get_mut(key) {
+                    *v = value;
+                }
+                if let Some(v) = expected.get_mut(key) {
+                    *v = value;
+                }
+            }
+        }
+        assert_eq!(map.get(key), expected.get(key));
+        assert_eq!(map.len(), expected.len());
+    }
+    assert!(map.iter().eq(expected.iter()));
+    assert_eq!(map.iter().len(), expected.len());
+    assert_eq!(map.first_key_value(), expected.first_key_value());
+    assert_eq!(map.last_key_value(), expected.last_key_value());
+    for key in &keys {
+        assert_eq!(map.get_key_value(key), expected.get_key_value(key));
+    }
+    let bound = |rng: &mut Xoshiro128StarStar| -> Bound<Vec<u8>> {
+        let key = keys[(rng.next_u64() as usize) % keys.len()].clone();
+        match rng.next_u64() % 3 {
+            0 => Included(key),
+            1 => Excluded(key),
+            _ => Unbounded,
+        }
+    };
+    for _ in 0..2000 {
+        let range = (bound(&mut rng), bound(&mut rng));
+        let res: Vec<_> = map.range::<Vec<u8>, _>(range.clone()).collect();
+        let inverted = match (&range.0, &range.1) {
+            (Included(s), Included(e)) => s > e,
+            (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s >= e,
+            _ => false,
+        };
+        if inverted {
+            assert!(res.is_empty());
+        } else {
+            assert_eq!(res, expected.range(range).collect::<Vec<_>>());
+        }
+    }
+    while let Some((key, _)) = expected.pop_first() {
+        assert!(map.remove(&key).is_some());
+    }
+    assert!(map.is_empty());
+    assert_eq!(map.iter().next(), None);
+    assert_eq!(map.range::<Vec<u8>, _>(..).next(), None);
+
+    // the structure only depends on the seed
+    let a: SkipListMap<u64, ()> = (0..1000).map(|i| (i, ())).collect();
+    let b: SkipListMap<u64, ()> = (0..1000).map(|i| (i, ())).collect();
+    assert_eq!(format!("{a:?}"), format!("{b:?}"));
+
+    // every search is a valid comparison sequence
+    let mut map = SkipListMap::new();
+    for _ in 0..5000 {
+        let key = Checked(keys[(rng.next_u64() as usize) % keys.len()].clone());
+        let end = Checked(keys[(rng.next_u64() as usize) % keys.len()].clone());
+        match rng.next_u64() % 4 {
+            0 | 1 => {
+                map.insert(key, ());
+            }
+            2 => {
+                map.remove(&key);
+            }
+            _ => {
+                map.get(&key);
+                map.range(&key..=&end).count();
+                map.range((Excluded(&end), Unbounded)).count();
+            }
+        }
+    }
+}
+
+#[test]
+fn skip_list_map_comparisons() {
+    use tree_ord::{collections::SkipListMap, OrdToTreeOrd};
+    // long string keys with long shared prefixes
+    let keys: Vec<Vec<COrd<u8>>> = (0..(1u64 << 12))
+        .map(|i| {
+            let i = i.wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 52;
+            format!("/usr/share/some/long/common/directory/{}/{i:06}", i % 7)
+                .into_bytes()
+                .into_iter()
+                .map(COrd)
+                .collect()
+        })
+        .collect();
+    let map: SkipListMap<Vec<COrd<u8>>, ()> = keys.iter().map(|k| (k.clone(), ())).collect();
+    // the same structure, since the levels only depend on the seed
+    let ord_map: SkipListMap<OrdToTreeOrd<Vec<COrd<u8>>>, ()> =
+        keys.iter().map(|k| (OrdToTreeOrd(k.clone()), ())).collect();
+    let scope = CountScope::new();
+    for key in &keys {
+        assert!(map.contains_key(key));
+    }
+    let tree_count = scope.finish();
+    let scope = CountScope::new();
+    for key in &keys {
+        assert!(ord_map.contains_key(&OrdToTreeOrd(key.clone())));
+    }
+    let ord_count = scope.finish();
+    // most of the bytes of each comparison are skipped
+    assert!((tree_count * 4) < ord_count, "{tree_count} {ord_count}");
+}
+
+#[test]
+fn tree_ord_searchable() {
+    use std::{
+        collections::BTreeSet,
+        ops::{Bound, Bound::*},
+    };
+
+    use tree_ord::collections::{SkipListMap, SortedVecMultiMap, SortedVecSet, TreeMap};
+
+    // returns which queries are found and the length of each range, which do not
+    // depend on the backend
+    fn summarize<S, Q>(
+        s: &S,
+        queries: &[Q],
+        ranges: &[(Bound<Q>, Bound<Q>)],
+    ) -> (Vec<bool>, Vec<usize>)
+    where
+        S: TreeOrdSearchable<Q> + ?Sized,
+        Q: Clone,
+    {
+        let found = queries.iter().map(|q| s.tree_find(q).is_some()).collect();
+        let lens = ranges
+            .iter()
+            .map(|range| s.tree_range(range.clone()).count())
+            .collect();
+        (found, lens)
+    }
+
+    let mut rng = Xoshiro128StarStar::seed_from_u64(0);
+    let mut keys: Vec<Vec<u8>> = gen_bytes().into_iter().step_by(64).collect();
+    keys.dedup();
+    let mut queries: Vec<Vec<u8>> = keys.iter().step_by(3).cloned().collect();
+    // queries between the keys and outside of the keys
+    queries.extend(keys.iter().step_by(7).map(|k| {
+        let mut k = k.clone();
+        k.push(1);
+        k
+    }));
+    queries.extend([vec![], vec![255; 1 << 10]]);
+    let bound = |rng: &mut Xoshiro128StarStar| -> Bound<Vec<u8>> {
+        let key = queries[(rng.next_u64() as usize) % queries.len()].clone();
+        match rng.next_u64() % 3 {
+            0 => Included(key),
+            1 => Excluded(key),
+            _ => Unbounded,
+        }
+    };
+    let ranges: Vec<_> = (0..500)
+        .map(|_| (bound(&mut rng), bound(&mut rng)))
+        .collect();
+
+    let expected = BTreeSet::from_iter(keys.iter().cloned());
+    let expected_found: Vec<bool> = queries.iter().map(|q| expected.contains(q)).collect();
+    let expected_lens: Vec<usize> = ranges
+        .iter()
+        .map(|range| {
+            let inverted = match range {
+                (Included(s), Included(e)) => s > e,
+                (Included(s) | Excluded(s), Included(e) | Excluded(e)) => s >= e,
+                _ => false,
+            };
+            if inverted {
+                0
+            } else {
+                expected.range(range.clone()).count()
+            }
+        })
+        .collect();
+    let expected = (expected_found, expected_lens);
+
+    let set = SortedVecSet::from_unsorted(keys.clone());
+    let tree_map: TreeMap<Vec<u8>, usize> = keys.iter().cloned().zip(0..).collect();
+    let skip_list: SkipListMap<Vec<u8>, usize> = keys.iter().cloned().zip(0..).collect();
+    let multimap: SortedVecMultiMap<Vec<u8>, usize> = keys.iter().cloned().zip(0..).collect();
+    assert_eq!(summarize(&keys[..], &queries, &ranges), expected);
+    assert_eq!(summarize(&keys, &queries, &ranges), expected);
+    assert_eq!(summarize(&set, &queries, &ranges), expected);
+    assert_eq!(summarize(&tree_map, &queries, &ranges), expected);
+    assert_eq!(summarize(&skip_list, &queries, &ranges), expected);
+    assert_eq!(summarize(&multimap, &queries, &ranges), expected);
+
+    // sets and slices find keys, and maps find values
+    assert_eq!(keys.tree_find(&keys[5]), Some(&keys[5]));
+    assert_eq!(set.tree_find(&keys[5]), Some(&keys[5]));
+    assert_eq!(tree_map.tree_find(&keys[5]), Some(&5));
+    assert_eq!(skip_list.tree_find(&keys[5]), Some(&5));
+    assert_eq!(multimap.tree_find(&keys[5]), Some(&[5][..]));
+    assert_eq!([1u8, 3, 5].tree_find(&3), Some(&3));
+    assert!([1u8, 3, 5].tree_range(2..).eq(&[3, 5]));
+}